@@ -0,0 +1,116 @@
+//! Microbenchmarks of the byte classification: lookup table vs `match`, and plain ASCII spans.
+//!
+//! Run with `cargo bench --bench classify`.
+use pretty::classify::{Class, classify, plain_ascii_len};
+use pretty::format::{Color, Formatter};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITER: usize = 20;
+
+/// Length of the string starting at the beginning of `input`, classified with `match`.
+fn string_len_match(input: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while let Some(&b) = input.get(i) {
+        match b {
+            b'"' => return Some(i),
+            b'\\' => i += 1,
+            0x00..=0x1F => return None,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Length of the string starting at the beginning of `input`, classified with the table.
+fn string_len_table(input: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while let Some(&b) = input.get(i) {
+        match classify(b) {
+            Class::Quote => return Some(i),
+            Class::Escape => i += 1,
+            Class::Control | Class::Whitespace if b < 0x20 => return None,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Length of the string starting at the beginning of `input`, with plain ASCII spans skipped 8
+/// bytes at a time.
+fn string_len_plain(input: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    loop {
+        i += plain_ascii_len(&input[i..]);
+        let b = *input.get(i)?;
+        match classify(b) {
+            Class::Quote => return Some(i),
+            Class::Escape => i += 1,
+            Class::Control | Class::Whitespace if b < 0x20 => return None,
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+fn whitespaces_match(input: &[u8]) -> usize {
+    input
+        .iter()
+        .filter(|b| matches!(b, b' ' | b'\n' | b'\r' | b'\t'))
+        .count()
+}
+
+fn whitespaces_table(input: &[u8]) -> usize {
+    input
+        .iter()
+        .filter(|&&b| classify(b) == Class::Whitespace)
+        .count()
+}
+
+/// Runs `f` `ITER` times, prints the throughput on `input`.
+fn bench(name: &str, input: &[u8], f: impl Fn(&[u8]) -> usize) {
+    let mut elapsed = Duration::MAX;
+    for _ in 0..ITER {
+        let start = Instant::now();
+        black_box(f(black_box(input)));
+        elapsed = elapsed.min(start.elapsed());
+    }
+    let throughput = input.len() as f64 / elapsed.as_secs_f64() / 1_000_000.0;
+    println!(
+        "{name:<24} {:>10.3} ms {throughput:>10.1} MB/s",
+        elapsed.as_secs_f64() * 1000.0
+    );
+}
+
+fn main() {
+    // Mixed text: ASCII, spaces and escapes, ending with a quote.
+    let mut text = "Lorem ipsum dolor sit amet, \\\"consectetur\\\" adipiscing elit. "
+        .repeat(200_000)
+        .into_bytes();
+    text.push(b'"');
+    bench("string (match)", &text, |input| {
+        string_len_match(input).unwrap_or(0)
+    });
+    bench("string (table)", &text, |input| {
+        string_len_table(input).unwrap_or(0)
+    });
+    bench("string (plain ascii)", &text, |input| {
+        string_len_plain(input).unwrap_or(0)
+    });
+    bench("whitespaces (match)", &text, whitespaces_match);
+    bench("whitespaces (table)", &text, whitespaces_table);
+
+    let json =
+        "{\"id\": 12345, \"name\": \"Lorem ipsum\", \"tags\": [\"a\", \"b\"], \"ok\": true}, "
+            .repeat(100_000);
+    let json = format!("[{}{{}}]", json);
+    bench("format", json.as_bytes(), |input| {
+        let mut out = String::with_capacity(2 * input.len());
+        Formatter::new(input, Color::NoColor)
+            .format(&mut out)
+            .unwrap();
+        out.len()
+    });
+}
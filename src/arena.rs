@@ -0,0 +1,154 @@
+//! A bump allocator for the transient text of formatting (annotations, sort keys...).
+//!
+//! Allocations are only freed all at once, when the arena is reset, and the memory is reused by
+//! the next allocations: an arena reset between NDJSON records or `--iter` iterations avoids
+//! allocating and freeing memory for each record.
+use std::fmt;
+use std::fmt::Write;
+use std::ptr;
+use std::sync::Mutex;
+
+/// Minimum size of a chunk of memory, in bytes.
+const CHUNK_SIZE: usize = 4 * 1024;
+
+/// A bump allocator of strings.
+///
+/// Allocated strings live as long as the shared borrow of the arena; [Arena::reset] needs an
+/// exclusive borrow so no string is used after being reclaimed. An arena can be shared between
+/// threads.
+#[derive(Default)]
+pub struct Arena {
+    chunks: Mutex<Chunks>,
+}
+
+#[derive(Default)]
+struct Chunks {
+    /// Allocated chunks, the last one being the current one.
+    chunks: Vec<Chunk>,
+    /// Bytes used in the current chunk.
+    used: usize,
+    /// Buffer for formatted strings, before they are copied to a chunk.
+    scratch: String,
+}
+
+/// A block of memory, never moved nor resized while allocated.
+struct Chunk(*mut [u8]);
+
+// The memory of a chunk is only written through the arena lock.
+unsafe impl Send for Chunk {}
+
+impl Chunk {
+    fn new(size: usize) -> Self {
+        Chunk(Box::into_raw(vec![0; size].into_boxed_slice()))
+    }
+
+    fn size(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        // SAFETY: the pointer comes from `Box::into_raw` and is only freed here.
+        drop(unsafe { Box::from_raw(self.0) });
+    }
+}
+
+impl Chunks {
+    fn alloc_str<'a>(&mut self, s: &str) -> &'a str {
+        if s.is_empty() {
+            return "";
+        }
+        let fits = self
+            .chunks
+            .last()
+            .is_some_and(|chunk| chunk.size() - self.used >= s.len());
+        if !fits {
+            let last = self.chunks.last().map_or(0, Chunk::size);
+            let size = s.len().max(CHUNK_SIZE).max(2 * last);
+            self.chunks.push(Chunk::new(size));
+            self.used = 0;
+        }
+        let chunk = &self.chunks[self.chunks.len() - 1];
+        // SAFETY: the bytes from `used` are in the chunk and not shared yet. The chunk memory
+        // lives until the arena is reset or dropped, which requires an exclusive borrow, so the
+        // returned string can not outlive it.
+        unsafe {
+            let dst = (chunk.0 as *mut u8).add(self.used);
+            ptr::copy_nonoverlapping(s.as_ptr(), dst, s.len());
+            self.used += s.len();
+            str::from_utf8_unchecked(std::slice::from_raw_parts(dst, s.len()))
+        }
+    }
+}
+
+impl Arena {
+    /// Copies `s` in the arena.
+    pub fn alloc_str(&self, s: &str) -> &str {
+        self.chunks.lock().unwrap().alloc_str(s)
+    }
+
+    /// Formats `args` in the arena.
+    ///
+    /// `args` must not allocate in the same arena.
+    pub fn alloc_fmt(&self, args: fmt::Arguments) -> &str {
+        let mut chunks = self.chunks.lock().unwrap();
+        let mut scratch = std::mem::take(&mut chunks.scratch);
+        scratch.clear();
+        // Writing to a String never fails.
+        let _ = scratch.write_fmt(args);
+        let s = chunks.alloc_str(&scratch);
+        chunks.scratch = scratch;
+        s
+    }
+
+    /// Frees all the allocated strings. The largest chunk of memory is kept for the next
+    /// allocations.
+    pub fn reset(&mut self) {
+        let chunks = self.chunks.get_mut().unwrap();
+        // Chunks are allocated with increasing sizes.
+        if let Some(largest) = chunks.chunks.pop() {
+            chunks.chunks.clear();
+            chunks.chunks.push(largest);
+        }
+        chunks.used = 0;
+    }
+
+    /// Returns the memory reserved by the arena, in bytes.
+    pub fn capacity(&self) -> usize {
+        let chunks = self.chunks.lock().unwrap();
+        chunks.chunks.iter().map(Chunk::size).sum()
+    }
+}
+
+impl fmt::Debug for Arena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Arena")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arena, CHUNK_SIZE};
+
+    #[test]
+    fn alloc_and_reset() {
+        let mut arena = Arena::default();
+        assert_eq!(arena.capacity(), 0);
+        let a = arena.alloc_str("hello");
+        let b = arena.alloc_fmt(format_args!("{a}, {}!", "world"));
+        let large = "x".repeat(CHUNK_SIZE + 1);
+        let c = arena.alloc_str(&large);
+        assert_eq!(a, "hello");
+        assert_eq!(b, "hello, world!");
+        assert_eq!(c, large);
+        assert_eq!(arena.capacity(), CHUNK_SIZE + 2 * CHUNK_SIZE);
+
+        arena.reset();
+        assert_eq!(arena.capacity(), 2 * CHUNK_SIZE);
+        assert_eq!(arena.alloc_str("again"), "again");
+        assert_eq!(arena.capacity(), 2 * CHUNK_SIZE);
+    }
+}
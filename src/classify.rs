@@ -0,0 +1,122 @@
+//! Classification of input bytes with a lookup table.
+//!
+//! The hot loops of the formatter (strings, whitespaces, values dispatch) classify each byte with
+//! a 256 entries table instead of a chain of comparisons: one load per byte, and fewer branches
+//! to mispredict. See `benches/classify.rs` for a comparison with `match`.
+
+/// Class of a byte, for the JSON grammar.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Class {
+    /// Any other ASCII byte.
+    Other,
+    /// `"`
+    Quote,
+    /// `\`
+    Escape,
+    /// Control chars, except the whitespaces (invalid in strings).
+    Control,
+    /// ` `, `\t`, `\n` and `\r` (`\t`, `\n` and `\r` are invalid in strings).
+    Whitespace,
+    /// `0` to `9`.
+    Digit,
+    /// Bytes of multibyte UTF-8 sequences.
+    NonAscii,
+}
+
+/// Classes of all the bytes.
+pub static CLASSES: [Class; 256] = classes();
+
+const fn classes() -> [Class; 256] {
+    let mut classes = [Class::Other; 256];
+    let mut b = 0;
+    while b < 256 {
+        classes[b] = match b as u8 {
+            b'"' => Class::Quote,
+            b'\\' => Class::Escape,
+            b' ' | b'\t' | b'\n' | b'\r' => Class::Whitespace,
+            0x00..=0x1F => Class::Control,
+            b'0'..=b'9' => Class::Digit,
+            0x80..=0xFF => Class::NonAscii,
+            _ => Class::Other,
+        };
+        b += 1;
+    }
+    classes
+}
+
+/// Returns the class of `b`.
+#[inline(always)]
+pub fn classify(b: u8) -> Class {
+    CLASSES[b as usize]
+}
+
+/// Returns the length of the prefix of `bytes` that can be copied as is in a string: ASCII chars,
+/// except control chars, `"` and `\`. This prefix is valid UTF-8.
+///
+/// Bytes are checked 8 at a time, with bitwise operations on `u64` words.
+#[inline]
+pub fn plain_ascii_len(bytes: &[u8]) -> usize {
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    const HIGHS: u64 = 0x8080_8080_8080_8080;
+    // Sets the high bit of the bytes of `word` lesser than `n` (and possibly of the bytes after
+    // them, that are not looked at).
+    #[inline(always)]
+    fn lesser_than(word: u64, n: u8) -> u64 {
+        word.wrapping_sub(ONES * n as u64) & !word & HIGHS
+    }
+
+    let mut chunks = bytes.chunks_exact(8);
+    let mut len = 0;
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap());
+        let special = (word & HIGHS)
+            | lesser_than(word, 0x20)
+            | lesser_than(word ^ (ONES * b'"' as u64), 1)
+            | lesser_than(word ^ (ONES * b'\\' as u64), 1);
+        if special != 0 {
+            // The first special byte is the lowest one.
+            return len + special.trailing_zeros() as usize / 8;
+        }
+        len += 8;
+    }
+    let rest = chunks.remainder();
+    len + rest
+        .iter()
+        .position(|&b| !(0x20..0x80).contains(&b) || b == b'"' || b == b'\\')
+        .unwrap_or(rest.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Class, classify, plain_ascii_len};
+
+    #[test]
+    fn classify_bytes() {
+        assert_eq!(classify(b'"'), Class::Quote);
+        assert_eq!(classify(b'\\'), Class::Escape);
+        assert_eq!(classify(0x01), Class::Control);
+        assert_eq!(classify(b'\n'), Class::Whitespace);
+        assert_eq!(classify(b' '), Class::Whitespace);
+        assert_eq!(classify(b'7'), Class::Digit);
+        assert_eq!(classify(b'-'), Class::Other);
+        assert_eq!(classify(b'a'), Class::Other);
+        assert_eq!(classify(0x7F), Class::Other);
+        assert_eq!(classify(0xC3), Class::NonAscii);
+    }
+
+    #[test]
+    fn plain_ascii_prefix() {
+        assert_eq!(plain_ascii_len(b""), 0);
+        assert_eq!(plain_ascii_len(b"abc"), 3);
+        assert_eq!(plain_ascii_len(b"abc\""), 3);
+        assert_eq!(plain_ascii_len(b"Hello, world! 0123456789 ~"), 26);
+        for special in [b'"', b'\\', b'\n', 0x00, 0x1F, 0x7F, 0x80, 0xC3, 0xFF] {
+            for i in 0..20 {
+                let mut bytes = vec![b'a'; 20];
+                bytes[i] = special;
+                let expected = if special == 0x7F { 20 } else { i };
+                assert_eq!(plain_ascii_len(&bytes), expected, "{special:02x} at {i}");
+            }
+        }
+    }
+}
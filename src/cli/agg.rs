@@ -0,0 +1,195 @@
+//! Aggregations over the values selected by a query (`--agg 'max(..latency)'`).
+//!
+//! Aggregations are computed while the input is scanned, with a [Visitor]: no tree is built, so
+//! large inputs are aggregated without allocating their values.
+use pretty::format::{Color, FormatError, Formatter};
+use pretty::num;
+use pretty::path::Path;
+use pretty::query::Query;
+use pretty::value::escape;
+use pretty::visit::Visitor;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// An aggregation function.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Function {
+    /// Number of selected values.
+    Count,
+    /// Sum of the selected numbers.
+    Sum,
+    /// Minimum of the selected numbers.
+    Min,
+    /// Maximum of the selected numbers.
+    Max,
+}
+
+/// A function applied to the values selected by a query: `count(.items[*])`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Aggregation {
+    pub function: Function,
+    pub query: Query,
+    /// The aggregation as written, used as the name of its result.
+    pub expr: String,
+}
+
+impl FromStr for Aggregation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((name, query)) = s.strip_suffix(')').and_then(|s| s.split_once('(')) else {
+            return Err(format!(
+                "invalid aggregation {s} (expected FUNCTION(QUERY))"
+            ));
+        };
+        let function = match name.trim() {
+            "count" => Function::Count,
+            "sum" => Function::Sum,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            name => {
+                return Err(format!(
+                    "unknown function {name} (expected count, sum, min or max)"
+                ));
+            }
+        };
+        Ok(Aggregation {
+            function,
+            query: query.trim().parse()?,
+            expr: s.to_string(),
+        })
+    }
+}
+
+/// The result of an aggregation.
+#[derive(Clone, Debug, Default)]
+struct Accumulator {
+    count: usize,
+    sum: f64,
+    /// Minimum and maximum values, with their raw numbers.
+    min: Option<(f64, String)>,
+    max: Option<(f64, String)>,
+}
+
+impl Accumulator {
+    fn add(&mut self, raw: &str) {
+        self.count += 1;
+        let Ok(n) = raw.parse::<f64>() else {
+            return;
+        };
+        self.sum += n;
+        if self.min.as_ref().is_none_or(|(min, _)| n < *min) {
+            self.min = Some((n, raw.to_string()));
+        }
+        if self.max.as_ref().is_none_or(|(max, _)| n > *max) {
+            self.max = Some((n, raw.to_string()));
+        }
+    }
+
+    /// Returns the result of `function` as JSON (`null` for the minimum of no numbers).
+    fn result(&self, function: Function) -> String {
+        let raw = |value: &Option<(f64, String)>| {
+            value
+                .as_ref()
+                .map_or_else(|| "null".to_string(), |(_, raw)| raw.clone())
+        };
+        match function {
+            Function::Count => self.count.to_string(),
+            Function::Sum => num::format_f64(self.sum).unwrap_or_else(|| "null".to_string()),
+            Function::Min => raw(&self.min),
+            Function::Max => raw(&self.max),
+        }
+    }
+}
+
+/// Feeds the selected values to the accumulators.
+struct Aggregator<'a> {
+    aggregations: &'a [Aggregation],
+    accumulators: Vec<Accumulator>,
+}
+
+impl Visitor for Aggregator<'_> {
+    fn on_value_end(&mut self, raw: &str, _span: Range<usize>, path: &str) {
+        // The path of the root value is empty.
+        let path = if path.is_empty() {
+            Path::default()
+        } else {
+            match path.parse::<Path>() {
+                Ok(path) => path,
+                Err(_) => return,
+            }
+        };
+        for (aggregation, accumulator) in self.aggregations.iter().zip(&mut self.accumulators) {
+            if aggregation.query.matches(&path) {
+                accumulator.add(raw);
+            }
+        }
+    }
+}
+
+/// Discards the formatted output, only the visited values are used.
+struct Discard;
+
+impl fmt::Write for Discard {
+    fn write_str(&mut self, _: &str) -> fmt::Result {
+        Ok(())
+    }
+}
+
+/// Evaluates `aggregations` over the JSON input, returns their results as a compact JSON object,
+/// keyed by the aggregations expressions.
+pub fn evaluate(input: &[u8], aggregations: &[Aggregation]) -> Result<String, FormatError> {
+    let mut aggregator = Aggregator {
+        aggregations,
+        accumulators: vec![Accumulator::default(); aggregations.len()],
+    };
+    Formatter::new(input, Color::NoColor)
+        .with_visitor(&mut aggregator)
+        .format(&mut Discard)?;
+    let members = aggregations
+        .iter()
+        .zip(&aggregator.accumulators)
+        .map(|(aggregation, accumulator)| {
+            let result = accumulator.result(aggregation.function);
+            format!("{}:{result}", escape(&aggregation.expr))
+        })
+        .collect::<Vec<_>>();
+    Ok(format!("{{{}}}", members.join(",")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aggregation, Function, evaluate};
+
+    #[test]
+    fn parse_aggregations() {
+        let aggregation = "count(.items[*])".parse::<Aggregation>().unwrap();
+        assert_eq!(aggregation.function, Function::Count);
+        assert_eq!(aggregation.query, ".items[*]".parse().unwrap());
+        assert!("avg(.a)".parse::<Aggregation>().is_err());
+        assert!("count .a".parse::<Aggregation>().is_err());
+        assert!("count(a)".parse::<Aggregation>().is_err());
+    }
+
+    #[test]
+    fn evaluate_aggregations() {
+        let input = br#"{"items": [{"ms": 12}, {"ms": 3.5}, {"ms": "n/a"}, {"ms": 120}]}"#;
+        let aggregations = [
+            "count(.items[*])",
+            "sum(..ms)",
+            "min(..ms)",
+            "max(.items[1:].ms)",
+        ]
+        .map(|s| s.parse::<Aggregation>().unwrap());
+        assert_eq!(
+            evaluate(input, &aggregations).unwrap(),
+            r#"{"count(.items[*])":4,"sum(..ms)":135.5,"min(..ms)":3.5,"max(.items[1:].ms)":120}"#
+        );
+        let aggregations = ["min(.none)".parse::<Aggregation>().unwrap()];
+        assert_eq!(
+            evaluate(b"[]", &aggregations).unwrap(),
+            r#"{"min(.none)":null}"#
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Anonymization of personal data (`--anonymize emails,uuids,ips`), to share documents in bug
+//! reports.
+//!
+//! Matching strings (values and keys) are replaced by pseudonyms derived from an HMAC-SHA256 of
+//! the original with a key: a value is always replaced by the same pseudonym, so references
+//! between parts of the document are kept. Without `--anonymize-key`, the key is random and
+//! pseudonyms change at each run, so they can't be reversed by hashing guessed values.
+use crate::cli::hash::hmac_sha256;
+use pretty::value::{Node, Value, escape, unescape};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Kinds of strings replaced with [anonymize].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Pii {
+    pub emails: bool,
+    pub uuids: bool,
+    pub ips: bool,
+}
+
+impl FromStr for Pii {
+    type Err = String;
+
+    /// Parses a comma separated list of `emails`, `uuids` and `ips`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pii = Pii::default();
+        for kind in s.split(',') {
+            match kind.trim() {
+                "emails" => pii.emails = true,
+                "uuids" => pii.uuids = true,
+                "ips" => pii.ips = true,
+                kind => {
+                    return Err(format!(
+                        "invalid kind {kind}, expected emails, uuids or ips"
+                    ));
+                }
+            }
+        }
+        Ok(pii)
+    }
+}
+
+/// Returns a random key, different at each run.
+pub fn random_key() -> String {
+    use std::hash::{BuildHasher, RandomState};
+    let state = RandomState::new();
+    format!("{:016x}{:016x}", state.hash_one(0), state.hash_one(1))
+}
+
+/// Replaces recursively the strings of `node` (values and keys) of the `pii` kinds by their
+/// pseudonyms with `key`.
+pub fn anonymize(node: &mut Node, pii: Pii, key: &str) {
+    match &mut node.value {
+        Value::String(raw) => {
+            if let Some(pseudonym) = pseudonym(&unescape(raw), pii, key) {
+                *raw = escape(&pseudonym).into();
+            }
+        }
+        Value::Object(members) => {
+            for member in members {
+                if let Some(pseudonym) = pseudonym(&unescape(&member.key), pii, key) {
+                    member.key = escape(&pseudonym).into();
+                }
+                anonymize(&mut member.node, pii, key);
+            }
+        }
+        Value::Array(nodes) => nodes.iter_mut().for_each(|n| anonymize(n, pii, key)),
+        _ => {}
+    }
+}
+
+/// Returns the pseudonym of `s` if it's one of the `pii` kinds: emails are replaced by
+/// `user-<32 hex digits>@example.com`, UUIDs by v4 UUIDs, IPv4 and IPv6 by addresses of
+/// `fd00::/8`. Pseudonyms keep at least 120 bits of the digest so different values don't collide
+/// (IPv4 pseudonyms would only have 24 bits).
+///
+/// Emails and UUIDs are compared case-insensitively, IPs once normalized (`::0:1` is `::1`).
+fn pseudonym(s: &str, pii: Pii, key: &str) -> Option<String> {
+    let digest =
+        |kind: &str, s: &str| hmac_sha256(key.as_bytes(), format!("{kind}:{s}").as_bytes());
+    if pii.emails && is_email(s) {
+        let h = digest("email", &s.to_lowercase());
+        return Some(format!("user-{}@example.com", hex(&h[..16])));
+    }
+    if pii.uuids && is_uuid(s) {
+        let mut h = digest("uuid", &s.to_lowercase());
+        h[6] = (h[6] & 0x0f) | 0x40;
+        h[8] = (h[8] & 0x3f) | 0x80;
+        return Some(format!(
+            "{}-{}-{}-{}-{}",
+            hex(&h[..4]),
+            hex(&h[4..6]),
+            hex(&h[6..8]),
+            hex(&h[8..10]),
+            hex(&h[10..16])
+        ));
+    }
+    if pii.ips
+        && let Ok(ip) = s.parse::<IpAddr>()
+    {
+        let h = digest("ip", &ip.to_string());
+        let mut segments = [0u16; 8];
+        segments[0] = 0xfd00 | u16::from(h[0]);
+        for (i, segment) in segments.iter_mut().enumerate().skip(1) {
+            *segment = u16::from_be_bytes([h[2 * i - 1], h[2 * i]]);
+        }
+        return Some(std::net::Ipv6Addr::from(segments).to_string());
+    }
+    None
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Returns `true` if `s` looks like an email address: `local@domain.tld`, without whitespace.
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.split_once('@') else {
+        return false;
+    };
+    let valid = |c: char| !c.is_whitespace() && !c.is_control() && !"@<>()[],;:\"".contains(c);
+    !local.is_empty()
+        && local.chars().all(valid)
+        && domain.chars().all(valid)
+        && domain
+            .split_once('.')
+            .is_some_and(|(name, tld)| !name.is_empty() && !tld.is_empty())
+        && !domain.ends_with('.')
+}
+
+/// Returns `true` if `s` is a UUID: `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` hex digits.
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pii, anonymize, is_email, is_uuid};
+    use pretty::value;
+
+    #[test]
+    fn detect_pii() {
+        assert!(is_email("bob@example.org"));
+        assert!(is_email("bob.smith+tag@mail.example.org"));
+        assert!(!is_email("bob@localhost"));
+        assert!(!is_email("@example.org"));
+        assert!(!is_email("bob smith@example.org"));
+        assert!(!is_email("a@b@example.org"));
+        assert!(is_uuid("123e4567-E89B-12d3-a456-426614174000"));
+        assert!(!is_uuid("123e4567e89b12d3a456426614174000"));
+        assert!(!is_uuid("123e4567-e89b-12d3-a456-42661417400g"));
+        assert!("emails,ips".parse::<Pii>().is_ok());
+        assert!("emails,phones".parse::<Pii>().is_err());
+    }
+
+    #[test]
+    fn anonymize_documents() {
+        let input = br#"{
+            "users": [
+                {"id": "123e4567-e89b-12d3-a456-426614174000", "email": "Bob@Example.org"},
+                {"id": "00000000-0000-0000-0000-000000000001", "email": "eve@example.org"}
+            ],
+            "owner": "bob@example.org",
+            "roles": {"123e4567-e89b-12d3-a456-426614174000": "admin"},
+            "hosts": ["192.168.1.12", "2001:db8::1", "not an ip"]
+        }"#;
+        let pii = "emails,uuids,ips".parse::<Pii>().unwrap();
+        let mut root = value::parse(input).unwrap();
+        anonymize(&mut root, pii, "key");
+        let json = root.to_compact_string();
+        let doc = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+
+        let bob = doc["users"][0]["email"].as_str().unwrap();
+        assert!(
+            bob.starts_with("user-") && bob.ends_with("@example.com") && bob.len() == 49,
+            "{bob}"
+        );
+        assert_eq!(doc["owner"], bob);
+        assert_ne!(doc["users"][1]["email"], bob);
+
+        let id = doc["users"][0]["id"].as_str().unwrap();
+        assert_ne!(id, "123e4567-e89b-12d3-a456-426614174000");
+        assert!(is_uuid(id) && id.as_bytes()[14] == b'4', "{id}");
+        assert_eq!(doc["roles"][id], "admin");
+
+        let hosts = doc["hosts"].as_array().unwrap();
+        assert!(hosts[0].as_str().unwrap().starts_with("fd"));
+        assert_ne!(hosts[0], hosts[1]);
+        assert!(hosts[1].as_str().unwrap().starts_with("fd"));
+        assert_eq!(hosts[2], "not an ip");
+
+        // Same key, same pseudonyms; other key, other pseudonyms.
+        let mut again = value::parse(input).unwrap();
+        anonymize(&mut again, pii, "key");
+        assert_eq!(again.to_compact_string(), json);
+        let mut other = value::parse(input).unwrap();
+        anonymize(&mut other, pii, "other");
+        assert_ne!(other.to_compact_string(), json);
+
+        let mut root = value::parse(input).unwrap();
+        anonymize(&mut root, "ips".parse().unwrap(), "key");
+        assert!(root.to_compact_string().contains("eve@example.org"));
+    }
+}
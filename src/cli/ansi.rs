@@ -0,0 +1,68 @@
+/// Removes ANSI escape sequences from `input`.
+///
+/// Control Sequence Introducer sequences (`ESC [ ... m` for colors), Operating System Commands
+/// (`ESC ] ... BEL` or `ESC ] ... ESC \`, for hyperlinks) and two bytes escapes are removed, other
+/// bytes are kept as is.
+pub fn strip(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] != 0x1b {
+            out.push(input[i]);
+            i += 1;
+            continue;
+        }
+        i += 1;
+        match input.get(i) {
+            Some(b'[') => {
+                // Parameters and intermediate bytes, up to a final byte in 0x40..=0x7e.
+                i += 1;
+                while i < input.len() && !(0x40..=0x7e).contains(&input[i]) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            Some(b']') => {
+                i += 1;
+                while i < input.len() {
+                    if input[i] == 0x07 {
+                        i += 1;
+                        break;
+                    }
+                    if input[i] == 0x1b && input.get(i + 1) == Some(&b'\\') {
+                        i += 2;
+                        break;
+                    }
+                    i += 1;
+                }
+            }
+            Some(_) => {
+                // Other escapes: intermediate bytes in 0x20..=0x2f and a final byte.
+                while i < input.len() && (0x20..=0x2f).contains(&input[i]) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            None => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip;
+
+    #[test]
+    fn strip_escapes() {
+        assert_eq!(
+            strip(b"\x1b[1;39m{\x1b[0m\n  \x1b[1;34m\"a\"\x1b[0m: 1\n}"),
+            b"{\n  \"a\": 1\n}"
+        );
+        assert_eq!(
+            strip(b"\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x07 \x1b(B\xc3\xa9"),
+            "link é".as_bytes()
+        );
+        assert_eq!(strip(b"abc\x1b[1;3"), b"abc");
+    }
+}
@@ -0,0 +1,98 @@
+//! AWS log envelopes: CloudTrail and S3/SNS/SQS event records (`{"Records": [...]}`) and
+//! CloudWatch Logs events (`{"logEvents": [...]}`, `{"events": [...]}`).
+use pretty::value;
+use pretty::value::{Node, Value};
+
+/// Keys of the envelope arrays, in detection order.
+const ENVELOPES: [&str; 3] = ["Records", "logEvents", "events"];
+
+/// Keys of string members which can contain an embedded JSON document: CloudWatch Logs messages,
+/// SNS messages and SQS bodies.
+const EMBEDDED: [&str; 3] = ["message", "Message", "body"];
+
+/// Returns the events of an AWS envelope as compact JSON, with their embedded JSON messages
+/// parsed.
+pub fn events(input: &[u8]) -> Result<Vec<String>, String> {
+    let root = value::parse(input).map_err(|err| err.to_string())?;
+    let Value::Object(members) = root.value else {
+        return Err(envelope_error());
+    };
+    let Some(events) = ENVELOPES.iter().find_map(|envelope| {
+        members.iter().find_map(|m| match &m.node.value {
+            Value::Array(events) if value::unescape(&m.key) == *envelope => Some(events),
+            _ => None,
+        })
+    }) else {
+        return Err(envelope_error());
+    };
+    let events = events.iter().map(|event| {
+        let mut event = event.clone();
+        unwrap_messages(&mut event);
+        event.to_compact_string()
+    });
+    Ok(events.collect())
+}
+
+fn envelope_error() -> String {
+    "Invalid AWS events: expected an object with a Records, logEvents or events array".to_string()
+}
+
+/// Replaces recursively the embedded JSON messages of a node by their parsed value.
+fn unwrap_messages(node: &mut Node) {
+    match &mut node.value {
+        Value::Object(members) => {
+            for member in members {
+                if let Value::String(raw) = &member.node.value
+                    && EMBEDDED.contains(&value::unescape(&member.key).as_str())
+                    && let Some(embedded) = parse_embedded(raw)
+                {
+                    member.node = embedded;
+                }
+                unwrap_messages(&mut member.node);
+            }
+        }
+        Value::Array(nodes) => nodes.iter_mut().for_each(unwrap_messages),
+        _ => {}
+    }
+}
+
+/// Parses a raw string value containing a JSON object or array.
+fn parse_embedded(raw: &str) -> Option<Node<'static>> {
+    let text = value::unescape(raw);
+    if !matches!(text.trim_start().as_bytes().first(), Some(b'{' | b'[')) {
+        return None;
+    }
+    value::parse(text.as_bytes()).ok().map(Node::into_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::events;
+
+    #[test]
+    fn unwrap_cloudwatch_events() {
+        let input = br#"{"messageType": "DATA_MESSAGE", "logGroup": "/aws/lambda/f", "logEvents": [
+            {"id": "1", "timestamp": 1700000000000, "message": "{\"level\":\"info\",\"data\":\"{\\\"a\\\":1}\"}"},
+            {"id": "2", "timestamp": 1700000000001, "message": "START RequestId: 42"}
+        ]}"#;
+        assert_eq!(
+            events(input).unwrap(),
+            vec![
+                r#"{"id":"1","timestamp":1700000000000,"message":{"level":"info","data":"{\"a\":1}"}}"#,
+                r#"{"id":"2","timestamp":1700000000001,"message":"START RequestId: 42"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn unwrap_records() {
+        let input =
+            br#"{"Records": [{"eventSource": "aws:sqs", "body": "{\"Message\":\"[1,2]\"}"}]}"#;
+        assert_eq!(
+            events(input).unwrap(),
+            vec![r#"{"eventSource":"aws:sqs","body":{"Message":[1,2]}}"#]
+        );
+        assert!(events(br#"{"records": []}"#).is_err());
+        assert!(events(b"[]").is_err());
+    }
+}
@@ -0,0 +1,76 @@
+/// Decodes base64, with the standard (`+/`) or the URL-safe (`-_`) alphabet. Padding is
+/// optional and ASCII whitespaces (line wraps) are ignored.
+///
+/// Returns `None` if the input is not base64.
+pub fn decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    let mut padding = 0;
+    for &b in input.iter().filter(|b| !b.is_ascii_whitespace()) {
+        if b == b'=' {
+            padding += 1;
+            continue;
+        }
+        if padding > 0 {
+            return None;
+        }
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    // A last group of 1 char (6 bits) can't encode a byte.
+    if bits == 6 || padding > 2 {
+        return None;
+    }
+    Some(decoded)
+}
+
+/// Returns the decoded input if it looks like base64-wrapped JSON: base64 that decodes to an
+/// object or an array.
+pub fn decode_json(input: &[u8]) -> Option<Vec<u8>> {
+    let input = input.trim_ascii();
+    // Fast path for JSON objects and arrays, which can't be base64.
+    if input.len() < 4 || matches!(input[0], b'{' | b'[') {
+        return None;
+    }
+    let decoded = decode(input)?;
+    let is_json = matches!(decoded.trim_ascii_start().first(), Some(b'{' | b'['));
+    is_json.then_some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_json};
+
+    #[test]
+    fn decode_base64() {
+        assert_eq!(decode(b"eyJhIjoxfQ==").unwrap(), br#"{"a":1}"#);
+        assert_eq!(decode(b"eyJhIjoxfQ").unwrap(), br#"{"a":1}"#);
+        assert_eq!(decode(b"-_8\n").unwrap(), [0xfb, 0xff]);
+        assert_eq!(decode(b"+/8=").unwrap(), [0xfb, 0xff]);
+        assert!(decode(b"abcde").is_none());
+        assert!(decode(b"ab=c").is_none());
+        assert!(decode(b"a.b").is_none());
+    }
+
+    #[test]
+    fn detect_base64_json() {
+        assert_eq!(decode_json(b" eyJhIjoxfQ==\n").unwrap(), br#"{"a":1}"#);
+        assert_eq!(decode_json(b"WzFd").unwrap(), b"[1]");
+        assert!(decode_json(br#"{"a":1}"#).is_none());
+        assert!(decode_json(b"1234").is_none());
+        assert!(decode_json(b"aGVsbG8=").is_none());
+    }
+}
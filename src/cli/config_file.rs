@@ -0,0 +1,269 @@
+//! Options read from configuration files: the user config (`~/.prettyjsonrc`) and the project
+//! config (the first `.prettyjsonrc` found in the current directory or its ancestors).
+//!
+//! A config file has an option per line, optionally followed by its value
+//! (`--preset diff-friendly`). Empty lines and lines starting with `#` are ignored. Options of
+//! the project config take precedence over the user config, and options of the command line
+//! take precedence over both: they're parsed after the options of the config files.
+//!
+//! A line `name = "options"` defines a shortcut: `@name` on the command line is replaced by its
+//! options, `levels = "--ndjson --group-by .level --count"` being invoked as `pretty @levels
+//! app.log`. Shortcuts of the project config replace the shortcuts of the user config with the
+//! same name.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the config files.
+pub const CONFIG_FILE_NAME: &str = ".prettyjsonrc";
+
+/// Options and shortcuts of config files.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct ConfigFile {
+    /// Options, as command line arguments.
+    pub args: Vec<String>,
+    /// Shortcuts, by name, with their options.
+    pub shortcuts: Vec<(String, Vec<String>)>,
+}
+
+impl ConfigFile {
+    /// Adds the options and shortcuts of `other`, which take precedence.
+    fn extend(&mut self, other: ConfigFile) {
+        self.args.extend(other.args);
+        for (name, args) in other.shortcuts {
+            self.shortcuts.retain(|(n, _)| *n != name);
+            self.shortcuts.push((name, args));
+        }
+    }
+
+    /// Replaces the `@name` arguments of `args` by the options of their shortcuts.
+    pub fn expand_shortcuts(&self, args: Vec<String>) -> Result<Vec<String>, String> {
+        let mut expanded = Vec::with_capacity(args.len());
+        for arg in args {
+            let Some(name) = arg.strip_prefix('@') else {
+                expanded.push(arg);
+                continue;
+            };
+            let Some((_, options)) = self.shortcuts.iter().find(|(n, _)| n == name) else {
+                return Err(format!("Unknown shortcut @{name}"));
+            };
+            expanded.extend(options.iter().cloned());
+        }
+        Ok(expanded)
+    }
+}
+
+/// Returns the options and shortcuts of the user and project config files, the options in order
+/// of precedence (the user options first).
+pub fn config_args() -> Result<ConfigFile, String> {
+    let user = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(CONFIG_FILE_NAME));
+    let user = user.filter(|path| path.is_file());
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|dir| find_project_config(&dir));
+    // In a directory of the home directory, the user config is also found as the project config.
+    let project = project.filter(|path| Some(path) != user.as_ref());
+
+    let mut config = ConfigFile::default();
+    for path in user.iter().chain(&project) {
+        config.extend(read_config(path)?);
+    }
+    Ok(config)
+}
+
+/// Returns the first config file in `dir` or its ancestors.
+pub fn find_project_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Reads the options and shortcuts of a config file.
+fn read_config(path: &Path) -> Result<ConfigFile, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Error reading config file '{}': {err}", path.display()))?;
+    parse_config(&text).map_err(|err| format!("{}:{err}", path.display()))
+}
+
+/// Parses the options of a config file, as command line arguments, and its shortcuts.
+pub fn parse_config(text: &str) -> Result<ConfigFile, String> {
+    let mut config = ConfigFile::default();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((name, options)) = line.split_once('=')
+            && is_shortcut_name(name.trim())
+        {
+            let options =
+                parse_shortcut(options.trim()).map_err(|err| format!("{}: {err}", i + 1))?;
+            config.shortcuts.retain(|(n, _)| n != name.trim());
+            config.shortcuts.push((name.trim().to_string(), options));
+            continue;
+        }
+        if !line.starts_with("--") {
+            return Err(format!("{}: expected an option, found {line}", i + 1));
+        }
+        match line.split_once(char::is_whitespace) {
+            Some((option, value)) => {
+                config.args.push(option.to_string());
+                config.args.push(value.trim().to_string());
+            }
+            None => config.args.push(line.to_string()),
+        }
+    }
+    Ok(config)
+}
+
+/// Returns `true` if `name` is a valid shortcut name: letters, digits, `-` and `_`.
+fn is_shortcut_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parses the options of a shortcut, a double quoted string: options are separated by
+/// whitespace, single quotes group an argument with whitespace (`--record-template '{n} {.a}'`).
+fn parse_shortcut(value: &str) -> Result<Vec<String>, String> {
+    let Some(value) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    else {
+        return Err(format!("expected quoted options, found {value}"));
+    };
+    let mut args = Vec::new();
+    let mut arg = None::<String>;
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '\'' => {
+                quoted = !quoted;
+                arg.get_or_insert_default();
+            }
+            c if c.is_whitespace() && !quoted => args.extend(arg.take()),
+            c => arg.get_or_insert_default().push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("unclosed quote in \"{value}\""));
+    }
+    args.extend(arg);
+    if args.is_empty() {
+        return Err("empty shortcut".to_string());
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CONFIG_FILE_NAME, ConfigFile, find_project_config, parse_config, read_config};
+    use crate::cli::options::parse_arg_list;
+    use std::fs;
+
+    #[test]
+    fn parse_config_files() {
+        let text = "# Team settings\n\n--sort-keys\n  --preset   diff-friendly \n--set .a=1 2\n";
+        assert_eq!(
+            parse_config(text).unwrap().args,
+            [
+                "--sort-keys",
+                "--preset",
+                "diff-friendly",
+                "--set",
+                ".a=1 2"
+            ]
+        );
+        assert_eq!(
+            parse_config("--top 3\nfile.json").unwrap_err(),
+            "2: expected an option, found file.json"
+        );
+    }
+
+    #[test]
+    fn find_and_apply_config_files() {
+        let dir = std::env::temp_dir().join(format!("pretty-config-{}", std::process::id()));
+        let home = dir.join("home");
+        let project = home.join("repo");
+        let nested = project.join("src/data");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(home.join(CONFIG_FILE_NAME), "--top 5\n--no-color\n").unwrap();
+        fs::write(project.join(CONFIG_FILE_NAME), "--top 20\n--sort-keys\n").unwrap();
+
+        let found = find_project_config(&nested).unwrap();
+        assert_eq!(found, project.join(CONFIG_FILE_NAME));
+        assert_eq!(
+            find_project_config(&home).unwrap(),
+            home.join(CONFIG_FILE_NAME)
+        );
+
+        // The project config takes precedence over the user config, and the command line over
+        // both.
+        let config_args = || {
+            let mut config = read_config(&home.join(CONFIG_FILE_NAME)).unwrap();
+            config.extend(read_config(&found).unwrap());
+            config.args
+        };
+        let config = parse_arg_list(config_args().into_iter().chain(["a.json".to_string()]));
+        let config = config.unwrap();
+        assert_eq!(config.top, 20);
+        assert!(config.sort_keys && !config.with_color);
+        let cli = ["--top", "1", "a.json"].map(String::from);
+        let config = parse_arg_list(config_args().into_iter().chain(cli)).unwrap();
+        assert_eq!(config.top, 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn expand_shortcuts() {
+        let text = r#"
+levels = "--ndjson --group-by .level --count"
+last = "--ndjson --record-template '--- {n} {.msg} ---' --tail 3"
+--sort-keys
+"#;
+        let mut config = parse_config(text).unwrap();
+        assert_eq!(config.args, ["--sort-keys"]);
+        config.extend(parse_config(r#"last="--ndjson --tail 5""#).unwrap());
+        let args = ["@levels", "app.log"].map(String::from).to_vec();
+        let expanded = config.expand_shortcuts(args).unwrap();
+        assert_eq!(
+            expanded,
+            ["--ndjson", "--group-by", ".level", "--count", "app.log"]
+        );
+        let options = parse_arg_list(expanded.into_iter()).unwrap();
+        assert!(options.ndjson && options.group_by.is_some());
+        let args = ["@last", "app.log"].map(String::from).to_vec();
+        let options = parse_arg_list(config.expand_shortcuts(args).unwrap().into_iter()).unwrap();
+        assert_eq!(options.tail, Some(5));
+
+        let last = parse_config(text).unwrap().shortcuts[1].1.clone();
+        assert_eq!(
+            last,
+            [
+                "--ndjson",
+                "--record-template",
+                "--- {n} {.msg} ---",
+                "--tail",
+                "3"
+            ]
+        );
+        let options = parse_arg_list(last.into_iter().chain(["app.log".to_string()])).unwrap();
+        assert!(options.record_template.is_some());
+        assert_eq!(options.tail, Some(3));
+        let args = vec!["@none".to_string()];
+        assert_eq!(
+            ConfigFile::default().expand_shortcuts(args).unwrap_err(),
+            "Unknown shortcut @none"
+        );
+        assert_eq!(
+            parse_config("a = --top 3").unwrap_err(),
+            "1: expected quoted options, found --top 3"
+        );
+        assert_eq!(
+            parse_config("a = \"--tail 'b\"").unwrap_err(),
+            "1: unclosed quote in \"--tail 'b\""
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use pretty::format::FormatError;
+use pretty::token;
+use std::fmt::Write;
+
+/// Maximum number of chars of a token text in the dump.
+const MAX_TEXT: usize = 40;
+
+/// Number of bytes displayed before and after an error offset.
+const ERROR_CONTEXT: usize = 8;
+
+/// Writes each token of `input` on its own line, with its byte range, nesting level and kind.
+///
+/// If the input is invalid, tokens are dumped up to the error, followed by a hex dump of the bytes
+/// around the error offset, and the error is returned.
+pub fn dump(input: &[u8], out: &mut String) -> Result<(), FormatError> {
+    let result = token::tokenize(input, |token| {
+        let text = String::from_utf8_lossy(&input[token.span.clone()]);
+        // Tokens have been validated, they can't contain control chars.
+        let mut text = text.to_string();
+        if text.chars().count() > MAX_TEXT {
+            text = text.chars().take(MAX_TEXT - 1).collect::<String>() + "…";
+        }
+        let range = format!("{}..{}", token.span.start, token.span.end);
+        _ = writeln!(
+            out,
+            "{range:<16} {:>3}  {:<15}  {text}",
+            token.level, token.kind
+        );
+    });
+    if let Err(err) = &result {
+        let offset = err.offset().unwrap_or(input.len());
+        write_hex_context(input, offset, out);
+    }
+    result
+}
+
+/// Writes the bytes around `offset` in hex (and as text), the byte at `offset` is bracketed.
+fn write_hex_context(input: &[u8], offset: usize, out: &mut String) {
+    let start = offset.saturating_sub(ERROR_CONTEXT);
+    let end = (offset + ERROR_CONTEXT + 1).min(input.len());
+    let mut hex = String::new();
+    let mut text = String::new();
+    for (i, &b) in input.iter().enumerate().take(end).skip(start) {
+        if i == offset {
+            _ = write!(hex, "[{b:02x}] ");
+        } else {
+            _ = write!(hex, "{b:02x} ");
+        }
+        text.push(if b.is_ascii_graphic() || b == b' ' {
+            b as char
+        } else {
+            '.'
+        });
+    }
+    _ = writeln!(out, "bytes {start}..{end}: {}  |{text}|", hex.trim_end());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dump;
+
+    #[test]
+    fn dump_tokens() {
+        let mut out = String::new();
+        dump(b"{\"a\": [1, \"x\\ny\"]}", &mut out).unwrap();
+        assert_eq!(
+            out,
+            "0..1               0  begin-object     {\n\
+             1..4               1  key              \"a\"\n\
+             4..5               1  name-separator   :\n\
+             6..7               1  begin-array      [\n\
+             7..8               2  number           1\n\
+             8..9               2  value-separator  ,\n\
+             10..16             2  string           \"x\\ny\"\n\
+             16..17             1  end-array        ]\n\
+             17..18             0  end-object       }\n"
+        );
+    }
+
+    #[test]
+    fn dump_error_context() {
+        let mut out = String::new();
+        let err = dump(b"[1,\t]", &mut out).unwrap_err();
+        assert_eq!(err.to_string(), "invalid byte <5d> at offset 4");
+        assert!(out.ends_with("bytes 0..5: 5b 31 2c 09 [5d]  |[1,.]|\n"));
+    }
+}
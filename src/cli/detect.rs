@@ -0,0 +1,93 @@
+//! Detection of the format of the input from its first bytes (`--from auto`).
+//!
+//! JSON values, XML documents, JWTs, base64-wrapped JSON and URL-encoded forms are recognized.
+//! MessagePack and UTF-16 inputs are recognized too, but can't be decoded: they're reported as
+//! errors instead of invalid JSON. Anything else is parsed as JSON.
+use crate::cli::InputFormat;
+use crate::cli::base64;
+
+/// Returns the format of `input`, or an error if it's a binary format that can't be decoded.
+pub fn detect(input: &[u8]) -> Result<InputFormat, String> {
+    if input.starts_with(&[0xfe, 0xff]) || input.starts_with(&[0xff, 0xfe]) {
+        return Err("UTF-16 input is not supported (convert it to UTF-8)".to_string());
+    }
+    let text = input.strip_prefix(b"\xef\xbb\xbf").unwrap_or(input);
+    let text = text.trim_ascii();
+    let Some(&first) = text.first() else {
+        return Ok(InputFormat::Json);
+    };
+    let format = match first {
+        b'{' | b'[' | b'"' | b'-' | b'/' | b'0'..=b'9' => InputFormat::Json,
+        b'<' => InputFormat::Xml,
+        // Fixmap, fixarray, array 16/32 and map 16/32 markers, which don't start UTF-8 text.
+        0x80..=0x9f | 0xdc..=0xdf if std::str::from_utf8(text).is_err() => {
+            return Err("MessagePack input is not supported".to_string());
+        }
+        _ if is_jwt(text) => InputFormat::Jwt,
+        _ if base64::decode_json(text).is_some() => InputFormat::Base64,
+        _ if is_form(text) => InputFormat::Form,
+        _ => InputFormat::Json,
+    };
+    Ok(format)
+}
+
+/// Returns `true` if `text` looks like a JWT: 3 base64url parts, the header being a JSON object
+/// (`eyJ` is the base64 of `{"`).
+fn is_jwt(text: &[u8]) -> bool {
+    let text = text.strip_prefix(b"Bearer ").unwrap_or(text);
+    text.starts_with(b"eyJ")
+        && text.iter().filter(|&&b| b == b'.').count() == 2
+        && text
+            .iter()
+            .all(|&b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'='))
+}
+
+/// Returns `true` if `text` looks like a URL-encoded form, a query string or an URL with a query
+/// string: `name=value` pairs separated by `&`, without whitespace.
+fn is_form(text: &[u8]) -> bool {
+    let query = match text.iter().position(|&b| b == b'?') {
+        Some(i) => &text[i + 1..],
+        None => text,
+    };
+    !query.is_empty()
+        && !text.iter().any(u8::is_ascii_whitespace)
+        && query
+            .split(|&b| b == b'&')
+            .all(|pair| pair.iter().position(|&b| b == b'=').is_some_and(|i| i > 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::detect;
+    use crate::cli::InputFormat;
+
+    #[test]
+    fn detect_input_formats() {
+        let cases: [(&[u8], InputFormat); 12] = [
+            (br#"{"a": 1}"#, InputFormat::Json),
+            (b"\xef\xbb\xbf [1, 2]", InputFormat::Json),
+            (b"  -1.5\n", InputFormat::Json),
+            (b"", InputFormat::Json),
+            (b"true", InputFormat::Json),
+            (b"<?xml version=\"1.0\"?><a/>", InputFormat::Xml),
+            (b"<a><b>1</b></a>", InputFormat::Xml),
+            (
+                b"eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxIn0.c2ln",
+                InputFormat::Jwt,
+            ),
+            (b"eyJhIjoxfQ==\n", InputFormat::Base64),
+            (b"a=1&b[0]=x&c", InputFormat::Json),
+            (b"a=1&b[0]=x&c[d]=y", InputFormat::Form),
+            (b"https://a.com/search?q=json&page=2", InputFormat::Form),
+        ];
+        for (input, format) in cases {
+            let input_text = String::from_utf8_lossy(input);
+            assert_eq!(detect(input).unwrap(), format, "{input_text}");
+        }
+        assert_eq!(
+            detect(b"\x82\xa1a\x01\xa1b\xc3").unwrap_err(),
+            "MessagePack input is not supported"
+        );
+        assert!(detect(b"\xff\xfe{\x00}\x00").is_err());
+    }
+}
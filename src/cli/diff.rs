@@ -0,0 +1,335 @@
+use std::fmt::Write;
+
+/// A line edit operation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Edit {
+    /// Line present in both texts (old index, new index).
+    Equal(usize, usize),
+    /// Line removed from the old text (old index).
+    Delete(usize),
+    /// Line inserted from the new text (new index).
+    Insert(usize),
+}
+
+/// Number of unchanged lines displayed around changes.
+const CONTEXT: usize = 3;
+
+/// Maximum number of edits searched from each end for a middle snake. Beyond, the lines are
+/// reported as deleted and inserted: the diff is not the shortest, but it's computed in bounded
+/// time.
+const MAX_SEARCH: isize = 1024;
+
+/// Returns a unified diff between `old` and `new`, with `old_name` and `new_name` as file headers.
+/// Returns an empty string if texts are equal.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str, color: bool) -> String {
+    let old_lines = old.lines().collect::<Vec<_>>();
+    let new_lines = new.lines().collect::<Vec<_>>();
+    let edits = diff_lines(&old_lines, &new_lines);
+    if edits.iter().all(|e| matches!(e, Edit::Equal(..))) {
+        return String::new();
+    }
+
+    let (red, green, cyan, reset) = if color {
+        ("\x1b[31m", "\x1b[32m", "\x1b[36m", "\x1b[0m")
+    } else {
+        ("", "", "", "")
+    };
+
+    let mut out = String::new();
+    _ = writeln!(out, "--- {old_name}");
+    _ = writeln!(out, "+++ {new_name}");
+
+    for hunk in hunks(&edits) {
+        let hunk = &edits[hunk.0..hunk.1];
+        let (old_start, new_start) = start_of(hunk);
+        let old_count = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Insert(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Delete(_)))
+            .count();
+        _ = writeln!(
+            out,
+            "{cyan}@@ -{},{old_count} +{},{new_count} @@{reset}",
+            old_start + 1,
+            new_start + 1
+        );
+        for edit in hunk {
+            match *edit {
+                Edit::Equal(i, _) => _ = writeln!(out, " {}", old_lines[i]),
+                Edit::Delete(i) => _ = writeln!(out, "{red}-{}{reset}", old_lines[i]),
+                Edit::Insert(j) => _ = writeln!(out, "{green}+{}{reset}", new_lines[j]),
+            }
+        }
+    }
+    out
+}
+
+/// Returns the (old, new) start line indexes of a hunk.
+fn start_of(hunk: &[Edit]) -> (usize, usize) {
+    let mut old = None;
+    let mut new = None;
+    for edit in hunk {
+        match *edit {
+            Edit::Equal(i, j) => {
+                old.get_or_insert(i);
+                new.get_or_insert(j);
+            }
+            Edit::Delete(i) => _ = old.get_or_insert(i),
+            Edit::Insert(j) => _ = new.get_or_insert(j),
+        }
+    }
+    // For a pure insertion (or deletion), the start is the line before (0 if at the beginning).
+    (old.unwrap_or(0), new.unwrap_or(0))
+}
+
+/// Groups edits in hunks of changes with `CONTEXT` lines around, returns ranges of edits.
+fn hunks(edits: &[Edit]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (i, edit) in edits.iter().enumerate() {
+        if matches!(edit, Edit::Equal(..)) {
+            continue;
+        }
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+/// Computes the shortest edit script between two lists of lines, with the linear space version
+/// of the Myers algorithm (<http://www.xmailserver.org/diff2.pdf>, section 4b).
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    diff_range(old, new, 0, 0, &mut edits);
+    edits
+}
+
+/// Pushes the edits between `old` and `new`, lines starting at `x` in the old text and at `y` in
+/// the new text.
+fn diff_range(old: &[&str], new: &[&str], x: usize, y: usize, edits: &mut Vec<Edit>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    edits.extend((0..prefix).map(|i| Edit::Equal(x + i, y + i)));
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let (x, y) = (x + prefix, y + prefix);
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    match (old, new) {
+        ([], _) => edits.extend((0..new.len()).map(|j| Edit::Insert(y + j))),
+        (_, []) => edits.extend((0..old.len()).map(|i| Edit::Delete(x + i))),
+        // A single line is either kept or deleted, without searching for a snake.
+        ([line], _) => match new.iter().position(|l| l == line) {
+            Some(j) => {
+                edits.extend((0..j).map(|j| Edit::Insert(y + j)));
+                edits.push(Edit::Equal(x, y + j));
+                edits.extend((j + 1..new.len()).map(|j| Edit::Insert(y + j)));
+            }
+            None => {
+                edits.push(Edit::Delete(x));
+                edits.extend((0..new.len()).map(|j| Edit::Insert(y + j)));
+            }
+        },
+        (_, [line]) => match old.iter().position(|l| l == line) {
+            Some(i) => {
+                edits.extend((0..i).map(|i| Edit::Delete(x + i)));
+                edits.push(Edit::Equal(x + i, y));
+                edits.extend((i + 1..old.len()).map(|i| Edit::Delete(x + i)));
+            }
+            None => {
+                edits.extend((0..old.len()).map(|i| Edit::Delete(x + i)));
+                edits.push(Edit::Insert(y));
+            }
+        },
+        _ => {
+            let Some((start, end)) = middle_snake(old, new) else {
+                edits.extend((0..old.len()).map(|i| Edit::Delete(x + i)));
+                edits.extend((0..new.len()).map(|j| Edit::Insert(y + j)));
+                return push_suffix(old, new, x, y, suffix, edits);
+            };
+            // The edits before the middle snake, the snake, and the edits after it.
+            diff_range(&old[..start.0], &new[..start.1], x, y, edits);
+            edits.extend(
+                (0..end.0 - start.0).map(|i| Edit::Equal(x + start.0 + i, y + start.1 + i)),
+            );
+            diff_range(&old[end.0..], &new[end.1..], x + end.0, y + end.1, edits);
+        }
+    }
+
+    push_suffix(old, new, x, y, suffix, edits);
+}
+
+/// Pushes the `suffix` equal lines following `old` and `new`.
+fn push_suffix(
+    old: &[&str],
+    new: &[&str],
+    x: usize,
+    y: usize,
+    suffix: usize,
+    edits: &mut Vec<Edit>,
+) {
+    let (x, y) = (x + old.len(), y + new.len());
+    edits.extend((0..suffix).map(|i| Edit::Equal(x + i, y + i)));
+}
+
+/// Returns the start and the end `(x, y)` of the middle snake of the shortest edit script between
+/// two non-empty lists of lines: the diagonal (equal lines) crossed halfway of the edit script.
+///
+/// Edit paths are searched from both ends at the same time until they overlap, only keeping the
+/// furthest reaching paths of the current edit distance: memory is linear. Returns `None` if the
+/// paths don't overlap after [MAX_SEARCH] edits.
+fn middle_snake(old: &[&str], new: &[&str]) -> Option<((usize, usize), (usize, usize))> {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    // Furthest x of each diagonal k (x - y), forwards and backwards (from the ends of the texts).
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=max.min(MAX_SEARCH) {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let start = (x, y);
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            let kb = delta - k;
+            if odd && (-(d - 1)..=d - 1).contains(&kb) && x + backward[at(kb)] >= n {
+                return Some((
+                    (start.0 as usize, start.1 as usize),
+                    (x as usize, y as usize),
+                ));
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let end = (n - x, m - y);
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            let kf = delta - k;
+            if !odd && (-d..=d).contains(&kf) && x + forward[at(kf)] >= n {
+                return Some((
+                    ((n - x) as usize, (m - y) as usize),
+                    (end.0 as usize, end.1 as usize),
+                ));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_equal_texts() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "a", "b", false), "");
+    }
+
+    #[test]
+    fn diff_lines_edits() {
+        let edits = diff_lines(&["a", "b", "c"], &["a", "c", "d"]);
+        assert_eq!(
+            edits,
+            vec![
+                Edit::Equal(0, 0),
+                Edit::Delete(1),
+                Edit::Equal(2, 1),
+                Edit::Insert(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_lines_shortest() {
+        let old = ["a", "b", "c", "a", "b", "b", "a"];
+        let new = ["c", "b", "a", "b", "a", "c"];
+        let edits = diff_lines(&old, &new);
+        // 5 edits, the length of the shortest edit script of the paper.
+        let changes = edits.iter().filter(|e| !matches!(e, Edit::Equal(..)));
+        assert_eq!(changes.count(), 5);
+        // Applying the edits to the old lines gives the new lines.
+        let mut applied = Vec::new();
+        for edit in &edits {
+            match *edit {
+                Edit::Equal(i, j) => {
+                    assert_eq!(old[i], new[j]);
+                    applied.push(old[i]);
+                }
+                Edit::Insert(j) => applied.push(new[j]),
+                Edit::Delete(_) => {}
+            }
+        }
+        assert_eq!(applied, new);
+
+        // A minified document against its pretty printed form.
+        let old = ["[1,2,3]"];
+        let new = (0..20_000).map(|_| "  1,").collect::<Vec<_>>();
+        assert_eq!(diff_lines(&old, &new).len(), 20_001);
+
+        // Too many edits: lines are deleted and inserted.
+        let old = (0..5_000).map(|i| format!("{i}")).collect::<Vec<_>>();
+        let new = (0..5_000).map(|i| format!("{i}.0")).collect::<Vec<_>>();
+        let old = old.iter().map(String::as_str).collect::<Vec<_>>();
+        let new = new.iter().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(diff_lines(&old, &new).len(), 10_000);
+    }
+
+    #[test]
+    fn diff_unified() {
+        let old = "{\"a\":1,\n\"b\":2}\n";
+        let new = "{\n  \"a\": 1,\n  \"b\": 2\n}\n";
+        assert_eq!(
+            unified_diff(old, new, "a.json", "a.json", false),
+            "--- a.json\n\
+             +++ a.json\n\
+             @@ -1,2 +1,4 @@\n\
+             -{\"a\":1,\n\
+             -\"b\":2}\n\
+             +{\n\
+             +  \"a\": 1,\n\
+             +  \"b\": 2\n\
+             +}\n"
+        );
+    }
+
+    #[test]
+    fn diff_hunks_with_context() {
+        let old = (0..20).map(|i| format!("{i}\n")).collect::<String>();
+        let new = old.replace("10\n", "ten\n");
+        assert_eq!(
+            unified_diff(&old, &new, "a", "b", false),
+            "--- a\n+++ b\n@@ -8,7 +8,7 @@\n 7\n 8\n 9\n-10\n+ten\n 11\n 12\n 13\n"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Formatting settings of a file from its `.editorconfig` files (<https://editorconfig.org>).
+//!
+//! The `.editorconfig` files of the file directory and its ancestors are read, up to a file with
+//! `root = true`. Settings of the sections whose glob matches the file are applied, the closest
+//! files and the last sections taking precedence.
+use crate::cli::files::{match_components, wildcard_match};
+use pretty::format::{Indent, LineEnding};
+use std::fs;
+use std::path::Path;
+
+/// The settings of `.editorconfig` used to format JSON files.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Settings {
+    pub indent: Option<Indent>,
+    pub line_ending: Option<LineEnding>,
+    pub final_newline: Option<bool>,
+}
+
+/// Returns the settings of `file` from the `.editorconfig` files of its ancestors.
+pub fn settings(file: &Path) -> Settings {
+    let file = std::path::absolute(file).unwrap_or_else(|_| file.to_path_buf());
+    let mut configs = Vec::new();
+    for dir in file.ancestors().skip(1) {
+        let Ok(text) = fs::read_to_string(dir.join(".editorconfig")) else {
+            continue;
+        };
+        let root = is_root(&text);
+        configs.push((dir.to_path_buf(), text));
+        if root {
+            break;
+        }
+    }
+
+    // Properties are applied from the farthest file to the closest one.
+    let mut style = None;
+    let mut size = None;
+    let mut settings = Settings::default();
+    for (dir, text) in configs.iter().rev() {
+        let Ok(relative) = file.strip_prefix(dir) else {
+            continue;
+        };
+        for (key, value) in properties(text, relative) {
+            match key.as_str() {
+                "indent_style" => style = Some(value),
+                "indent_size" => size = Some(value),
+                "end_of_line" => {
+                    settings.line_ending = match value.as_str() {
+                        "lf" => Some(LineEnding::Lf),
+                        "crlf" => Some(LineEnding::Crlf),
+                        _ => settings.line_ending,
+                    }
+                }
+                "insert_final_newline" => {
+                    settings.final_newline = match value.as_str() {
+                        "true" => Some(true),
+                        "false" => Some(false),
+                        _ => settings.final_newline,
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    settings.indent = match (style.as_deref(), size.as_deref()) {
+        (Some("tab"), _) => Some(Indent::Tab),
+        (Some("space") | None, Some(size)) => size.parse::<usize>().ok().map(Indent::Spaces),
+        _ => None,
+    };
+    settings
+}
+
+/// Returns `true` if a `.editorconfig` file has `root = true` in its preamble.
+fn is_root(text: &str) -> bool {
+    text.lines()
+        .map(str::trim)
+        .take_while(|line| !line.starts_with('['))
+        .filter_map(|line| line.split_once('='))
+        .any(|(key, value)| {
+            key.trim().eq_ignore_ascii_case("root") && value.trim().eq_ignore_ascii_case("true")
+        })
+}
+
+/// Returns the properties (lowercased keys and values) of the sections of a `.editorconfig` file
+/// matching `relative`, the path of a file relative to the directory of the `.editorconfig`.
+fn properties(text: &str, relative: &Path) -> Vec<(String, String)> {
+    let components = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let mut properties = Vec::new();
+    let mut matched = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            matched = section_matches(section, &components);
+            continue;
+        }
+        if matched && let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim().to_ascii_lowercase();
+            properties.push((key, value));
+        }
+    }
+    properties
+}
+
+/// Returns `true` if the glob of a section matches a path. Globs without `/` match file names in
+/// any directory, other globs match paths relative to the directory of the `.editorconfig`.
+fn section_matches(glob: &str, components: &[String]) -> bool {
+    expand_braces(glob).iter().any(|glob| {
+        if glob.contains('/') {
+            let pattern = glob
+                .trim_start_matches('/')
+                .split('/')
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            match_components(&pattern, components)
+        } else {
+            components
+                .last()
+                .is_some_and(|name| wildcard_match(glob, name))
+        }
+    })
+}
+
+/// Expands the alternatives of a glob: `*.{json,jsonc}` is `*.json` and `*.jsonc`.
+fn expand_braces(glob: &str) -> Vec<String> {
+    let Some(open) = glob.find('{') else {
+        return vec![glob.to_string()];
+    };
+    let Some(close) = glob[open..].find('}').map(|i| open + i) else {
+        return vec![glob.to_string()];
+    };
+    let (prefix, suffix) = (&glob[..open], &glob[close + 1..]);
+    glob[open + 1..close]
+        .split(',')
+        .flat_map(|alternative| expand_braces(&format!("{prefix}{alternative}{suffix}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Settings, expand_braces, settings};
+    use pretty::format::{Indent, LineEnding};
+    use std::fs;
+
+    #[test]
+    fn expand_globs() {
+        assert_eq!(expand_braces("*.{json,jsonc}"), ["*.json", "*.jsonc"]);
+        assert_eq!(
+            expand_braces("{a,b}/*.{x,y}"),
+            ["a/*.x", "a/*.y", "b/*.x", "b/*.y"]
+        );
+        assert_eq!(expand_braces("*.json"), ["*.json"]);
+    }
+
+    #[test]
+    fn read_editorconfig_settings() {
+        let dir = std::env::temp_dir().join(format!("pretty-editorconfig-{}", std::process::id()));
+        let project = dir.join("project");
+        let nested = project.join("config");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(
+            dir.join(".editorconfig"),
+            "[*]\nindent_style = tab\nend_of_line = crlf\n",
+        )
+        .unwrap();
+        fs::write(
+            project.join(".editorconfig"),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 2\n\n\
+             [*.{json,jsonc}]\nindent_size = 4\nend_of_line = lf\n\n\
+             [config/**.json]\ninsert_final_newline = false\n",
+        )
+        .unwrap();
+        fs::write(
+            nested.join(".editorconfig"),
+            "[app.json]\nIndent_Style = Tab\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings(&project.join("a.json")),
+            Settings {
+                indent: Some(Indent::Spaces(4)),
+                line_ending: Some(LineEnding::Lf),
+                final_newline: None,
+            }
+        );
+        assert_eq!(
+            settings(&nested.join("app.json")),
+            Settings {
+                indent: Some(Indent::Tab),
+                line_ending: Some(LineEnding::Lf),
+                final_newline: Some(false),
+            }
+        );
+        // The parent .editorconfig is not read, the project one being the root.
+        assert_eq!(settings(&project.join("a.txt")).line_ending, None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+//! Example documents generated from a JSON Schema (`pretty example --schema schema.json`).
+//!
+//! Values come from `const`, `examples`, `default` and `enum` when the schema has them, and are
+//! made up from the type and format otherwise. Local references (`$ref: "#/$defs/item"`) are
+//! followed, recursive references stop at the first cycle. Objects have all their properties and
+//! arrays a single item (or `minItems` items).
+use pretty::value::{Node, Value, escape, unescape};
+use std::fmt::Write;
+
+/// Maximum `minItems` and `minLength` of the schemas.
+const MAX_COUNT: usize = 65_536;
+/// Maximum size of an example, in bytes (nested arrays of `minItems` items grow exponentially).
+const MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// Returns an example document, as compact JSON, of the schema `root`.
+pub fn generate(root: &Node) -> Result<String, String> {
+    let mut generator = Generator {
+        root,
+        refs: Vec::new(),
+    };
+    let mut out = String::new();
+    generator.generate(root, "", &mut out)?;
+    Ok(out)
+}
+
+struct Generator<'a, 'input> {
+    root: &'a Node<'input>,
+    /// References being followed, to detect cycles.
+    refs: Vec<String>,
+}
+
+impl<'a, 'input> Generator<'a, 'input> {
+    /// Writes an example of `schema` to `out`. `name` is the name of the property, if any.
+    /// Returns `false` (and writes nothing) if the schema is a reference to one of its parents.
+    fn generate(
+        &mut self,
+        schema: &'a Node<'input>,
+        name: &str,
+        out: &mut String,
+    ) -> Result<bool, String> {
+        let Value::Object(_) = &schema.value else {
+            // `true` (any value) and `false` (no value) schemas.
+            out.push_str("null");
+            return Ok(true);
+        };
+        if let Some(reference) = string(schema, "$ref") {
+            if self.refs.contains(&reference) {
+                return Ok(false);
+            }
+            let target = self.resolve(&reference)?;
+            self.refs.push(reference);
+            let generated = self.generate(target, name, out);
+            self.refs.pop();
+            return generated;
+        }
+        if let Some(value) = member(schema, "const").or_else(|| member(schema, "default")) {
+            out.push_str(&value.to_compact_string());
+            return Ok(true);
+        }
+        if let Some(value) = first_item(schema, "examples").or_else(|| first_item(schema, "enum")) {
+            out.push_str(&value.to_compact_string());
+            return Ok(true);
+        }
+        if let Some(value) = member(schema, "example") {
+            out.push_str(&value.to_compact_string());
+            return Ok(true);
+        }
+        if let Some(all) = items(schema, "allOf") {
+            return self.generate_all_of(schema, all, name, out);
+        }
+        if let Some(first) = first_item(schema, "oneOf").or_else(|| first_item(schema, "anyOf")) {
+            return self.generate(first, name, out);
+        }
+        match schema_type(schema).as_deref() {
+            Some("object") => self.generate_object(&[schema], out)?,
+            Some("array") => self.generate_array(schema, out)?,
+            Some("string") => out.push_str(&example_string(schema, name)?),
+            Some("integer") => out.push_str(&example_number(schema, true)),
+            Some("number") => out.push_str(&example_number(schema, false)),
+            Some("boolean") => out.push_str("true"),
+            _ => out.push_str("null"),
+        }
+        Ok(true)
+    }
+
+    /// Writes an example of a schema with `allOf`: an object with the properties of all the
+    /// schemas, or an example of the first schema if they're not objects.
+    fn generate_all_of(
+        &mut self,
+        schema: &'a Node<'input>,
+        all: &'a [Node<'input>],
+        name: &str,
+        out: &mut String,
+    ) -> Result<bool, String> {
+        let mut schemas = vec![schema];
+        for item in all {
+            schemas.push(self.follow(item)?);
+        }
+        if schemas
+            .iter()
+            .any(|s| schema_type(s).as_deref() == Some("object"))
+        {
+            self.generate_object(&schemas, out)?;
+            return Ok(true);
+        }
+        match all.first() {
+            Some(first) => self.generate(first, name, out),
+            None => {
+                out.push_str("null");
+                Ok(true)
+            }
+        }
+    }
+
+    /// Writes an object with the properties of `schemas`.
+    fn generate_object(
+        &mut self,
+        schemas: &[&'a Node<'input>],
+        out: &mut String,
+    ) -> Result<(), String> {
+        out.push('{');
+        let mut written = Vec::new();
+        for schema in schemas {
+            let Some(Value::Object(properties)) = member(schema, "properties").map(|n| &n.value)
+            else {
+                continue;
+            };
+            for property in properties {
+                if written.contains(&property.key) {
+                    continue;
+                }
+                let start = out.len();
+                if !written.is_empty() {
+                    out.push(',');
+                }
+                let _ = write!(out, "{}:", property.key);
+                if self.generate(&property.node, &unescape(&property.key), out)? {
+                    written.push(property.key.clone());
+                } else {
+                    out.truncate(start);
+                }
+            }
+        }
+        out.push('}');
+        Ok(())
+    }
+
+    /// Writes an array of `minItems` items (at least one).
+    fn generate_array(&mut self, schema: &'a Node<'input>, out: &mut String) -> Result<(), String> {
+        out.push('[');
+        let count = number(schema, "minItems").map_or(1, |n| n.max(1.0) as usize);
+        if count > MAX_COUNT {
+            return Err(format!("minItems {count} is larger than {MAX_COUNT}"));
+        }
+        let prefix = items(schema, "prefixItems").unwrap_or_default();
+        let item = member(schema, "items").filter(|n| matches!(n.value, Value::Object(_)));
+        for i in 0..count.max(prefix.len()) {
+            let Some(schema) = prefix.get(i).or(item) else {
+                break;
+            };
+            let start = out.len();
+            if i > 0 {
+                out.push(',');
+            }
+            if !self.generate(schema, "", out)? {
+                out.truncate(start);
+                break;
+            }
+            if out.len() > MAX_SIZE {
+                return Err(format!("example is larger than {MAX_SIZE} bytes"));
+            }
+        }
+        out.push(']');
+        Ok(())
+    }
+
+    /// Returns the schema referenced by `schema`, or `schema` itself if it's not a reference.
+    fn follow(&self, schema: &'a Node<'input>) -> Result<&'a Node<'input>, String> {
+        match string(schema, "$ref") {
+            Some(reference) => self.resolve(&reference),
+            None => Ok(schema),
+        }
+    }
+
+    /// Returns the schema of a local reference, a JSON pointer (`#/$defs/item`).
+    fn resolve(&self, reference: &str) -> Result<&'a Node<'input>, String> {
+        let Some(pointer) = reference.strip_prefix('#') else {
+            return Err(format!(
+                "unsupported reference {reference} (only local references)"
+            ));
+        };
+        let mut node = self.root;
+        for token in pointer.split('/').skip(1) {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            let next = match &node.value {
+                Value::Object(_) => member(node, &token),
+                Value::Array(nodes) => token.parse::<usize>().ok().and_then(|i| nodes.get(i)),
+                _ => None,
+            };
+            node = next.ok_or_else(|| format!("reference {reference} not found"))?;
+        }
+        Ok(node)
+    }
+}
+
+/// Returns the member `key` of an object.
+fn member<'a, 'input>(node: &'a Node<'input>, key: &str) -> Option<&'a Node<'input>> {
+    let Value::Object(members) = &node.value else {
+        return None;
+    };
+    members
+        .iter()
+        .find(|m| unescape(&m.key) == key)
+        .map(|m| &m.node)
+}
+
+/// Returns the items of the array member `key`.
+fn items<'a, 'input>(node: &'a Node<'input>, key: &str) -> Option<&'a [Node<'input>]> {
+    match member(node, key).map(|n| &n.value) {
+        Some(Value::Array(nodes)) => Some(nodes),
+        _ => None,
+    }
+}
+
+fn first_item<'a, 'input>(node: &'a Node<'input>, key: &str) -> Option<&'a Node<'input>> {
+    items(node, key)?.first()
+}
+
+fn string(node: &Node, key: &str) -> Option<String> {
+    match member(node, key).map(|n| &n.value) {
+        Some(Value::String(raw)) => Some(unescape(raw)),
+        _ => None,
+    }
+}
+
+fn number(node: &Node, key: &str) -> Option<f64> {
+    match member(node, key).map(|n| &n.value) {
+        Some(Value::Number(raw)) => raw.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Returns the type of a schema, the first one that is not `null` if it has several types. A
+/// schema without a type is an object if it has properties, an array if it has items.
+fn schema_type(schema: &Node) -> Option<String> {
+    match member(schema, "type").map(|n| &n.value) {
+        Some(Value::String(raw)) => return Some(unescape(raw)),
+        Some(Value::Array(types)) => {
+            let types = types.iter().filter_map(|t| match &t.value {
+                Value::String(raw) => Some(unescape(raw)),
+                _ => None,
+            });
+            let types = types.collect::<Vec<_>>();
+            return types
+                .iter()
+                .find(|t| *t != "null")
+                .or(types.first())
+                .cloned();
+        }
+        _ => {}
+    }
+    if member(schema, "properties").is_some() {
+        Some("object".to_string())
+    } else if member(schema, "items").is_some() || member(schema, "prefixItems").is_some() {
+        Some("array".to_string())
+    } else {
+        None
+    }
+}
+
+/// Returns an example string (JSON, with quotes) of its format, or made of the property name,
+/// within `minLength` and `maxLength`.
+fn example_string(schema: &Node, name: &str) -> Result<String, String> {
+    let example = match string(schema, "format").as_deref() {
+        Some("date-time") => "2024-01-15T09:30:00Z",
+        Some("date") => "2024-01-15",
+        Some("time") => "09:30:00Z",
+        Some("duration") => "PT1H30M",
+        Some("email" | "idn-email") => "user@example.com",
+        Some("hostname" | "idn-hostname") => "example.com",
+        Some("ipv4") => "192.0.2.1",
+        Some("ipv6") => "2001:db8::1",
+        Some("uri" | "iri" | "url") => "https://example.com/",
+        Some("uri-reference" | "iri-reference") => "/path",
+        Some("uuid") => "3fa85f64-5717-4562-b3fc-2c963f66afa6",
+        Some("byte") => "ZXhhbXBsZQ==",
+        Some("password") => "********",
+        _ if !name.is_empty() => name,
+        _ => "string",
+    };
+    let mut example = example.to_string();
+    let min = number(schema, "minLength").map_or(0, |n| n as usize);
+    let max = number(schema, "maxLength").map_or(usize::MAX, |n| n as usize);
+    if min > MAX_COUNT {
+        return Err(format!("minLength {min} is larger than {MAX_COUNT}"));
+    }
+    let count = example.chars().count();
+    if count < min {
+        example.push_str(&"x".repeat(min - count));
+    }
+    if let Some((i, _)) = example.char_indices().nth(max) {
+        example.truncate(i);
+    }
+    Ok(escape(&example))
+}
+
+/// Returns an example number within `minimum` and `maximum` (exclusive or not), `0` if possible.
+fn example_number(schema: &Node, integer: bool) -> String {
+    let mut value = 0.0;
+    let step = if integer { 1.0 } else { 0.5 };
+    if let Some(min) = number(schema, "minimum") {
+        value = f64::max(value, min);
+    }
+    if let Some(min) = number(schema, "exclusiveMinimum")
+        && value <= min
+    {
+        value = min + step;
+    }
+    if let Some(max) = number(schema, "maximum") {
+        value = f64::min(value, max);
+    }
+    if let Some(max) = number(schema, "exclusiveMaximum")
+        && value >= max
+    {
+        value = max - step;
+    }
+    if integer {
+        value = value.ceil();
+        format!("{value}")
+    } else {
+        format!("{value:?}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate;
+    use pretty::value;
+
+    fn example(schema: &str) -> String {
+        generate(&value::parse(schema.as_bytes()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn generate_examples() {
+        let schema = r##"{
+            "type": "object",
+            "properties": {
+                "id": {"type": "string", "format": "uuid"},
+                "name": {"type": "string"},
+                "age": {"type": "integer", "minimum": 18},
+                "score": {"type": ["number", "null"], "exclusiveMaximum": 0},
+                "status": {"enum": ["active", "disabled"]},
+                "tags": {"type": "array", "items": {"type": "string"}, "minItems": 2},
+                "created": {"type": "string", "format": "date-time", "examples": ["2020-02-02"]},
+                "admin": {"type": "boolean", "default": false},
+                "address": {"$ref": "#/$defs/address"}
+            },
+            "$defs": {
+                "address": {"properties": {"city": {"type": "string", "maxLength": 3}}}
+            }
+        }"##;
+        assert_eq!(
+            example(schema),
+            r#"{"id":"3fa85f64-5717-4562-b3fc-2c963f66afa6","name":"name","age":18,"score":-0.5,"status":"active","tags":["string","string"],"created":"2020-02-02","admin":false,"address":{"city":"cit"}}"#
+        );
+    }
+
+    #[test]
+    fn generate_compositions() {
+        let schema = r##"{
+            "allOf": [
+                {"$ref": "#/definitions/base"},
+                {"type": "object", "properties": {"b": {"oneOf": [{"type": "integer"}, {"type": "string"}]}}}
+            ],
+            "definitions": {"base": {"type": "object", "properties": {"a": {"const": [1]}}}}
+        }"##;
+        assert_eq!(example(schema), r#"{"a":[1],"b":0}"#);
+
+        // Recursive schemas stop at the first cycle.
+        let schema = r##"{
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "children": {"type": "array", "items": {"$ref": "#"}},
+                "parent": {"$ref": "#"}
+            }
+        }"##;
+        assert_eq!(
+            example(schema),
+            r#"{"name":"name","children":[{"name":"name","children":[]}],"parent":{"name":"name","children":[]}}"#
+        );
+
+        let schema = value::parse(br##"{"$ref": "#/$defs/missing"}"##).unwrap();
+        assert!(generate(&schema).is_err());
+    }
+
+    #[test]
+    fn limit_example_sizes() {
+        assert_eq!(
+            example(r#"{"type": "string", "minLength": 5000}"#).len(),
+            5002
+        );
+        let schema = value::parse(br#"{"type": "string", "minLength": 1e12}"#).unwrap();
+        assert_eq!(
+            generate(&schema).unwrap_err(),
+            "minLength 1000000000000 is larger than 65536"
+        );
+        let schema = value::parse(br#"{"type": "array", "minItems": 1e12}"#).unwrap();
+        assert_eq!(
+            generate(&schema).unwrap_err(),
+            "minItems 1000000000000 is larger than 65536"
+        );
+        let schema = br#"{
+            "type": "array", "minItems": 60000,
+            "items": {"type": "array", "minItems": 60000, "items": {"type": "string", "minLength": 60000}}
+        }"#;
+        assert_eq!(
+            generate(&value::parse(schema).unwrap()).unwrap_err(),
+            "example is larger than 16777216 bytes"
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Expands a file argument into a list of files.
+///
+/// - a directory is walked recursively and all its `*.json` files are returned,
+/// - a glob pattern (with `*`, `?` or `**` components) is matched against the file system,
+/// - any other path is returned as is.
+///
+/// Files are returned in a stable (sorted) order. If `gitignore` is `true`, files and directories
+/// ignored by `.gitignore` files are skipped. Symbolic links to directories are not followed, so
+/// walks always end (a link to a parent directory would be walked forever).
+pub fn expand(path: &Path, gitignore: bool) -> Vec<Result<PathBuf, String>> {
+    let mut walker = Walker {
+        gitignore,
+        root: None,
+        ignore: Vec::new(),
+        files: Vec::new(),
+    };
+    let pattern = path.to_string_lossy();
+    if is_glob(&pattern) {
+        let (base, components) = split_glob(&pattern);
+        walker.load_parent_ignores(&base);
+        walker.walk_glob(&base, &components);
+        if walker.files.is_empty() {
+            walker
+                .files
+                .push(Err(format!("No files matching '{pattern}'")));
+        }
+    } else if path.is_dir() {
+        walker.load_parent_ignores(path);
+        walker.walk_dir(path);
+    } else {
+        walker.files.push(Ok(path.to_path_buf()));
+    }
+    walker.files
+}
+
+/// Returns `true` if this path contains glob wildcards.
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Splits a glob pattern in a base directory (without wildcards) and the remaining components.
+fn split_glob(pattern: &str) -> (PathBuf, Vec<String>) {
+    let mut base = PathBuf::new();
+    let mut components = Vec::new();
+    for component in pattern.split('/') {
+        if components.is_empty() && !is_glob(component) {
+            if component.is_empty() {
+                // Absolute path
+                base.push("/");
+            } else {
+                base.push(component);
+            }
+        } else {
+            components.push(component.to_string());
+        }
+    }
+    if base.as_os_str().is_empty() {
+        base.push(".");
+    }
+    (base, components)
+}
+
+/// A `.gitignore` rule.
+struct IgnoreRule {
+    /// Directory of the `.gitignore` file defining this rule.
+    base: PathBuf,
+    /// Pattern components, split on `/`.
+    pattern: Vec<String>,
+    /// The rule applies to the path relative to `base`, not just the file name.
+    anchored: bool,
+    /// The rule applies only to directories (trailing `/`).
+    dir_only: bool,
+    /// The rule re-includes a previously ignored path (leading `!`).
+    negated: bool,
+}
+
+impl IgnoreRule {
+    fn parse(base: &Path, line: &str) -> Option<IgnoreRule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        Some(IgnoreRule {
+            base: base.to_path_buf(),
+            pattern: line.split('/').map(str::to_string).collect(),
+            anchored,
+            dir_only,
+            negated,
+        })
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+        let components = relative
+            .iter()
+            .map(|c| c.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        if self.anchored {
+            match_components(&self.pattern, &components)
+        } else {
+            components
+                .last()
+                .is_some_and(|name| wildcard_match(&self.pattern[0], name))
+        }
+    }
+}
+
+/// Recursively collects files.
+struct Walker {
+    gitignore: bool,
+    /// The walked root directory and its canonical path.
+    root: Option<(PathBuf, PathBuf)>,
+    ignore: Vec<IgnoreRule>,
+    files: Vec<Result<PathBuf, String>>,
+}
+
+impl Walker {
+    /// Loads the `.gitignore` files of the parent directories of `dir`, up to the Git repository
+    /// root.
+    fn load_parent_ignores(&mut self, path: &Path) {
+        if !self.gitignore {
+            return;
+        }
+        let Ok(dir) = path.canonicalize() else {
+            return;
+        };
+        let mut parents = Vec::new();
+        for parent in dir.ancestors().skip(1) {
+            parents.push(parent);
+            if parent.join(".git").exists() {
+                break;
+            }
+        }
+        // The rules of the repository root are loaded first.
+        for parent in parents.iter().rev() {
+            self.load_ignore(parent);
+        }
+        self.root = Some((path.to_path_buf(), dir));
+    }
+
+    /// Returns the canonical path of a walked `path`, rules being defined on canonical paths.
+    fn canonical(&self, path: &Path) -> PathBuf {
+        match &self.root {
+            Some((root, canonical_root)) => match path.strip_prefix(root) {
+                Ok(relative) => canonical_root.join(relative),
+                Err(_) => path.to_path_buf(),
+            },
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Loads the rules of `dir/.gitignore`, returns the number of rules added.
+    fn load_ignore(&mut self, dir: &Path) -> usize {
+        let dir = &self.canonical(dir);
+        let Ok(content) = fs::read_to_string(dir.join(".gitignore")) else {
+            return 0;
+        };
+        let count = self.ignore.len();
+        self.ignore
+            .extend(content.lines().filter_map(|l| IgnoreRule::parse(dir, l)));
+        self.ignore.len() - count
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+        if !self.gitignore {
+            return false;
+        }
+        let path = self.canonical(path);
+        let mut ignored = false;
+        for rule in &self.ignore {
+            if rule.matches(&path, is_dir) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+
+    /// Returns the sorted entries of a directory, reporting errors. Symbolic links to directories
+    /// are skipped.
+    fn read_dir(&mut self, dir: &Path) -> Vec<(PathBuf, bool)> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                let err = format!("Error reading directory '{}': {}", dir.display(), err);
+                self.files.push(Err(err));
+                return Vec::new();
+            }
+        };
+        let mut entries = entries
+            .filter_map(Result::ok)
+            .filter_map(|e| {
+                let path = e.path();
+                let file_type = e.file_type().ok()?;
+                let is_dir = path.is_dir();
+                if file_type.is_symlink() && is_dir {
+                    return None;
+                }
+                Some((path, is_dir))
+            })
+            .filter(|(path, is_dir)| !self.is_ignored(path, *is_dir))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    fn walk_dir(&mut self, dir: &Path) {
+        let added = if self.gitignore {
+            self.load_ignore(dir)
+        } else {
+            0
+        };
+        for (path, is_dir) in self.read_dir(dir) {
+            if is_dir {
+                self.walk_dir(&path);
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                self.files.push(Ok(path));
+            }
+        }
+        self.ignore.truncate(self.ignore.len() - added);
+    }
+
+    fn walk_glob(&mut self, dir: &Path, components: &[String]) {
+        let Some((first, rest)) = components.split_first() else {
+            return;
+        };
+        let added = if self.gitignore {
+            self.load_ignore(dir)
+        } else {
+            0
+        };
+        let entries = self.read_dir(dir);
+        if first == "**" {
+            // `**` matches zero or more directories.
+            self.walk_glob(dir, rest);
+            for (path, is_dir) in &entries {
+                if *is_dir {
+                    self.walk_glob(path, components);
+                }
+            }
+        } else {
+            for (path, is_dir) in &entries {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if !wildcard_match(first, &name) {
+                    continue;
+                }
+                if rest.is_empty() {
+                    if !is_dir {
+                        self.files.push(Ok(path.clone()));
+                    }
+                } else if *is_dir {
+                    self.walk_glob(path, rest);
+                }
+            }
+        }
+        self.ignore.truncate(self.ignore.len() - added);
+    }
+}
+
+/// Matches path components against pattern components, `**` matching zero or more components.
+pub fn match_components(pattern: &[String], components: &[String]) -> bool {
+    match pattern.split_first() {
+        None => components.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=components.len()).any(|i| match_components(rest, &components[i..]))
+        }
+        Some((first, rest)) => match components.split_first() {
+            Some((c, components)) => wildcard_match(first, c) && match_components(rest, components),
+            None => false,
+        },
+    }
+}
+
+/// Matches a name against a pattern where `*` matches any sequence of characters and `?` matches
+/// any single character.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    // Position to backtrack to on mismatch: after the last `*` and the name position it matched.
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_match_names() {
+        assert!(wildcard_match("*.json", "a.json"));
+        assert!(wildcard_match("*.json", ".json"));
+        assert!(!wildcard_match("*.json", "a.jsonc"));
+        assert!(wildcard_match("a?c", "abc"));
+        assert!(!wildcard_match("a?c", "ac"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("a*b*c", "aXXbYYbc"));
+        assert!(!wildcard_match("a*b*c", "aXXbYY"));
+    }
+
+    #[test]
+    fn match_path_components() {
+        let pattern = |p: &str| p.split('/').map(str::to_string).collect::<Vec<_>>();
+        assert!(match_components(&pattern("**/*.json"), &pattern("a.json")));
+        assert!(match_components(
+            &pattern("**/*.json"),
+            &pattern("a/b/c.json")
+        ));
+        assert!(match_components(&pattern("a/**/c"), &pattern("a/c")));
+        assert!(match_components(&pattern("a/**/c"), &pattern("a/b/b/c")));
+        assert!(!match_components(&pattern("a/*/c"), &pattern("a/b/b/c")));
+    }
+
+    #[test]
+    fn split_glob_pattern() {
+        assert_eq!(
+            split_glob("src/**/*.json"),
+            (
+                PathBuf::from("src"),
+                vec!["**".to_string(), "*.json".to_string()]
+            )
+        );
+        assert_eq!(
+            split_glob("*.json"),
+            (PathBuf::from("."), vec!["*.json".to_string()])
+        );
+    }
+
+    #[test]
+    fn gitignore_rules() {
+        let base = Path::new(".");
+        let rule = IgnoreRule::parse(base, "target/").unwrap();
+        assert!(rule.matches(Path::new("./target"), true));
+        assert!(rule.matches(Path::new("./a/target"), true));
+        assert!(!rule.matches(Path::new("./target"), false));
+
+        let rule = IgnoreRule::parse(base, "/fixtures/*.json").unwrap();
+        assert!(rule.matches(Path::new("./fixtures/a.json"), false));
+        assert!(!rule.matches(Path::new("./a/fixtures/a.json"), false));
+
+        assert!(IgnoreRule::parse(base, "# comment").is_none());
+        assert!(IgnoreRule::parse(base, "!keep.json").unwrap().negated);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skip_symlinked_directories() {
+        let dir = std::env::temp_dir().join(format!("pretty-files-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::write(dir.join("a/b.json"), "{}").unwrap();
+        fs::write(dir.join("c.json"), "{}").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("a/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("c.json"), dir.join("a/d.json")).unwrap();
+
+        let files = |path: &Path| {
+            let mut files = expand(path, false)
+                .into_iter()
+                .map(Result::unwrap)
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        };
+        let expected = [
+            dir.join("a/b.json"),
+            dir.join("a/d.json"),
+            dir.join("c.json"),
+        ];
+        assert_eq!(files(&dir), expected);
+        assert_eq!(files(&dir.join("**/*.json")), expected);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+//! Conversion of URL-encoded forms and query strings to JSON.
+//!
+//! `a=1&b[0]=x&b[1]=y&c[d]=z` is `{"a":"1","b":["x","y"],"c":{"d":"z"}}`: brackets in keys are
+//! object members or array indexes (`[]` appends to an array), and a repeated key is an array of
+//! its values. All values are strings.
+//!
+//! As with the `qs` library, indexes larger than [ARRAY_LIMIT] are object keys (`a[9999]=x` is
+//! `{"a":{"9999":"x"}}`), so that a short key can't create a huge array.
+use pretty::value;
+use pretty::value::{Member, Node, Value};
+use std::borrow::Cow;
+
+/// A value being built.
+enum Field {
+    Null,
+    Text(String),
+    Object(Vec<(String, Field)>),
+    Array(Vec<Field>),
+}
+
+/// Largest index of an array, larger indexes are object keys.
+const ARRAY_LIMIT: usize = 20;
+
+/// A segment of a bracketed key.
+enum Segment {
+    Key(String),
+    Index(usize),
+    Append,
+}
+
+/// Converts a URL-encoded form body, a query string or an URL with a query string to compact
+/// JSON.
+pub fn from_form(input: &[u8]) -> Result<Vec<u8>, String> {
+    let input = std::str::from_utf8(input).map_err(|err| format!("Invalid form: {err}"))?;
+    let mut query = input.trim();
+    if query.contains("://") {
+        query = query.split_once('?').map_or("", |(_, query)| query);
+    }
+    let query = query.split('#').next().unwrap_or_default();
+    let query = query.strip_prefix('?').unwrap_or(query);
+
+    let mut root = Field::Object(Vec::new());
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, text) = pair.split_once('=').unwrap_or((pair, ""));
+        let key = decode(key);
+        let mut segments = vec![];
+        let name = parse_key(&key, &mut segments);
+        segments.insert(0, Segment::Key(name.to_string()));
+        set(&mut root, &segments, decode(text));
+    }
+    Ok(to_node(root).to_compact_string().into_bytes())
+}
+
+/// Parses a key with brackets (`a[b][0][]`), pushes its bracketed segments and returns its name.
+/// A key with unbalanced brackets is a plain name.
+fn parse_key<'a>(key: &'a str, segments: &mut Vec<Segment>) -> &'a str {
+    let Some(start) = key.find('[').filter(|&i| i > 0) else {
+        return key;
+    };
+    let mut rest = &key[start..];
+    while let Some(inner) = rest.strip_prefix('[') {
+        let Some(end) = inner.find(']') else {
+            segments.clear();
+            return key;
+        };
+        let segment = &inner[..end];
+        segments.push(match segment.parse::<usize>() {
+            _ if segment.is_empty() => Segment::Append,
+            Ok(index) if index <= ARRAY_LIMIT => Segment::Index(index),
+            _ => Segment::Key(segment.to_string()),
+        });
+        rest = &inner[end + 1..];
+    }
+    if !rest.is_empty() {
+        segments.clear();
+        return key;
+    }
+    &key[..start]
+}
+
+fn set(field: &mut Field, segments: &[Segment], text: String) {
+    let Some((segment, rest)) = segments.split_first() else {
+        match field {
+            Field::Null => *field = Field::Text(text),
+            Field::Text(_) => {
+                let first = std::mem::replace(field, Field::Null);
+                *field = Field::Array(vec![first, Field::Text(text)]);
+            }
+            Field::Array(items) => items.push(Field::Text(text)),
+            Field::Object(_) => *field = Field::Text(text),
+        }
+        return;
+    };
+    match segment {
+        Segment::Key(key) => {
+            match field {
+                Field::Object(_) => {}
+                // Items of an array become members, keyed by their index.
+                Field::Array(items) => {
+                    let members = std::mem::take(items)
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(_, item)| !matches!(item, Field::Null))
+                        .map(|(i, item)| (i.to_string(), item))
+                        .collect();
+                    *field = Field::Object(members);
+                }
+                _ => *field = Field::Object(Vec::new()),
+            }
+            let Field::Object(members) = field else {
+                unreachable!()
+            };
+            set(member(members, key), rest, text);
+        }
+        // An index of an object is a key.
+        Segment::Index(i) if matches!(field, Field::Object(_)) => {
+            let Field::Object(members) = field else {
+                unreachable!()
+            };
+            set(member(members, &i.to_string()), rest, text);
+        }
+        Segment::Index(_) | Segment::Append => {
+            if !matches!(field, Field::Array(_)) {
+                *field = Field::Array(Vec::new());
+            }
+            let Field::Array(items) = field else {
+                unreachable!()
+            };
+            let i = match segment {
+                Segment::Index(i) => *i,
+                _ => items.len(),
+            };
+            // Missing items are null.
+            while items.len() <= i {
+                items.push(Field::Null);
+            }
+            set(&mut items[i], rest, text);
+        }
+    }
+}
+
+/// Returns the member `key` of an object, added if it doesn't exist.
+fn member<'a>(members: &'a mut Vec<(String, Field)>, key: &str) -> &'a mut Field {
+    let i = match members.iter().position(|(name, _)| name == key) {
+        Some(i) => i,
+        None => {
+            members.push((key.to_string(), Field::Null));
+            members.len() - 1
+        }
+    };
+    &mut members[i].1
+}
+
+fn to_node(field: Field) -> Node<'static> {
+    let value = match field {
+        Field::Null => Value::Null,
+        Field::Text(text) => Value::String(Cow::Owned(value::escape(&text))),
+        Field::Array(items) => Value::Array(items.into_iter().map(to_node).collect()),
+        Field::Object(members) => Value::Object(
+            members
+                .into_iter()
+                .map(|(key, field)| Member {
+                    key: Cow::Owned(value::escape(&key)),
+                    key_span: 0..0,
+                    node: to_node(field),
+                })
+                .collect(),
+        ),
+    };
+    Node::new(value)
+}
+
+/// Decodes a percent-encoded component, where `+` is a space. Invalid UTF-8 sequences are
+/// replaced by U+FFFD.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = |b: &u8| (*b as char).to_digit(16);
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match (
+                bytes.get(i + 1).and_then(hex),
+                bytes.get(i + 2).and_then(hex),
+            ) {
+                (Some(high), Some(low)) => {
+                    decoded.push((high * 16 + low) as u8);
+                    i += 2;
+                }
+                _ => decoded.push(b'%'),
+            },
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_form;
+
+    fn form(input: &str) -> String {
+        String::from_utf8(from_form(input.as_bytes()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn convert_forms() {
+        assert_eq!(
+            form("a=1&b[0]=x&b[1]=y&c[d][]=z&c[d][]=w"),
+            r#"{"a":"1","b":["x","y"],"c":{"d":["z","w"]}}"#
+        );
+        assert_eq!(
+            form("q=hello+world&tag=a&tag=b%26c&empty&e%5B0%5D=%C3%A9"),
+            r#"{"q":"hello world","tag":["a","b&c"],"empty":"","e":["é"]}"#
+        );
+        assert_eq!(
+            form("https://example.com/search?q=json&page=2#top"),
+            r#"{"q":"json","page":"2"}"#
+        );
+        assert_eq!(
+            form("a[2]=x&b[=1&[c]=2"),
+            r#"{"a":[null,null,"x"],"b[":"1","[c]":"2"}"#
+        );
+        assert_eq!(form(""), "{}");
+    }
+
+    #[test]
+    fn convert_large_indexes_to_keys() {
+        assert_eq!(form("a[9999999999]=x"), r#"{"a":{"9999999999":"x"}}"#);
+        assert_eq!(
+            form("a[0]=x&a[21]=y&a[1]=z"),
+            r#"{"a":{"0":"x","21":"y","1":"z"}}"#
+        );
+        assert_eq!(form("a[20]=x").matches("null").count(), 20);
+    }
+}
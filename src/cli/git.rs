@@ -0,0 +1,90 @@
+//! Staged files of a git repository, for the `git-hook` command.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A file of the index.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StagedFile {
+    /// Path of the file relative to the root of the repository, as in the index.
+    pub name: String,
+    /// Path of the file in the working tree, relative to the current directory if it's in it.
+    pub path: PathBuf,
+}
+
+/// Runs a git command, returns its standard output.
+fn git(args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|err| format!("Error running git: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Error running git {}: {}", args[0], stderr.trim()));
+    }
+    Ok(output.stdout)
+}
+
+/// Returns the JSON files added, copied, modified or renamed in the index.
+pub fn staged_json_files() -> Result<Vec<StagedFile>, String> {
+    let root = git(&["rev-parse", "--show-toplevel"])?;
+    let root = PathBuf::from(String::from_utf8_lossy(&root).trim_end());
+    let names = git(&[
+        "diff",
+        "--cached",
+        "--name-only",
+        "--diff-filter=ACMR",
+        "-z",
+    ])?;
+    let cwd = std::env::current_dir().map_err(|err| format!("Error reading directory: {err}"))?;
+    Ok(json_files(&names, &root, &cwd))
+}
+
+/// Returns the JSON files of NUL separated names of the index.
+fn json_files(names: &[u8], root: &Path, cwd: &Path) -> Vec<StagedFile> {
+    names
+        .split(|&b| b == 0)
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .filter(|name| name.ends_with(".json"))
+        .map(|name| {
+            let path = root.join(&name);
+            let path = match path.strip_prefix(cwd) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => path,
+            };
+            StagedFile { name, path }
+        })
+        .collect()
+}
+
+/// Returns the content of a file in the index.
+pub fn staged_content(file: &StagedFile) -> Result<Vec<u8>, String> {
+    git(&["show", &format!(":{}", file.name)])
+}
+
+/// Adds a file to the index.
+pub fn stage(path: &Path) -> Result<(), String> {
+    git(&["add", "--", &path.display().to_string()]).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StagedFile, json_files};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn parse_staged_names() {
+        let names = b"a.json\0src/b.rs\0conf/c d.json\0e.jsonl\0";
+        let file = |name: &str, path: &str| StagedFile {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+        };
+        assert_eq!(
+            json_files(names, Path::new("/repo"), Path::new("/repo/conf")),
+            [
+                file("a.json", "/repo/a.json"),
+                file("conf/c d.json", "c d.json")
+            ]
+        );
+        assert!(json_files(b"", Path::new("/repo"), Path::new("/repo")).is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+//! HTTP Archive (HAR) files presentation.
+use crate::cli::base64;
+use pretty::path::Path;
+use pretty::value::{Node, Value};
+use std::fmt::Write;
+
+/// An HTTP request and its response, recorded in a HAR file.
+pub struct Entry {
+    pub method: String,
+    pub url: String,
+    /// Response status and status text (ex: `200 OK`).
+    pub status: String,
+    /// Total elapsed time of the request, in milliseconds.
+    pub time: Option<f64>,
+    pub request_body: Option<Body>,
+    pub response_body: Option<Body>,
+}
+
+/// A request or response body.
+pub struct Body {
+    pub mime_type: String,
+    /// The body content, decoded if it was base64 encoded.
+    pub content: Vec<u8>,
+}
+
+/// Returns the entries (`.log.entries`) of a HAR document.
+pub fn entries(root: &Node) -> Result<Vec<Entry>, String> {
+    let entries = root.get(&path(".log.entries"));
+    let Some(Value::Array(entries)) = entries.map(|node| &node.value) else {
+        return Err("Invalid HAR file: missing .log.entries array".to_string());
+    };
+    Ok(entries.iter().map(entry).collect())
+}
+
+fn path(s: &str) -> Path {
+    s.parse().expect("valid path")
+}
+
+/// Returns the text of the value at `p`, an empty string if there is no value.
+fn text(node: &Node, p: &str) -> String {
+    node.get(&path(p))
+        .map(|node| node.to_text())
+        .unwrap_or_default()
+}
+
+fn entry(node: &Node) -> Entry {
+    let status = [
+        text(node, ".response.status"),
+        text(node, ".response.statusText"),
+    ];
+    let status = status.join(" ").trim().to_string();
+    let time = text(node, ".time").parse::<f64>().ok();
+    let request_body = node.get(&path(".request.postData")).map(|data| Body {
+        mime_type: text(data, ".mimeType"),
+        content: text(data, ".text").into_bytes(),
+    });
+    let response_body = node.get(&path(".response.content")).and_then(|content| {
+        let body = text(content, ".text");
+        if body.is_empty() {
+            return None;
+        }
+        let content_bytes = if text(content, ".encoding") == "base64" {
+            base64::decode(body.as_bytes()).unwrap_or_else(|| body.into_bytes())
+        } else {
+            body.into_bytes()
+        };
+        Some(Body {
+            mime_type: text(content, ".mimeType"),
+            content: content_bytes,
+        })
+    });
+    Entry {
+        method: text(node, ".request.method"),
+        url: text(node, ".request.url"),
+        status,
+        time,
+        request_body,
+        response_body,
+    }
+}
+
+/// Lists entries, one per line: index, method, status, time and URL.
+pub fn list(entries: &[Entry]) -> String {
+    let mut out = String::new();
+    _ = writeln!(
+        out,
+        "{:>4}  {:<7} {:<20} {:>10}  URL",
+        "#", "METHOD", "STATUS", "TIME"
+    );
+    for (i, entry) in entries.iter().enumerate() {
+        _ = writeln!(
+            out,
+            "{:>4}  {:<7} {:<20} {:>10}  {}",
+            i + 1,
+            entry.method,
+            entry.status,
+            format_time(entry.time),
+            entry.url
+        );
+    }
+    out
+}
+
+/// Formats a time in milliseconds (`-` if unknown).
+pub fn format_time(time: Option<f64>) -> String {
+    match time {
+        Some(time) if time >= 1000.0 => format!("{:.2} s", time / 1000.0),
+        Some(time) => format!("{time:.0} ms"),
+        None => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{entries, list};
+    use pretty::value;
+
+    #[test]
+    fn list_entries() {
+        let input = br#"{"log": {"entries": [
+            {"time": 123.4, "request": {"method": "POST", "url": "https://a.com/api",
+              "postData": {"mimeType": "application/json", "text": "{\"a\":1}"}},
+             "response": {"status": 201, "statusText": "Created",
+              "content": {"mimeType": "application/json", "text": "eyJpZCI6MX0=", "encoding": "base64"}}},
+            {"time": 1500, "request": {"method": "GET", "url": "https://a.com/"},
+             "response": {"status": 0, "statusText": "", "content": {"size": 0}}}
+        ]}}"#;
+        let root = value::parse(input).unwrap();
+        let entries = entries(&root).unwrap();
+        assert_eq!(
+            list(&entries),
+            "   #  METHOD  STATUS                     TIME  URL
+   1  POST    201 Created              123 ms  https://a.com/api
+   2  GET     0                        1.50 s  https://a.com/
+"
+        );
+        let request = entries[0].request_body.as_ref().unwrap();
+        assert_eq!(request.content, br#"{"a":1}"#);
+        let response = entries[0].response_body.as_ref().unwrap();
+        assert_eq!(response.content, br#"{"id":1}"#);
+        assert!(entries[1].response_body.is_none());
+
+        let root = value::parse(br#"{"log": {}}"#).unwrap();
+        assert!(super::entries(&root).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Hash of the canonical form of a document (`--hash sha256`): equal documents have the same
+//! hash, whatever their formatting.
+//!
+//! The canonical form is the output of `--preset canonical`: keys sorted, numbers normalized,
+//! on one line without whitespace. Strings and keys are also escaped the same way (`"\u0041"` is
+//! `"A"`).
+use pretty::arena::Arena;
+use pretty::transform;
+use pretty::value::{self, Node, Value, escape, unescape};
+use std::str::FromStr;
+
+/// A hash algorithm.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HashAlgorithm {
+    Sha256,
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            _ => Err(format!("unknown algorithm {s} (expected sha256)")),
+        }
+    }
+}
+
+/// Returns the hash of the canonical form of a JSON document, in lowercase hexadecimal.
+pub fn hash(input: &[u8], algorithm: HashAlgorithm) -> Result<String, String> {
+    let mut root = value::parse(input).map_err(|err| err.to_string())?;
+    // Keys are sorted once unescaped.
+    normalize_strings(&mut root);
+    let arena = Arena::default();
+    transform::sort_keys_in(&mut root, &arena);
+    transform::shortest_floats(&mut root);
+    transform::normalize_numbers(&mut root);
+    let canonical = root.to_compact_string();
+    let digest = match algorithm {
+        HashAlgorithm::Sha256 => {
+            let mut sha = Sha256::new();
+            sha.update(canonical.as_bytes());
+            sha.finish()
+        }
+    };
+    Ok(digest.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Re-escapes recursively the strings of `node` (values and keys) with their shortest escapes.
+fn normalize_strings(node: &mut Node) {
+    match &mut node.value {
+        Value::String(raw) => *raw = escape(&unescape(raw)).into(),
+        Value::Object(members) => {
+            for member in members {
+                member.key = escape(&unescape(&member.key)).into();
+                normalize_strings(&mut member.node);
+            }
+        }
+        Value::Array(nodes) => nodes.iter_mut().for_each(normalize_strings),
+        _ => {}
+    }
+}
+
+/// Returns the HMAC-SHA256 (RFC 2104) of `message` with `key`.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > 64 {
+        let mut sha = Sha256::new();
+        sha.update(key);
+        block[..32].copy_from_slice(&sha.finish());
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(&block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha256::new();
+    outer.update(&block.map(|b| b ^ 0x5c));
+    outer.update(&inner.finish());
+    outer.finish()
+}
+
+/// Round constants of SHA-256: the first 32 bits of the fractional parts of the cube roots of
+/// the first 64 primes.
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// A SHA-256 hasher (FIPS 180-4), fed with [Sha256::update].
+struct Sha256 {
+    state: [u32; 8],
+    /// Bytes of the current block, not yet processed.
+    block: Vec<u8>,
+    /// Number of bytes hashed.
+    len: u64,
+}
+
+impl Sha256 {
+    fn new() -> Self {
+        Sha256 {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+            block: Vec::with_capacity(64),
+            len: 0,
+        }
+    }
+
+    fn update(&mut self, mut bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        while !bytes.is_empty() {
+            let n = (64 - self.block.len()).min(bytes.len());
+            self.block.extend_from_slice(&bytes[..n]);
+            bytes = &bytes[n..];
+            if self.block.len() == 64 {
+                let block = std::mem::take(&mut self.block);
+                self.compress(&block);
+                self.block = block;
+                self.block.clear();
+            }
+        }
+    }
+
+    /// Pads the last block and returns the digest.
+    fn finish(mut self) -> [u8; 32] {
+        let bits = self.len * 8;
+        self.update(&[0x80]);
+        while self.block.len() != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_be_bytes());
+        let mut digest = [0; 32];
+        for (chunk, word) in digest.chunks_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let mut w = [0u32; 64];
+        for (i, chunk) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HashAlgorithm, Sha256, hash, hmac_sha256};
+
+    fn sha256(bytes: &[u8]) -> String {
+        let mut sha = Sha256::new();
+        sha.update(bytes);
+        sha.finish().iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn sha256_digests() {
+        assert_eq!(
+            sha256(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            sha256(&[b'a'; 1000]),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+
+    #[test]
+    fn hmac_sha256_digests() {
+        let hex =
+            |digest: [u8; 32]| -> String { digest.iter().map(|b| format!("{b:02x}")).collect() };
+        // RFC 4231, test cases 2 and 6.
+        assert_eq!(
+            hex(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex(hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn hash_canonical_forms() {
+        let a = hash(br#"{"b": [1.50, 2], "a": "x"}"#, HashAlgorithm::Sha256).unwrap();
+        let b = hash(br#"{"a":"x","b":[1.5,2]}"#, HashAlgorithm::Sha256).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, sha256(br#"{"a":"x","b":[1.5,2]}"#));
+        assert_ne!(
+            a,
+            hash(br#"{"a":"y","b":[1.5,2]}"#, HashAlgorithm::Sha256).unwrap()
+        );
+        assert!(hash(b"{", HashAlgorithm::Sha256).is_err());
+
+        // Strings and keys are compared unescaped.
+        let a = hash(br#"{"\u0042": "\u0041\/", "a": 1}"#, HashAlgorithm::Sha256).unwrap();
+        let b = hash(br#"{"a": 1, "B": "A/"}"#, HashAlgorithm::Sha256).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, sha256(br#"{"B":"A/","a":1}"#));
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(feature = "http")]
+use std::io::Read;
+
+/// Fetches `url` with a GET request and returns the response body.
+///
+/// The response Content-Type is checked: a warning is printed on stderr if it doesn't look like
+/// JSON, but the body is formatted anyway (many APIs send JSON as `text/plain`). At most `limit`
+/// bytes of the body are read.
+#[cfg(feature = "http")]
+pub fn fetch(url: &str, headers: &[(String, String)], limit: u64) -> Result<Vec<u8>, String> {
+    let mut request = ureq::get(url).header("Accept", "application/json");
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let mut response = request.call().map_err(|err| err.to_string())?;
+
+    let content_type = response
+        .headers()
+        .get("Content-Type")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    if !is_json_content_type(content_type) {
+        eprintln!("Warning: unexpected Content-Type '{content_type}' for {url}");
+    }
+
+    let mut body = Vec::new();
+    response
+        .body_mut()
+        .as_reader()
+        .take(limit)
+        .read_to_end(&mut body)
+        .map_err(|err| err.to_string())?;
+    Ok(body)
+}
+
+#[cfg(not(feature = "http"))]
+pub fn fetch(url: &str, _headers: &[(String, String)], _limit: u64) -> Result<Vec<u8>, String> {
+    Err(format!(
+        "can not fetch {url}, pretty has been built without the `http` feature"
+    ))
+}
+
+/// Returns `true` if this Content-Type is a JSON media type (`application/json`,
+/// `application/problem+json`, `text/json` etc...).
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+fn is_json_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    mime == "application/json" || mime == "text/json" || mime.ends_with("+json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_json_content_type;
+
+    #[test]
+    fn json_content_types() {
+        assert!(is_json_content_type("application/json"));
+        assert!(is_json_content_type("application/json; charset=utf-8"));
+        assert!(is_json_content_type("Application/JSON"));
+        assert!(is_json_content_type("application/problem+json"));
+        assert!(is_json_content_type("text/json"));
+        assert!(!is_json_content_type("text/html"));
+        assert!(!is_json_content_type(""));
+    }
+}
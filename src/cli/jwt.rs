@@ -0,0 +1,54 @@
+use crate::cli::base64;
+
+/// Decodes a compact JWT (`header.payload.signature`) to a JSON object with its decoded header
+/// and payload, the signature is replaced by its size:
+/// `{"header":{"alg":"HS256"},"payload":{"sub":"1"},"signature":"<32 bytes>"}`.
+///
+/// The signature is not verified.
+pub fn from_jwt(input: &[u8]) -> Result<Vec<u8>, String> {
+    let input = input.trim_ascii();
+    let input = input.strip_prefix(b"Bearer ").unwrap_or(input);
+    let parts = input.split(|&b| b == b'.').collect::<Vec<_>>();
+    let [header, payload, signature] = parts.as_slice() else {
+        let err = format!(
+            "Invalid JWT: expected 3 parts separated by dots, found {}",
+            parts.len()
+        );
+        return Err(err);
+    };
+    let decode = |part: &[u8], name: &str| {
+        base64::decode(part).ok_or_else(|| format!("Invalid JWT: {name} is not base64url"))
+    };
+    let header = decode(header, "header")?;
+    let payload = decode(payload, "payload")?;
+    let signature = decode(signature, "signature")?;
+
+    let mut json = b"{\"header\":".to_vec();
+    json.extend_from_slice(&header);
+    json.extend_from_slice(b",\"payload\":");
+    json.extend_from_slice(&payload);
+    json.extend_from_slice(format!(",\"signature\":\"<{} bytes>\"}}", signature.len()).as_bytes());
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_jwt;
+
+    #[test]
+    fn decode_jwt() {
+        let token = b"eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+            eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ.\
+            SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c\n";
+        let json = String::from_utf8(from_jwt(token).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"header":{"alg":"HS256","typ":"JWT"},"payload":{"sub":"1234567890","name":"John Doe","iat":1516239022},"signature":"<32 bytes>"}"#
+        );
+        assert_eq!(
+            from_jwt(b"abc.def").unwrap_err(),
+            "Invalid JWT: expected 3 parts separated by dots, found 2"
+        );
+        assert!(from_jwt(b"a!.b.c").is_err());
+    }
+}
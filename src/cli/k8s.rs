@@ -0,0 +1,69 @@
+//! Kubernetes manifests (`kubectl get -o json`) cleanup.
+use pretty::path::Path;
+use pretty::transform;
+use pretty::value;
+use pretty::value::{Node, Value};
+
+/// Paths of a resource removed by default with `--k8s`.
+pub const NOISE_PATHS: [&str; 2] = [
+    ".metadata.managedFields",
+    r#".metadata.annotations["kubectl.kubernetes.io/last-applied-configuration"]"#,
+];
+
+/// Members of a resource written first, in this order.
+const FIRST_KEYS: [&str; 5] = ["apiVersion", "kind", "metadata", "spec", "status"];
+
+/// Removes the `noise` paths of each resource of the document and orders their members
+/// (`apiVersion`, `kind`, `metadata`, `spec`, `status` first).
+///
+/// A resource is an object with `apiVersion` and `kind` members: the root value, or the items of
+/// a `List`.
+pub fn clean(node: &mut Node, noise: &[Path]) {
+    if is_resource(node) {
+        for path in noise {
+            transform::remove(node, path);
+        }
+        transform::order_keys(node, &FIRST_KEYS);
+    }
+    match &mut node.value {
+        Value::Object(members) => members.iter_mut().for_each(|m| clean(&mut m.node, noise)),
+        Value::Array(nodes) => nodes.iter_mut().for_each(|n| clean(n, noise)),
+        _ => {}
+    }
+}
+
+fn is_resource(node: &Node) -> bool {
+    let Value::Object(members) = &node.value else {
+        return false;
+    };
+    let has_string = |key: &str| {
+        members
+            .iter()
+            .any(|m| value::unescape(&m.key) == key && matches!(m.node.value, Value::String(_)))
+    };
+    has_string("apiVersion") && has_string("kind")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NOISE_PATHS, clean};
+    use pretty::path::Path;
+    use pretty::value;
+
+    #[test]
+    fn clean_resources() {
+        let input = br#"{"kind": "List", "items": [
+            {"status": {"phase": "Running"}, "spec": {}, "kind": "Pod", "apiVersion": "v1",
+             "metadata": {"name": "a", "managedFields": [{"manager": "kubectl"}],
+               "annotations": {"kubectl.kubernetes.io/last-applied-configuration": "{}", "x": "y"}}}
+        ], "apiVersion": "v1", "metadata": {"resourceVersion": ""}}"#;
+        let mut root = value::parse(input).unwrap();
+        let mut noise = NOISE_PATHS.map(|p| p.parse::<Path>().unwrap()).to_vec();
+        noise.push(".status".parse().unwrap());
+        clean(&mut root, &noise);
+        assert_eq!(
+            root.to_compact_string(),
+            r#"{"apiVersion":"v1","kind":"List","metadata":{"resourceVersion":""},"items":[{"apiVersion":"v1","kind":"Pod","metadata":{"name":"a","annotations":{"x":"y"}},"spec":{}}]}"#
+        );
+    }
+}
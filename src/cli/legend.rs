@@ -0,0 +1,41 @@
+//! Legend of the colors of the output (`--legend`).
+use pretty::format::{OutputStyle, Style};
+
+/// Styles of the legend, with their names.
+const ENTRIES: [(Style, &str); 7] = [
+    (Style::Key, "key"),
+    (Style::String, "string"),
+    (Style::Number, "number"),
+    (Style::Boolean, "bool"),
+    (Style::Null, "null"),
+    (Style::Punctuation, "punct"),
+    (Style::Comment, "comment"),
+];
+
+/// Returns a line with the name of each kind of token, written with its style.
+pub fn legend(style: &impl OutputStyle) -> String {
+    let mut out = String::new();
+    for (i, (entry, name)) in ENTRIES.iter().enumerate() {
+        if i > 0 {
+            out.push_str("  ");
+        }
+        _ = style.write_styled(*entry, name, &mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::legend;
+    use pretty::format::{AnsiStyle, Color};
+
+    #[test]
+    fn print_legend() {
+        assert_eq!(
+            legend(&AnsiStyle::new(Color::NoColor)),
+            "key  string  number  bool  null  punct  comment"
+        );
+        let colored = legend(&AnsiStyle::new(Color::AnsiCode));
+        assert!(colored.starts_with("\x1b[1;34mkey\x1b[0m  \x1b[0;32mstring\x1b[0m"));
+    }
+}
@@ -0,0 +1,107 @@
+//! Timing of the phases of the formatting of an input (`--verbose`), printed on standard error.
+use crate::cli::Input;
+use pretty::value::escape;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Format of the `--verbose` logs.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum LogFormat {
+    /// One line of text per phase.
+    #[default]
+    Text,
+    /// One JSON object per line and per phase.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format {s} (expected text or json)")),
+        }
+    }
+}
+
+/// Logs the phases of an input, does nothing if logs are disabled.
+pub struct Log {
+    format: Option<LogFormat>,
+    /// Name of the input.
+    input: String,
+    /// Start of the current phase.
+    start: Instant,
+}
+
+impl Log {
+    /// Starts logging the phases of `input`.
+    pub fn new(format: Option<LogFormat>, input: &Input) -> Self {
+        Log {
+            format,
+            input: input.to_string().trim_matches('\'').to_string(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Logs the end of `phase`, that has processed `bytes`, and starts the next phase.
+    pub fn phase(&mut self, phase: &str, bytes: usize) {
+        let Some(format) = self.format else {
+            return;
+        };
+        eprintln!("{}", self.line(format, phase, self.start.elapsed(), bytes));
+        self.start = Instant::now();
+    }
+
+    /// Logs the format detected for the input.
+    pub fn detected(&self, format: impl fmt::Display) {
+        match self.format {
+            Some(LogFormat::Text) => eprintln!("{}: detected {format}", self.input),
+            Some(LogFormat::Json) => eprintln!(
+                r#"{{"input":{},"detected":"{format}"}}"#,
+                escape(&self.input)
+            ),
+            None => {}
+        }
+    }
+
+    fn line(&self, format: LogFormat, phase: &str, elapsed: Duration, bytes: usize) -> String {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        match format {
+            LogFormat::Text => {
+                format!("{}: {phase:<6} {ms:>10.3} ms {bytes:>12} bytes", self.input)
+            }
+            LogFormat::Json => format!(
+                r#"{{"input":{},"phase":"{phase}","elapsed_ms":{ms:.3},"bytes":{bytes}}}"#,
+                escape(&self.input)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Log, LogFormat};
+    use crate::cli::Input;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn log_lines() {
+        let log = Log::new(
+            Some(LogFormat::Json),
+            &Input::File(PathBuf::from("a \"b\".json")),
+        );
+        let elapsed = Duration::from_micros(1500);
+        assert_eq!(
+            log.line(LogFormat::Text, "read", elapsed, 42),
+            "a \"b\".json: read        1.500 ms           42 bytes"
+        );
+        assert_eq!(
+            log.line(LogFormat::Json, "read", elapsed, 42),
+            r#"{"input":"a \"b\".json","phase":"read","elapsed_ms":1.500,"bytes":42}"#
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Conversion of JSON to Markdown, to paste payloads in issues and wikis.
+//!
+//! Objects are bullet lists of `**key**: value` items and arrays are bullet lists of their items,
+//! nested containers being nested lists. With the table layout, arrays of objects are tables with
+//! a column per key, and objects are tables of keys and values. Multiline strings and strings
+//! containing JSON are fenced code blocks, other scalars are inline.
+use pretty::value::{Node, Value, parse, unescape};
+use std::str::FromStr;
+
+/// Layout of objects and arrays in Markdown.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum MarkdownLayout {
+    /// Nested bullet lists.
+    #[default]
+    List,
+    /// Tables for arrays of objects and for objects, lists otherwise.
+    Table,
+}
+
+impl FromStr for MarkdownLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "list" => Ok(MarkdownLayout::List),
+            "table" => Ok(MarkdownLayout::Table),
+            _ => Err(format!("unknown layout {s} (expected list or table)")),
+        }
+    }
+}
+
+/// Converts a JSON document to Markdown.
+pub fn to_markdown(root: &Node, layout: MarkdownLayout) -> String {
+    let mut out = String::new();
+    if layout == MarkdownLayout::Table {
+        match &root.value {
+            Value::Array(nodes) if !nodes.is_empty() && nodes.iter().all(is_object) => {
+                write_table(nodes, &mut out);
+                return out;
+            }
+            Value::Object(members) if !members.is_empty() => {
+                write_row(&["Key".to_string(), "Value".to_string()], &mut out);
+                write_row(&["---".to_string(), "---".to_string()], &mut out);
+                for member in members {
+                    let key = escape_cell(&unescape(&member.key));
+                    write_row(&[key, cell(&member.node)], &mut out);
+                }
+                return out;
+            }
+            _ => {}
+        }
+    }
+    match scalar(root) {
+        Some(Scalar::Inline(text)) => {
+            out.push_str(&text);
+            out.push('\n');
+        }
+        Some(Scalar::Fenced(lang, text)) => write_fenced(lang, &text, 0, &mut out),
+        None => write_list(root, 0, &mut out),
+    }
+    out
+}
+
+fn is_object(node: &Node) -> bool {
+    matches!(node.value, Value::Object(_))
+}
+
+/// A scalar value, or an empty object or array, in Markdown.
+enum Scalar {
+    /// Inline text.
+    Inline(String),
+    /// A fenced code block, with its language.
+    Fenced(&'static str, String),
+}
+
+/// Returns the Markdown of a scalar value, `None` for non-empty objects and arrays.
+fn scalar(node: &Node) -> Option<Scalar> {
+    let scalar = match &node.value {
+        Value::Null => Scalar::Inline("`null`".to_string()),
+        Value::Bool(b) => Scalar::Inline(format!("`{b}`")),
+        Value::Number(n) => Scalar::Inline(format!("`{n}`")),
+        Value::String(raw) => {
+            let s = unescape(raw);
+            let trimmed = s.trim();
+            let json = (trimmed.starts_with('{') || trimmed.starts_with('['))
+                && parse(trimmed.as_bytes()).is_ok();
+            if json {
+                Scalar::Fenced("json", s)
+            } else if s.contains('\n') {
+                Scalar::Fenced("", s)
+            } else {
+                Scalar::Inline(escape_text(&s))
+            }
+        }
+        Value::Array(nodes) if nodes.is_empty() => Scalar::Inline("`[]`".to_string()),
+        Value::Object(members) if members.is_empty() => Scalar::Inline("`{}`".to_string()),
+        Value::Array(_) | Value::Object(_) => return None,
+    };
+    Some(scalar)
+}
+
+/// Writes the items of a non-empty object or array as a bullet list. Object members are
+/// labelled by their keys, array items by their indexes when they're not inline.
+fn write_list(node: &Node, depth: usize, out: &mut String) {
+    let items: Vec<(bool, String, &Node)> = match &node.value {
+        Value::Object(members) => members
+            .iter()
+            .map(|m| {
+                (
+                    true,
+                    format!("**{}**:", escape_text(&unescape(&m.key))),
+                    &m.node,
+                )
+            })
+            .collect(),
+        Value::Array(nodes) => nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (false, format!("**[{i}]**"), node))
+            .collect(),
+        _ => return,
+    };
+    let indent = "  ".repeat(depth);
+    for (keyed, label, node) in items {
+        out.push_str(&indent);
+        out.push_str("- ");
+        match scalar(node) {
+            Some(Scalar::Inline(text)) if keyed => out.push_str(&format!("{label} {text}\n")),
+            Some(Scalar::Inline(text)) => out.push_str(&format!("{text}\n")),
+            Some(Scalar::Fenced(lang, text)) => {
+                out.push_str(&format!("{label}\n"));
+                write_fenced(lang, &text, depth + 1, out);
+            }
+            None => {
+                out.push_str(&format!("{label}\n"));
+                write_list(node, depth + 1, out);
+            }
+        }
+    }
+}
+
+/// Writes a fenced code block, indented by `depth` list levels. The fence is longer than the
+/// backticks of the text.
+fn write_fenced(lang: &str, text: &str, depth: usize, out: &mut String) {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    let indent = "  ".repeat(depth);
+    out.push_str(&format!("{indent}{fence}{lang}\n"));
+    for line in text.lines() {
+        out.push_str(&indent);
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str(&format!("{indent}{fence}\n"));
+}
+
+/// Writes an array of objects as a table, with a column per key.
+fn write_table(rows: &[Node], out: &mut String) {
+    let mut columns = Vec::new();
+    for row in rows {
+        if let Value::Object(members) = &row.value {
+            for member in members {
+                let key = unescape(&member.key);
+                if !columns.contains(&key) {
+                    columns.push(key);
+                }
+            }
+        }
+    }
+    let header = columns
+        .iter()
+        .map(|key| escape_cell(key))
+        .collect::<Vec<_>>();
+    write_row(&header, out);
+    write_row(&vec!["---".to_string(); columns.len()], out);
+    for row in rows {
+        let Value::Object(members) = &row.value else {
+            continue;
+        };
+        let cells = columns
+            .iter()
+            .map(|key| {
+                members
+                    .iter()
+                    .find(|m| unescape(&m.key) == *key)
+                    .map_or_else(String::new, |m| cell(&m.node))
+            })
+            .collect::<Vec<_>>();
+        write_row(&cells, out);
+    }
+}
+
+fn write_row(cells: &[String], out: &mut String) {
+    out.push_str("| ");
+    out.push_str(&cells.join(" | "));
+    out.push_str(" |\n");
+}
+
+/// Returns the content of a table cell: strings as text, other values as inline code.
+fn cell(node: &Node) -> String {
+    match &node.value {
+        Value::String(raw) => escape_cell(&escape_text(&unescape(raw))),
+        _ => escape_cell(&format!("`{}`", node.to_compact_string())),
+    }
+}
+
+/// Escapes the chars of a table cell: `|` and line breaks.
+fn escape_cell(s: &str) -> String {
+    s.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+/// Escapes the Markdown chars of a text.
+fn escape_text(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MarkdownLayout, to_markdown};
+    use pretty::value::parse;
+
+    #[test]
+    fn convert_to_markdown_lists() {
+        let input = br#"{
+            "name": "api_v2",
+            "port": 8080,
+            "tags": ["a", {"b": null}, []],
+            "script": "echo 1\necho 2",
+            "payload": "{\"id\": 1}",
+            "empty": {}
+        }"#;
+        let root = parse(input).unwrap();
+        assert_eq!(
+            to_markdown(&root, MarkdownLayout::List),
+            r#"- **name**: api\_v2
+- **port**: `8080`
+- **tags**:
+  - a
+  - **[1]**
+    - **b**: `null`
+  - `[]`
+- **script**:
+  ```
+  echo 1
+  echo 2
+  ```
+- **payload**:
+  ```json
+  {"id": 1}
+  ```
+- **empty**: `{}`
+"#
+        );
+        assert_eq!(
+            to_markdown(&parse(b"\"a\"").unwrap(), MarkdownLayout::List),
+            "a\n"
+        );
+    }
+
+    #[test]
+    fn convert_to_markdown_tables() {
+        let input = br#"[{"name": "a|b", "n": 1}, {"name": "c", "tags": ["x"]}]"#;
+        let root = parse(input).unwrap();
+        assert_eq!(
+            to_markdown(&root, MarkdownLayout::Table),
+            r#"| name | n | tags |
+| --- | --- | --- |
+| a\|b | `1` |  |
+| c |  | `["x"]` |
+"#
+        );
+        let root = parse(br#"{"a": "x\ny", "b": true}"#).unwrap();
+        assert_eq!(
+            to_markdown(&root, MarkdownLayout::Table),
+            "| Key | Value |\n| --- | --- |\n| a | x<br>y |\n| b | `true` |\n"
+        );
+    }
+}
@@ -0,0 +1,55 @@
+pub mod agg;
+pub mod anonymize;
+pub mod ansi;
+pub mod aws;
+pub mod base64;
+pub mod config_file;
+pub mod debug_dump;
+pub mod detect;
+pub mod diff;
+pub mod editorconfig;
+pub mod example;
+pub mod files;
+pub mod form;
+pub mod git;
+pub mod har;
+pub mod hash;
+pub mod http;
+pub mod jwt;
+pub mod k8s;
+pub mod legend;
+pub mod log;
+pub mod markdown;
+pub mod ndjson;
+mod options;
+pub mod outline;
+pub mod preset;
+pub mod profile;
+pub mod proto;
+pub mod repl;
+pub mod serve;
+pub mod side_by_side;
+pub mod split;
+pub mod svg;
+pub mod table;
+pub mod terminal;
+pub mod tf;
+pub mod transform;
+pub mod verify;
+pub mod watch;
+pub mod xml;
+
+pub use options::{
+    Bom, Command, Config, ErrorFormat, Explode, Input, InputFormat, OutputFormat, parse_args,
+};
+
+/// The JSON input has been successfully formatted.
+pub const EXIT_OK: i32 = 0;
+/// The JSON input is invalid.
+pub const EXIT_INVALID_JSON: i32 = 1;
+/// An I/O error occurred (reading a file, fetching an URL, binding a socket etc...).
+pub const EXIT_IO_ERROR: i32 = 2;
+/// The command line arguments are invalid.
+pub const EXIT_USAGE: i32 = 3;
+/// Some inputs are not formatted (in check mode).
+pub const EXIT_UNFORMATTED: i32 = 4;
@@ -0,0 +1,493 @@
+use pretty::path::Path;
+use pretty::value;
+use pretty::value::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// A record of an NDJSON input.
+pub struct Record<'input> {
+    /// The 1-based line number of this record in the input.
+    pub line: usize,
+    /// The record bytes, without its line ending.
+    pub bytes: &'input [u8],
+}
+
+/// Returns the records of an NDJSON input: its non-blank lines.
+pub fn records(input: &[u8]) -> impl Iterator<Item = Record<'_>> {
+    input
+        .split(|&b| b == b'\n')
+        .enumerate()
+        .map(|(i, bytes)| Record {
+            line: i + 1,
+            bytes: bytes.strip_suffix(b"\r").unwrap_or(bytes),
+        })
+        .filter(|record| !record.bytes.iter().all(u8::is_ascii_whitespace))
+}
+
+/// Returns the last `n` items of an iterator.
+pub fn last<T>(iter: impl Iterator<Item = T>, n: usize) -> VecDeque<T> {
+    let mut items = VecDeque::with_capacity(n);
+    if n == 0 {
+        return items;
+    }
+    for item in iter {
+        if items.len() == n {
+            items.pop_front();
+        }
+        items.push_back(item);
+    }
+    items
+}
+
+/// Counts the records by their value at `path`, returns the groups sorted by decreasing count
+/// (then by value). String values are unescaped, records without a value at `path` are counted
+/// in `(missing)`.
+///
+/// Only the value at `path` is read, other values of the records are skipped without being
+/// parsed.
+pub fn group_count<'a>(
+    records: impl Iterator<Item = Record<'a>>,
+    path: &Path,
+) -> Vec<(String, usize)> {
+    let mut groups = HashMap::new();
+    for record in records {
+        let group = match value::get_raw(record.bytes, path) {
+            Ok(Some(raw)) if raw.starts_with('"') => value::unescape(raw),
+            Ok(Some(raw)) => raw.to_string(),
+            Ok(None) => "(missing)".to_string(),
+            Err(_) => "(invalid)".to_string(),
+        };
+        *groups.entry(group).or_insert(0) += 1;
+    }
+    let mut groups = groups.into_iter().collect::<Vec<_>>();
+    groups.sort_by(|(v1, c1), (v2, c2)| c2.cmp(c1).then_with(|| v1.cmp(v2)));
+    groups
+}
+
+/// Returns a table of the groups of [group_count], with their counts and percentages.
+pub fn group_table(groups: &[(String, usize)], path: &Path) -> String {
+    let total = groups.iter().map(|(_, count)| count).sum::<usize>();
+    let mut out = String::new();
+    _ = writeln!(out, "{:>9} {:>7}  {path}", "count", "%");
+    for (group, count) in groups {
+        let percent = *count as f64 * 100.0 / total.max(1) as f64;
+        _ = writeln!(out, "{count:>9} {percent:>6.1}%  {group}");
+    }
+    _ = writeln!(out, "{total:>9} {:>6.1}%  (total)", 100.0);
+    out
+}
+
+/// An action of an Elasticsearch `_bulk` request (ex: `{"index":{"_id":"1"}}`), with its
+/// document for `index`, `create` and `update` actions.
+pub struct BulkAction<'input> {
+    pub action: Record<'input>,
+    pub document: Option<Record<'input>>,
+}
+
+/// Returns the actions of an Elasticsearch `_bulk` request body, where action lines are followed
+/// by a document line (except for `delete` actions).
+pub fn bulk_actions(input: &[u8]) -> Result<Vec<BulkAction<'_>>, String> {
+    let mut records = records(input);
+    let mut actions = Vec::new();
+    while let Some(action) = records.next() {
+        let name = match value::parse(action.bytes).map(|root| root.value) {
+            Ok(Value::Object(members)) if members.len() == 1 => value::unescape(&members[0].key),
+            _ => String::new(),
+        };
+        let document = match name.as_str() {
+            "delete" => None,
+            "index" | "create" | "update" => {
+                let Some(document) = records.next() else {
+                    let err = format!("line {}: missing document of {name} action", action.line);
+                    return Err(err);
+                };
+                Some(document)
+            }
+            _ => {
+                let err = format!(
+                    "line {}: expected a bulk action (index, create, update or delete)",
+                    action.line
+                );
+                return Err(err);
+            }
+        };
+        actions.push(BulkAction { action, document });
+    }
+    Ok(actions)
+}
+
+/// A template printed before each record, ex: `--- record {n} ({bytes} bytes) ---`.
+///
+/// Placeholders are `{n}` (the 1-based record index), `{line}` (the line number in the input),
+/// `{bytes}` (the record size) and any path in the record (ex: `{.timestamp}`). `{{` and `}}` are
+/// literal braces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Index,
+    Line,
+    Bytes,
+    Field(Path),
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed placeholder {{{name}")),
+                        }
+                    }
+                    let part = match name.as_str() {
+                        "n" => Part::Index,
+                        "line" => Part::Line,
+                        "bytes" => Part::Bytes,
+                        path if path.starts_with(['.', '[']) => Part::Field(path.parse()?),
+                        _ => return Err(format!("unknown placeholder {{{name}}}")),
+                    };
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(part);
+                }
+                _ => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+}
+
+impl Template {
+    /// Renders this template for the `n`th record (1-based) at the end of `out`. Fields missing
+    /// in the record (or an invalid record) are rendered empty.
+    pub fn render(&self, n: usize, record: &Record, out: &mut String) {
+        let has_fields = self.parts.iter().any(|p| matches!(p, Part::Field(_)));
+        let root = if has_fields {
+            value::parse(record.bytes).ok()
+        } else {
+            None
+        };
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Index => _ = write!(out, "{n}"),
+                Part::Line => _ = write!(out, "{}", record.line),
+                Part::Bytes => _ = write!(out, "{}", record.bytes.len()),
+                Part::Field(path) => {
+                    if let Some(node) = root.as_ref().and_then(|root| root.get(path)) {
+                        out.push_str(&node.to_text());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Records selection by timestamp, in milliseconds since the Unix epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeWindow {
+    /// Path of the timestamp in the records.
+    pub field: Path,
+    /// Records before this timestamp are skipped.
+    pub since: Option<i64>,
+    /// Records after this timestamp are skipped.
+    pub until: Option<i64>,
+}
+
+impl TimeWindow {
+    /// Returns `true` if this record has a timestamp within this window.
+    ///
+    /// Only the timestamp field is read, other values of the record are skipped without being
+    /// parsed.
+    pub fn contains(&self, record: &[u8]) -> bool {
+        let Ok(Some(raw)) = value::get_raw(record, &self.field) else {
+            return false;
+        };
+        let time = match raw.strip_prefix('"') {
+            Some(_) => parse_timestamp(&value::unescape(raw)),
+            None => raw.parse::<f64>().ok().map(epoch_millis),
+        };
+        let Some(time) = time else {
+            return false;
+        };
+        self.since.is_none_or(|since| time >= since) && self.until.is_none_or(|until| time <= until)
+    }
+}
+
+/// Converts a Unix time in seconds, or in milliseconds if it's too large to be seconds, to
+/// milliseconds.
+fn epoch_millis(time: f64) -> i64 {
+    if time.abs() < 1e11 {
+        (time * 1000.0) as i64
+    } else {
+        time as i64
+    }
+}
+
+/// Parses a timestamp to milliseconds since the Unix epoch.
+///
+/// Accepted formats are RFC 3339 / ISO 8601 dates and times (`2024-01-31`,
+/// `2024-01-31T10:00:00.123Z`, `2024-01-31 10:00:00+02:00`, UTC if there's no offset) and Unix
+/// times (in seconds or milliseconds).
+pub fn parse_timestamp(s: &str) -> Option<i64> {
+    if let Ok(time) = s.parse::<f64>() {
+        return Some(epoch_millis(time));
+    }
+    let b = s.as_bytes();
+    let num = |range| digits(b, range);
+    if b.len() < 10 || b[4] != b'-' || b[7] != b'-' {
+        return None;
+    }
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+    let mut rest = &b[10..];
+    if let [b'T' | b't' | b' ', time @ ..] = rest {
+        let b = time;
+        let num = |range| digits(b, range);
+        if b.get(2) != Some(&b':') {
+            return None;
+        }
+        let (hours, minutes) = (num(0..2)?, num(3..5)?);
+        millis += (hours * 60 + minutes) * 60_000;
+        let mut i = 5;
+        if b.get(i) == Some(&b':') {
+            millis += num(6..8)? * 1000;
+            i = 8;
+            if b.get(i) == Some(&b'.') {
+                let frac_len = b[i + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+                // Only milliseconds are kept: "5" is 500ms, "123456" is 123ms.
+                let mut frac = b[i + 1..i + 1 + frac_len].to_vec();
+                frac.resize(3, b'0');
+                millis += digits(&frac, 0..3)?;
+                i += 1 + frac_len;
+            }
+        }
+        rest = &time[i..];
+    }
+    match rest {
+        [] | [b'Z' | b'z'] => Some(millis),
+        [sign @ (b'+' | b'-'), offset @ ..] => {
+            let offset = offset
+                .iter()
+                .filter(|&&b| b != b':')
+                .copied()
+                .collect::<Vec<_>>();
+            if offset.len() != 4 {
+                return None;
+            }
+            let (hours, minutes) = (digits(&offset, 0..2)?, digits(&offset, 2..4)?);
+            let offset = (hours * 60 + minutes) * 60_000;
+            Some(if *sign == b'+' {
+                millis - offset
+            } else {
+                millis + offset
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Parses the end of a time window, as [parse_timestamp]: a date without time (`2024-01-31`) is
+/// the end of this day, included.
+pub fn parse_end_timestamp(s: &str) -> Option<i64> {
+    let time = parse_timestamp(s)?;
+    let date_only = s.len() == 10 && s.as_bytes()[4] == b'-';
+    Some(if date_only { time + 86_399_999 } else { time })
+}
+
+/// Parses the decimal digits of `b` in `range`.
+fn digits(b: &[u8], range: std::ops::Range<usize>) -> Option<i64> {
+    let digits = b.get(range)?;
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    std::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+/// Returns the number of days since 1970-01-01 of a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // From <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Record, Template, TimeWindow, bulk_actions, group_count, group_table, last,
+        parse_end_timestamp, parse_timestamp, records,
+    };
+
+    #[test]
+    fn split_records() {
+        let input = b"{\"a\":1}\r\n\n  \n[2]\n3";
+        let records = records(input)
+            .map(|r| (r.line, r.bytes))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            vec![(1, &b"{\"a\":1}"[..]), (4, &b"[2]"[..]), (5, &b"3"[..])]
+        );
+    }
+
+    #[test]
+    fn count_groups() {
+        let input = br#"{"level":"info"}
+{"level":"warn","n":[1,2]}
+{"msg":"no level"}
+{"level":"info"}
+{"level":"a\u0062"}
+{"level":
+"#;
+        let path = ".level".parse().unwrap();
+        let groups = group_count(records(input), &path);
+        assert_eq!(
+            groups,
+            [
+                ("info".to_string(), 2),
+                ("(invalid)".to_string(), 1),
+                ("(missing)".to_string(), 1),
+                ("ab".to_string(), 1),
+                ("warn".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            group_table(&groups[..2], &path),
+            "    count       %  .level
+        2   66.7%  info
+        1   33.3%  (invalid)
+        3  100.0%  (total)
+"
+        );
+    }
+
+    #[test]
+    fn last_items() {
+        assert_eq!(last(1..=5, 2), [4, 5]);
+        assert_eq!(last(1..=5, 8), [1, 2, 3, 4, 5]);
+        assert!(last(1..=5, 0).is_empty());
+    }
+
+    #[test]
+    fn split_bulk_actions() {
+        let input = br#"{"index":{"_index":"logs","_id":"1"}}
+{"msg":"a"}
+{"delete":{"_id":"2"}}
+{"update":{"_id":"3"}}
+{"doc":{"msg":"b"}}
+"#;
+        let actions = bulk_actions(input)
+            .unwrap()
+            .iter()
+            .map(|a| (a.action.line, a.document.as_ref().map(|d| d.line)))
+            .collect::<Vec<_>>();
+        assert_eq!(actions, vec![(1, Some(2)), (3, None), (4, Some(5))]);
+        assert_eq!(
+            bulk_actions(b"{\"index\":{}}\n").err().unwrap(),
+            "line 1: missing document of index action"
+        );
+        assert_eq!(
+            bulk_actions(b"{\"msg\":\"a\"}").err().unwrap(),
+            "line 1: expected a bulk action (index, create, update or delete)"
+        );
+    }
+
+    #[test]
+    fn render_template() {
+        let template = "--- {n} {{{bytes}}} {.ts} {.user.name}{.missing} ---"
+            .parse::<Template>()
+            .unwrap();
+        let record = Record {
+            line: 3,
+            bytes: br#"{"ts":"2024-01-01","user":{"name":1}}"#,
+        };
+        let mut out = String::new();
+        template.render(2, &record, &mut out);
+        assert_eq!(out, "--- 2 {37} 2024-01-01 1 ---");
+        assert!("{foo}".parse::<Template>().is_err());
+        assert!("{n".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn parse_timestamps() {
+        assert_eq!(parse_timestamp("1970-01-01"), Some(0));
+        assert_eq!(
+            parse_timestamp("2024-02-29T12:30:15Z"),
+            Some(1_709_209_815_000)
+        );
+        assert_eq!(
+            parse_timestamp("2024-02-29 12:30:15.5"),
+            Some(1_709_209_815_500)
+        );
+        assert_eq!(
+            parse_timestamp("2024-02-29T14:30:15+02:00"),
+            Some(1_709_209_815_000)
+        );
+        assert_eq!(
+            parse_timestamp("2024-02-29T12:30Z"),
+            Some(1_709_209_800_000)
+        );
+        assert_eq!(parse_timestamp("1709209815"), Some(1_709_209_815_000));
+        assert_eq!(parse_timestamp("1709209815000"), Some(1_709_209_815_000));
+        assert_eq!(parse_timestamp("2024-13-01"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn filter_records_by_time() {
+        let window = TimeWindow {
+            field: ".ts".parse().unwrap(),
+            since: parse_timestamp("2024-01-02"),
+            until: parse_end_timestamp("2024-01-03"),
+        };
+        assert!(window.contains(br#"{"msg":{"ts":0},"ts":"2024-01-02T10:00:00Z"}"#));
+        assert!(window.contains(br#"{"ts":1704189600}"#));
+        assert!(!window.contains(br#"{"ts":"2024-01-01T23:59:59Z"}"#));
+        // A date-only end includes the whole day.
+        assert!(window.contains(br#"{"ts":"2024-01-03T23:59:59.999Z"}"#));
+        assert!(!window.contains(br#"{"ts":"2024-01-04T00:00:00Z"}"#));
+        assert_eq!(
+            parse_end_timestamp("2024-01-03T10:00:00Z"),
+            parse_timestamp("2024-01-03T10:00:00Z")
+        );
+        assert_eq!(
+            parse_end_timestamp("1704189600"),
+            parse_timestamp("1704189600")
+        );
+        assert!(!window.contains(br#"{"time":"2024-01-02"}"#));
+    }
+}
@@ -0,0 +1,1930 @@
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::cli::EXIT_OK;
+use crate::cli::agg::Aggregation;
+use crate::cli::anonymize::{self, Pii};
+use crate::cli::config_file;
+use crate::cli::hash::HashAlgorithm;
+use crate::cli::k8s;
+use crate::cli::log::LogFormat;
+use crate::cli::markdown::MarkdownLayout;
+use crate::cli::ndjson::{Template, TimeWindow, parse_end_timestamp, parse_timestamp};
+use crate::cli::preset::Preset;
+use crate::cli::split::Split;
+use crate::cli::table::parse_columns;
+use crate::cli::xml::{XmlOptions, element_name};
+use pretty::decorate::{FileSizes, HexFields, Humanize, Humanizer};
+use pretty::format::{Folds, Indent, LineEnding};
+use pretty::map::Map;
+use pretty::path::{Path, Segment};
+use pretty::query::Query;
+use pretty::theme::{Background, FieldType, Theme, TypeMap};
+use pretty::transform::{Case, Prune};
+
+/// The command run by the CLI.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Command {
+    /// Formats a JSON file (or stdin).
+    Format,
+    /// Formats each JSON snippet typed on the standard input.
+    Repl,
+    /// Runs an HTTP server formatting POSTed JSON.
+    Serve,
+    /// Displays two JSON documents in two columns.
+    SideBySide,
+    /// Checks (or formats) the JSON files staged in a git repository.
+    GitHook,
+    /// Prints an example document generated from a JSON Schema.
+    Example,
+}
+
+/// Format of the input.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InputFormat {
+    /// Detected from the first bytes of the input.
+    Auto,
+    /// JSON, or base64-wrapped JSON (detected).
+    Json,
+    Xml,
+    /// URL-encoded form or query string.
+    Form,
+    /// JSON Web Token, its header and payload are decoded.
+    Jwt,
+    /// Base64-wrapped JSON.
+    Base64,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(InputFormat::Auto),
+            "json" => Ok(InputFormat::Json),
+            "xml" => Ok(InputFormat::Xml),
+            "form" => Ok(InputFormat::Form),
+            "jwt" => Ok(InputFormat::Jwt),
+            "base64" => Ok(InputFormat::Base64),
+            _ => Err(format!(
+                "unknown format {s} (expected auto, json, xml, form, jwt or base64)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InputFormat::Auto => "auto",
+            InputFormat::Json => "json",
+            InputFormat::Xml => "xml",
+            InputFormat::Form => "form",
+            InputFormat::Jwt => "jwt",
+            InputFormat::Base64 => "base64",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Format of the output.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Xml,
+    Markdown,
+    Svg,
+    AnsiHtml,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "xml" => Ok(OutputFormat::Xml),
+            "markdown" => Ok(OutputFormat::Markdown),
+            "svg" => Ok(OutputFormat::Svg),
+            "ansi-html" => Ok(OutputFormat::AnsiHtml),
+            _ => Err(format!(
+                "unknown format {s} (expected json, xml, markdown, svg or ansi-html)"
+            )),
+        }
+    }
+}
+
+/// Format of the errors printed on standard error.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum ErrorFormat {
+    /// One line of text per error (or a diff of an unformatted input).
+    #[default]
+    Text,
+    /// One JSON object per line and per error, with the input, the exit code and the message.
+    Json,
+}
+
+impl FromStr for ErrorFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ErrorFormat::Text),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(format!("unknown error format {s} (expected text or json)")),
+        }
+    }
+}
+
+/// Layout of the records printed by `--explode`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Explode {
+    /// One compact record per line (NDJSON).
+    Compact,
+    /// Pretty printed records.
+    Pretty,
+}
+
+/// UTF-8 byte order mark (BOM) policy of the output.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Bom {
+    /// Write a BOM if the input starts with one.
+    Keep,
+    /// Never write a BOM.
+    #[default]
+    Strip,
+    /// Always write a BOM.
+    Add,
+}
+
+impl FromStr for Bom {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Bom::Keep),
+            "strip" => Ok(Bom::Strip),
+            "add" => Ok(Bom::Add),
+            _ => Err(format!(
+                "unknown BOM policy {s} (expected keep, strip or add)"
+            )),
+        }
+    }
+}
+
+/// Where the JSON input is read from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Input {
+    /// The standard input, with the name used in messages if any (`--stdin-name`).
+    Stdin(Option<String>),
+    File(PathBuf),
+    /// An HTTP(S) URL, fetched with a GET request.
+    Url(String),
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Stdin(Some(name)) => write!(f, "'{name}'"),
+            Input::Stdin(None) => write!(f, "<stdin>"),
+            Input::File(path) => write!(f, "'{}'", path.display()),
+            Input::Url(url) => write!(f, "'{url}'"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub command: Command,
+    pub with_serde: bool,
+    /// Compare the formatter with serde on the input instead of formatting it.
+    pub verify: bool,
+    pub with_color: bool,
+    pub iter_count: usize,
+    /// The inputs to format, at least one for the `Format` command.
+    pub inputs: Vec<Input>,
+    /// Additional HTTP headers (`Name: Value`) sent when input is an URL.
+    pub headers: Vec<(String, String)>,
+    /// Address the server listens on (`serve` command).
+    pub bind: String,
+    /// Port the server listens on (`serve` command).
+    pub port: u16,
+    /// JSON Schema of the generated document (`example` command).
+    pub schema: Option<PathBuf>,
+    /// Re-format the input file each time it changes.
+    pub watch: bool,
+    /// Don't write anything, only signal validity with the exit code.
+    pub quiet: bool,
+    /// With multiple inputs, report errors and go on with the next input.
+    pub skip_errors: bool,
+    /// Format of the errors printed on standard error.
+    pub error_format: ErrorFormat,
+    /// On invalid JSON, print the output formatted before the error.
+    pub partial: bool,
+    /// Print the warnings of the inputs (duplicate keys, lossy numbers...) on standard error.
+    pub warnings: bool,
+    /// Fail on inputs with warnings.
+    pub deny_warnings: bool,
+    /// Write invisible chars of strings as `\u` escapes.
+    pub reveal_invisible: bool,
+    /// Format files in place instead of printing them.
+    pub write: bool,
+    /// Skip files ignored by `.gitignore` when walking directories and globs.
+    pub gitignore: bool,
+    /// Check that files are formatted, without printing or modifying them.
+    pub check: bool,
+    /// In check mode, print a diff of what would change.
+    pub diff: bool,
+    /// Output width in characters (`side-by-side` command), the terminal width if not set.
+    pub width: Option<usize>,
+    /// Print a report of the largest subtrees and keys instead of the formatted document.
+    pub profile: bool,
+    /// Number of entries in reports.
+    pub top: usize,
+    /// Print an outline of the keys instead of the formatted document.
+    pub keys_only: bool,
+    /// Maximum number of levels of keys in the outline.
+    pub depth: Option<usize>,
+    /// Rename all object keys to this case convention.
+    pub rename_keys: Option<Case>,
+    /// Remove object members whose values are null or empty.
+    pub prune: Option<Prune>,
+    /// Also remove object members whose values become empty after pruning.
+    pub prune_fixed_point: bool,
+    /// Values to replace: a path and a JSON value.
+    pub sets: Vec<(Path, String)>,
+    /// Transform programs, applied in order.
+    pub maps: Vec<Map>,
+    /// Sort object members by key.
+    pub sort_keys: bool,
+    /// Rewrite numbers in a canonical form (`1.50E+07` is `1.5e7`).
+    pub normalize_numbers: bool,
+    /// Rewrite floats with the shortest decimal parsing back to the same double.
+    pub shortest_floats: bool,
+    /// Write the document on one line, without whitespace.
+    pub compact: bool,
+    /// Extract strings larger than a size (in bytes) to files in a directory.
+    pub extract_strings: Option<(usize, PathBuf)>,
+    /// Clean Kubernetes resources, removing the values at these paths of each resource.
+    pub k8s: Option<Vec<Path>>,
+    /// Replace these kinds of strings by pseudonyms derived from a key.
+    pub anonymize: Option<(Pii, String)>,
+    /// Select values with a query, printed instead of the document.
+    pub query: Option<Query>,
+    /// Print the selected values as NDJSON instead of an array.
+    pub query_ndjson: bool,
+    /// Print the records of an NDJSON input as the items of an array.
+    pub collect: bool,
+    /// Print the hash of the canonical form of the document instead of the document.
+    pub hash: Option<HashAlgorithm>,
+    /// With `hash`, also print the document, the hash being printed on standard error.
+    pub hash_with_output: bool,
+    /// Write the records of `--ndjson` and `--explode` to numbered files.
+    pub split: Option<Split>,
+    /// Print each item of a top-level array as a record: on one line (NDJSON), or pretty
+    /// printed.
+    pub explode: Option<Explode>,
+    /// Aggregations to compute, printed instead of the document.
+    pub aggregations: Vec<Aggregation>,
+    /// Print these fields of the objects of an array (or of NDJSON records) as a table.
+    pub table: Option<Vec<Path>>,
+    /// Semantic types of fields, colorized with dedicated colors.
+    pub types: TypeMap,
+    /// Align values of each object in a column.
+    pub align_values: bool,
+    /// Write empty objects and arrays on two lines.
+    pub expand_empty: bool,
+    /// Write objects and arrays with a single member on one line.
+    pub compact_single: bool,
+    /// Number of threads formatting the items of a top-level array.
+    pub threads: usize,
+    /// Maximum size of an input, in bytes.
+    pub max_input_size: Option<usize>,
+    /// Maximum size of a formatted output, in bytes.
+    pub max_output_size: Option<usize>,
+    /// Maximum number of values of an input.
+    pub max_elements: Option<usize>,
+    /// Maximum length of keys, in bytes.
+    pub max_key_length: Option<usize>,
+    /// Maximum length of string values, in bytes.
+    pub max_string_length: Option<usize>,
+    /// Line breaks of the output, `\n` if not set.
+    pub line_ending: Option<LineEnding>,
+    /// Indentation of the output, 2 spaces if not set.
+    pub indent: Option<Indent>,
+    /// With `--write` and `--check`, use the settings of `.editorconfig` files not set by options.
+    pub editorconfig: bool,
+    /// End the output with a line break. If not set, the output ends with a line break, except
+    /// with `--write` and `--check` where the convention of the original file is kept.
+    pub final_newline: Option<bool>,
+    /// Write a UTF-8 byte order mark at the start of the output.
+    pub bom: Bom,
+    /// Annotate matching fields with humanized values (display only).
+    pub humanizers: Vec<Humanizer>,
+    /// Annotate integers that can't be represented exactly by a double (display only).
+    pub js_unsafe: bool,
+    /// Annotate the integers of matching fields with their hexadecimal and binary values
+    /// (display only).
+    pub hex_fields: HexFields,
+    /// Annotate the sizes in bytes of matching fields with binary units (display only).
+    pub file_sizes: FileSizes,
+    /// Summarize GeoJSON coordinates arrays (display only).
+    pub geojson: bool,
+    /// Display Avro JSON encoded unions as their annotated values.
+    pub avro: bool,
+    /// Annotate proto3 JSON values (64-bit integers, durations, timestamps, Any types).
+    pub proto: bool,
+    /// Check the proto3 JSON shapes of the document before formatting.
+    pub proto_strict: bool,
+    /// Objects and arrays displayed as one-line summaries.
+    pub folds: Folds,
+    /// Accept and preserve comments (JSONC).
+    pub jsonc: bool,
+    /// Format each line of the input as a separate record (NDJSON).
+    pub ndjson: bool,
+    /// List the entries of an HTTP Archive (HAR) file.
+    pub har: bool,
+    /// Print the request and response of this HAR entry (1-based) instead of listing entries.
+    pub har_entry: Option<usize>,
+    /// Format each event of an AWS envelope (CloudTrail records, CloudWatch Logs events).
+    pub aws: bool,
+    /// List the resource changes of a Terraform plan (or the resources of a state).
+    pub tf: bool,
+    /// Print this Terraform resource (by address) instead of listing resources.
+    pub tf_address: Option<String>,
+    /// Format the input as an Elasticsearch `_bulk` request body.
+    pub es_bulk: bool,
+    /// Header printed before each NDJSON record.
+    pub record_template: Option<Template>,
+    /// Only NDJSON records within this time window are printed.
+    pub time_window: Option<TimeWindow>,
+    /// Only print every Nth NDJSON record.
+    pub sample: Option<usize>,
+    /// Only print the first N NDJSON records.
+    pub head: Option<usize>,
+    /// Only print the last N NDJSON records.
+    pub tail: Option<usize>,
+    /// Print consecutive identical NDJSON records once, with their count.
+    pub dedup: bool,
+    /// Print the number of NDJSON records per value at this path instead of the records.
+    pub group_by: Option<Path>,
+    /// Format string values containing JSON as nested JSON (display only).
+    pub expand_embedded: bool,
+    /// Render `\n` in string values as line breaks (display only).
+    pub render_newlines: bool,
+    /// Style the quotes and the escape sequences of strings separately from their content.
+    pub split_strings: bool,
+    /// Print a legend of the colors after the output.
+    pub legend: bool,
+    /// Colors of the output.
+    pub theme: Theme,
+    /// Choose the theme from the background of the terminal, when the output is a terminal.
+    pub detect_background: bool,
+    /// Write URL string values as OSC 8 hyperlinks.
+    pub hyperlinks: bool,
+    /// Write hyperlinks if the output is a terminal supporting them.
+    pub detect_hyperlinks: bool,
+    /// Only remove ANSI escape sequences from the input.
+    pub strip_ansi: bool,
+    /// Print the parsed tokens instead of formatting.
+    pub debug_dump: bool,
+    /// Print formatting statistics on standard error.
+    pub report: bool,
+    /// Print the duration of each phase (read, format, write...) on standard error, if set.
+    pub log: Option<LogFormat>,
+    /// Format of the input, converted to JSON before formatting.
+    pub from: InputFormat,
+    /// Format of the output.
+    pub to: OutputFormat,
+    /// Options of the XML output.
+    pub xml: XmlOptions,
+    /// Layout of the Markdown output.
+    pub markdown_layout: MarkdownLayout,
+}
+
+/// Paths folded by `--openapi`: schemas (OpenAPI 3 and Swagger 2) and operation responses.
+const OPENAPI_FOLDS: [&str; 3] = [
+    ".components.schemas.*",
+    ".definitions.*",
+    ".paths[*].*.responses",
+];
+
+const USAGE: &str = "\
+Usage: pretty [OPTIONS] <JSON_FILE>...
+       pretty [OPTIONS] repl
+       pretty [OPTIONS] serve
+       pretty [OPTIONS] side-by-side <JSON_FILE> <JSON_FILE>
+       pretty [OPTIONS] git-hook
+       pretty [OPTIONS] example --schema <SCHEMA_FILE>
+
+A fast JSON pretty-printer
+
+Commands:
+  repl         Format each JSON line (or block ended by a blank line) typed on stdin
+  serve        Run an HTTP server formatting the JSON POSTed on any path
+  side-by-side Display two JSON documents side by side (width from $COLUMNS, or --width)
+  git-hook     Check the *.json files staged in the git index, for a pre-commit hook. Staged
+               files that are not formatted are listed (with a diff with --diff). With --write,
+               they're formatted and staged again, if they have no unstaged changes
+  example      Print an example document generated from a JSON Schema, for documentation or
+               test fixtures: values come from const, default, examples and enum, or are made
+               up from types and formats (date-time, email, uuid...)
+
+Arguments:
+  <JSON_FILE>  Path to the JSON file to format (use '-' for stdin), or an HTTP(S) URL.
+               Multiple files are formatted one after the other. Directories are walked
+               recursively for *.json files, glob patterns (*, ?, **) are expanded
+
+Options:
+  --serde       Use serde for JSON parsing
+  --verify      Don't format, compare the formatter with serde on the input: both must accept
+                or reject it, and the formatted output must only differ from the input by
+                whitespace and have the same value for serde. Divergences are reported with
+                their offsets (exit code 1)
+  --no-color    Disable colored output
+  --no-config   Don't read options from config files: ~/.prettyjsonrc and the first
+                .prettyjsonrc of the current directory or its ancestors (an option per line,
+                with its value, ex: --preset diff-friendly). Options of the project config
+                take precedence over the user config, and the command line over both. A line
+                NAME = \"OPTIONS\" defines a shortcut, @NAME on the command line being
+                replaced by OPTIONS (ex: levels = \"--ndjson --group-by .level --count\",
+                invoked as pretty @levels app.log)
+  --iter <N>    Number of iterations to run [default: 1]
+  --threads <N> Format the items of large top-level arrays with N threads [default: 1]
+  --max-input-size <SIZE>
+                Fail on inputs larger than SIZE (ex: 10M), for untrusted inputs (with serve,
+                request bodies larger than SIZE are rejected) [default for serve: 64M]
+  --max-output-size <SIZE>
+                Fail when the formatted output is larger than SIZE (ex: 50M)
+  --max-elements <N>
+                Fail on inputs with more than N values
+  --max-key-length <SIZE>
+                Fail on inputs with keys longer than SIZE
+  --max-string-length <SIZE>
+                Fail on inputs with string values longer than SIZE (ex: 1M)
+  -H, --header <NAME:VALUE>
+                Add a header to the HTTP request (when input is an URL)
+  --bind <ADDR> Address the server listens on [default: 127.0.0.1]
+  --port <PORT> Port the server listens on [default: 8080]
+  --schema <SCHEMA_FILE>
+                JSON Schema of the example command
+  -w, --watch   Re-format the file each time it changes on disk
+  -q, --quiet   Don't print anything, only signal validity with the exit code
+  --skip-errors With multiple files, report unreadable or invalid files and go on
+  --error-format <FORMAT>
+                Format of the errors: text or json (one object per line with the input, the
+                exit code and the message) [default: text]
+  --stdin-name <NAME>
+                Name of the standard input in errors and logs (ex: the file being edited)
+  --partial     On invalid JSON, print the output formatted before the error
+  --warnings    Print duplicate keys, lossy numbers, lone surrogates, unnecessary escapes and
+                invisible chars
+  --deny-warnings
+                Fail on inputs with warnings (implies --warnings)
+  --reveal-invisible
+                Write invisible and bidirectional formatting chars of strings (zero-width
+                spaces, right-to-left overrides...) as \\u escapes, and warn about them
+                (implies --warnings)
+  --write       Format files in place
+  --gitignore   Skip files ignored by .gitignore when walking directories and globs
+  --check       Check that files are formatted, list the files that are not
+  --diff        With --check, print a diff of what would change
+  --width <N>   Output width for side-by-side [default: $COLUMNS or 160]
+  --profile     Report the subtrees and keys contributing the most bytes to the document
+  --report      Print sizes, token counts, max depth and elapsed time on standard error
+  -v, --verbose Print the duration of each phase (read, format, write, flush) and the detected
+                input format on standard error
+  --log <FORMAT>
+                Print the --verbose logs as text or json (one object per line) [default: text]
+  --top <N>     Number of entries in reports [default: 10]
+  --keys-only   Print an outline of the keys of the document, without values, the keys of the
+                objects of an array being merged
+  --depth <N>   With --keys-only, print N levels of keys
+  --from <FORMAT>
+                Convert the input from auto, json, xml, form, jwt or base64 [default: auto].
+                auto detects the format from the first bytes of the input (--verbose prints
+                it), and JSON input that is base64-wrapped JSON is decoded. XML elements
+                are objects with @attribute members, repeated elements are arrays, text-only
+                elements are strings and empty elements are null. Forms are URL-encoded bodies,
+                query strings or URLs, with brackets for nesting (ex: a=1&b[0]=x&c[d]=y). JWTs
+                are decoded to their header and payload (the signature is not verified)
+  --to <FORMAT> Convert the document to json, xml, markdown, svg or ansi-html [default: json].
+                In XML, members are elements named after their keys, and array items are
+                repeated elements. SVG is an image of the colored pretty output, and ansi-html
+                the colored pretty output in a <pre> element with inline styles
+  --xml-root <NAME>
+                With --to xml, name of the root element [default: root]
+  --xml-attributes
+                With --to xml, write strings, numbers and booleans members as attributes
+  --markdown-layout <LAYOUT>
+                With --to markdown, write objects and arrays as nested bullet lists (list) or
+                as tables (table) [default: list]
+  --rename-keys <CASE>
+                Rename object keys to snake_case, camelCase or kebab-case
+  --prune <KINDS>
+                Remove object members whose values are nulls, empty-objects or empty-arrays
+                (comma separated list)
+  --prune-fixed-point
+                With --prune, also remove members whose values become empty once pruned
+  --set <PATH=JSON>
+                Replace the value at PATH by a JSON value (ex: --set '.config.debug=true')
+  --map <PROGRAM>
+                Transform the document with a jq-like program: del(QUERY, ...),
+                rename(.OLD, .NEW) and pick(.KEY, ...) separated by |, applied to the values
+                selected by an optional QUERY |= (ex: --map '.items[] |= del(.debug)'). The
+                whole document is loaded in memory
+  --sort-keys   Sort object members by key
+  --lockfile    Canonical formatting for JSON lockfiles (package-lock.json, composer.lock...)
+                so that regenerated files have minimal diffs: keys are sorted, numbers are
+                normalized (1.50E+07 is 1.5e7) and lines end with LF
+  --shortest-floats
+                Rewrite floats with the shortest decimal parsing back to the same double
+                (0.10000000000000001 is 0.1), identically on every platform
+  --compact     Write the document on one line, without whitespace (and without colors)
+  --preset <PRESET>
+                Use a set of options: compact (--compact), pretty (the default), canonical
+                (--compact --sort-keys --shortest-floats, numbers normalized), diff-friendly (--sort-keys,
+                numbers normalized, no colors, final line break) or log-view
+                (--expand-embedded --render-newlines)
+  --extract-strings-over <SIZE> <DIR>
+                Write strings larger than SIZE (ex: 64k) to files in DIR, replaced by
+                \"@file:DIR/<path>.txt\" references
+  --k8s         Clean Kubernetes resources (kubectl get -o json): remove metadata.managedFields
+                and the last-applied-configuration annotation, write apiVersion, kind,
+                metadata, spec and status first
+  --k8s-drop <PATH>
+                With --k8s, also remove PATH from each resource (ex: --k8s-drop '.status')
+  --anonymize <KINDS>
+                Replace emails, uuids and ips (comma separated list) in strings and keys by
+                pseudonyms, the same value always having the same pseudonym
+                (ex: --anonymize emails,uuids gives \"user-3f2a9c1e@example.com\")
+  --anonymize-key <KEY>
+                With --anonymize, derive pseudonyms from KEY, to get the same pseudonyms across
+                runs [default: a random key]
+  --query <QUERY>
+                Print the values selected by QUERY as an array, with wildcards, slices and
+                recursive descent (ex: '.items[*].name', '.data[2:10]', '..id')
+  --query-ndjson
+                With --query, print one selected value per line (NDJSON)
+  --hash <ALGORITHM>
+                Print the sha256 hash of the canonical form of the document (as with --preset
+                canonical: sorted keys, normalized numbers, no whitespace) instead of the
+                document: equal documents have the same hash, whatever their formatting
+  --hash-with-output
+                With --hash, also print the document, the hash being printed on standard error
+  --split-size <SIZE>
+                With --ndjson or --explode, write the records to numbered files of at most
+                SIZE bytes (ex: 50M) instead of standard output, without colors
+  --split-records <N>
+                With --ndjson or --explode, write the records to numbered files of at most N
+                records instead of standard output, without colors
+  --split-name <NAME>
+                Names of the files of --split-size and --split-records, where {n} is the number
+                of the file [default: part-{n}.ndjson]
+  --collect     Print the records of an NDJSON input as the items of a pretty printed array,
+                each record being printed as soon as it's read
+  --explode     Print each item of a top-level array as a record on one line (NDJSON)
+  --explode-pretty
+                Like --explode, with each record pretty printed
+  --agg <FUNCTION(QUERY)>
+                Print the count, sum, min or max of the values selected by QUERY, computed
+                without building a tree (ex: --agg 'max(..latency_ms)'), can be repeated
+  --table <PATHS>
+                Print the values at PATHS (comma separated) of each object of an array, or of
+                each NDJSON record, as an aligned table (ex: --table .name,.status)
+  --jsonc       Accept JSON with comments and trailing commas (VS Code settings, tsconfig),
+                comments are kept and re-indented, trailing commas are removed
+  --ndjson      Format each line of the input as a separate JSON record (NDJSON, JSON Lines).
+                From standard input, records are printed as soon as their line is read
+                (except with --tail and --dedup)
+  --har         List the entries of an HTTP Archive (HAR) file: method, status, time and URL
+  --entry <N>   With --har, print the request and response bodies of the Nth entry (base64
+                bodies are decoded, JSON bodies are formatted)
+  --aws         Format each event of AWS CloudTrail records or CloudWatch Logs events, embedded
+                JSON messages (message, SNS Message, SQS body) are parsed and formatted
+  --tf          List the resource changes of a Terraform plan (terraform show -json): actions and
+                address, followed by a summary (resources of a state are listed by address)
+  --address <ADDRESS>
+                With --tf, print the resource change (or the state resource) at ADDRESS
+  --es-bulk     Format an Elasticsearch _bulk request body: each action is printed compact,
+                followed by its formatted document, separated by a blank line
+  --record-template <TEMPLATE>
+                With --ndjson, print a header before each record. Placeholders are {n} (record
+                index), {line}, {bytes} and record fields (ex: '--- {n} {.timestamp} ---')
+  --since <TIME>
+                With --ndjson, only print records at or after TIME (ex: 2024-01-31T10:00:00Z,
+                2024-01-31 or a Unix time)
+  --until <TIME>
+                With --ndjson, only print records at or before TIME (a date includes the whole
+                day: --until 2024-01-31 is before 2024-02-01)
+  --time-field <PATH>
+                Path of the record timestamp for --since and --until [default: .timestamp]
+  --sample <N>  With --ndjson, only print every Nth record
+  --head <N>    With --ndjson, only print the first N records
+  --tail <N>    With --ndjson, only print the last N records
+  --dedup       With --ndjson, print consecutive identical records once, followed by their
+                count (ex: × 57)
+  --group-by <PATH> --count
+                With --ndjson, print the number of records per value at PATH (ex: --group-by
+                .level --count)
+  --expand-embedded
+                Format string values containing serialized JSON as nested JSON, marked with
+                /* json */ (display only, the output is not the input JSON anymore)
+  --split-strings
+                Color the quotes (dimmed) and the escape sequences (highlighted) of keys and
+                strings separately from their content
+  --theme-file <FILE>
+                Read the colors of the output from a TOML file, a color per token class
+                (punctuation, key, string, number, boolean, null, comment, quote, escape, id,
+                timestamp, url, email): named colors, 256 colors or #rrggbb, with bold, dim,
+                italic or underline (ex: key = \"bold #66d9ef\"). background and foreground
+                are the #rrggbb colors of SVG and HTML outputs
+  --background <BACKGROUND>
+                Background of the terminal: auto, dark or light, for a theme readable on it.
+                auto queries the terminal and reads COLORFGBG [default: auto]
+  --hyperlinks <WHEN>
+                Make URL string values clickable with OSC 8 hyperlinks: auto, always or never.
+                auto writes hyperlinks if the terminal supports them [default: auto]
+  --legend      Print a legend of the colors (keys, strings, numbers...) after the output
+  --no-legend   Don't print the legend (ex: to override --legend in a config file)
+  --render-newlines
+                Render \\n in string values as line breaks, marked with ⏎ (display only, the
+                output is not valid JSON anymore)
+  --strip-ansi  Don't format, only remove ANSI escape sequences (colors) from the input, for
+                instance to archive colored output
+  --debug-dump  Don't format, print each token with its byte range, nesting level and kind
+                (and the bytes around the error for invalid JSON)
+  --line-ending <ENDING>
+                Line endings of the output: lf, crlf or native (the platform line ending)
+                [default: lf]
+  --indent <INDENT>
+                Indentation of each level: a number of spaces or tab [default: 2]
+  --no-editorconfig
+                With --write and --check, don't read .editorconfig files. By default,
+                indent_style, indent_size, end_of_line and insert_final_newline of the sections
+                matching a file are used, unless set with options
+  --final-newline[=true|false]
+                End the output with a line break or not [default: true, with --write and
+                --check the convention of the original file is kept]
+  --bom <POLICY>
+                Byte order mark of the output: keep (if the input has one), strip or add
+                [default: strip]
+  --align-values
+                Pad keys so that the values of each object are aligned in a column
+  --expand-empty
+                Write empty objects and arrays on two lines, instead of {} and []
+  --compact-single
+                Write objects and arrays with a single member on one line, if the member fits
+                on one line (ex: {\"id\": [1]})
+  --types <PATTERN=TYPE>
+                Colorize fields matching PATTERN as id, timestamp, url or email. PATTERN is a
+                key name (ex: --types id=id) or a path where * matches anything
+                (ex: --types '.users[*].created=timestamp')
+  --humanize    Annotate *_ms fields with durations, *_bytes fields with sizes and *ratio
+                fields with percents (ex: 1536000  /* 1.5 MB */, display only)
+  --humanize-field <PATTERN=KIND>
+                Annotate fields whose key matches PATTERN (* matches anything) as duration-ms,
+                duration-s, bytes or percent (ex: --humanize-field 'timeout=duration-s')
+  --hex-fields <PATTERNS>
+                Annotate the integers of fields whose key matches one of PATTERNS (comma
+                separated, * matches anything) with their hexadecimal and binary values
+                (ex: --hex-fields flags,mask gives 42  /* 0x2a 0b10_1010 */, display only)
+  --file-sizes  Annotate *_bytes, size and *_size fields with sizes in binary units
+                (ex: \"size\": 1048576  /* 1.0 MiB */, display only)
+  --file-size-fields <PATTERNS>
+                Annotate fields whose key matches one of PATTERNS (comma separated, * matches
+                anything) with sizes in binary units, instead of the fields of --file-sizes
+  --js-unsafe   Annotate integers that can't be represented exactly by a double, and are
+                changed when read by JavaScript (ex: 9007199254740993  /* !JS-unsafe */,
+                display only)
+  --geojson     Display GeoJSON coordinates arrays as their number of points and their bounding
+                box (ex: [[…] 12,408 points]  /* bbox [...] */, display only)
+  --avro        Display Avro JSON encoded unions as their value followed by their type
+                (ex: {\"int\": 5} is displayed as 5  /* int */, display only)
+  --proto       Annotate proto3 JSON values: 64-bit integers as strings, Durations,
+                Timestamps and Any types (display only)
+  --proto-strict
+                With --proto, fail on invalid proto3 JSON shapes: unsafe 64-bit integers not
+                written as strings, invalid Durations, Timestamps or @type URLs
+  --openapi     Fold the verbose sections of OpenAPI and Swagger specs (components.schemas.*,
+                paths.*.*.responses, definitions.*) to one-line summaries (display only)
+  --collapse <PATH>
+                Fold the objects and arrays matching PATH (* matches anything) to one-line
+                summaries (ex: --collapse .metadata, --collapse '.*' to fold everything,
+                display only)
+  --expand <PATH>
+                With --openapi or --collapse, don't fold PATH, its ancestors and its
+                descendants (ex: --collapse '.*' --expand .data.items)
+  -h, --help    Print this help message
+
+Exit codes:
+  0  Success
+  1  Invalid JSON
+  2  I/O error (reading input, fetching URL, binding server socket...)
+  3  Invalid command line arguments
+  4  Some files are not formatted (--check)
+";
+
+fn print_usage() {
+    print!("{USAGE}");
+}
+
+pub fn parse_args(args: impl Iterator<Item = String>) -> Result<Config, String> {
+    let args: Vec<String> = args.skip(1).collect();
+
+    // Handle help flags first
+    if args.is_empty() {
+        print_usage();
+        std::process::exit(EXIT_OK);
+    }
+
+    for arg in &args {
+        if arg == "--help" || arg == "-h" {
+            print_usage();
+            std::process::exit(EXIT_OK);
+        }
+    }
+
+    // Options of the config files are parsed first, so that options of the command line take
+    // precedence.
+    if args.iter().any(|arg| arg == "--no-config") {
+        return parse_arg_list(args.into_iter());
+    }
+    let config = config_file::config_args()?;
+    let args = config.expand_shortcuts(args)?;
+    let mut all_args = config.args;
+    all_args.extend(args);
+    parse_arg_list(all_args.into_iter())
+}
+
+/// Parses the arguments of the command line, without the program name.
+pub(crate) fn parse_arg_list(args: impl Iterator<Item = String>) -> Result<Config, String> {
+    let mut command = Command::Format;
+    let mut with_serde = false;
+    let mut verify = false;
+    let mut with_color = true;
+    let mut iter_count = 1;
+    let mut inputs = Vec::new();
+    let mut headers = Vec::new();
+    let mut bind = "127.0.0.1".to_string();
+    let mut port = 8080;
+    let mut schema = None;
+    let mut watch = false;
+    let mut quiet = false;
+    let mut skip_errors = false;
+    let mut error_format = ErrorFormat::default();
+    let mut stdin_name = None;
+    let mut partial = false;
+    let mut warnings = false;
+    let mut deny_warnings = false;
+    let mut reveal_invisible = false;
+    let mut write = false;
+    let mut gitignore = false;
+    let mut check = false;
+    let mut diff = false;
+    let mut width = None;
+    let mut profile = false;
+    let mut top = 10;
+    let mut keys_only = false;
+    let mut depth = None;
+    let mut rename_keys = None;
+    let mut prune = None;
+    let mut prune_fixed_point = false;
+    let mut sets = Vec::new();
+    let mut maps = Vec::new();
+    let mut extract_strings = None;
+    let mut sort_keys = false;
+    let mut normalize_numbers = false;
+    let mut shortest_floats = false;
+    let mut compact = false;
+    let mut k8s = false;
+    let mut k8s_drops = Vec::new();
+    let mut anonymize = None;
+    let mut anonymize_key = None;
+    let mut query = None;
+    let mut query_ndjson = false;
+    let mut explode = None;
+    let mut collect = false;
+    let mut split_size = None;
+    let mut hash = None;
+    let mut hash_with_output = false;
+    let mut split_records = None;
+    let mut split_name = None;
+    let mut aggregations = Vec::new();
+    let mut table = None;
+    let mut types = TypeMap::default();
+    let mut align_values = false;
+    let mut expand_empty = false;
+    let mut compact_single = false;
+    let mut threads = 1;
+    let mut max_input_size = None;
+    let mut max_output_size = None;
+    let mut max_elements = None;
+    let mut max_key_length = None;
+    let mut max_string_length = None;
+    let mut line_ending = None;
+    let mut indent = None;
+    let mut editorconfig = true;
+    let mut final_newline = None;
+    let mut bom = Bom::Strip;
+    let mut humanize = false;
+    let mut geojson = false;
+    let mut avro = false;
+    let mut js_unsafe = false;
+    let mut hex_fields = HexFields::default();
+    let mut file_sizes = false;
+    let mut file_size_fields = FileSizes::default();
+    let mut proto = false;
+    let mut proto_strict = false;
+    let mut openapi = false;
+    let mut expands = Vec::new();
+    let mut collapses = Vec::new();
+    let mut from = InputFormat::Auto;
+    let mut to = OutputFormat::Json;
+    let mut xml_root = None;
+    let mut xml_attributes = false;
+    let mut markdown_layout = None;
+    let mut humanize_fields = Vec::new();
+    let mut jsonc = false;
+    let mut ndjson = false;
+    let mut har = false;
+    let mut har_entry = None;
+    let mut aws = false;
+    let mut tf = false;
+    let mut tf_address = None;
+    let mut es_bulk = false;
+    let mut record_template = None;
+    let mut since = None;
+    let mut until = None;
+    let mut time_field = None;
+    let mut sample = None;
+    let mut head = None;
+    let mut tail = None;
+    let mut dedup = false;
+    let mut group_by = None;
+    let mut count = false;
+    let mut expand_embedded = false;
+    let mut render_newlines = false;
+    let mut split_strings = false;
+    let mut legend = false;
+    let mut theme_file = None;
+    let mut background = None;
+    let mut hyperlinks = None;
+    let mut strip_ansi = false;
+    let mut debug_dump = false;
+    let mut report = false;
+    let mut log = None;
+    let mut args_iter = args;
+
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--serde" => {
+                with_serde = true;
+            }
+            "--verify" => {
+                verify = true;
+            }
+            "--no-color" => {
+                with_color = false;
+            }
+            "--no-config" => {}
+            "--iter" => {
+                iter_count = parse_value(&mut args_iter, "--iter")?;
+            }
+            "--threads" => {
+                threads = parse_value::<NonZeroUsize>(&mut args_iter, "--threads")?.get();
+            }
+            "--max-input-size" => {
+                let size = next_value(&mut args_iter, "--max-input-size")?;
+                let size = parse_size(&size)
+                    .map_err(|err| format!("Invalid value for --max-input-size: {err}"))?;
+                max_input_size = Some(size);
+            }
+            "--max-output-size" => {
+                let size = next_value(&mut args_iter, "--max-output-size")?;
+                let size = parse_size(&size)
+                    .map_err(|err| format!("Invalid value for --max-output-size: {err}"))?;
+                max_output_size = Some(size);
+            }
+            "--max-elements" => {
+                max_elements = Some(parse_value(&mut args_iter, "--max-elements")?);
+            }
+            "--max-key-length" => {
+                let size = next_value(&mut args_iter, "--max-key-length")?;
+                let size = parse_size(&size)
+                    .map_err(|err| format!("Invalid value for --max-key-length: {err}"))?;
+                max_key_length = Some(size);
+            }
+            "--max-string-length" => {
+                let size = next_value(&mut args_iter, "--max-string-length")?;
+                let size = parse_size(&size)
+                    .map_err(|err| format!("Invalid value for --max-string-length: {err}"))?;
+                max_string_length = Some(size);
+            }
+            "-H" | "--header" => {
+                let value = next_value(&mut args_iter, "--header")?;
+                headers.push(parse_header(&value)?);
+            }
+            "--bind" => {
+                bind = next_value(&mut args_iter, "--bind")?;
+            }
+            "--port" => {
+                port = parse_value(&mut args_iter, "--port")?;
+            }
+            "--schema" => {
+                schema = Some(PathBuf::from(next_value(&mut args_iter, "--schema")?));
+            }
+            "-w" | "--watch" => {
+                watch = true;
+            }
+            "-q" | "--quiet" => {
+                quiet = true;
+            }
+            "--skip-errors" => {
+                skip_errors = true;
+            }
+            "--error-format" => {
+                error_format = parse_value(&mut args_iter, "--error-format")?;
+            }
+            "--stdin-name" => {
+                stdin_name = Some(next_value(&mut args_iter, "--stdin-name")?);
+            }
+            "--partial" => {
+                partial = true;
+            }
+            "--warnings" => {
+                warnings = true;
+            }
+            "--deny-warnings" => {
+                warnings = true;
+                deny_warnings = true;
+            }
+            "--reveal-invisible" => {
+                warnings = true;
+                reveal_invisible = true;
+            }
+            "--write" => {
+                write = true;
+            }
+            "--gitignore" => {
+                gitignore = true;
+            }
+            "--check" => {
+                check = true;
+            }
+            "--diff" => {
+                diff = true;
+            }
+            "--width" => {
+                width = Some(parse_value(&mut args_iter, "--width")?);
+            }
+            "--profile" => {
+                profile = true;
+            }
+            "--top" => {
+                top = parse_value(&mut args_iter, "--top")?;
+            }
+            "--keys-only" => {
+                keys_only = true;
+            }
+            "--depth" => {
+                depth = Some(parse_value::<usize>(&mut args_iter, "--depth")?);
+            }
+            "--rename-keys" => {
+                let value = next_value(&mut args_iter, "--rename-keys")?;
+                rename_keys = Some(
+                    value
+                        .parse::<Case>()
+                        .map_err(|err| format!("Invalid value for --rename-keys: {err}"))?,
+                );
+            }
+            "--prune" => {
+                let value = next_value(&mut args_iter, "--prune")?;
+                prune = Some(
+                    value
+                        .parse::<Prune>()
+                        .map_err(|err| format!("Invalid value for --prune: {err}"))?,
+                );
+            }
+            "--prune-fixed-point" => {
+                prune_fixed_point = true;
+            }
+            "--map" => {
+                let value = next_value(&mut args_iter, "--map")?;
+                let map = value
+                    .parse::<Map>()
+                    .map_err(|err| format!("Invalid value for --map: {err}"))?;
+                maps.push(map);
+            }
+            "--set" => {
+                let value = next_value(&mut args_iter, "--set")?;
+                sets.push(parse_set(&value)?);
+            }
+            "--sort-keys" => {
+                sort_keys = true;
+            }
+            "--lockfile" => {
+                sort_keys = true;
+                normalize_numbers = true;
+                // Not overridden by .editorconfig files.
+                line_ending = Some(LineEnding::Lf);
+            }
+            "--shortest-floats" => {
+                shortest_floats = true;
+            }
+            "--compact" => {
+                compact = true;
+            }
+            "--preset" => {
+                let options = parse_value::<Preset>(&mut args_iter, "--preset")?.options();
+                compact |= options.compact;
+                sort_keys |= options.sort_keys;
+                normalize_numbers |= options.normalize_numbers;
+                shortest_floats |= options.shortest_floats;
+                with_color &= options.color;
+                expand_embedded |= options.expand_embedded;
+                render_newlines |= options.render_newlines;
+                final_newline = final_newline.or(options.final_newline);
+            }
+            "--extract-strings-over" => {
+                let size = next_value(&mut args_iter, "--extract-strings-over")?;
+                let size = parse_size(&size)
+                    .map_err(|err| format!("Invalid value for --extract-strings-over: {err}"))?;
+                let dir = next_value(&mut args_iter, "--extract-strings-over")?;
+                extract_strings = Some((size, PathBuf::from(dir)));
+            }
+            "--k8s" => {
+                k8s = true;
+            }
+            "--k8s-drop" => {
+                let value = next_value(&mut args_iter, "--k8s-drop")?;
+                let path = value
+                    .parse::<Path>()
+                    .map_err(|err| format!("Invalid value for --k8s-drop: {err}"))?;
+                k8s_drops.push(path);
+            }
+            "--anonymize" => {
+                let value = next_value(&mut args_iter, "--anonymize")?;
+                anonymize = Some(
+                    value
+                        .parse::<Pii>()
+                        .map_err(|err| format!("Invalid value for --anonymize: {err}"))?,
+                );
+            }
+            "--anonymize-key" => {
+                anonymize_key = Some(next_value(&mut args_iter, "--anonymize-key")?);
+            }
+            "--query" => {
+                let value = next_value(&mut args_iter, "--query")?;
+                let parsed = value
+                    .parse::<Query>()
+                    .map_err(|err| format!("Invalid value for --query: {err}"))?;
+                query = Some(parsed);
+            }
+            "--query-ndjson" => {
+                query_ndjson = true;
+            }
+            "--hash" => {
+                hash = Some(parse_value(&mut args_iter, "--hash")?);
+            }
+            "--hash-with-output" => {
+                hash_with_output = true;
+            }
+            "--split-size" => {
+                let size = next_value(&mut args_iter, "--split-size")?;
+                let size = parse_size(&size)
+                    .map_err(|err| format!("Invalid value for --split-size: {err}"))?;
+                split_size = Some(size);
+            }
+            "--split-records" => {
+                split_records = Some(parse_value::<usize>(&mut args_iter, "--split-records")?);
+            }
+            "--split-name" => {
+                split_name = Some(next_value(&mut args_iter, "--split-name")?);
+            }
+            "--collect" => {
+                collect = true;
+            }
+            "--explode" => {
+                explode = Some(Explode::Compact);
+            }
+            "--explode-pretty" => {
+                explode = Some(Explode::Pretty);
+            }
+            "--agg" => {
+                let value = next_value(&mut args_iter, "--agg")?;
+                let aggregation = value
+                    .parse::<Aggregation>()
+                    .map_err(|err| format!("Invalid value for --agg: {err}"))?;
+                aggregations.push(aggregation);
+            }
+            "--table" => {
+                let value = next_value(&mut args_iter, "--table")?;
+                let columns = parse_columns(&value)
+                    .map_err(|err| format!("Invalid value for --table: {err}"))?;
+                table = Some(columns);
+            }
+            "--jsonc" => {
+                jsonc = true;
+            }
+            "--ndjson" => {
+                ndjson = true;
+            }
+            "--har" => {
+                har = true;
+            }
+            "--entry" => {
+                har_entry = Some(parse_value::<NonZeroUsize>(&mut args_iter, "--entry")?.get());
+            }
+            "--aws" => {
+                aws = true;
+            }
+            "--tf" => {
+                tf = true;
+            }
+            "--address" => {
+                tf_address = Some(next_value(&mut args_iter, "--address")?);
+            }
+            "--es-bulk" => {
+                es_bulk = true;
+            }
+            "--record-template" => {
+                let value = next_value(&mut args_iter, "--record-template")?;
+                let template = value
+                    .parse::<Template>()
+                    .map_err(|err| format!("Invalid value for --record-template: {err}"))?;
+                record_template = Some(template);
+            }
+            "--since" | "--until" => {
+                let value = next_value(&mut args_iter, &arg)?;
+                let time = if arg == "--since" {
+                    parse_timestamp(&value)
+                } else {
+                    parse_end_timestamp(&value)
+                };
+                let Some(time) = time else {
+                    return Err(format!("Invalid value for {arg}: {value}"));
+                };
+                if arg == "--since" {
+                    since = Some(time);
+                } else {
+                    until = Some(time);
+                }
+            }
+            "--time-field" => {
+                let value = next_value(&mut args_iter, "--time-field")?;
+                let path = value
+                    .parse::<Path>()
+                    .map_err(|err| format!("Invalid value for --time-field: {err}"))?;
+                time_field = Some(path);
+            }
+            "--sample" => {
+                sample = Some(parse_value::<NonZeroUsize>(&mut args_iter, "--sample")?.get());
+            }
+            "--head" => {
+                head = Some(parse_value(&mut args_iter, "--head")?);
+            }
+            "--tail" => {
+                tail = Some(parse_value(&mut args_iter, "--tail")?);
+            }
+            "--dedup" => {
+                dedup = true;
+            }
+            "--group-by" => {
+                let value = next_value(&mut args_iter, "--group-by")?;
+                let path = value
+                    .parse::<Path>()
+                    .map_err(|err| format!("Invalid value for --group-by: {err}"))?;
+                group_by = Some(path);
+            }
+            "--count" => {
+                count = true;
+            }
+            "--expand-embedded" => {
+                expand_embedded = true;
+            }
+            "--split-strings" => {
+                split_strings = true;
+            }
+            "--theme-file" => {
+                let path = next_value(&mut args_iter, "--theme-file")?;
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("Error reading theme file {path}: {err}"))?;
+                let theme = Theme::parse(&text)
+                    .map_err(|err| format!("Invalid theme file {path}: {err}"))?;
+                theme_file = Some(theme);
+            }
+            "--background" => {
+                background = match next_value(&mut args_iter, "--background")?.as_str() {
+                    "auto" => None,
+                    value => Some(
+                        value
+                            .parse::<Background>()
+                            .map_err(|err| format!("Invalid value for --background: {err}"))?,
+                    ),
+                };
+            }
+            "--hyperlinks" => {
+                hyperlinks = match next_value(&mut args_iter, "--hyperlinks")?.as_str() {
+                    "auto" => None,
+                    "always" => Some(true),
+                    "never" => Some(false),
+                    value => {
+                        let err = format!(
+                            "Invalid value for --hyperlinks: {value} (expected auto, always or never)"
+                        );
+                        return Err(err);
+                    }
+                };
+            }
+            "--legend" => {
+                legend = true;
+            }
+            "--no-legend" => {
+                legend = false;
+            }
+            "--render-newlines" => {
+                render_newlines = true;
+            }
+            "--strip-ansi" => {
+                strip_ansi = true;
+            }
+            "--report" => {
+                report = true;
+            }
+            "-v" | "--verbose" => {
+                log = log.or(Some(LogFormat::Text));
+            }
+            "--log" => {
+                log = Some(parse_value(&mut args_iter, "--log")?);
+            }
+            "--debug-dump" => {
+                debug_dump = true;
+            }
+            "--align-values" => {
+                align_values = true;
+            }
+            "--expand-empty" => {
+                expand_empty = true;
+            }
+            "--compact-single" => {
+                compact_single = true;
+            }
+            "--final-newline" | "--final-newline=true" => {
+                final_newline = Some(true);
+            }
+            "--final-newline=false" => {
+                final_newline = Some(false);
+            }
+            "--bom" => {
+                bom = parse_value::<Bom>(&mut args_iter, "--bom")?;
+            }
+            "--line-ending" => {
+                line_ending = Some(parse_value::<LineEnding>(&mut args_iter, "--line-ending")?);
+            }
+            "--indent" => {
+                indent = Some(parse_value::<Indent>(&mut args_iter, "--indent")?);
+            }
+            "--no-editorconfig" => {
+                editorconfig = false;
+            }
+            "--types" => {
+                let value = next_value(&mut args_iter, "--types")?;
+                let Some((pattern, field_type)) = value.rsplit_once('=') else {
+                    let err = format!("Invalid value for --types: {value}, expected PATTERN=TYPE");
+                    return Err(err);
+                };
+                let field_type = field_type
+                    .parse::<FieldType>()
+                    .map_err(|err| format!("Invalid value for --types: {err}"))?;
+                types.insert(pattern, field_type);
+            }
+            "--from" => {
+                from = parse_value(&mut args_iter, "--from")?;
+            }
+            "--to" => {
+                to = parse_value(&mut args_iter, "--to")?;
+            }
+            "--xml-root" => {
+                let value = next_value(&mut args_iter, "--xml-root")?;
+                if element_name(&value) != value {
+                    let err = format!("Invalid value for --xml-root: {value} is not an XML name");
+                    return Err(err);
+                }
+                xml_root = Some(value);
+            }
+            "--markdown-layout" => {
+                markdown_layout = Some(parse_value(&mut args_iter, "--markdown-layout")?);
+            }
+            "--xml-attributes" => {
+                xml_attributes = true;
+            }
+            "--geojson" => {
+                geojson = true;
+            }
+            "--avro" => {
+                avro = true;
+            }
+            "--hex-fields" => {
+                let value = next_value(&mut args_iter, "--hex-fields")?;
+                hex_fields
+                    .patterns
+                    .extend(value.split(',').map(|p| p.trim().to_string()));
+            }
+            "--file-sizes" => {
+                file_sizes = true;
+            }
+            "--file-size-fields" => {
+                let value = next_value(&mut args_iter, "--file-size-fields")?;
+                file_size_fields
+                    .patterns
+                    .extend(value.split(',').map(|p| p.trim().to_string()));
+            }
+            "--js-unsafe" => {
+                js_unsafe = true;
+            }
+            "--proto" => {
+                proto = true;
+            }
+            "--proto-strict" => {
+                proto_strict = true;
+            }
+            "--openapi" => {
+                openapi = true;
+            }
+            "--collapse" => {
+                collapses.push(next_value(&mut args_iter, "--collapse")?);
+            }
+            "--expand" => {
+                expands.push(next_value(&mut args_iter, "--expand")?);
+            }
+            "--humanize" => {
+                humanize = true;
+            }
+            "--humanize-field" => {
+                let value = next_value(&mut args_iter, "--humanize-field")?;
+                let Some((pattern, kind)) = value.rsplit_once('=') else {
+                    let err = format!(
+                        "Invalid value for --humanize-field: {value}, expected PATTERN=KIND"
+                    );
+                    return Err(err);
+                };
+                let kind = kind
+                    .parse::<Humanize>()
+                    .map_err(|err| format!("Invalid value for --humanize-field: {err}"))?;
+                humanize_fields.push(Humanizer::new(pattern, kind));
+            }
+            other if other.starts_with('-') && other != "-" => {
+                let err = format!("Unknown option: {other}");
+                return Err(err);
+            }
+            other => {
+                if matches!(
+                    command,
+                    Command::Repl | Command::Serve | Command::GitHook | Command::Example
+                ) {
+                    let err = format!("Unknown argument: {other}");
+                    return Err(err);
+                }
+                // Subcommands take the place of the first file argument.
+                if inputs.is_empty() && other == "repl" {
+                    command = Command::Repl;
+                } else if inputs.is_empty() && other == "serve" {
+                    command = Command::Serve;
+                } else if inputs.is_empty() && other == "side-by-side" {
+                    command = Command::SideBySide;
+                } else if inputs.is_empty() && other == "git-hook" {
+                    command = Command::GitHook;
+                } else if inputs.is_empty() && other == "example" {
+                    command = Command::Example;
+                } else if other == "-" {
+                    inputs.push(Input::Stdin(None));
+                } else if other.starts_with("http://") || other.starts_with("https://") {
+                    inputs.push(Input::Url(other.to_string()));
+                } else {
+                    inputs.push(Input::File(PathBuf::from(other)));
+                }
+            }
+        }
+    }
+
+    if command == Command::Format && inputs.is_empty() {
+        return Err("Missing required argument: JSON file path".to_string());
+    }
+    if command == Command::Example && schema.is_none() {
+        return Err("example requires a JSON Schema (--schema <SCHEMA_FILE>)".to_string());
+    }
+    if schema.is_some() && command != Command::Example {
+        return Err("--schema can only be used with example".to_string());
+    }
+    if command == Command::SideBySide && inputs.len() != 2 {
+        return Err("side-by-side requires two JSON files".to_string());
+    }
+    if write && inputs.iter().any(|input| !matches!(input, Input::File(_))) {
+        return Err("--write can only be used with files".to_string());
+    }
+    if write && check {
+        return Err("--write and --check can not be used together".to_string());
+    }
+    if diff && !check && command != Command::GitHook {
+        return Err("--diff can only be used with --check or git-hook".to_string());
+    }
+    if ndjson && (write || check) {
+        return Err("--ndjson can not be used with --write or --check".to_string());
+    }
+    if har && (write || check || ndjson || es_bulk) {
+        let err = "--har can not be used with --write, --check, --ndjson or --es-bulk";
+        return Err(err.to_string());
+    }
+    if har_entry.is_some() && !har {
+        return Err("--entry can only be used with --har".to_string());
+    }
+    if aws && (write || check || ndjson || har || tf || es_bulk) {
+        let err = "--aws can not be used with --write, --check, --ndjson, --har, --tf or --es-bulk";
+        return Err(err.to_string());
+    }
+    if tf && (write || check || ndjson || har || es_bulk) {
+        let err = "--tf can not be used with --write, --check, --ndjson, --har or --es-bulk";
+        return Err(err.to_string());
+    }
+    if tf_address.is_some() && !tf {
+        return Err("--address can only be used with --tf".to_string());
+    }
+    if es_bulk && (write || check || ndjson) {
+        return Err("--es-bulk can not be used with --write, --check or --ndjson".to_string());
+    }
+    if let Some(name) = stdin_name {
+        if !inputs.contains(&Input::Stdin(None)) {
+            return Err("--stdin-name can only be used with standard input (-)".to_string());
+        }
+        for input in &mut inputs {
+            if let Input::Stdin(stdin) = input {
+                *stdin = Some(name.clone());
+            }
+        }
+    }
+    if verify && (with_serde || write || check || ndjson) {
+        return Err(
+            "--verify can not be used with --serde, --write, --check or --ndjson".to_string(),
+        );
+    }
+    if record_template.is_some() && !ndjson {
+        return Err("--record-template can only be used with --ndjson".to_string());
+    }
+    if (since.is_some() || until.is_some()) && !ndjson {
+        return Err("--since and --until can only be used with --ndjson".to_string());
+    }
+    if (sample.is_some() || head.is_some() || tail.is_some()) && !ndjson {
+        return Err("--sample, --head and --tail can only be used with --ndjson".to_string());
+    }
+    if dedup && !ndjson {
+        return Err("--dedup can only be used with --ndjson".to_string());
+    }
+    if (group_by.is_some() || count) && !ndjson {
+        return Err("--group-by and --count can only be used with --ndjson".to_string());
+    }
+    if group_by.is_some() != count {
+        return Err("--group-by and --count must be used together".to_string());
+    }
+    if head.is_some() && tail.is_some() {
+        return Err("--head and --tail can not be used together".to_string());
+    }
+    if time_field.is_some() && since.is_none() && until.is_none() {
+        return Err("--time-field can only be used with --since or --until".to_string());
+    }
+    let time_window = (since.is_some() || until.is_some()).then(|| TimeWindow {
+        field: time_field.unwrap_or_else(|| Path {
+            segments: vec![Segment::Key("timestamp".to_string())],
+        }),
+        since,
+        until,
+    });
+    if expand_embedded && (write || check) {
+        return Err("--expand-embedded can not be used with --write or --check".to_string());
+    }
+    if strip_ansi && (write || check || ndjson) {
+        return Err("--strip-ansi can not be used with --write, --check or --ndjson".to_string());
+    }
+    if report && (with_serde || write || check || ndjson) {
+        let err = "--report can not be used with --serde, --write, --check or --ndjson";
+        return Err(err.to_string());
+    }
+    if debug_dump && (write || check || ndjson) {
+        return Err("--debug-dump can not be used with --write, --check or --ndjson".to_string());
+    }
+    if render_newlines && (write || check) {
+        return Err("--render-newlines can not be used with --write or --check".to_string());
+    }
+    let mut humanizers = if humanize {
+        Humanizer::defaults()
+    } else {
+        Vec::new()
+    };
+    humanizers.extend(humanize_fields);
+    if !humanizers.is_empty() && (write || check) {
+        let err = "--humanize and --humanize-field can not be used with --write or --check";
+        return Err(err.to_string());
+    }
+    if !hex_fields.patterns.is_empty() && (write || check) {
+        return Err("--hex-fields can not be used with --write or --check".to_string());
+    }
+    let file_sizes = if file_size_fields.patterns.is_empty() && file_sizes {
+        FileSizes::defaults()
+    } else {
+        file_size_fields
+    };
+    if !file_sizes.patterns.is_empty() && (write || check) {
+        let err = "--file-sizes and --file-size-fields can not be used with --write or --check";
+        return Err(err.to_string());
+    }
+    if explode.is_some()
+        && (write || check || ndjson || query.is_some() || to != OutputFormat::Json)
+    {
+        let err = "--explode can not be used with --write, --check, --ndjson, --query or --to";
+        return Err(err.to_string());
+    }
+    if table.is_some()
+        && (write
+            || check
+            || explode.is_some()
+            || query.is_some()
+            || !aggregations.is_empty()
+            || to != OutputFormat::Json)
+    {
+        let err =
+            "--table can not be used with --write, --check, --explode, --query, --agg or --to";
+        return Err(err.to_string());
+    }
+    if hash.is_some()
+        && (write
+            || check
+            || ndjson
+            || collect
+            || explode.is_some()
+            || query.is_some()
+            || to != OutputFormat::Json)
+    {
+        let err = "--hash can not be used with --write, --check, --ndjson, --collect, --explode, \
+                   --query or --to";
+        return Err(err.to_string());
+    }
+    if hash_with_output && hash.is_none() {
+        return Err("--hash-with-output can only be used with --hash".to_string());
+    }
+    let split = if split_size.is_some() || split_records.is_some() {
+        if !ndjson && explode.is_none() {
+            let err =
+                "--split-size and --split-records can only be used with --ndjson or --explode";
+            return Err(err.to_string());
+        }
+        if split_size == Some(0) || split_records == Some(0) {
+            return Err("--split-size and --split-records must be at least 1".to_string());
+        }
+        // Files don't contain colors.
+        with_color = false;
+        Some(Split {
+            size: split_size,
+            records: split_records,
+            name: split_name.unwrap_or_else(|| "part-{n}.ndjson".to_string()),
+        })
+    } else if split_name.is_some() {
+        return Err(
+            "--split-name can only be used with --split-size or --split-records".to_string(),
+        );
+    } else {
+        None
+    };
+    if collect
+        && (write
+            || check
+            || ndjson
+            || compact
+            || with_serde
+            || explode.is_some()
+            || query.is_some()
+            || to != OutputFormat::Json)
+    {
+        let err = "--collect can not be used with --write, --check, --ndjson, --compact, --serde, \
+                   --explode, --query or --to";
+        return Err(err.to_string());
+    }
+    if keys_only && (with_serde || write || check || ndjson || jsonc) {
+        let err = "--keys-only can not be used with --serde, --write, --check, --ndjson or --jsonc";
+        return Err(err.to_string());
+    }
+    if depth.is_some() && !keys_only {
+        return Err("--depth can only be used with --keys-only".to_string());
+    }
+    if depth == Some(0) {
+        return Err("Invalid value for --depth: 0 (expected at least 1)".to_string());
+    }
+    let detect_background = with_color && theme_file.is_none() && background.is_none();
+    let detect_hyperlinks = with_color && hyperlinks.is_none();
+    let hyperlinks = hyperlinks.unwrap_or(false);
+    if hyperlinks && (write || check) {
+        return Err("--hyperlinks can not be used with --write or --check".to_string());
+    }
+    let theme = match (theme_file, background) {
+        (Some(theme), _) => theme,
+        (None, Some(background)) => background.theme().clone(),
+        (None, None) => Theme::default(),
+    };
+    if legend && (write || check) {
+        return Err("--legend can not be used with --write or --check".to_string());
+    }
+    if compact_single && jsonc {
+        return Err("--compact-single can not be used with --jsonc".to_string());
+    }
+    if js_unsafe && (write || check) {
+        return Err("--js-unsafe can not be used with --write or --check".to_string());
+    }
+    if to == OutputFormat::Xml && (with_serde || write || check || ndjson || jsonc) {
+        let err = "--to xml can not be used with --serde, --write, --check, --ndjson or --jsonc";
+        return Err(err.to_string());
+    }
+    if to == OutputFormat::Markdown && (with_serde || write || check || ndjson || jsonc) {
+        let err =
+            "--to markdown can not be used with --serde, --write, --check, --ndjson or --jsonc";
+        return Err(err.to_string());
+    }
+    if matches!(to, OutputFormat::Svg | OutputFormat::AnsiHtml)
+        && (with_serde || write || check || ndjson)
+    {
+        let err = format!(
+            "--to {} can not be used with --serde, --write, --check or --ndjson",
+            if to == OutputFormat::Svg {
+                "svg"
+            } else {
+                "ansi-html"
+            }
+        );
+        return Err(err);
+    }
+    if compact && (with_serde || write || check || ndjson || jsonc || to != OutputFormat::Json) {
+        let err =
+            "--compact can not be used with --serde, --write, --check, --ndjson, --jsonc or --to";
+        return Err(err.to_string());
+    }
+    if markdown_layout.is_some() && to != OutputFormat::Markdown {
+        return Err("--markdown-layout can only be used with --to markdown".to_string());
+    }
+    if !matches!(from, InputFormat::Auto | InputFormat::Json)
+        && (write || check || ndjson || jsonc || strip_ansi || debug_dump)
+    {
+        let err = "--from can not be used with --write, --check, --ndjson, --jsonc, --strip-ansi \
+                   or --debug-dump";
+        return Err(err.to_string());
+    }
+    if (xml_root.is_some() || xml_attributes) && to != OutputFormat::Xml {
+        return Err("--xml-root and --xml-attributes can only be used with --to xml".to_string());
+    }
+    let xml = XmlOptions {
+        root: xml_root.unwrap_or_else(|| XmlOptions::default().root),
+        attributes: xml_attributes,
+    };
+    if geojson && (write || check || jsonc) {
+        return Err("--geojson can not be used with --write, --check or --jsonc".to_string());
+    }
+    if avro && (write || check || jsonc) {
+        return Err("--avro can not be used with --write, --check or --jsonc".to_string());
+    }
+    if proto && (write || check || ndjson) {
+        return Err("--proto can not be used with --write, --check or --ndjson".to_string());
+    }
+    if proto_strict && !proto {
+        return Err("--proto-strict can only be used with --proto".to_string());
+    }
+    if !k8s_drops.is_empty() && !k8s {
+        return Err("--k8s-drop can only be used with --k8s".to_string());
+    }
+    let k8s = k8s.then(|| {
+        let noise = k8s::NOISE_PATHS.map(|p| p.parse::<Path>().expect("valid path"));
+        noise.into_iter().chain(k8s_drops).collect()
+    });
+    if anonymize_key.is_some() && anonymize.is_none() {
+        return Err("--anonymize-key can only be used with --anonymize".to_string());
+    }
+    let anonymize = anonymize.map(|pii| (pii, anonymize_key.unwrap_or_else(anonymize::random_key)));
+    if line_ending.is_some_and(|ending| ending != LineEnding::Lf)
+        && (with_serde || to == OutputFormat::Xml)
+    {
+        return Err("--line-ending can not be used with --serde or --to xml".to_string());
+    }
+    if indent.is_some() && (with_serde || compact || to != OutputFormat::Json) {
+        return Err("--indent can not be used with --serde, --compact or --to".to_string());
+    }
+    let mut folds = Folds::default();
+    if openapi {
+        if write || check {
+            return Err("--openapi can not be used with --write or --check".to_string());
+        }
+        for pattern in OPENAPI_FOLDS {
+            folds.fold(pattern);
+        }
+    }
+    if !collapses.is_empty() && (write || check) {
+        return Err("--collapse can not be used with --write or --check".to_string());
+    }
+    for pattern in &collapses {
+        folds.fold(pattern);
+    }
+    if folds.is_empty() && !expands.is_empty() {
+        return Err("--expand can only be used with --openapi or --collapse".to_string());
+    }
+    for pattern in &expands {
+        folds.expand(pattern);
+    }
+    if watch && !matches!(inputs.as_slice(), [Input::File(_)]) {
+        return Err("--watch can only be used with a single file".to_string());
+    }
+
+    Ok(Config {
+        command,
+        with_serde,
+        verify,
+        with_color,
+        iter_count,
+        inputs,
+        headers,
+        bind,
+        port,
+        schema,
+        watch,
+        quiet,
+        skip_errors,
+        error_format,
+        partial,
+        warnings,
+        deny_warnings,
+        reveal_invisible,
+        write,
+        gitignore,
+        check,
+        diff,
+        width,
+        profile,
+        top,
+        keys_only,
+        depth,
+        rename_keys,
+        prune,
+        prune_fixed_point,
+        sets,
+        maps,
+        extract_strings,
+        sort_keys,
+        normalize_numbers,
+        shortest_floats,
+        compact,
+        k8s,
+        anonymize,
+        query,
+        query_ndjson,
+        explode,
+        collect,
+        hash,
+        hash_with_output,
+        split,
+        aggregations,
+        table,
+        types,
+        align_values,
+        expand_empty,
+        compact_single,
+        threads,
+        max_input_size,
+        max_output_size,
+        max_elements,
+        max_key_length,
+        max_string_length,
+        line_ending,
+        indent,
+        editorconfig,
+        final_newline,
+        bom,
+        humanizers,
+        js_unsafe,
+        hex_fields,
+        file_sizes,
+        geojson,
+        avro,
+        proto,
+        proto_strict,
+        folds,
+        jsonc,
+        ndjson,
+        har,
+        har_entry,
+        aws,
+        tf,
+        tf_address,
+        es_bulk,
+        record_template,
+        time_window,
+        sample,
+        head,
+        tail,
+        dedup,
+        group_by,
+        expand_embedded,
+        render_newlines,
+        split_strings,
+        legend,
+        theme,
+        detect_background,
+        hyperlinks,
+        detect_hyperlinks,
+        strip_ansi,
+        debug_dump,
+        report,
+        log,
+        from,
+        to,
+        xml,
+        markdown_layout: markdown_layout.unwrap_or_default(),
+    })
+}
+
+/// Returns the value of the option `name`.
+fn next_value(args: &mut impl Iterator<Item = String>, name: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("Missing value for {name}"))
+}
+
+/// Returns the value of the option `name`, parsed to `T`.
+fn parse_value<T: FromStr>(
+    args: &mut impl Iterator<Item = String>,
+    name: &str,
+) -> Result<T, String> {
+    let value = next_value(args, name)?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {name}: {value}"))
+}
+
+/// Parses a size in bytes, with an optional `k`, `M` or `G` (power of 1024) suffix.
+fn parse_size(value: &str) -> Result<usize, String> {
+    let (digits, unit) = match value.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((i, _)) => value.split_at(i),
+        None => (value, ""),
+    };
+    let multiplier = match unit {
+        "" | "B" => 1,
+        "k" | "K" | "kB" | "KB" | "KiB" => 1 << 10,
+        "m" | "M" | "MB" | "MiB" => 1 << 20,
+        "g" | "G" | "GB" | "GiB" => 1 << 30,
+        _ => return Err(format!("invalid size {value}")),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size {value}"))
+}
+
+/// Parses a value replacement `PATH=JSON`.
+fn parse_set(value: &str) -> Result<(Path, String), String> {
+    // The separator is the first `=` outside of a quoted key.
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut separator = None;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '=' if !quoted => {
+                separator = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let Some(separator) = separator else {
+        return Err(format!(
+            "Invalid value for --set: {value}, expected PATH=JSON"
+        ));
+    };
+    let (path, json) = (&value[..separator], &value[separator + 1..]);
+    let path = path
+        .parse::<Path>()
+        .map_err(|err| format!("Invalid value for --set: {err}"))?;
+    if let Err(err) = pretty::value::parse(json.as_bytes()) {
+        return Err(format!(
+            "Invalid value for --set: invalid JSON {json}: {err}"
+        ));
+    }
+    Ok((path, json.to_string()))
+}
+
+/// Parses an HTTP header `Name: Value`.
+fn parse_header(value: &str) -> Result<(String, String), String> {
+    match value.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("Invalid value for --header: {value}")),
+    }
+}
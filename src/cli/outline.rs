@@ -0,0 +1,152 @@
+//! Outline of the keys of a document, without its values (`--keys-only`).
+//!
+//! Each key is printed once per object, indented by its depth, the objects of an array being
+//! merged: the outline of `[{"id": 1}, {"id": 2, "name": "b"}]` is `[]` with the keys `id` and
+//! `name`. Arrays are marked with `[]` after their key.
+use pretty::format::{AnsiStyle, OutputStyle, Style};
+use pretty::value::{Node, Value, unescape};
+
+/// The key structure of values: their keys, and the merged structure of their items.
+#[derive(Debug, Default)]
+struct Shape {
+    /// Keys of objects, in the order of their first appearance, with the shape of their values.
+    keys: Vec<(String, Shape)>,
+    /// Shape of array items, if some values are arrays.
+    items: Option<Box<Shape>>,
+}
+
+impl Shape {
+    /// Merges the structure of `node` into this shape.
+    fn add(&mut self, node: &Node) {
+        match &node.value {
+            Value::Object(members) => {
+                for member in members {
+                    let key = unescape(&member.key);
+                    let index = match self.keys.iter().position(|(k, _)| *k == key) {
+                        Some(index) => index,
+                        None => {
+                            self.keys.push((key, Shape::default()));
+                            self.keys.len() - 1
+                        }
+                    };
+                    self.keys[index].1.add(&member.node);
+                }
+            }
+            Value::Array(nodes) => {
+                let items = self.items.get_or_insert_default();
+                for node in nodes {
+                    items.add(node);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.items.is_none()
+    }
+}
+
+/// Returns the outline of the keys of `root`, up to `depth` levels of keys if set.
+pub fn outline(root: &Node, depth: Option<usize>, style: AnsiStyle) -> String {
+    let mut shape = Shape::default();
+    shape.add(root);
+    let mut writer = OutlineWriter {
+        style,
+        depth: depth.unwrap_or(usize::MAX),
+        out: String::new(),
+    };
+    writer.write_children(&shape, 0);
+    writer.out
+}
+
+struct OutlineWriter<'a> {
+    style: AnsiStyle<'a>,
+    /// Maximum number of levels of keys.
+    depth: usize,
+    out: String,
+}
+
+impl OutlineWriter<'_> {
+    /// Writes the keys of `shape` at `level`, and a `[]` line for its items.
+    fn write_children(&mut self, shape: &Shape, level: usize) {
+        if level >= self.depth {
+            return;
+        }
+        for (key, shape) in &shape.keys {
+            self.write_line(Some(key), shape, level);
+        }
+        if let Some(items) = &shape.items {
+            self.write_line(None, items, level);
+        }
+    }
+
+    /// Writes a line for a key, or `[]` for array items, followed by a `[]` for each level of
+    /// nested arrays without keys, then the lines of its children.
+    fn write_line(&mut self, key: Option<&str>, mut shape: &Shape, level: usize) {
+        self.out.push_str(&"  ".repeat(level));
+        match key {
+            Some(key) => _ = self.style.write_styled(Style::Key, key, &mut self.out),
+            None => {
+                _ = self
+                    .style
+                    .write_styled(Style::Punctuation, "[]", &mut self.out)
+            }
+        }
+        while shape.keys.is_empty()
+            && let Some(items) = &shape.items
+        {
+            _ = self
+                .style
+                .write_styled(Style::Punctuation, "[]", &mut self.out);
+            shape = items;
+        }
+        if level + 1 >= self.depth && !shape.is_empty() {
+            self.out.push(' ');
+            _ = self.style.write_styled(Style::Comment, "…", &mut self.out);
+        }
+        self.out.push('\n');
+        self.write_children(shape, level + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::outline;
+    use pretty::format::{AnsiStyle, Color};
+    use pretty::value::parse;
+
+    #[test]
+    fn outline_keys() {
+        let style = AnsiStyle::new(Color::NoColor);
+        let input = br#"{
+            "users": [{"id": 1, "address": {"city": "a"}}, {"id": 2, "tags": ["x"]}],
+            "matrix": [[1, 2], [3]],
+            "meta": {"count": 2, "items": [1], "next": null},
+            "a\"b": true
+        }"#;
+        let root = parse(input).unwrap();
+        assert_eq!(
+            outline(&root, None, style),
+            r#"users[]
+  id
+  address
+    city
+  tags[]
+matrix[][]
+meta
+  count
+  items[]
+  next
+a"b
+"#
+        );
+        assert_eq!(
+            outline(&root, Some(1), style),
+            "users[] …\nmatrix[][]\nmeta …\na\"b\n"
+        );
+        let root = parse(br#"[{"id": 1}, {"id": 2, "name": "b"}]"#).unwrap();
+        assert_eq!(outline(&root, None, style), "[]\n  id\n  name\n");
+        assert_eq!(outline(&parse(b"1").unwrap(), None, style), "");
+    }
+}
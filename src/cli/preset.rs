@@ -0,0 +1,135 @@
+//! Named sets of formatting options (`--preset diff-friendly`).
+use std::str::FromStr;
+
+/// A formatting preset.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Preset {
+    /// One line, without whitespace.
+    Compact,
+    /// The default formatting, colored.
+    Pretty,
+    /// One line, with sorted keys and normalized numbers: equal documents have the same output.
+    Canonical,
+    /// Sorted keys and normalized numbers, one value per line: regenerated files have minimal
+    /// diffs.
+    DiffFriendly,
+    /// Embedded JSON expanded and multiline strings rendered, to read logs.
+    LogView,
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "compact" => Ok(Preset::Compact),
+            "pretty" => Ok(Preset::Pretty),
+            "canonical" => Ok(Preset::Canonical),
+            "diff-friendly" => Ok(Preset::DiffFriendly),
+            "log-view" => Ok(Preset::LogView),
+            _ => Err(format!(
+                "unknown preset {s} (expected compact, pretty, canonical, diff-friendly or log-view)"
+            )),
+        }
+    }
+}
+
+/// Options set by a preset, from the command line options (sorting keys, normalizing numbers...
+/// are not [pretty::format::FormatterOptions]). Options of the command line are combined with
+/// them: a preset only enables options, or disables colors.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PresetOptions {
+    pub compact: bool,
+    pub sort_keys: bool,
+    pub normalize_numbers: bool,
+    pub shortest_floats: bool,
+    pub color: bool,
+    pub expand_embedded: bool,
+    pub render_newlines: bool,
+    pub final_newline: Option<bool>,
+}
+
+impl Default for PresetOptions {
+    fn default() -> Self {
+        PresetOptions {
+            compact: false,
+            sort_keys: false,
+            normalize_numbers: false,
+            shortest_floats: false,
+            color: true,
+            expand_embedded: false,
+            render_newlines: false,
+            final_newline: None,
+        }
+    }
+}
+
+impl Preset {
+    /// Returns the options of this preset.
+    pub fn options(self) -> PresetOptions {
+        match self {
+            Preset::Compact => compact(),
+            Preset::Pretty => pretty(),
+            Preset::Canonical => canonical(),
+            Preset::DiffFriendly => diff_friendly(),
+            Preset::LogView => log_view(),
+        }
+    }
+}
+
+fn compact() -> PresetOptions {
+    PresetOptions {
+        compact: true,
+        color: false,
+        ..PresetOptions::default()
+    }
+}
+
+fn pretty() -> PresetOptions {
+    PresetOptions::default()
+}
+
+fn canonical() -> PresetOptions {
+    PresetOptions {
+        sort_keys: true,
+        normalize_numbers: true,
+        shortest_floats: true,
+        ..compact()
+    }
+}
+
+fn diff_friendly() -> PresetOptions {
+    PresetOptions {
+        sort_keys: true,
+        normalize_numbers: true,
+        color: false,
+        final_newline: Some(true),
+        ..PresetOptions::default()
+    }
+}
+
+fn log_view() -> PresetOptions {
+    PresetOptions {
+        expand_embedded: true,
+        render_newlines: true,
+        ..PresetOptions::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Preset, PresetOptions};
+
+    #[test]
+    fn preset_options() {
+        let canonical = "canonical".parse::<Preset>().unwrap().options();
+        assert!(canonical.compact && canonical.sort_keys && canonical.normalize_numbers);
+        assert!(canonical.shortest_floats && !Preset::DiffFriendly.options().shortest_floats);
+        assert!(!canonical.color);
+        assert_eq!(Preset::Pretty.options(), PresetOptions::default());
+        assert_eq!(Preset::DiffFriendly.options().final_newline, Some(true));
+        assert!(!Preset::DiffFriendly.options().compact);
+        assert!(Preset::LogView.options().color);
+        assert!("minified".parse::<Preset>().is_err());
+    }
+}
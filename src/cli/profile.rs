@@ -0,0 +1,147 @@
+use pretty::path::is_identifier;
+use pretty::value::{Node, Value, unescape};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Write;
+
+/// Returns a report of the subtrees and keys contributing the most bytes to the document.
+///
+/// Sizes are computed from the spans of the parsed tree, so they're the sizes in the input (with
+/// its original whitespaces), not in the formatted output.
+pub fn report(root: &Node, top: usize) -> String {
+    let mut profiler = Profiler {
+        top,
+        subtrees: BinaryHeap::new(),
+        keys: HashMap::new(),
+        path: String::new(),
+    };
+    profiler.visit(root);
+
+    let total = root.span.len().max(1);
+    let mut out = String::new();
+    _ = writeln!(out, "Total: {} bytes", root.span.len());
+    _ = writeln!(out);
+    _ = writeln!(out, "Largest subtrees:");
+    _ = writeln!(out, "{:>12} {:>7}  path", "bytes", "%");
+    let mut subtrees = profiler.subtrees.into_vec();
+    subtrees.sort();
+    for Reverse((size, path)) in subtrees {
+        let percent = size as f64 * 100.0 / total as f64;
+        _ = writeln!(out, "{size:>12} {percent:>6.1}%  {path}");
+    }
+
+    _ = writeln!(out);
+    _ = writeln!(out, "Largest keys (cumulated key and value sizes):");
+    _ = writeln!(out, "{:>12} {:>7} {:>9}  key", "bytes", "%", "count");
+    let mut keys = profiler.keys.into_iter().collect::<Vec<_>>();
+    keys.sort_by(|(k1, (c1, s1)), (k2, (c2, s2))| (s2, c2, k1).cmp(&(s1, c1, k2)));
+    for (key, (count, size)) in keys.into_iter().take(top) {
+        let percent = size as f64 * 100.0 / total as f64;
+        _ = writeln!(out, "{size:>12} {percent:>6.1}% {count:>9}  {key}");
+    }
+    out
+}
+
+struct Profiler {
+    top: usize,
+    /// Min-heap of the `top` largest subtrees (size, path).
+    subtrees: BinaryHeap<Reverse<(usize, String)>>,
+    /// Count and cumulated size per key.
+    keys: HashMap<String, (usize, usize)>,
+    /// Path of the current node, updated while walking the tree.
+    path: String,
+}
+
+impl Profiler {
+    fn visit(&mut self, node: &Node) {
+        self.add_subtree(node.span.len());
+        match &node.value {
+            Value::Array(nodes) => {
+                for (i, node) in nodes.iter().enumerate() {
+                    let len = self.path.len();
+                    _ = write!(self.path, "[{i}]");
+                    self.visit(node);
+                    self.path.truncate(len);
+                }
+            }
+            Value::Object(members) => {
+                for member in members {
+                    let key = unescape(&member.key);
+                    let len = self.path.len();
+                    push_key(&mut self.path, &key, &member.key);
+
+                    let size = member.key_span.len() + member.node.span.len();
+                    let stats = self.keys.entry(key).or_default();
+                    stats.0 += 1;
+                    stats.1 += size;
+
+                    self.visit(&member.node);
+                    self.path.truncate(len);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_subtree(&mut self, size: usize) {
+        if self.top == 0 {
+            return;
+        }
+        if self.subtrees.len() < self.top {
+            self.subtrees.push(Reverse((size, self.path_or_root())));
+        } else if let Some(Reverse((min, _))) = self.subtrees.peek()
+            && size > *min
+        {
+            self.subtrees.pop();
+            self.subtrees.push(Reverse((size, self.path_or_root())));
+        }
+    }
+
+    fn path_or_root(&self) -> String {
+        if self.path.is_empty() {
+            ".".to_string()
+        } else {
+            self.path.clone()
+        }
+    }
+}
+
+/// Appends an object key to a path: `.key` for simple keys, `["raw key"]` otherwise.
+fn push_key(path: &mut String, key: &str, raw: &str) {
+    if is_identifier(key) {
+        path.push('.');
+        path.push_str(key);
+    } else {
+        path.push('[');
+        path.push_str(raw);
+        path.push(']');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty::value::parse;
+
+    #[test]
+    fn profile_report() {
+        let input = br#"{"a":[1,2,3],"b":{"a":"xxxxxxxxxx"},"c d":null}"#;
+        let root = parse(input).unwrap();
+        assert_eq!(
+            report(&root, 3),
+            "Total: 47 bytes\n\
+             \n\
+             Largest subtrees:\n\
+             \x20      bytes       %  path\n\
+             \x20         47  100.0%  .\n\
+             \x20         18   38.3%  .b\n\
+             \x20         12   25.5%  .b.a\n\
+             \n\
+             Largest keys (cumulated key and value sizes):\n\
+             \x20      bytes       %     count  key\n\
+             \x20         25   53.2%         2  a\n\
+             \x20         21   44.7%         1  b\n\
+             \x20          9   19.1%         1  c d\n"
+        );
+    }
+}
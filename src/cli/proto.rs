@@ -0,0 +1,234 @@
+//! Protocol Buffers canonical JSON (proto3 JSON mapping) support.
+//!
+//! In proto3 JSON, 64-bit integers are strings (`"9007199254740993"`), `google.protobuf.Duration`
+//! values are strings in seconds (`"1.5s"`), `google.protobuf.Timestamp` values are RFC 3339
+//! strings and `google.protobuf.Any` messages have a `@type` member with a type URL.
+use pretty::decorate::ValueDecorator;
+use pretty::path::{Path, Segment};
+use pretty::value;
+use pretty::value::{Node, Value};
+
+/// Annotates the values with a proto3 well-known shape: `"12"  /* int64 */`.
+pub struct Proto3;
+
+impl ValueDecorator for Proto3 {
+    fn decorate(&self, key: &str, raw: &str) -> Option<String> {
+        let s = raw.strip_prefix('"')?.strip_suffix('"')?;
+        let annotation = if key == "@type" {
+            let (_, name) = s.rsplit_once('/')?;
+            format!("Any {name}")
+        } else if is_int64(s) {
+            "int64".to_string()
+        } else if is_duration(s) {
+            "Duration".to_string()
+        } else if is_timestamp(s) {
+            "Timestamp".to_string()
+        } else {
+            return None;
+        };
+        Some(annotation)
+    }
+}
+
+/// Largest integer exactly representable by a JSON number parsed as a double.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Max number of seconds of a `google.protobuf.Duration` (10,000 years).
+const MAX_DURATION_SECONDS: u64 = 315_576_000_000;
+
+/// Checks the proto3 shapes of a document, returns a message for each error.
+///
+/// `@type` members must be type URLs, integers that can't be represented by a double must be
+/// strings, and strings that look like durations or timestamps must be valid.
+pub fn check(root: &Node) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut path = Path { segments: vec![] };
+    check_node(root, &mut path, &mut errors);
+    errors
+}
+
+fn check_node(node: &Node, path: &mut Path, errors: &mut Vec<String>) {
+    match &node.value {
+        Value::Number(n) => {
+            let is_integer = !n.contains(['.', 'e', 'E']);
+            if is_integer && n.parse::<f64>().is_ok_and(|n| n.abs() > MAX_SAFE_INTEGER) {
+                errors.push(format!("{path}: 64-bit integer {n} should be a string"));
+            }
+        }
+        Value::String(raw) => {
+            let s = value::unescape(raw);
+            let is_type = matches!(path.segments.last(), Some(Segment::Key(key)) if key == "@type");
+            if is_type && !is_type_url(&s) {
+                errors.push(format!("{path}: {raw} is not a type URL"));
+            } else if looks_like_duration(&s) && !is_duration(&s) {
+                errors.push(format!("{path}: {raw} is not a valid Duration"));
+            } else if looks_like_timestamp(&s) && !is_timestamp(&s) {
+                errors.push(format!("{path}: {raw} is not a valid Timestamp"));
+            }
+        }
+        Value::Array(nodes) => {
+            for (i, node) in nodes.iter().enumerate() {
+                path.segments.push(Segment::Index(i));
+                check_node(node, path, errors);
+                path.segments.pop();
+            }
+        }
+        Value::Object(members) => {
+            for member in members {
+                let key = value::unescape(&member.key);
+                if key == "@type" && !matches!(member.node.value, Value::String(_)) {
+                    errors.push(format!("{path}: @type should be a string"));
+                }
+                path.segments.push(Segment::Key(key));
+                check_node(&member.node, path, errors);
+                path.segments.pop();
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
+
+/// `type.googleapis.com/google.protobuf.Duration`
+fn is_type_url(s: &str) -> bool {
+    s.rsplit_once('/').is_some_and(|(_, name)| {
+        !name.is_empty()
+            && name.split('.').all(|part| {
+                !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+            })
+    })
+}
+
+/// A decimal integer, as 64-bit integers are written.
+fn is_int64(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    !digits.is_empty()
+        && digits.len() <= 20
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && s.parse::<i128>()
+            .is_ok_and(|n| n >= i64::MIN.into() && n <= u64::MAX.into())
+}
+
+/// A decimal number followed by `s`.
+fn looks_like_duration(s: &str) -> bool {
+    let Some(number) = s.strip_suffix('s') else {
+        return false;
+    };
+    let number = number.strip_prefix('-').unwrap_or(number);
+    number.starts_with(|c: char| c.is_ascii_digit())
+        && number.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+}
+
+/// Seconds with up to 9 fractional digits, followed by `s`: `"1.5s"`, `"-3s"`.
+fn is_duration(s: &str) -> bool {
+    if !looks_like_duration(s) {
+        return false;
+    }
+    let number = s.trim_start_matches('-').trim_end_matches('s');
+    let (seconds, nanos) = number.split_once('.').unwrap_or((number, "0"));
+    !nanos.is_empty()
+        && nanos.len() <= 9
+        && !nanos.contains('.')
+        && seconds
+            .parse::<u64>()
+            .is_ok_and(|s| s <= MAX_DURATION_SECONDS)
+}
+
+/// A date followed by a time: `"2024-01-31T..."`.
+fn looks_like_timestamp(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() > 10
+        && b[..10].iter().enumerate().all(|(i, b)| {
+            if i == 4 || i == 7 {
+                *b == b'-'
+            } else {
+                b.is_ascii_digit()
+            }
+        })
+        && matches!(b[10], b'T' | b't')
+}
+
+/// A RFC 3339 timestamp with seconds: `"2024-01-31T10:00:00.123Z"`.
+fn is_timestamp(s: &str) -> bool {
+    if !looks_like_timestamp(s) {
+        return false;
+    }
+    let b = s.as_bytes();
+    let num = |range: std::ops::Range<usize>| -> Option<u32> {
+        let digits = b.get(range)?;
+        digits
+            .iter()
+            .all(u8::is_ascii_digit)
+            .then(|| std::str::from_utf8(digits).ok()?.parse().ok())?
+    };
+    let valid_time = (|| {
+        let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+        let (hours, minutes, seconds) = (num(11..13)?, num(14..16)?, num(17..19)?);
+        let valid = year >= 1
+            && (1..=12).contains(&month)
+            && (1..=31).contains(&day)
+            && hours < 24
+            && minutes < 60
+            && seconds < 60
+            && b[13] == b':'
+            && b[16] == b':';
+        valid.then_some(())
+    })();
+    if valid_time.is_none() {
+        return false;
+    }
+    let mut rest = &s[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 || len > 9 {
+            return false;
+        }
+        rest = &fraction[len..];
+    }
+    match rest.as_bytes() {
+        [b'Z' | b'z'] => true,
+        [b'+' | b'-', h1, h2, b':', m1, m2] => [h1, h2, m1, m2].iter().all(|b| b.is_ascii_digit()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Proto3, check};
+    use pretty::decorate::ValueDecorator;
+    use pretty::value;
+
+    #[test]
+    fn annotate_proto3_values() {
+        let annotate = |key, raw| Proto3.decorate(key, raw);
+        assert_eq!(annotate("id", r#""9007199254740993""#).unwrap(), "int64");
+        assert_eq!(annotate("timeout", r#""1.5s""#).unwrap(), "Duration");
+        assert_eq!(
+            annotate("created", r#""2024-01-31T10:00:00.123456789Z""#).unwrap(),
+            "Timestamp"
+        );
+        assert_eq!(
+            annotate("@type", r#""type.googleapis.com/acme.v1.User""#).unwrap(),
+            "Any acme.v1.User"
+        );
+        assert_eq!(annotate("id", "12"), None);
+        assert_eq!(annotate("name", r#""bob""#), None);
+    }
+
+    #[test]
+    fn check_proto3_shapes() {
+        let input = br#"{"a": 9007199254740993, "b": "9007199254740993", "c": [1.5e300, 3],
+            "d": {"@type": "User"}, "e": "1.1234567891s", "f": "2024-01-31T10:00:00",
+            "g": "2024-02-30T10:00:00+01:00", "h": "400000000000s"}"#;
+        let root = value::parse(input).unwrap();
+        assert_eq!(
+            check(&root),
+            vec![
+                ".a: 64-bit integer 9007199254740993 should be a string",
+                r#".d["@type"]: "User" is not a type URL"#,
+                r#".e: "1.1234567891s" is not a valid Duration"#,
+                r#".f: "2024-01-31T10:00:00" is not a valid Timestamp"#,
+                r#".h: "400000000000s" is not a valid Duration"#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use pretty::format::{Color, FormatError, Formatter};
+use std::io;
+use std::io::{BufRead, IsTerminal, Write};
+
+const PROMPT: &str = "> ";
+const PROMPT_CONTINUE: &str = ". ";
+
+/// Runs an interactive session: each line read on standard input is formatted as soon as it is
+/// a complete JSON value. Incomplete values (a pasted multi-lines block for instance) are
+/// accumulated until a blank line is read. Errors are displayed inline and don't stop the session.
+pub fn run(color: Color) -> io::Result<()> {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut stdout = io::stdout();
+    let mut buffer = String::new();
+
+    loop {
+        if interactive {
+            let prompt = if buffer.is_empty() {
+                PROMPT
+            } else {
+                PROMPT_CONTINUE
+            };
+            stdout.write_all(prompt.as_bytes())?;
+            stdout.flush()?;
+        }
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            // End of input: flush what remains in the buffer.
+            if !buffer.trim().is_empty() {
+                write_result(&mut stdout, &buffer, color)?;
+            }
+            return Ok(());
+        }
+
+        if line.trim().is_empty() {
+            // A blank line terminates a block.
+            if !buffer.trim().is_empty() {
+                write_result(&mut stdout, &buffer, color)?;
+            }
+            buffer.clear();
+            continue;
+        }
+
+        buffer.push_str(&line);
+        match format(&buffer, color) {
+            // An incomplete value: we wait for more lines.
+            Err(FormatError::Eof) => continue,
+            _ => {
+                write_result(&mut stdout, &buffer, color)?;
+                buffer.clear();
+            }
+        }
+    }
+}
+
+fn format(input: &str, color: Color) -> Result<String, FormatError> {
+    let mut formatter = Formatter::new(input.as_bytes(), color);
+    let mut output = String::new();
+    formatter.format(&mut output)?;
+    Ok(output)
+}
+
+fn write_result(out: &mut impl Write, input: &str, color: Color) -> io::Result<()> {
+    match format(input, color) {
+        Ok(output) => writeln!(out, "{output}"),
+        Err(err) if color == Color::AnsiCode => writeln!(out, "\x1b[1;31merror\x1b[0m: {err}"),
+        Err(err) => writeln!(out, "error: {err}"),
+    }
+}
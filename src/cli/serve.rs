@@ -0,0 +1,350 @@
+use pretty::format::{Color, Formatter};
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Runs an HTTP/1.1 server listening on `bind:port`.
+///
+/// Any JSON POSTed (on any path) is returned pretty-printed, as `text/plain` without color or as
+/// HTML if the client accepts `text/html` (or if the query string contains `format=html`).
+/// Each connection is handled in its own thread and closed after the response, with at most
+/// [MAX_CONNECTIONS] connections at the same time. Bodies larger than `max_body` bytes are
+/// rejected.
+pub fn run(bind: &str, port: u16, max_body: usize) -> io::Result<()> {
+    let listener = TcpListener::bind((bind, port))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+
+    let connections = Arc::new(Connections::default());
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let slot = Connections::acquire(&connections);
+                thread::spawn(move || {
+                    let _slot = slot;
+                    if let Err(err) = handle_connection(stream, max_body) {
+                        eprintln!("Error handling connection: {err}");
+                    }
+                });
+            }
+            Err(err) => eprintln!("Error accepting connection: {err}"),
+        }
+    }
+    Ok(())
+}
+
+/// Default maximum size of a request body, without `--max-input-size`.
+pub const DEFAULT_MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// Maximum length of the request line and of a header line.
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+
+/// Maximum number of header lines of a request.
+const MAX_HEADERS: usize = 100;
+
+/// Maximum number of connections handled at the same time, the next ones are accepted once a
+/// connection is closed.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Maximum duration of a read or a write on a connection, so idle clients don't hold a slot.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Count of the connections being handled.
+#[derive(Default)]
+struct Connections {
+    count: Mutex<usize>,
+    released: Condvar,
+}
+
+/// A connection being handled, released when dropped.
+struct Slot(Arc<Connections>);
+
+impl Connections {
+    /// Waits until less than [MAX_CONNECTIONS] connections are handled, and returns a new slot.
+    fn acquire(connections: &Arc<Connections>) -> Slot {
+        let mut count = connections.count.lock().unwrap();
+        while *count >= MAX_CONNECTIONS {
+            count = connections.released.wait(count).unwrap();
+        }
+        *count += 1;
+        Slot(Arc::clone(connections))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut count = self.0.count.lock().unwrap();
+        *count -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+fn handle_connection(stream: TcpStream, max_body: usize) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    handle(&mut reader, &mut writer, max_body)
+}
+
+/// Reads one request from `reader` and writes its response to `writer`.
+fn handle(reader: &mut impl BufRead, writer: &mut impl Write, max_body: usize) -> io::Result<()> {
+    let response = match read_request(reader, max_body)? {
+        Ok(request) => respond(&request),
+        Err(response) => response,
+    };
+    write_response(writer, &response)
+}
+
+/// An HTTP request, with only the parts we need.
+struct Request {
+    method: String,
+    target: String,
+    accept: String,
+    body: Vec<u8>,
+}
+
+/// An HTTP response.
+struct Response {
+    status: u16,
+    reason: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn error(status: u16, reason: &'static str) -> Self {
+        Response {
+            status,
+            reason,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{reason}\n").into_bytes(),
+        }
+    }
+}
+
+/// Reads a line of at most [MAX_LINE_LENGTH] bytes in `line`, returns `false` if the line is
+/// longer.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<bool> {
+    line.clear();
+    let n = reader.take(MAX_LINE_LENGTH as u64 + 1).read_line(line)?;
+    Ok(n <= MAX_LINE_LENGTH)
+}
+
+/// Reads a request, returns an error response if the request is not supported.
+fn read_request(
+    reader: &mut impl BufRead,
+    max_body: usize,
+) -> io::Result<Result<Request, Response>> {
+    let mut line = String::new();
+    if !read_line(reader, &mut line)? {
+        return Ok(Err(Response::error(414, "URI Too Long")));
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(Err(Response::error(400, "Bad Request")));
+    };
+    let method = method.to_string();
+    let target = target.to_string();
+
+    let mut content_length = None;
+    let mut accept = String::new();
+    for headers in 0.. {
+        if headers > MAX_HEADERS || !read_line(reader, &mut line)? {
+            return Ok(Err(Response::error(431, "Request Header Fields Too Large")));
+        }
+        if line.is_empty() {
+            return Ok(Err(Response::error(400, "Bad Request")));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Ok(Err(Response::error(400, "Bad Request")));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            match value.parse::<usize>() {
+                Ok(length) => content_length = Some(length),
+                Err(_) => return Ok(Err(Response::error(400, "Bad Request"))),
+            }
+        } else if name.eq_ignore_ascii_case("Accept") {
+            accept = value.to_string();
+        }
+    }
+
+    if method != "POST" {
+        return Ok(Err(Response::error(405, "Method Not Allowed")));
+    }
+    // Chunked bodies are not supported.
+    let Some(content_length) = content_length else {
+        return Ok(Err(Response::error(411, "Length Required")));
+    };
+    if content_length > max_body {
+        return Ok(Err(Response::error(413, "Payload Too Large")));
+    }
+    // The body is read as it arrives, not allocated from the announced length.
+    let mut body = Vec::new();
+    reader.take(content_length as u64).read_to_end(&mut body)?;
+    if body.len() != content_length {
+        return Ok(Err(Response::error(400, "Bad Request")));
+    }
+
+    Ok(Ok(Request {
+        method,
+        target,
+        accept,
+        body,
+    }))
+}
+
+fn respond(request: &Request) -> Response {
+    debug_assert!(request.method == "POST");
+    let mut output = String::new();
+    let mut formatter = Formatter::new(&request.body, Color::NoColor);
+    if let Err(err) = formatter.format(&mut output) {
+        return Response {
+            status: 400,
+            reason: "Bad Request",
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{err}\n").into_bytes(),
+        };
+    }
+    output.push('\n');
+
+    let query = request
+        .target
+        .split_once('?')
+        .map_or("", |(_, query)| query);
+    let html = request.accept.contains("text/html") || query.split('&').any(|p| p == "format=html");
+    if html {
+        let body = format!(
+            "<!DOCTYPE html>\n<html>\n<body>\n<pre>{}</pre>\n</body>\n</html>\n",
+            escape_html(&output)
+        );
+        Response {
+            status: 200,
+            reason: "OK",
+            content_type: "text/html; charset=utf-8",
+            body: body.into_bytes(),
+        }
+    } else {
+        Response {
+            status: 200,
+            reason: "OK",
+            content_type: "text/plain; charset=utf-8",
+            body: output.into_bytes(),
+        }
+    }
+}
+
+fn write_response(writer: &mut impl Write, response: &Response) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.reason,
+        response.content_type,
+        response.body.len()
+    )?;
+    writer.write_all(&response.body)?;
+    writer.flush()
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Connections, DEFAULT_MAX_BODY, MAX_CONNECTIONS, handle};
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    fn call(request: &str) -> String {
+        let mut reader = Cursor::new(request.as_bytes().to_vec());
+        let mut writer = Vec::new();
+        handle(&mut reader, &mut writer, DEFAULT_MAX_BODY).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    #[test]
+    fn limit_connections() {
+        let connections = Arc::new(Connections::default());
+        let mut slots = (0..MAX_CONNECTIONS)
+            .map(|_| Connections::acquire(&connections))
+            .collect::<Vec<_>>();
+        let waiting = {
+            let connections = Arc::clone(&connections);
+            std::thread::spawn(move || drop(Connections::acquire(&connections)))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!waiting.is_finished());
+        slots.pop();
+        waiting.join().unwrap();
+        drop(slots);
+        assert_eq!(*connections.count.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn post_json_returns_text() {
+        let response = call("POST / HTTP/1.1\r\nContent-Length: 7\r\n\r\n{\"a\":1}");
+        assert_eq!(
+            response,
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 13\r\n\
+             Connection: close\r\n\
+             \r\n\
+             {\n  \"a\": 1\n}\n"
+        );
+    }
+
+    #[test]
+    fn post_json_returns_html() {
+        let response =
+            call("POST /?format=html HTTP/1.1\r\nContent-Length: 9\r\n\r\n{\"a\":\"<\"}");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/html"));
+        assert!(response.contains("<pre>{\n  &quot;a&quot;: &quot;&lt;&quot;\n}\n</pre>"));
+    }
+
+    #[test]
+    fn post_invalid_json() {
+        let response = call("POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\n[1,]");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(response.ends_with("invalid byte <5d> at offset 3\n"));
+    }
+
+    #[test]
+    fn reject_large_requests() {
+        let response = call("POST / HTTP/1.1\r\nContent-Length: 900000000000000\r\n\r\n{}");
+        assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+        let response = call("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        let header = format!("X-Long: {}\r\n", "a".repeat(10_000));
+        let response = call(&format!("POST / HTTP/1.1\r\n{header}\r\n"));
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+        let headers = "X-A: b\r\n".repeat(200);
+        let response = call(&format!("POST / HTTP/1.1\r\n{headers}\r\n"));
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+    }
+
+    #[test]
+    fn get_not_allowed() {
+        let response = call("GET / HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
@@ -0,0 +1,145 @@
+/// Separator between the two columns.
+const SEPARATOR: &str = " │ ";
+
+/// Default width if the terminal width is unknown.
+const DEFAULT_WIDTH: usize = 160;
+
+/// Returns the terminal width, from the `COLUMNS` environment variable.
+pub fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&columns| columns > SEPARATOR.len())
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+/// Renders two texts (possibly with ANSI escape codes) in two aligned columns, fitting in `width`
+/// characters. Lines too long for their column are truncated with `…`.
+pub fn render(left: &str, right: &str, width: usize) -> String {
+    let column = width.saturating_sub(SEPARATOR.chars().count()) / 2;
+    let mut left_lines = left.lines();
+    let mut right_lines = right.lines();
+    let mut out = String::new();
+    loop {
+        let (l, r) = (left_lines.next(), right_lines.next());
+        if l.is_none() && r.is_none() {
+            return out;
+        }
+        let l = fit(l.unwrap_or(""), column);
+        let r = fit(r.unwrap_or(""), column);
+        out.push_str(&l);
+        out.push_str(SEPARATOR);
+        out.push_str(r.trim_end());
+        out.push('\n');
+    }
+}
+
+/// Truncates or pads `line` so that its displayed width is exactly `width`.
+/// ANSI escape codes are kept and don't count in the width.
+fn fit(line: &str, width: usize) -> String {
+    let line_width = visible_width(line);
+    let mut out = String::with_capacity(line.len());
+    if line_width <= width {
+        out.push_str(line);
+        out.extend(std::iter::repeat_n(' ', width - line_width));
+        return out;
+    }
+
+    // Keep one column for the ellipsis.
+    let max = width.saturating_sub(1);
+    let mut current = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Copy the escape sequence `ESC [ ... m` as is.
+            out.push(c);
+            for c in chars.by_ref() {
+                out.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        let w = char_width(c);
+        if current + w > max {
+            break;
+        }
+        out.push(c);
+        current += w;
+    }
+    out.push('…');
+    current += 1;
+    // Reset the color that could have been started.
+    if out.contains('\x1b') {
+        out.push_str("\x1b[0m");
+    }
+    out.extend(std::iter::repeat_n(' ', width.saturating_sub(current)));
+    out
+}
+
+/// Returns the displayed width of a string, ignoring ANSI escape codes.
+fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            width += char_width(c);
+        }
+    }
+    width
+}
+
+/// Returns the number of columns used to display this char in a terminal (approximated: 2 for
+/// East Asian wide chars and emojis, 0 for combining marks and zero-width chars, 1 otherwise).
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_lines() {
+        assert_eq!(fit("abc", 5), "abc  ");
+        assert_eq!(fit("abcde", 5), "abcde");
+        assert_eq!(fit("abcdef", 5), "abcd…");
+        assert_eq!(fit("你好世界", 5), "你好…");
+        assert_eq!(fit("\x1b[1;34m\"key\"\x1b[0m", 4), "\x1b[1;34m\"ke…\x1b[0m");
+        assert_eq!(fit("\x1b[1;34mab\x1b[0m", 3), "\x1b[1;34mab\x1b[0m ");
+    }
+
+    #[test]
+    fn render_columns() {
+        let left = "{\n  \"a\": 1\n}";
+        let right = "[\n  1,\n  2\n]";
+        assert_eq!(
+            render(left, right, 23),
+            "{          │ [\n  \"a\": 1   │   1,\n}          │   2\n           │ ]\n"
+        );
+    }
+}
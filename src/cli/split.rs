@@ -0,0 +1,117 @@
+//! Records written to sequentially numbered files (`--split-size 50M`, `--split-records 10000`).
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+/// Limits of the output files, and their names.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Split {
+    /// Maximum size of a file, in bytes. A record larger than this size is written alone in a
+    /// file.
+    pub size: Option<usize>,
+    /// Maximum number of records of a file.
+    pub records: Option<usize>,
+    /// Names of the files, where `{n}` is replaced by the 1-based number of the file, on 5 digits.
+    pub name: String,
+}
+
+impl Split {
+    /// Returns the path of the `n`th file.
+    fn path(&self, n: usize) -> PathBuf {
+        PathBuf::from(self.name.replace("{n}", &format!("{n:05}")))
+    }
+}
+
+/// Writes records to files, starting a new file when the current one is full.
+pub struct SplitWriter {
+    split: Split,
+    /// The current file, with its path.
+    file: Option<(BufWriter<File>, PathBuf)>,
+    /// Number of files created.
+    files: usize,
+    /// Size and number of records of the current file.
+    size: usize,
+    records: usize,
+}
+
+impl SplitWriter {
+    pub fn new(split: Split) -> Self {
+        SplitWriter {
+            split,
+            file: None,
+            files: 0,
+            size: 0,
+            records: 0,
+        }
+    }
+
+    /// Writes a record (with its line ending) in the current file, or in a new file if the
+    /// record doesn't fit.
+    pub fn write_record(&mut self, record: &str) -> Result<(), String> {
+        let full = self.split.records.is_some_and(|max| self.records >= max)
+            || self
+                .split
+                .size
+                .is_some_and(|max| self.records > 0 && self.size + record.len() > max);
+        if self.file.is_none() || full {
+            self.flush()?;
+            self.files += 1;
+            let path = self.split.path(self.files);
+            let file = File::create(&path).map_err(|err| error(&path, err))?;
+            self.file = Some((BufWriter::new(file), path));
+            self.size = 0;
+            self.records = 0;
+        }
+        if let Some((file, path)) = &mut self.file {
+            file.write_all(record.as_bytes())
+                .map_err(|err| error(path, err))?;
+        }
+        self.size += record.len();
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Writes the buffered records of the current file.
+    pub fn flush(&mut self) -> Result<(), String> {
+        match &mut self.file {
+            Some((file, path)) => file.flush().map_err(|err| error(path, err)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn error(path: &std::path::Path, err: std::io::Error) -> String {
+    format!("Error writing file '{}': {err}", path.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Split, SplitWriter};
+    use std::fs;
+
+    #[test]
+    fn split_records() {
+        let dir = std::env::temp_dir().join(format!("pretty-split-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let name = dir.join("part-{n}.ndjson").display().to_string();
+        let file = |n: &str| fs::read_to_string(dir.join(format!("part-{n}.ndjson"))).unwrap();
+
+        let split = Split {
+            size: Some(10),
+            records: Some(3),
+            name,
+        };
+        let mut writer = SplitWriter::new(split);
+        for record in ["1\n", "2\n", "3\n", "4\n", "12345678901\n", "5\n"] {
+            writer.write_record(record).unwrap();
+        }
+        writer.flush().unwrap();
+        assert_eq!(file("00001"), "1\n2\n3\n");
+        assert_eq!(file("00002"), "4\n");
+        assert_eq!(file("00003"), "12345678901\n");
+        assert_eq!(file("00004"), "5\n");
+        assert!(!dir.join("part-00005.ndjson").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
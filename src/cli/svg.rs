@@ -0,0 +1,99 @@
+//! Pretty output rendered as an SVG image, to embed colored JSON in docs and slides.
+//!
+//! The output of a formatter with a [SvgStyle](pretty::format::SvgStyle) is made of lines of
+//! `<tspan>` elements. Each line is a `<text>` element of a monospaced image, on the background of a terminal.
+use pretty::theme::Theme;
+use std::fmt::Write;
+
+const FONT_SIZE: f64 = 14.0;
+const LINE_HEIGHT: f64 = 18.0;
+/// Width of a char of a monospaced font of [FONT_SIZE].
+const CHAR_WIDTH: f64 = 8.4;
+const PADDING: f64 = 16.0;
+
+/// Wraps the lines of `formatted`, the output of a formatter with a `SvgStyle`, in an SVG
+/// document sized to fit the text, with the background and text colors of `theme`.
+pub fn to_svg(formatted: &str, theme: &Theme) -> String {
+    let (background, foreground) = (theme.background, theme.foreground);
+    let lines = formatted.lines().collect::<Vec<_>>();
+    let columns = lines.iter().map(|l| visible_width(l)).max().unwrap_or(0);
+    let width = (columns as f64 * CHAR_WIDTH + 2.0 * PADDING).ceil();
+    let height = (lines.len() as f64 * LINE_HEIGHT + 2.0 * PADDING).ceil();
+
+    let mut out = String::new();
+    _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    _ = writeln!(
+        out,
+        r#"<rect width="100%" height="100%" rx="6" fill="{background}"/>"#
+    );
+    _ = writeln!(
+        out,
+        r#"<g font-family="ui-monospace, Menlo, Consolas, monospace" font-size="{FONT_SIZE}" fill="{foreground}">"#
+    );
+    for (i, line) in lines.iter().enumerate() {
+        // Text is placed on its baseline.
+        let y = PADDING + i as f64 * LINE_HEIGHT + FONT_SIZE;
+        _ = writeln!(
+            out,
+            r#"<text x="{PADDING}" y="{y}" xml:space="preserve">{line}</text>"#
+        );
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+/// Returns the number of chars displayed by a line of SVG text: tags are skipped and entities
+/// are one char.
+fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut in_tag = false;
+    let mut in_entity = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if in_tag => {}
+            '&' => {
+                in_entity = true;
+                width += 1;
+            }
+            ';' if in_entity => in_entity = false,
+            _ if in_entity => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_svg, visible_width};
+    use pretty::format::{Formatter, SvgStyle};
+    use pretty::theme::{LIGHT_THEME, Theme};
+
+    #[test]
+    fn render_svg() {
+        let mut formatted = String::new();
+        Formatter::new_styled(br#"{"a<b": [1]}"#, SvgStyle::new())
+            .format(&mut formatted)
+            .unwrap();
+        let lines = formatted.lines().collect::<Vec<_>>();
+        assert_eq!(visible_width(lines[1]), r#"  "a<b": ["#.len());
+
+        let svg = to_svg(&formatted, &Theme::default());
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="116" height="122" viewBox="0 0 116 122">"#
+        ));
+        assert!(svg.contains(
+            r#"<text x="16" y="30" xml:space="preserve"><tspan font-weight="bold">{</tspan></text>"#
+        ));
+        assert_eq!(svg.matches("<text ").count(), 5);
+        assert!(svg.contains(r##"fill="#1e1e1e""##));
+        let svg = to_svg(&formatted, &LIGHT_THEME);
+        assert!(svg.contains(r##"fill="#ffffff""##) && svg.contains(r##"fill="#1e1e1e""##));
+        assert!(svg.ends_with("</g>\n</svg>\n"));
+    }
+}
@@ -0,0 +1,142 @@
+//! Fields of objects printed as an aligned table (`--table .name,.status`).
+use pretty::format::{AnsiStyle, OutputStyle, Style};
+use pretty::path::Path;
+use pretty::value::{Node, Value, unescape};
+
+/// Parses the columns of a table: comma separated paths (commas in quoted keys are kept).
+pub fn parse_columns(s: &str) -> Result<Vec<Path>, String> {
+    let mut columns = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                columns.push(s[start..i].trim().parse()?);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    columns.push(s[start..].trim().parse()?);
+    Ok(columns)
+}
+
+/// A table cell: its text on one line and its style.
+struct Cell {
+    text: String,
+    style: Style,
+}
+
+impl Cell {
+    fn new(node: Option<&Node>) -> Self {
+        let Some(node) = node else {
+            return Cell {
+                text: String::new(),
+                style: Style::Null,
+            };
+        };
+        let (text, style) = match &node.value {
+            Value::Null => ("null".to_string(), Style::Null),
+            Value::Bool(b) => (b.to_string(), Style::Boolean),
+            Value::Number(n) => (n.to_string(), Style::Number),
+            Value::String(raw) => (unescape(raw), Style::String),
+            Value::Array(_) | Value::Object(_) => (node.to_compact_string(), Style::Punctuation),
+        };
+        let text = text.replace(['\n', '\r', '\t'], " ");
+        Cell { text, style }
+    }
+
+    fn width(&self) -> usize {
+        self.text.chars().count()
+    }
+}
+
+/// Renders the values at `columns` of each row as an aligned table, with a header. Numbers are
+/// right-aligned, strings are unescaped and missing values are empty.
+pub fn render(rows: &[&Node], columns: &[Path], style: AnsiStyle) -> String {
+    let header = columns
+        .iter()
+        .map(|path| Cell {
+            text: path.to_string(),
+            style: Style::Key,
+        })
+        .collect::<Vec<_>>();
+    let rows = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|path| Cell::new(row.get(path)))
+                .collect()
+        })
+        .collect::<Vec<Vec<_>>>();
+    let widths = (0..columns.len())
+        .map(|i| {
+            let cells = std::iter::once(&header).chain(&rows).map(|row| &row[i]);
+            cells.map(Cell::width).max().unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let mut line = String::new();
+        for (i, (cell, width)) in row.iter().zip(&widths).enumerate() {
+            if i > 0 {
+                line.push_str("  ");
+            }
+            let padding = " ".repeat(width - cell.width());
+            if cell.style == Style::Number {
+                line.push_str(&padding);
+            }
+            _ = style.write_styled(cell.style, &cell.text, &mut line);
+            if cell.style != Style::Number {
+                line.push_str(&padding);
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_columns, render};
+    use pretty::format::{AnsiStyle, Color};
+    use pretty::value::{Value, parse};
+
+    #[test]
+    fn parse_table_columns() {
+        let columns = parse_columns(r#".name, .status,.["a,\"b"].c"#).unwrap();
+        let columns = columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        assert_eq!(columns, [".name", ".status", r#"["a,\"b"].c"#]);
+        assert!(parse_columns(".a,b").is_err());
+    }
+
+    #[test]
+    fn render_table() {
+        let input = br#"[
+            {"name": "api", "status": "up", "latency_ms": 12},
+            {"name": "db\nprimary", "latency_ms": 1234.5, "tags": ["a"]},
+            {"name": "cache", "status": null, "latency_ms": 3}
+        ]"#;
+        let root = parse(input).unwrap();
+        let Value::Array(rows) = &root.value else {
+            panic!("not an array");
+        };
+        let rows = rows.iter().collect::<Vec<_>>();
+        let columns = parse_columns(".name,.status,.latency_ms,.tags").unwrap();
+        assert_eq!(
+            render(&rows, &columns, AnsiStyle::new(Color::NoColor)),
+            ".name       .status  .latency_ms  .tags
+api         up                12
+db primary                1234.5  [\"a\"]
+cache       null               3
+"
+        );
+    }
+}
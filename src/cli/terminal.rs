@@ -0,0 +1,197 @@
+//! Detection of the capabilities of the terminal: its background, to choose a theme readable on
+//! it, and its support of hyperlinks.
+//!
+//! The terminal is queried with an OSC 11 escape sequence, answered by most terminal emulators
+//! with their background color. If the terminal doesn't answer, the `COLORFGBG` environment
+//! variable (set by rxvt, Konsole...) is used. Terminals don't answer queries about OSC 8
+//! hyperlinks, their support is guessed from the environment variables set by the terminals.
+use pretty::theme::{Background, Rgb};
+use std::fs::{File, OpenOptions};
+use std::io::{IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Returns the background of the terminal, `None` if it can't be detected.
+pub fn background() -> Option<Background> {
+    if std::env::var("TERM").is_ok_and(|term| term == "dumb") {
+        return None;
+    }
+    query_background()
+        .map(Background::of)
+        .or_else(|| parse_colorfgbg(&std::env::var("COLORFGBG").ok()?))
+}
+
+/// Maximum duration of the OSC 11 query.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Queries the background color of the terminal with OSC 11. The terminal is switched to raw
+/// mode with `stty` while waiting for the answer, for at most [QUERY_TIMEOUT].
+///
+/// The terminal is only queried from the foreground: a background job changing the terminal mode
+/// would be stopped by `SIGTTOU`, and would steal the answer from the foreground job. The answer
+/// is read byte by byte up to its end, so that keys typed after it are kept for the next reader.
+fn query_background() -> Option<Rgb> {
+    if !std::io::stdin().is_terminal() || !is_foreground() {
+        return None;
+    }
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    // Reads return after 0.1 s without input.
+    let saved = stty("stty -g && stty raw -echo min 0 time 1")?;
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut response = Vec::new();
+    if tty.write_all(b"\x1b]11;?\x07").is_ok() && tty.flush().is_ok() {
+        let mut byte = [0];
+        while Instant::now() < deadline && tty.read(&mut byte).is_ok_and(|n| n == 1) {
+            response.push(byte[0]);
+            if response.ends_with(b"\x07") || response.ends_with(b"\x1b\\") {
+                break;
+            }
+        }
+    }
+    stty(&format!("stty '{}'", saved.trim()));
+    parse_osc11(&String::from_utf8_lossy(&response))
+}
+
+/// Returns `true` if the process is in the foreground process group of the terminal of its
+/// standard input.
+#[cfg(unix)]
+fn is_foreground() -> bool {
+    unsafe extern "C" {
+        fn getpgrp() -> i32;
+        fn tcgetpgrp(fd: i32) -> i32;
+    }
+    // SAFETY: these functions only read the process groups, and fail without a terminal.
+    let (group, foreground) = unsafe { (getpgrp(), tcgetpgrp(0)) };
+    foreground != -1 && group == foreground
+}
+
+#[cfg(not(unix))]
+fn is_foreground() -> bool {
+    false
+}
+
+/// Runs the `stty` shell `command` on the terminal, returns its standard output.
+fn stty(command: &str) -> Option<String> {
+    let output = Command::new("sh")
+        .args(["-c", command])
+        .stdin(File::open("/dev/tty").ok()?)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns `true` if the terminal supports OSC 8 hyperlinks.
+pub fn hyperlinks() -> bool {
+    supports_hyperlinks(|name| std::env::var(name).ok())
+}
+
+/// Returns `true` if the terminal described by the environment variables of `var` is known to
+/// support OSC 8 hyperlinks.
+fn supports_hyperlinks(var: impl Fn(&str) -> Option<String>) -> bool {
+    const TERM_PROGRAMS: [&str; 6] = [
+        "iTerm.app",
+        "WezTerm",
+        "vscode",
+        "ghostty",
+        "Hyper",
+        "Tabby",
+    ];
+    const TERMS: [&str; 5] = ["kitty", "alacritty", "foot", "wezterm", "ghostty"];
+    if var("TERM").is_some_and(|term| term == "dumb") {
+        return false;
+    }
+    var("TERM_PROGRAM").is_some_and(|program| TERM_PROGRAMS.contains(&program.as_str()))
+        // VTE terminals (GNOME Terminal, Tilix...) support hyperlinks since 0.50.
+        || var("VTE_VERSION").and_then(|v| v.parse::<u32>().ok()).is_some_and(|v| v >= 5000)
+        || var("KITTY_WINDOW_ID").is_some()
+        || var("WT_SESSION").is_some()
+        || var("TERM").is_some_and(|term| TERMS.iter().any(|t| term.contains(t)))
+}
+
+/// Parses the answer to an OSC 11 query: `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` followed by `BEL` or
+/// `ESC \`, with 1 to 4 hex digits per component.
+fn parse_osc11(response: &str) -> Option<Rgb> {
+    let (_, color) = response.split_once("]11;rgb:")?;
+    let color = color
+        .trim_end_matches(['\x07', '\\'])
+        .trim_end_matches('\x1b');
+    let mut components = color.split('/').map(|hex| {
+        let max = 16u32.checked_pow(hex.len() as u32)?.checked_sub(1)?;
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        (max > 0 && hex.len() <= 4).then(|| (value * 255 / max) as u8)
+    });
+    let (r, g, b) = (
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    );
+    components.next().is_none().then_some(Rgb(r, g, b))
+}
+
+/// Parses `COLORFGBG`, `"foreground;background"` ANSI color indexes (`"15;0"`): backgrounds 7
+/// (white) and 9 to 15 (bright colors) are light.
+fn parse_colorfgbg(value: &str) -> Option<Background> {
+    let background = value.rsplit(';').next()?.parse::<u8>().ok()?;
+    let light = matches!(background, 7 | 9..=15);
+    Some(if light {
+        Background::Light
+    } else {
+        Background::Dark
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_colorfgbg, parse_osc11, supports_hyperlinks};
+    use pretty::theme::{Background, Rgb};
+
+    #[test]
+    fn parse_terminal_answers() {
+        assert_eq!(
+            parse_osc11("\x1b]11;rgb:ffff/ffff/ffff\x07"),
+            Some(Rgb(255, 255, 255))
+        );
+        assert_eq!(
+            parse_osc11("\x1b]11;rgb:1e1e/1e1e/1e1e\x1b\\"),
+            Some(Rgb(0x1e, 0x1e, 0x1e))
+        );
+        assert_eq!(parse_osc11("\x1b]11;rgb:f/8/0\x07"), Some(Rgb(255, 136, 0)));
+        assert_eq!(parse_osc11(""), None);
+        assert_eq!(parse_osc11("\x1b]11;rgb:ffff/ffff\x07"), None);
+        assert_eq!(parse_osc11("\x1b]11;rgb:fffff/0/0\x07"), None);
+
+        assert_eq!(parse_colorfgbg("15;0"), Some(Background::Dark));
+        assert_eq!(parse_colorfgbg("0;15"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("0;default;15"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("default"), None);
+    }
+
+    #[test]
+    fn detect_hyperlinks() {
+        let env = |vars: &'static [(&str, &str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert!(supports_hyperlinks(env(&[("TERM_PROGRAM", "iTerm.app")])));
+        assert!(supports_hyperlinks(env(&[("VTE_VERSION", "6003")])));
+        assert!(supports_hyperlinks(env(&[("TERM", "xterm-kitty")])));
+        assert!(supports_hyperlinks(env(&[("WT_SESSION", "1")])));
+        assert!(!supports_hyperlinks(env(&[("VTE_VERSION", "4803")])));
+        assert!(!supports_hyperlinks(env(&[("TERM", "xterm-256color")])));
+        assert!(!supports_hyperlinks(env(&[
+            ("TERM", "dumb"),
+            ("WT_SESSION", "1")
+        ])));
+    }
+}
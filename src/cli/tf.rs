@@ -0,0 +1,176 @@
+//! Terraform plans and states (`terraform show -json`) presentation.
+use pretty::path::Path;
+use pretty::value;
+use pretty::value::{Node, Value};
+use std::fmt::Write;
+
+/// A resource of a plan (a resource change) or of a state.
+pub struct Resource<'a, 'input> {
+    /// Resource address (ex: `module.db.aws_instance.main[0]`).
+    pub address: String,
+    /// Planned actions (ex: `["delete", "create"]`), empty for a state resource.
+    pub actions: Vec<String>,
+    /// The resource change (or the state resource) object.
+    pub node: &'a Node<'input>,
+}
+
+impl Resource<'_, '_> {
+    /// Returns the symbol of the planned actions, as printed by `terraform plan`.
+    pub fn symbol(&self) -> &'static str {
+        let actions = self.actions.iter().map(String::as_str).collect::<Vec<_>>();
+        match actions.as_slice() {
+            ["create"] => "+",
+            ["update"] => "~",
+            ["delete"] => "-",
+            ["delete", "create"] => "-/+",
+            ["create", "delete"] => "+/-",
+            ["read"] => "<=",
+            _ => "",
+        }
+    }
+}
+
+fn path(s: &str) -> Path {
+    s.parse().expect("valid path")
+}
+
+/// Returns the text of the string at `p` in `node`, if any.
+fn string(node: &Node, p: &str) -> Option<String> {
+    match &node.get(&path(p))?.value {
+        Value::String(raw) => Some(value::unescape(raw)),
+        _ => None,
+    }
+}
+
+/// Returns the resource changes (`.resource_changes`) of a plan, or the resources
+/// (`.values.root_module`, with child modules) of a state.
+pub fn resources<'a, 'input>(root: &'a Node<'input>) -> Result<Vec<Resource<'a, 'input>>, String> {
+    if let Some(Value::Array(changes)) = root.get(&path(".resource_changes")).map(|n| &n.value) {
+        let resources = changes.iter().map(|node| {
+            let actions = match node.get(&path(".change.actions")).map(|n| &n.value) {
+                Some(Value::Array(actions)) => actions
+                    .iter()
+                    .filter_map(|action| match &action.value {
+                        Value::String(raw) => Some(value::unescape(raw)),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            Resource {
+                address: string(node, ".address").unwrap_or_default(),
+                actions,
+                node,
+            }
+        });
+        return Ok(resources.collect());
+    }
+    let Some(module) = root.get(&path(".values.root_module")) else {
+        let err = "Invalid Terraform JSON: missing .resource_changes or .values.root_module";
+        return Err(err.to_string());
+    };
+    let mut resources = Vec::new();
+    push_module_resources(module, &mut resources);
+    Ok(resources)
+}
+
+fn push_module_resources<'a, 'input>(
+    module: &'a Node<'input>,
+    resources: &mut Vec<Resource<'a, 'input>>,
+) {
+    if let Some(Value::Array(nodes)) = module.get(&path(".resources")).map(|n| &n.value) {
+        resources.extend(nodes.iter().map(|node| Resource {
+            address: string(node, ".address").unwrap_or_default(),
+            actions: vec![],
+            node,
+        }));
+    }
+    if let Some(Value::Array(modules)) = module.get(&path(".child_modules")).map(|n| &n.value) {
+        for module in modules {
+            push_module_resources(module, resources);
+        }
+    }
+}
+
+/// Lists resources, one per line: actions symbol, address and actions, followed by the plan
+/// summary line for resource changes.
+pub fn list(resources: &[Resource]) -> String {
+    let mut out = String::new();
+    let (mut add, mut change, mut destroy) = (0, 0, 0);
+    for resource in resources {
+        let actions = resource.actions.join(", ");
+        if actions.is_empty() {
+            _ = writeln!(out, "{}", resource.address);
+        } else {
+            _ = writeln!(
+                out,
+                "{:>3} {}  ({actions})",
+                resource.symbol(),
+                resource.address
+            );
+        }
+        for action in &resource.actions {
+            match action.as_str() {
+                "create" => add += 1,
+                "update" => change += 1,
+                "delete" => destroy += 1,
+                _ => {}
+            }
+        }
+    }
+    if resources
+        .iter()
+        .any(|resource| !resource.actions.is_empty())
+    {
+        _ = writeln!(
+            out,
+            "\nPlan: {add} to add, {change} to change, {destroy} to destroy."
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{list, resources};
+    use pretty::value;
+
+    #[test]
+    fn list_plan_changes() {
+        let input = br#"{"format_version": "1.2", "resource_changes": [
+            {"address": "aws_instance.web", "change": {"actions": ["update"], "before": {}, "after": {}}},
+            {"address": "aws_s3_bucket.logs", "change": {"actions": ["create"]}},
+            {"address": "module.db.aws_db_instance.main", "change": {"actions": ["delete", "create"]}},
+            {"address": "aws_iam_role.ci", "change": {"actions": ["no-op"]}}
+        ]}"#;
+        let root = value::parse(input).unwrap();
+        let resources = resources(&root).unwrap();
+        assert_eq!(
+            list(&resources),
+            "  ~ aws_instance.web  (update)
+  + aws_s3_bucket.logs  (create)
+-/+ module.db.aws_db_instance.main  (delete, create)
+    aws_iam_role.ci  (no-op)
+
+Plan: 2 to add, 1 to change, 1 to destroy.
+"
+        );
+    }
+
+    #[test]
+    fn list_state_resources() {
+        let input = br#"{"values": {"root_module": {
+            "resources": [{"address": "aws_instance.web"}],
+            "child_modules": [{"resources": [{"address": "module.db.aws_db_instance.main"}]}]
+        }}}"#;
+        let root = value::parse(input).unwrap();
+        let resources = resources(&root).unwrap();
+        assert_eq!(
+            list(&resources),
+            "aws_instance.web\nmodule.db.aws_db_instance.main\n"
+        );
+
+        let root = value::parse(b"{}").unwrap();
+        assert!(super::resources(&root).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::cli;
+use crate::cli::{Config, EXIT_INVALID_JSON, EXIT_IO_ERROR};
+use pretty::arena::Arena;
+use pretty::path::{Path, Segment};
+use pretty::transform;
+use pretty::value::{self, Node, Value, escape, unescape};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Returns `true` if some options of this configuration transform the document.
+pub fn has_transforms(config: &Config) -> bool {
+    config.rename_keys.is_some()
+        || config.prune.is_some()
+        || !config.sets.is_empty()
+        || !config.maps.is_empty()
+        || config.extract_strings.is_some()
+        || config.k8s.is_some()
+        || config.anonymize.is_some()
+        || config.sort_keys
+        || config.normalize_numbers
+        || config.shortest_floats
+}
+
+/// Applies the transformations of this configuration to the JSON input, returns the transformed
+/// document as compact JSON (or an exit code and an error message). Decoded keys are allocated in
+/// `arena`.
+pub fn apply(input: &[u8], config: &Config, arena: &Arena) -> Result<Vec<u8>, (i32, String)> {
+    let invalid = |err: String| (EXIT_INVALID_JSON, err);
+    let mut root = value::parse(input).map_err(|err| invalid(err.to_string()))?;
+    if let Some(case) = config.rename_keys {
+        transform::rename_keys(&mut root, case);
+    }
+    if let Some(kinds) = config.prune {
+        transform::prune(&mut root, kinds, config.prune_fixed_point);
+    }
+    if let Some(noise) = &config.k8s {
+        cli::k8s::clean(&mut root, noise);
+    }
+    for (path, json) in &config.sets {
+        // Values have already been validated when parsing arguments.
+        let value = value::parse(json.as_bytes()).map_err(|err| invalid(err.to_string()))?;
+        transform::set(&mut root, path, value).map_err(invalid)?;
+    }
+    for map in &config.maps {
+        map.apply(&mut root);
+    }
+    if let Some((pii, key)) = &config.anonymize {
+        cli::anonymize::anonymize(&mut root, *pii, key);
+    }
+    if config.sort_keys {
+        transform::sort_keys_in(&mut root, arena);
+    }
+    if config.shortest_floats {
+        transform::shortest_floats(&mut root);
+    }
+    if config.normalize_numbers {
+        transform::normalize_numbers(&mut root);
+    }
+    if let Some((threshold, dir)) = &config.extract_strings {
+        let mut extractor = Extractor {
+            threshold: *threshold,
+            dir,
+            path: Path::default(),
+            names: HashSet::new(),
+        };
+        extractor
+            .extract(&mut root)
+            .map_err(|err| (EXIT_IO_ERROR, err))?;
+    }
+    Ok(root.to_compact_string().into_bytes())
+}
+
+/// Extracts large string values to files.
+struct Extractor<'a> {
+    /// Strings larger than this size (in bytes, once decoded) are extracted.
+    threshold: usize,
+    /// Directory where files are written.
+    dir: &'a PathBuf,
+    /// Path of the current node.
+    path: Path,
+    /// File names already used.
+    names: HashSet<String>,
+}
+
+impl Extractor<'_> {
+    fn extract(&mut self, node: &mut Node) -> Result<(), String> {
+        match &mut node.value {
+            Value::String(raw) if raw.len() > self.threshold => {
+                let content = unescape(raw);
+                if content.len() <= self.threshold {
+                    return Ok(());
+                }
+                let file = self.dir.join(self.file_name());
+                std::fs::create_dir_all(self.dir)
+                    .and_then(|_| std::fs::write(&file, content))
+                    .map_err(|err| format!("Error writing file '{}': {}", file.display(), err))?;
+                let reference = format!("@file:{}", file.display());
+                *raw = Cow::Owned(escape(&reference));
+            }
+            Value::Array(nodes) => {
+                for (i, node) in nodes.iter_mut().enumerate() {
+                    self.path.segments.push(Segment::Index(i));
+                    self.extract(node)?;
+                    self.path.segments.pop();
+                }
+            }
+            Value::Object(members) => {
+                for member in members {
+                    self.path.segments.push(Segment::Key(unescape(&member.key)));
+                    self.extract(&mut member.node)?;
+                    self.path.segments.pop();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns a unique file name for the current path: `.data[0].cert` is `data.0.cert.txt`.
+    fn file_name(&mut self) -> String {
+        let mut base = self
+            .path
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => key
+                    .chars()
+                    .map(|c| {
+                        if c.is_alphanumeric() || c == '-' || c == '_' {
+                            c
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect(),
+                Segment::Index(index) => index.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(".");
+        if base.is_empty() {
+            base.push_str("root");
+        }
+        let mut name = format!("{base}.txt");
+        let mut n = 1;
+        while !self.names.insert(name.clone()) {
+            n += 1;
+            name = format!("{base}-{n}.txt");
+        }
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Extractor;
+    use pretty::path::Path;
+    use pretty::value;
+    use std::collections::HashSet;
+
+    #[test]
+    fn extract_large_strings() {
+        let dir = std::env::temp_dir().join(format!("pretty-extract-{}", std::process::id()));
+        let mut root =
+            value::parse(br#"{"a":["0123456789",{"b c":"0123456789"}],"d":"012"}"#).unwrap();
+        let mut extractor = Extractor {
+            threshold: 8,
+            dir: &dir,
+            path: Path::default(),
+            names: HashSet::new(),
+        };
+        extractor.extract(&mut root).unwrap();
+
+        let a0 = dir.join("a.0.txt");
+        let a1 = dir.join("a.1.b_c.txt");
+        assert_eq!(
+            root.to_compact_string(),
+            format!(
+                r#"{{"a":["@file:{}",{{"b c":"@file:{}"}}],"d":"012"}}"#,
+                a0.display(),
+                a1.display()
+            )
+        );
+        assert_eq!(std::fs::read_to_string(&a0).unwrap(), "0123456789");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+//! Differential check of the formatter against serde_json (`--verify`), to find inputs where
+//! they disagree.
+//!
+//! Both must accept or reject the input. When they accept it, the formatted output must only
+//! differ from the input by whitespace, and serde must parse it to the same value as the input.
+use pretty::format::{Color, Formatter};
+use serde_json::Value;
+
+/// Result of the verification of an input.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Verdict {
+    /// The formatter and serde both accept the input, and agree on its value.
+    Accepted,
+    /// The formatter and serde both reject the input, with the formatter error.
+    Rejected(String),
+    /// The formatter and serde disagree, with a description of each divergence.
+    Diverged(Vec<String>),
+}
+
+/// Formats the input with the formatter and parses it with serde, and compares the results.
+pub fn verify(input: &[u8]) -> Verdict {
+    let mut formatted = String::new();
+    let ours = Formatter::new(input, Color::NoColor).format(&mut formatted);
+    let theirs = serde_json::from_slice::<Value>(input);
+    let value = match (ours, theirs) {
+        (Err(err), Err(_)) => return Verdict::Rejected(err.to_string()),
+        (Ok(()), Err(err)) => {
+            let offset = offset(input, err.line(), err.column());
+            return Verdict::Diverged(vec![format!(
+                "accepted by the formatter, rejected by serde at offset {offset}: {err}"
+            )]);
+        }
+        (Err(err), Ok(_)) => {
+            return Verdict::Diverged(vec![format!(
+                "rejected by the formatter ({err}), accepted by serde"
+            )]);
+        }
+        (Ok(()), Ok(value)) => value,
+    };
+
+    let mut divergences = Vec::new();
+    let (input_tokens, offsets) = strip_whitespace(input);
+    let (output_tokens, _) = strip_whitespace(formatted.as_bytes());
+    if input_tokens != output_tokens {
+        let i = input_tokens
+            .iter()
+            .zip(&output_tokens)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let offset = offsets.get(i).copied().unwrap_or(input.len());
+        divergences.push(format!(
+            "the formatted output differs from the input by more than whitespace at offset {offset}"
+        ));
+    }
+    match serde_json::from_str::<Value>(&formatted) {
+        Ok(output) if output == value => {}
+        Ok(output) => {
+            let path = first_difference(&value, &output, String::new());
+            divergences.push(format!(
+                "serde parses the formatted output to a different value at path {path}"
+            ));
+        }
+        Err(err) => divergences.push(format!("serde rejects the formatted output: {err}")),
+    }
+    if divergences.is_empty() {
+        Verdict::Accepted
+    } else {
+        Verdict::Diverged(divergences)
+    }
+}
+
+/// Returns the bytes of a JSON input without the whitespace outside of strings (and without a
+/// starting BOM), with the offset of each byte in the input.
+fn strip_whitespace(input: &[u8]) -> (Vec<u8>, Vec<usize>) {
+    let start = if input.starts_with(b"\xEF\xBB\xBF") {
+        3
+    } else {
+        0
+    };
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut offsets = Vec::with_capacity(input.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &b) in input.iter().enumerate().skip(start) {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+        } else if matches!(b, b' ' | b'\t' | b'\n' | b'\r') {
+            continue;
+        } else if b == b'"' {
+            in_string = true;
+        }
+        bytes.push(b);
+        offsets.push(i);
+    }
+    (bytes, offsets)
+}
+
+/// Returns the byte offset of a 1-based line and column of serde.
+fn offset(input: &[u8], line: usize, column: usize) -> usize {
+    let line_start = input
+        .split_inclusive(|&b| b == b'\n')
+        .take(line.saturating_sub(1))
+        .map(<[u8]>::len)
+        .sum::<usize>();
+    (line_start + column.saturating_sub(1)).min(input.len())
+}
+
+/// Returns the path of the first difference between two different values.
+fn first_difference(a: &Value, b: &Value, path: String) -> String {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                match b.get(key) {
+                    Some(other) if other == value => {}
+                    Some(other) => return first_difference(value, other, format!("{path}.{key}")),
+                    None => return format!("{path}.{key}"),
+                }
+            }
+            match b.keys().find(|key| !a.contains_key(*key)) {
+                Some(key) => format!("{path}.{key}"),
+                None => path,
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            match a.iter().zip(b).position(|(a, b)| a != b) {
+                Some(i) => first_difference(&a[i], &b[i], format!("{path}[{i}]")),
+                None => path,
+            }
+        }
+        _ if path.is_empty() => ".".to_string(),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Verdict, first_difference, strip_whitespace, verify};
+    use serde_json::json;
+
+    #[test]
+    fn verify_inputs() {
+        assert_eq!(
+            verify(br#"{"a": [1, 2.5e3, "x y"], "b": null}"#),
+            Verdict::Accepted
+        );
+        assert_eq!(
+            verify(b"[1,]"),
+            Verdict::Rejected("invalid byte <5d> at offset 3".to_string())
+        );
+        assert_eq!(
+            verify(b"\xEF\xBB\xBF[1]"),
+            Verdict::Diverged(vec![
+                "accepted by the formatter, rejected by serde at offset 0: expected value at \
+                 line 1 column 1"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn compare_outputs() {
+        let (bytes, offsets) = strip_whitespace(b" [1, \"a b\"]\n");
+        assert_eq!(bytes, b"[1,\"a b\"]");
+        assert_eq!(offsets[3], 5);
+        assert_eq!(
+            first_difference(
+                &json!({"a": [1, {"b": 2}]}),
+                &json!({"a": [1, {"b": 3}]}),
+                String::new()
+            ),
+            ".a[1].b"
+        );
+        assert_eq!(first_difference(&json!(1), &json!(2), String::new()), ".");
+    }
+}
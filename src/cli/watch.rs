@@ -0,0 +1,60 @@
+use crate::cli::log::Log;
+use crate::cli::options::{Config, Input};
+use pretty::format::Color;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Delay between two checks of the watched file.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// ANSI escape code to clear the screen and move the cursor to the top left corner.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Formats the file `path` with the options of `config` and re-formats it each time it's modified
+/// on disk, until the process is interrupted.
+///
+/// We poll the file modification time rather than relying on OS notifications: it works everywhere
+/// and survives editors that replace files instead of writing them in place.
+pub fn run(path: &Path, color: Color, config: &Config) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let mut last_state = None;
+
+    loop {
+        let state = std::fs::metadata(path).and_then(|m| m.modified());
+        let state = match state {
+            Ok(modified) => Some((modified, true)),
+            Err(_) => Some((SystemTime::UNIX_EPOCH, false)),
+        };
+        if state != last_state {
+            last_state = state;
+            stdout.write_all(CLEAR_SCREEN.as_bytes())?;
+            render(&mut stdout, path, color, config)?;
+            stdout.flush()?;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Writes the formatted file, or the error if the file can't be read or formatted. The file is
+/// read, converted and transformed as without `--watch`.
+fn render(out: &mut impl Write, path: &Path, color: Color, config: &Config) -> io::Result<()> {
+    let input = Input::File(path.to_path_buf());
+    let bytes = match crate::read_transformed_input(&input, config, &Log::new(None, &input)) {
+        Ok(bytes) => bytes,
+        Err((crate::EXIT_IO_ERROR, err)) => return writeln!(out, "{err}"),
+        Err((_, err)) => return writeln!(out, "Error: {err}"),
+    };
+    let mut formatter = crate::formatter(&bytes, color, config);
+    let mut output = String::new();
+    match formatter.format(&mut output) {
+        Ok(_) => writeln!(out, "{output}"),
+        Err(err) => {
+            // Even for an invalid file, we display what has been formatted so far.
+            writeln!(out, "{output}")?;
+            writeln!(out, "Error: {err}")
+        }
+    }
+}
@@ -0,0 +1,546 @@
+//! Conversion of JSON to XML, and of XML to JSON.
+//!
+//! The JSON input is converted token by token: object members become elements named after their
+//! keys, array items are repeated elements (`{"tag":["a","b"]}` is `<tag>a</tag><tag>b</tag>`)
+//! and `null` is an empty element. Items of root or nested arrays are `<item>` elements.
+//!
+//! XML input is converted the other way around: an element with only text is a string, an empty
+//! element is `null`, other elements are objects with attributes as `@name` members, child
+//! elements as members (an array for repeated elements) and text as a `#text` member.
+use pretty::format::{FormatError, MAX_INDENT_LEVEL};
+use pretty::token::{Token, TokenKind, tokenize};
+use pretty::value;
+use pretty::value::{Member, Node, Value};
+use std::borrow::Cow;
+
+/// Options of the XML conversion.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct XmlOptions {
+    /// Name of the root element.
+    pub root: String,
+    /// Write the scalar members of objects (strings, numbers and booleans) as attributes of
+    /// their object element instead of child elements.
+    pub attributes: bool,
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        XmlOptions {
+            root: "root".to_string(),
+            attributes: false,
+        }
+    }
+}
+
+/// Converts JSON input bytes to an indented XML document.
+pub fn to_xml(input: &[u8], options: &XmlOptions) -> Result<String, FormatError> {
+    let mut writer = XmlWriter {
+        input,
+        options,
+        stack: Vec::new(),
+        out: "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".to_string(),
+    };
+    tokenize(input, |token| writer.token(token))?;
+    Ok(writer.out)
+}
+
+/// An object or an array being converted.
+struct Frame {
+    /// Name of the element of this container, `None` for arrays that are object members: their
+    /// items are written as repeated elements in the parent element.
+    element: Option<String>,
+    array: bool,
+    /// Name of the next child element (the current key for objects).
+    child: String,
+    /// Indentation level of the children elements.
+    depth: usize,
+    attrs: String,
+    body: String,
+}
+
+struct XmlWriter<'a> {
+    input: &'a [u8],
+    options: &'a XmlOptions,
+    stack: Vec<Frame>,
+    out: String,
+}
+
+impl XmlWriter<'_> {
+    fn token(&mut self, token: Token) {
+        let raw = &self.input[token.span];
+        // Tokens have been validated, they're valid UTF-8.
+        let raw = std::str::from_utf8(raw).unwrap_or_default();
+        match token.kind {
+            TokenKind::Key => {
+                if let Some(frame) = self.stack.last_mut() {
+                    frame.child = element_name(&value::unescape(raw));
+                }
+            }
+            TokenKind::BeginObject => self.push(false),
+            TokenKind::BeginArray => self.push(true),
+            TokenKind::EndObject | TokenKind::EndArray => self.pop(),
+            TokenKind::String => self.scalar(&value::unescape(raw)),
+            TokenKind::Number | TokenKind::True | TokenKind::False => self.scalar(raw),
+            TokenKind::Null => {
+                let (name, depth) = self.child();
+                let element = format!("{}<{name}/>\n", indent(depth));
+                self.write(&element);
+            }
+            TokenKind::NameSeparator | TokenKind::ValueSeparator => {}
+        }
+    }
+
+    /// Returns the name and the indentation level of the next element.
+    fn child(&self) -> (String, usize) {
+        match self.stack.last() {
+            Some(frame) => (frame.child.clone(), frame.depth),
+            None => (self.options.root.clone(), 0),
+        }
+    }
+
+    /// Writes to the current element, or to the output if this is the root element.
+    fn write(&mut self, s: &str) {
+        match self.stack.last_mut() {
+            Some(frame) => frame.body.push_str(s),
+            None => self.out.push_str(s),
+        }
+    }
+
+    fn scalar(&mut self, text: &str) {
+        let (name, depth) = self.child();
+        let attributes = self.options.attributes;
+        if let Some(frame) = self.stack.last_mut().filter(|f| attributes && !f.array) {
+            let attr = format!(" {name}=\"{}\"", escape(text, true));
+            frame.attrs.push_str(&attr);
+            return;
+        }
+        let element = format!(
+            "{}<{name}>{}</{name}>\n",
+            indent(depth),
+            escape(text, false)
+        );
+        self.write(&element);
+    }
+
+    fn push(&mut self, array: bool) {
+        let (name, depth) = self.child();
+        let parent_is_object = self.stack.last().is_some_and(|frame| !frame.array);
+        let frame = if array && parent_is_object {
+            Frame {
+                element: None,
+                array,
+                child: name,
+                depth,
+                attrs: String::new(),
+                body: String::new(),
+            }
+        } else {
+            Frame {
+                element: Some(name),
+                array,
+                child: "item".to_string(),
+                depth: depth + 1,
+                attrs: String::new(),
+                body: String::new(),
+            }
+        };
+        self.stack.push(frame);
+    }
+
+    fn pop(&mut self) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+        let Some(name) = frame.element else {
+            self.write(&frame.body);
+            return;
+        };
+        let indent = indent(frame.depth - 1);
+        let attrs = frame.attrs;
+        let element = if frame.body.is_empty() {
+            format!("{indent}<{name}{attrs}/>\n")
+        } else {
+            format!("{indent}<{name}{attrs}>\n{}{indent}</{name}>\n", frame.body)
+        };
+        self.write(&element);
+    }
+}
+
+fn indent(depth: usize) -> String {
+    "  ".repeat(depth)
+}
+
+/// Returns a valid XML element name for a key, invalid chars are replaced by `_`.
+pub fn element_name(key: &str) -> String {
+    let mut name = key
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Escapes XML special chars. Control chars, which are not allowed in XML 1.0, are replaced by
+/// U+FFFD.
+fn escape(s: &str, attr: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attr => escaped.push_str("&quot;"),
+            '\n' if attr => escaped.push_str("&#xA;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => escaped.push('\u{FFFD}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Converts an XML document to compact JSON.
+///
+/// The parser is not validating: DTDs are skipped and only the predefined and numeric entities
+/// are decoded.
+pub fn from_xml(input: &[u8]) -> Result<Vec<u8>, String> {
+    let input = std::str::from_utf8(input).map_err(|err| format!("Invalid XML: {err}"))?;
+    let input = input.strip_prefix('\u{FEFF}').unwrap_or(input);
+    let mut parser = XmlParser {
+        input,
+        pos: 0,
+        depth: 0,
+    };
+    parser.skip_misc()?;
+    let (name, node) = parser.element()?;
+    parser.skip_misc()?;
+    if parser.pos < input.len() {
+        return Err(parser.error("unexpected content after the root element"));
+    }
+    let root = Node::new(Value::Object(vec![member(&name, node)]));
+    Ok(root.to_compact_string().into_bytes())
+}
+
+fn member(name: &str, node: Node<'static>) -> Member<'static> {
+    Member {
+        key: Cow::Owned(value::escape(name)),
+        key_span: 0..0,
+        node,
+    }
+}
+
+fn string(s: &str) -> Node<'static> {
+    Node::new(Value::String(Cow::Owned(value::escape(s))))
+}
+
+struct XmlParser<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Number of elements being parsed, limited like the nesting of JSON inputs.
+    depth: usize,
+}
+
+impl XmlParser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("Invalid XML: {message} at offset {}", self.pos)
+    }
+
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips everything up to and including `end`.
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.pos += i + end.len();
+                Ok(())
+            }
+            None => Err(self.error(&format!("missing {end}"))),
+        }
+    }
+
+    /// Skips whitespaces, comments, processing instructions and document type declarations.
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                // The internal subset, between brackets, may contain `>`.
+                let end = match (rest.find('['), rest.find('>')) {
+                    (Some(bracket), Some(gt)) if bracket < gt => "]>",
+                    _ => ">",
+                };
+                self.skip_past(end)?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '=' | '<'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        let name = rest[..len].to_string();
+        self.pos += len;
+        Ok(name)
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), String> {
+        if !self.rest().starts_with(s) {
+            return Err(self.error(&format!("expected {s}")));
+        }
+        self.pos += s.len();
+        Ok(())
+    }
+
+    /// Parses an element, returns its name and its JSON value.
+    fn element(&mut self) -> Result<(String, Node<'static>), String> {
+        if self.depth >= MAX_INDENT_LEVEL {
+            return Err(self.error("too deeply nested"));
+        }
+        self.depth += 1;
+        let element = self.element_content();
+        self.depth -= 1;
+        element
+    }
+
+    fn element_content(&mut self) -> Result<(String, Node<'static>), String> {
+        self.expect("<")?;
+        let name = self.name()?;
+        let mut members = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                let node = if members.is_empty() {
+                    Node::new(Value::Null)
+                } else {
+                    Node::new(Value::Object(members))
+                };
+                return Ok((name, node));
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let attr = self.name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(self.error("expected a quoted attribute value")),
+            };
+            self.pos += 1;
+            let Some(len) = self.rest().find(quote) else {
+                return Err(self.error("unclosed attribute value"));
+            };
+            let raw = &self.rest()[..len];
+            let text = self.decode(raw)?;
+            self.pos += len + 1;
+            members.push(member(&format!("@{attr}"), string(&text)));
+        }
+
+        let has_attributes = !members.is_empty();
+        let mut children: Vec<(String, Vec<Node<'static>>)> = Vec::new();
+        let mut text = String::new();
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let end = self.name()?;
+                if end != name {
+                    return Err(self.error(&format!("expected </{name}>")));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                break;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let Some(len) = cdata.find("]]>") else {
+                    return Err(self.error("unclosed CDATA section"));
+                };
+                text.push_str(&cdata[..len]);
+                self.pos += "<![CDATA[".len() + len + "]]>".len();
+            } else if rest.starts_with('<') {
+                let (child, node) = self.element()?;
+                match children.iter_mut().find(|(name, _)| *name == child) {
+                    Some((_, nodes)) => nodes.push(node),
+                    None => children.push((child, vec![node])),
+                }
+            } else if rest.is_empty() {
+                return Err(self.error(&format!("missing </{name}>")));
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                let raw = &rest[..len];
+                text.push_str(&self.decode(raw)?);
+                self.pos += len;
+            }
+        }
+
+        if !has_attributes && children.is_empty() {
+            let node = if text.is_empty() {
+                Node::new(Value::Null)
+            } else {
+                string(&text)
+            };
+            return Ok((name, node));
+        }
+        for (child, mut nodes) in children {
+            let node = if nodes.len() == 1 {
+                nodes.remove(0)
+            } else {
+                Node::new(Value::Array(nodes))
+            };
+            members.push(member(&child, node));
+        }
+        let text = text.trim();
+        if !text.is_empty() {
+            members.push(member("#text", string(text)));
+        }
+        Ok((name, Node::new(Value::Object(members))))
+    }
+
+    /// Decodes the entities of a text or an attribute value.
+    fn decode(&self, raw: &str) -> Result<String, String> {
+        let mut text = String::with_capacity(raw.len());
+        let mut rest = raw;
+        while let Some(i) = rest.find('&') {
+            text.push_str(&rest[..i]);
+            rest = &rest[i..];
+            let Some(end) = rest.find(';') else {
+                return Err(self.error("unterminated entity"));
+            };
+            let entity = &rest[1..end];
+            let c = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => match entity.strip_prefix('#') {
+                    Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16)
+                        .ok()
+                        .and_then(char::from_u32),
+                    Some(dec) => dec.parse().ok().and_then(char::from_u32),
+                    None => None,
+                },
+            };
+            match c {
+                Some(c) => text.push(c),
+                // Other entities (declared in a DTD) are kept as is.
+                None => text.push_str(&rest[..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        text.push_str(rest);
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{XmlOptions, from_xml, to_xml};
+
+    #[test]
+    fn convert_to_xml() {
+        let input = br#"{"user": {"id": 1, "first name": "A<B", "tags": ["x", "y"], "boss": null}, "list": [[1], {}]}"#;
+        let xml = to_xml(input, &XmlOptions::default()).unwrap();
+        assert_eq!(
+            xml,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<root>
+  <user>
+    <id>1</id>
+    <first_name>A&lt;B</first_name>
+    <tags>x</tags>
+    <tags>y</tags>
+    <boss/>
+  </user>
+  <list>
+    <item>1</item>
+  </list>
+  <list/>
+</root>
+"#
+        );
+    }
+
+    #[test]
+    fn convert_to_xml_with_attributes() {
+        let options = XmlOptions {
+            root: "doc".to_string(),
+            attributes: true,
+        };
+        let xml = to_xml(
+            br#"{"a": {"id": 1, "name": "\"x\"", "b": [true]}}"#,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            xml,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<doc>
+  <a id="1" name="&quot;x&quot;">
+    <b>true</b>
+  </a>
+</doc>
+"#
+        );
+        assert!(to_xml(b"[1,", &options).is_err());
+    }
+
+    #[test]
+    fn convert_from_xml() {
+        let input = br#"<?xml version="1.0"?>
+<!DOCTYPE catalog [<!ENTITY c "x">]>
+<!-- books -->
+<catalog version='2'>
+  <book id="1"><title>A &amp; B</title><tag>x</tag><tag>y</tag></book>
+  <book id="2">Text<empty/><![CDATA[<raw>]]></book>
+  <note></note>
+</catalog>"#;
+        let json = String::from_utf8(from_xml(input).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            r##"{"catalog":{"@version":"2","book":[{"@id":"1","title":"A & B","tag":["x","y"]},{"@id":"2","empty":null,"#text":"Text<raw>"}],"note":null}}"##
+        );
+        assert_eq!(
+            from_xml(b"<a><b></a>").unwrap_err(),
+            "Invalid XML: expected </b> at offset 9"
+        );
+        assert!(from_xml(b"<a/><b/>").is_err());
+
+        let deep = "<a>".repeat(200_000);
+        assert_eq!(
+            from_xml(deep.as_bytes()).unwrap_err(),
+            "Invalid XML: too deeply nested at offset 300"
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use crate::path::wildcard_match;
+use std::fmt;
+use std::str::FromStr;
+
+/// Annotates values in the formatted output.
+///
+/// Decorators are called by the [Formatter](crate::format::Formatter) for each scalar value of an
+/// object member, the annotation returned (if any) is appended after the value as a dimmed
+/// comment: `"size_bytes": 1536000  /* 1.5 MB */`. This is a display-only feature, the output is
+/// not valid JSON anymore.
+///
+/// Decorators are shared by the threads of [parallel formatting](crate::format::Formatter::format_parallel).
+pub trait ValueDecorator: Sync {
+    /// Returns an annotation for the value of the member `key`.
+    ///
+    /// `key` is the unescaped key name, `raw` is the raw value, as in the input (strings with their
+    /// quotes and escapes).
+    fn decorate(&self, key: &str, raw: &str) -> Option<String>;
+}
+
+/// Kind of humanization of numbers.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Humanize {
+    /// A duration in milliseconds (`90000` is `1 min 30 s`).
+    DurationMs,
+    /// A duration in seconds.
+    DurationS,
+    /// A size in bytes (`1536000` is `1.5 MB`).
+    Bytes,
+    /// A ratio (`0.125` is `12.5 %`).
+    Percent,
+}
+
+impl FromStr for Humanize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "duration-ms" => Ok(Humanize::DurationMs),
+            "duration-s" => Ok(Humanize::DurationS),
+            "bytes" => Ok(Humanize::Bytes),
+            "percent" => Ok(Humanize::Percent),
+            _ => Err(format!(
+                "unknown kind {s} (expected duration-ms, duration-s, bytes or percent)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Humanize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Humanize::DurationMs => "duration-ms",
+            Humanize::DurationS => "duration-s",
+            Humanize::Bytes => "bytes",
+            Humanize::Percent => "percent",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Humanizes numbers of members whose key matches a pattern (where `*` matches anything).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Humanizer {
+    pub pattern: String,
+    pub kind: Humanize,
+}
+
+impl Humanizer {
+    pub fn new(pattern: &str, kind: Humanize) -> Self {
+        Humanizer {
+            pattern: pattern.to_string(),
+            kind,
+        }
+    }
+
+    /// Returns the default humanizers: `*_ms` durations, `*_bytes` sizes and `*ratio` percents.
+    pub fn defaults() -> Vec<Humanizer> {
+        vec![
+            Humanizer::new("*_ms", Humanize::DurationMs),
+            Humanizer::new("*_bytes", Humanize::Bytes),
+            Humanizer::new("*ratio", Humanize::Percent),
+        ]
+    }
+}
+
+impl ValueDecorator for Humanizer {
+    fn decorate(&self, key: &str, raw: &str) -> Option<String> {
+        if !wildcard_match(&self.pattern, key) {
+            return None;
+        }
+        let n = raw.parse::<f64>().ok()?;
+        let text = match self.kind {
+            Humanize::DurationMs => humanize_duration(n / 1000.0),
+            Humanize::DurationS => humanize_duration(n),
+            Humanize::Bytes => humanize_bytes(n),
+            Humanize::Percent => format!("{} %", round(n * 100.0)),
+        };
+        Some(text)
+    }
+}
+
+/// Annotates integers that can't be represented exactly by a double, and are changed when read
+/// by JavaScript: `9007199254740993  /* !JS-unsafe */`.
+pub struct JsUnsafe;
+
+impl ValueDecorator for JsUnsafe {
+    fn decorate(&self, _key: &str, raw: &str) -> Option<String> {
+        if !raw.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+            || raw.contains(['.', 'e', 'E'])
+        {
+            return None;
+        }
+        let n = raw.parse::<f64>().ok()?;
+        // `{:.0}` writes the exact integer value of the double, not its shortest representation.
+        let exact = n.is_finite() && format!("{:.0}", n.abs()) == raw.trim_start_matches('-');
+        (!exact).then(|| "!JS-unsafe".to_string())
+    }
+}
+
+/// Annotates the non-negative integers of members whose key matches a pattern (where `*` matches
+/// anything) with their hexadecimal and binary values, for bit flags: `42  /* 0x2a 0b10_1010 */`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HexFields {
+    pub patterns: Vec<String>,
+}
+
+impl ValueDecorator for HexFields {
+    fn decorate(&self, key: &str, raw: &str) -> Option<String> {
+        if !self.patterns.iter().any(|p| wildcard_match(p, key)) {
+            return None;
+        }
+        let n = raw.parse::<u64>().ok()?;
+        let binary = format!("{n:b}");
+        // Bits are grouped by 4, from the lowest.
+        let mut grouped = String::new();
+        for (i, bit) in binary.chars().enumerate() {
+            if i > 0 && (binary.len() - i) % 4 == 0 {
+                grouped.push('_');
+            }
+            grouped.push(bit);
+        }
+        Some(format!("{n:#x} 0b{grouped}"))
+    }
+}
+
+/// Annotates the sizes in bytes of members whose key matches a pattern (where `*` matches
+/// anything) with binary units: `"size": 1048576  /* 1.0 MiB */`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FileSizes {
+    pub patterns: Vec<String>,
+}
+
+impl FileSizes {
+    /// Returns the default patterns: `*_bytes`, `size` and `*_size` fields.
+    pub fn defaults() -> FileSizes {
+        FileSizes {
+            patterns: vec![
+                "*_bytes".to_string(),
+                "size".to_string(),
+                "*_size".to_string(),
+            ],
+        }
+    }
+}
+
+impl ValueDecorator for FileSizes {
+    fn decorate(&self, key: &str, raw: &str) -> Option<String> {
+        if !self.patterns.iter().any(|p| wildcard_match(p, key)) {
+            return None;
+        }
+        let n = raw.parse::<f64>().ok()?;
+        (n.is_finite() && n >= 0.0).then(|| humanize_file_size(n))
+    }
+}
+
+/// Formats a size in bytes with binary units (multiples of 1024) and one decimal: `1.0 MiB`.
+fn humanize_file_size(bytes: f64) -> String {
+    const UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Formats a number with at most one decimal.
+fn round(n: f64) -> String {
+    let rounded = (n * 10.0).round() / 10.0;
+    if rounded.fract() == 0.0 {
+        format!("{rounded:.0}")
+    } else {
+        format!("{rounded:.1}")
+    }
+}
+
+fn humanize_duration(seconds: f64) -> String {
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    let seconds = seconds.abs();
+    if seconds < 1.0 {
+        return format!("{sign}{} ms", round(seconds * 1000.0));
+    }
+    if seconds < 60.0 {
+        return format!("{sign}{} s", round(seconds));
+    }
+    let seconds = seconds.round() as u64;
+    let (days, hours, minutes, seconds) = (
+        seconds / 86_400,
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+    );
+    if days > 0 {
+        format!("{sign}{days} d {hours} h")
+    } else if hours > 0 {
+        format!("{sign}{hours} h {minutes} min")
+    } else {
+        format!("{sign}{minutes} min {seconds} s")
+    }
+}
+
+/// Formats a size in bytes with SI units: `1.5 MB`.
+pub(crate) fn humanize_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 6] = ["B", "kB", "MB", "GB", "TB", "PB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value.abs() >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{} {}", round(value), UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileSizes, HexFields, Humanize, Humanizer, JsUnsafe, ValueDecorator};
+
+    #[test]
+    fn humanize_values() {
+        let decorate = |kind, raw| Humanizer::new("*", kind).decorate("x", raw);
+        assert_eq!(decorate(Humanize::Bytes, "1536000").unwrap(), "1.5 MB");
+        assert_eq!(decorate(Humanize::Bytes, "512").unwrap(), "512 B");
+        assert_eq!(decorate(Humanize::DurationMs, "250").unwrap(), "250 ms");
+        assert_eq!(decorate(Humanize::DurationMs, "1500").unwrap(), "1.5 s");
+        assert_eq!(
+            decorate(Humanize::DurationMs, "90000").unwrap(),
+            "1 min 30 s"
+        );
+        assert_eq!(decorate(Humanize::DurationS, "7260").unwrap(), "2 h 1 min");
+        assert_eq!(decorate(Humanize::DurationS, "90000").unwrap(), "1 d 1 h");
+        assert_eq!(decorate(Humanize::Percent, "0.125").unwrap(), "12.5 %");
+        assert_eq!(decorate(Humanize::Percent, r#""0.5""#), None);
+    }
+
+    #[test]
+    fn match_keys() {
+        let humanizer = Humanizer::new("*_ms", Humanize::DurationMs);
+        assert!(humanizer.decorate("latency_ms", "12").is_some());
+        assert!(humanizer.decorate("latency", "12").is_none());
+    }
+
+    #[test]
+    fn annotate_js_unsafe_integers() {
+        for raw in [
+            "0",
+            "-12",
+            "9007199254740992",
+            "9007199254740994",
+            "1e300",
+            "1.5",
+            r#""1""#,
+        ] {
+            assert_eq!(JsUnsafe.decorate("id", raw), None, "{raw}");
+        }
+        let overflow = format!("1{}", "0".repeat(400));
+        for raw in [
+            "9007199254740993",
+            "-9007199254740993",
+            "12345678901234567890",
+            &overflow,
+        ] {
+            assert_eq!(
+                JsUnsafe.decorate("id", raw).as_deref(),
+                Some("!JS-unsafe"),
+                "{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn annotate_hex_fields() {
+        let hex = HexFields {
+            patterns: vec!["flags".to_string(), "*mask".to_string()],
+        };
+        assert_eq!(hex.decorate("flags", "0").unwrap(), "0x0 0b0");
+        assert_eq!(hex.decorate("flags", "5").unwrap(), "0x5 0b101");
+        assert_eq!(hex.decorate("flags", "42").unwrap(), "0x2a 0b10_1010");
+        assert_eq!(
+            hex.decorate("net_mask", "4294967040").unwrap(),
+            "0xffffff00 0b1111_1111_1111_1111_1111_1111_0000_0000"
+        );
+        assert_eq!(hex.decorate("flags", "-1"), None);
+        assert_eq!(hex.decorate("flags", "1.5"), None);
+        assert_eq!(hex.decorate("flags", r#""42""#), None);
+        assert_eq!(hex.decorate("id", "42"), None);
+    }
+
+    #[test]
+    fn annotate_file_sizes() {
+        let sizes = FileSizes::defaults();
+        assert_eq!(sizes.decorate("size", "1048576").unwrap(), "1.0 MiB");
+        assert_eq!(sizes.decorate("content_bytes", "512").unwrap(), "512 B");
+        assert_eq!(sizes.decorate("file_size", "1536").unwrap(), "1.5 KiB");
+        assert_eq!(sizes.decorate("size", "5497558138880").unwrap(), "5.0 TiB");
+        assert_eq!(sizes.decorate("size", "-1"), None);
+        assert_eq!(sizes.decorate("size", r#""1024""#), None);
+        assert_eq!(sizes.decorate("sizes", "1024"), None);
+    }
+}
@@ -1,6 +1,27 @@
+use crate::arena::Arena;
+use crate::classify::{Class, classify, plain_ascii_len};
+use crate::decorate::ValueDecorator;
+use crate::theme::{FieldType, Theme, TypeMap};
+use crate::visit::{Visit, Visitor};
+use std::borrow::Cow;
 use std::cmp::PartialEq;
+use std::collections::HashSet;
 use std::fmt;
 use std::fmt::Write;
+use std::ops::Range;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+mod fold;
+mod jsonc;
+mod parallel;
+mod style;
+mod utf8;
+mod warning;
+
+pub use fold::Folds;
+pub use style::{AnsiStyle, HtmlStyle, OutputStyle, PlainStyle, Style, SvgStyle};
+pub use warning::Warning;
 
 /// A fast JSON formatter / pretty printer.
 /// This is a fast JSON formatter (x2 compared to pretty printing with [Serde JSON](https://github.com/serde-rs/json)).
@@ -8,26 +29,186 @@ use std::fmt::Write;
 /// UTF-8 validation is done in-place, on the fly, while parsing strings. This implementation try to not allocate
 /// anything. It does not try to normalise, remove unnecessary escaping, it just formats the actual input
 /// with spaces, newlines and (optionally) color.
-pub struct Formatter<'input> {
+pub struct Formatter<'input, S: OutputStyle = AnsiStyle<'input>> {
     /// The JSON input bytes to prettify.
     input: &'input [u8],
     /// Cursor position in byte offset.
-    pos: BytePos,
+    pub(crate) pos: BytePos,
     /// Current indentation level (this is maxed by `MAX_INDENT_LEVEL`)
     level: usize,
-    /// Use color with ANSI escape code when prettifying.
-    color: Color,
+    /// Decoration of the formatted text (colors...).
+    style: S,
+    /// Semantic types of fields, by path, if any.
+    types: Option<&'input TypeMap>,
+    /// Folded values, by path, if any.
+    folds: Option<&'input Folds>,
+    /// Path of the current value (only tracked when there are field types or folds).
+    path: String,
+    /// Semantic type of the current value.
+    field_type: Option<FieldType>,
+    /// Pad keys so that values of an object are aligned in a column.
+    align_values: bool,
+    /// Accept and re-emit comments (JSONC).
+    comments: bool,
+    /// Format strings values containing JSON as nested JSON.
+    expand_embedded: bool,
+    /// Render `\n` escapes in string values as line breaks.
+    render_newlines: bool,
+    /// Style the quotes and the escape sequences of strings separately from their content.
+    split_strings: bool,
+    /// Write invisible and bidirectional formatting chars of strings as `\u` escapes.
+    reveal_invisible: bool,
+    /// Write empty objects and arrays on two lines.
+    expand_empty: bool,
+    /// Write URL string values as hyperlinks.
+    hyperlinks: bool,
+    /// Write objects and arrays with a single member on one line, if the member fits on one line.
+    compact_single: bool,
+    /// The current value is in an object or array written on one line: nested objects and arrays
+    /// are on one line too, with a single member.
+    single_line: bool,
+    /// Summarize GeoJSON `coordinates` arrays.
+    geojson: bool,
+    /// Unwrap Avro unions (`{"int": 5}`) as annotated values.
+    avro_unions: bool,
+    /// Annotations of member values.
+    decorators: Vec<&'input dyn ValueDecorator>,
+    /// Line breaks of the output.
+    line_ending: LineEnding,
+    /// Indentation of each level of the output.
+    indent: Indent,
+    /// Memory for the text of annotations, if any.
+    arena: Option<&'input Arena>,
+    /// Maximum size of the input, in bytes.
+    max_input_size: Option<usize>,
+    /// Maximum size of the output, in bytes.
+    max_output_size: Option<usize>,
+    /// Maximum number of values.
+    max_elements: Option<usize>,
+    /// Maximum length of keys, in bytes.
+    max_key_length: Option<usize>,
+    /// Maximum length of string values, in bytes.
+    max_string_length: Option<usize>,
+    /// Number of values parsed.
+    elements: usize,
+    /// Warnings of the input, if collected.
+    warnings: Option<Vec<Warning>>,
+    /// Visitor of keys and values, if any.
+    visitor: Option<&'input mut dyn Visitor>,
+    /// Counters of the formatted input.
+    report: FormatReport,
+}
+
+/// The output formatted before an error, returned by [Formatter::format_partial].
+#[derive(Debug)]
+pub struct PartialOutput {
+    /// Formatted output of the input before the error.
+    pub output: String,
+    pub error: FormatError,
+}
+
+/// Statistics about a formatted input, returned by [Formatter::format_with_report].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FormatReport {
+    /// Size of the input, in bytes.
+    pub input_bytes: usize,
+    /// Size of the output, in bytes.
+    pub output_bytes: usize,
+    pub objects: usize,
+    pub arrays: usize,
+    /// Object keys.
+    pub keys: usize,
+    /// String values (keys excluded).
+    pub strings: usize,
+    pub numbers: usize,
+    /// `true`, `false` and `null`.
+    pub literals: usize,
+    /// Value separators (`,`).
+    pub commas: usize,
+    /// Maximum nesting level (0 for a scalar, 1 for a flat array etc...).
+    pub max_depth: usize,
+    /// Time spent formatting.
+    pub elapsed: Duration,
+}
+
+impl FormatReport {
+    /// Returns the total number of tokens (values, keys and punctuation).
+    pub fn tokens(&self) -> usize {
+        // Containers have two brackets, keys are followed by a colon.
+        2 * (self.objects + self.arrays + self.keys)
+            + self.strings
+            + self.numbers
+            + self.literals
+            + self.commas
+    }
+}
+
+/// A [Write] counting the bytes written.
+struct CountingWrite<'a, W> {
+    inner: &'a mut W,
+    count: usize,
+}
+
+impl<W: Write> Write for CountingWrite<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.count += s.len();
+        self.inner.write_str(s)
+    }
+}
+
+/// A [Write] failing when more than a maximum number of bytes are written.
+struct LimitedWrite<'a, W> {
+    inner: &'a mut W,
+    remaining: usize,
+    exceeded: bool,
+}
+
+impl<W: Write> Write for LimitedWrite<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.len() > self.remaining {
+            self.exceeded = true;
+            return Err(fmt::Error);
+        }
+        self.remaining -= s.len();
+        self.inner.write_str(s)
+    }
+}
+
+/// A [Write] discarding the bytes written.
+struct DiscardWrite;
+
+impl Write for DiscardWrite {
+    fn write_str(&mut self, _: &str) -> fmt::Result {
+        Ok(())
+    }
+}
+
+/// A [Write] comparing the bytes written with expected bytes, failing at the first difference.
+struct CompareWrite<'a> {
+    expected: &'a [u8],
+    pos: usize,
+}
+
+impl Write for CompareWrite<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if !self.expected[self.pos..].starts_with(s.as_bytes()) {
+            return Err(fmt::Error);
+        }
+        self.pos += s.len();
+        Ok(())
+    }
 }
 
 /// The maximum indentation level supported before errors.
-const MAX_INDENT_LEVEL: usize = 100;
+pub const MAX_INDENT_LEVEL: usize = 100;
 
 /// A byte position in a bytes stream.
-#[derive(Debug, Copy, Clone)]
-pub struct BytePos(usize);
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct BytePos(pub usize);
 
 /// Potential errors raised during formatting.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum FormatError {
     /// Unexpected end of file.
     Eof,
@@ -39,6 +220,15 @@ pub enum FormatError {
     InvalidEscape(u8, BytePos),
     /// The maximum indent level has been reached.
     MaxIndentLevel(usize, BytePos),
+    /// The input is larger than the maximum input size (size, maximum size).
+    InputTooLarge(usize, usize),
+    /// The output would be larger than the maximum output size.
+    OutputTooLarge(usize),
+    /// A limit of the input has been exceeded at this position.
+    LimitExceeded(Limit, BytePos),
+    /// The replacement of a visited value is invalid (error in the replacement, span of the
+    /// replaced value in the input).
+    InvalidReplacement(Box<FormatError>, Range<usize>),
     Fmt(fmt::Error),
 }
 
@@ -64,11 +254,123 @@ impl fmt::Display for FormatError {
             FormatError::MaxIndentLevel(level, pos) => {
                 write!(f, "maximum indent level {} at offset {}", level, pos.0)
             }
+            FormatError::InputTooLarge(size, max) => {
+                write!(f, "input of {size} bytes is larger than {max} bytes")
+            }
+            FormatError::OutputTooLarge(max) => write!(f, "output is larger than {max} bytes"),
+            FormatError::LimitExceeded(limit, pos) => match limit {
+                Limit::Elements(max) => write!(f, "more than {max} values at offset {}", pos.0),
+                Limit::KeyLength(max) => {
+                    write!(f, "key longer than {max} bytes at offset {}", pos.0)
+                }
+                Limit::StringLength(max) => {
+                    write!(f, "string longer than {max} bytes at offset {}", pos.0)
+                }
+            },
+            FormatError::InvalidReplacement(error, span) => write!(
+                f,
+                "invalid replacement of the value at offset {}..{}: {error}",
+                span.start, span.end
+            ),
             FormatError::Fmt(error) => write!(f, "error writing {error}"),
         }
     }
 }
 
+/// A limit of the input, to format untrusted inputs safely.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Limit {
+    /// Maximum number of values (objects, arrays, strings, numbers and literals).
+    Elements(usize),
+    /// Maximum length of keys, in bytes, quotes excluded.
+    KeyLength(usize),
+    /// Maximum length of string values, in bytes, quotes excluded.
+    StringLength(usize),
+}
+
+impl std::error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FormatError::InvalidReplacement(error, _) => Some(error.as_ref()),
+            FormatError::Fmt(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of a [FormatError], to handle errors without matching on their details.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input ends before the end of the JSON value.
+    UnexpectedEof,
+    /// The input is not valid JSON (invalid bytes, escapes or UTF-8 sequences).
+    Syntax,
+    /// The input is nested too deeply.
+    TooDeep,
+    /// The input or the output is larger than its maximum size, or a limit of the input has been
+    /// exceeded.
+    TooLarge,
+    /// The output could not be written.
+    Write,
+}
+
+impl FormatError {
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            FormatError::Eof => ErrorKind::UnexpectedEof,
+            FormatError::InvalidByte(..)
+            | FormatError::InvalidUtf8(..)
+            | FormatError::InvalidEscape(..) => ErrorKind::Syntax,
+            FormatError::MaxIndentLevel(..) => ErrorKind::TooDeep,
+            FormatError::InputTooLarge(..)
+            | FormatError::OutputTooLarge(_)
+            | FormatError::LimitExceeded(..) => ErrorKind::TooLarge,
+            FormatError::InvalidReplacement(error, _) => error.kind(),
+            FormatError::Fmt(_) => ErrorKind::Write,
+        }
+    }
+
+    /// Returns the byte offset in the input where this error occurred, if any.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            FormatError::InvalidByte(_, pos)
+            | FormatError::InvalidUtf8(_, _, pos)
+            | FormatError::InvalidEscape(_, pos)
+            | FormatError::MaxIndentLevel(_, pos)
+            | FormatError::LimitExceeded(_, pos) => Some(pos.0),
+            FormatError::InvalidReplacement(_, span) => Some(span.start),
+            FormatError::Eof
+            | FormatError::InputTooLarge(..)
+            | FormatError::OutputTooLarge(_)
+            | FormatError::Fmt(_) => None,
+        }
+    }
+
+    /// Returns the line and the column (1-based, in chars) in `input` where this error
+    /// occurred, if any. An unexpected end of file is at the end of `input`.
+    pub fn line_col(&self, input: &[u8]) -> Option<(usize, usize)> {
+        let offset = match self {
+            FormatError::Eof => input.len(),
+            _ => self.offset()?.min(input.len()),
+        };
+        let before = &input[..offset];
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let line = before[..line_start].iter().filter(|&&b| b == b'\n').count() + 1;
+        // Continuation bytes of UTF-8 sequences are not counted.
+        let col = before[line_start..]
+            .iter()
+            .filter(|&&b| b & 0xC0 != 0x80)
+            .count()
+            + 1;
+        Some((line, col))
+    }
+}
+
 impl From<fmt::Error> for FormatError {
     fn from(e: fmt::Error) -> Self {
         FormatError::Fmt(e)
@@ -81,6 +383,107 @@ pub enum Color {
     AnsiCode,
 }
 
+/// Line breaks of the output.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    Crlf,
+}
+
+impl LineEnding {
+    /// Returns the line ending of the current platform (`\r\n` on Windows).
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+impl FromStr for LineEnding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lf" => Ok(LineEnding::Lf),
+            "crlf" => Ok(LineEnding::Crlf),
+            "native" => Ok(LineEnding::native()),
+            _ => Err(format!(
+                "invalid line ending {s}, expected lf, crlf or native"
+            )),
+        }
+    }
+}
+
+/// Indentation of each level of the output.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Indent {
+    /// A number of spaces.
+    Spaces(usize),
+    /// A tab.
+    Tab,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Indent::Spaces(2)
+    }
+}
+
+impl FromStr for Indent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tab" => Ok(Indent::Tab),
+            _ => match s.parse::<usize>() {
+                Ok(n) if n <= 16 => Ok(Indent::Spaces(n)),
+                _ => Err(format!(
+                    "invalid indent {s}, expected a number of spaces (up to 16) or tab"
+                )),
+            },
+        }
+    }
+}
+
+/// The options of a [Formatter] that don't borrow data (types, folds, decorators... are set with
+/// their own methods), set at once with [Formatter::with_options].
+///
+/// The limits protect services formatting untrusted inputs from exhausting their memory.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct FormatterOptions {
+    pub align_values: bool,
+    pub comments: bool,
+    pub expand_embedded: bool,
+    pub render_newlines: bool,
+    pub split_strings: bool,
+    pub reveal_invisible: bool,
+    pub expand_empty: bool,
+    pub hyperlinks: bool,
+    pub compact_single: bool,
+    pub geojson: bool,
+    pub avro_unions: bool,
+    pub line_ending: LineEnding,
+    pub indent: Indent,
+    pub max_input_size: Option<usize>,
+    pub max_output_size: Option<usize>,
+    pub max_elements: Option<usize>,
+    pub max_key_length: Option<usize>,
+    pub max_string_length: Option<usize>,
+    pub warnings: bool,
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum StringMode {
     Key,
@@ -90,29 +493,274 @@ enum StringMode {
 type FormatResult<T> = Result<T, FormatError>;
 
 impl<'input> Formatter<'input> {
+    /// Creates a formatter writing ANSI colors (or plain text with [Color::NoColor]).
     pub fn new(input: &'input [u8], color: Color) -> Self {
+        Formatter::new_styled(input, AnsiStyle::new(color))
+    }
+
+    /// Uses `theme` to colorize the JSON input.
+    pub fn with_theme(mut self, theme: &'input Theme) -> Self {
+        self.style.theme = theme;
+        self
+    }
+}
+
+impl<'input, S: OutputStyle> Formatter<'input, S> {
+    /// Creates a formatter decorating its output with `style`.
+    pub fn new_styled(input: &'input [u8], style: S) -> Self {
         Formatter {
             input,
             pos: BytePos(0),
             level: 0,
-            color,
+            style,
+            types: None,
+            folds: None,
+            path: String::new(),
+            field_type: None,
+            align_values: false,
+            comments: false,
+            expand_embedded: false,
+            render_newlines: false,
+            split_strings: false,
+            reveal_invisible: false,
+            expand_empty: false,
+            hyperlinks: false,
+            compact_single: false,
+            single_line: false,
+            geojson: false,
+            avro_unions: false,
+            decorators: Vec::new(),
+            line_ending: LineEnding::Lf,
+            indent: Indent::default(),
+            arena: None,
+            max_input_size: None,
+            max_output_size: None,
+            max_elements: None,
+            max_key_length: None,
+            max_string_length: None,
+            elements: 0,
+            warnings: None,
+            visitor: None,
+            report: FormatReport::default(),
+        }
+    }
+
+    /// Sets all the `options`, as their `with_` methods do.
+    pub fn with_options(mut self, options: &FormatterOptions) -> Self {
+        self.align_values = options.align_values;
+        self.comments = options.comments;
+        self.expand_embedded = options.expand_embedded;
+        self.render_newlines = options.render_newlines;
+        self.split_strings = options.split_strings;
+        self.reveal_invisible = options.reveal_invisible;
+        self.expand_empty = options.expand_empty;
+        self.hyperlinks = options.hyperlinks;
+        self.compact_single = options.compact_single;
+        self.geojson = options.geojson;
+        self.avro_unions = options.avro_unions;
+        self.line_ending = options.line_ending;
+        self.indent = options.indent;
+        self.max_input_size = options.max_input_size;
+        self.max_output_size = options.max_output_size;
+        self.max_elements = options.max_elements;
+        self.max_key_length = options.max_key_length;
+        self.max_string_length = options.max_string_length;
+        self.with_warnings(options.warnings)
+    }
+
+    /// Aligns values of each object in a column, by padding keys.
+    pub fn with_align_values(mut self, align_values: bool) -> Self {
+        self.align_values = align_values;
+        self
+    }
+
+    /// Accepts `//` and `/* */` comments, and trailing commas, and re-emits the comments.
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Formats string values that contain JSON (objects or arrays) as nested JSON, marked with a
+    /// `/* json */` comment. This is a display-only mode, the output is not the input JSON anymore.
+    pub fn with_expand_embedded(mut self, expand_embedded: bool) -> Self {
+        self.expand_embedded = expand_embedded;
+        self
+    }
+
+    /// Renders `\n` escapes in string values as actual line breaks, marked with `⏎`. This is a
+    /// display-only mode, the output is not valid JSON anymore.
+    pub fn with_render_newlines(mut self, render_newlines: bool) -> Self {
+        self.render_newlines = render_newlines;
+        self
+    }
+
+    /// Writes the quotes of keys and string values with [Style::Quote] and their escape sequences
+    /// with [Style::Escape], instead of writing the whole strings with [Style::Key] or
+    /// [Style::String].
+    pub fn with_split_strings(mut self, split_strings: bool) -> Self {
+        self.split_strings = split_strings;
+        self
+    }
+
+    /// Writes the invisible chars of keys and strings (zero-width spaces, bidirectional
+    /// overrides...) as `\u` escapes with [Style::Escape], so that they can't hide the content of
+    /// strings. The output is the same JSON value.
+    pub fn with_reveal_invisible(mut self, reveal_invisible: bool) -> Self {
+        self.reveal_invisible = reveal_invisible;
+        self
+    }
+
+    /// Writes string values that are URLs (`http://`, `https://`) as hyperlinks, with
+    /// [OutputStyle::begin_link]: OSC 8 escape sequences for ANSI styles.
+    pub fn with_hyperlinks(mut self, hyperlinks: bool) -> Self {
+        self.hyperlinks = hyperlinks;
+        self
+    }
+
+    /// Writes empty objects and arrays on two lines, instead of `{}` and `[]`.
+    pub fn with_expand_empty(mut self, expand_empty: bool) -> Self {
+        self.expand_empty = expand_empty;
+        self
+    }
+
+    /// Writes objects and arrays with a single member on one line (`{"id": 1}`, `[[2]]`), if the
+    /// member fits on one line. This is not applied to JSONC inputs.
+    pub fn with_compact_single(mut self, compact_single: bool) -> Self {
+        self.compact_single = compact_single;
+        self
+    }
+
+    /// Displays GeoJSON `coordinates` arrays (except single points) as a summary with their
+    /// number of points and their bounding box: `[[…] 12,408 points]  /* bbox [...] */`. This is
+    /// a display-only mode, the output is not the input JSON anymore.
+    pub fn with_geojson(mut self, geojson: bool) -> Self {
+        self.geojson = geojson;
+        self
+    }
+
+    /// Displays Avro JSON encoded unions, objects with a single member named after a type
+    /// (`{"int": 5}`, `{"com.acme.User": {...}}`), as their value followed by the type:
+    /// `5  /* int */`. This is a display-only mode, the output is not the input JSON anymore.
+    pub fn with_avro_unions(mut self, avro_unions: bool) -> Self {
+        self.avro_unions = avro_unions;
+        self
+    }
+
+    /// Appends the annotations of `decorator` after member values, as dimmed comments. This is a
+    /// display-only mode, the output is not valid JSON anymore.
+    pub fn with_decorator(mut self, decorator: &'input dyn ValueDecorator) -> Self {
+        self.decorators.push(decorator);
+        self
+    }
+
+    /// Colorizes values according to their semantic types.
+    pub fn with_types(mut self, types: &'input TypeMap) -> Self {
+        if !types.is_empty() {
+            self.types = Some(types);
         }
+        self
+    }
+
+    /// Uses `line_ending` for line breaks (`\n` by default). Line breaks in comments are also
+    /// converted.
+    pub fn with_line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Indents each level of the output with `indent` (2 spaces by default).
+    pub fn with_indent(mut self, indent: Indent) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Displays the objects and arrays matching `folds` as one-line summaries:
+    /// `{…}  /* 12 members, 3.4 kB */`. This is a display-only mode, the output is not the input
+    /// JSON anymore.
+    pub fn with_folds(mut self, folds: &'input Folds) -> Self {
+        if !folds.is_empty() {
+            self.folds = Some(folds);
+        }
+        self
+    }
+
+    /// Allocates the text of annotations (decorations, summaries...) in `arena` instead of the
+    /// heap. Reusing an arena, reset between inputs, avoids allocating memory for each input.
+    pub fn with_arena(mut self, arena: &'input Arena) -> Self {
+        self.arena = Some(arena);
+        self
+    }
+
+    /// Fails with [FormatError::InputTooLarge] if the input is larger than `max_input_size` bytes.
+    pub fn with_max_input_size(mut self, max_input_size: usize) -> Self {
+        self.max_input_size = Some(max_input_size);
+        self
+    }
+
+    /// Fails with [FormatError::OutputTooLarge] as soon as the output would be larger than
+    /// `max_output_size` bytes, for untrusted inputs.
+    pub fn with_max_output_size(mut self, max_output_size: usize) -> Self {
+        self.max_output_size = Some(max_output_size);
+        self
+    }
+
+    /// Fails with [FormatError::LimitExceeded] if the input has more than `max_elements` values
+    /// (objects, arrays, strings, numbers and literals, keys excluded).
+    pub fn with_max_elements(mut self, max_elements: usize) -> Self {
+        self.max_elements = Some(max_elements);
+        self
+    }
+
+    /// Fails with [FormatError::LimitExceeded] if a key is longer than `max_key_length` bytes.
+    pub fn with_max_key_length(mut self, max_key_length: usize) -> Self {
+        self.max_key_length = Some(max_key_length);
+        self
+    }
+
+    /// Fails with [FormatError::LimitExceeded] if a string value is longer than
+    /// `max_string_length` bytes.
+    pub fn with_max_string_length(mut self, max_string_length: usize) -> Self {
+        self.max_string_length = Some(max_string_length);
+        self
+    }
+
+    /// Collects the [Warning]s of the input (duplicate keys, lossy numbers...) while formatting.
+    pub fn with_warnings(mut self, warnings: bool) -> Self {
+        self.warnings = warnings.then(Vec::new);
+        self
     }
 
+    /// Returns the warnings of the last formatted input, empty if warnings are not collected.
+    pub fn warnings(&self) -> &[Warning] {
+        self.warnings.as_deref().unwrap_or_default()
+    }
+
+    /// Calls `visitor` for the keys and values of the input while formatting.
+    pub fn with_visitor(mut self, visitor: &'input mut dyn Visitor) -> Self {
+        self.visitor = Some(visitor);
+        self
+    }
+
+    /// Returns `true` if the path of the current value is tracked.
     #[inline]
-    fn next_byte(&mut self) -> Option<u8> {
+    fn tracks_path(&self) -> bool {
+        self.types.is_some() || self.folds.is_some() || self.visitor.is_some()
+    }
+
+    #[inline]
+    pub(crate) fn next_byte(&mut self) -> Option<u8> {
         let b = self.peek_byte()?;
         self.pos.0 += 1;
         Some(b)
     }
 
     #[inline]
-    fn peek_byte(&mut self) -> Option<u8> {
+    pub(crate) fn peek_byte(&mut self) -> Option<u8> {
         self.input.get(self.pos.0).copied()
     }
 
     #[inline]
-    fn expect_byte(&mut self, expected: u8) -> FormatResult<()> {
+    pub(crate) fn expect_byte(&mut self, expected: u8) -> FormatResult<()> {
         match self.next_byte() {
             Some(b) if b == expected => Ok(()),
             Some(b) => Err(FormatError::InvalidByte(b, BytePos(self.pos.0 - 1))),
@@ -120,21 +768,49 @@ impl<'input> Formatter<'input> {
         }
     }
 
-    fn inc_level(&mut self) -> FormatResult<()> {
+    pub(crate) fn inc_level(&mut self) -> FormatResult<()> {
         if self.level >= MAX_INDENT_LEVEL {
             return Err(FormatError::MaxIndentLevel(self.level, self.pos));
         }
         self.level += 1;
+        self.report.max_depth = self.report.max_depth.max(self.level);
         Ok(())
     }
 
-    fn dec_level(&mut self) {
+    pub(crate) fn dec_level(&mut self) {
         self.level -= 1;
     }
 
 
     /// Formats and colorize the JSON input bytes.
     pub fn format(&mut self, out: &mut impl Write) -> FormatResult<()> {
+        if let Some(max) = self.max_input_size
+            && self.input.len() > max
+        {
+            return Err(FormatError::InputTooLarge(self.input.len(), max));
+        }
+        let Some(max) = self.max_output_size else {
+            return self.format_unlimited(out);
+        };
+        let mut out = LimitedWrite {
+            inner: out,
+            remaining: max,
+            exceeded: false,
+        };
+        match self.format_unlimited(&mut out) {
+            Err(FormatError::Fmt(_)) if out.exceeded => Err(FormatError::OutputTooLarge(max)),
+            result => result,
+        }
+    }
+
+    fn format_unlimited(&mut self, out: &mut impl Write) -> FormatResult<()> {
+        self.elements = 0;
+        if let Some(warnings) = &mut self.warnings {
+            warnings.clear();
+        }
+        if self.comments {
+            return self.format_jsonc(out);
+        }
         self.skip_start_bom();
 
         self.skip_whitespace();
@@ -149,8 +825,67 @@ impl<'input> Formatter<'input> {
         }
     }
 
+    /// Formats and colorize the JSON input bytes, returns statistics about the input and the output.
+    pub fn format_with_report(&mut self, out: &mut impl Write) -> FormatResult<FormatReport> {
+        let start = Instant::now();
+        let mut out = CountingWrite {
+            inner: out,
+            count: 0,
+        };
+        self.report = FormatReport::default();
+        self.format(&mut out)?;
+        let mut report = std::mem::take(&mut self.report);
+        report.input_bytes = self.input.len();
+        report.output_bytes = out.count;
+        report.elapsed = start.elapsed();
+        Ok(report)
+    }
+
+    /// Returns `true` if the formatted JSON input is `expected`, i.e. if `expected` is already
+    /// formatted. Nothing is allocated and the formatting stops at the first difference, so this
+    /// is faster than formatting and comparing.
+    pub fn formats_to(&mut self, expected: &[u8]) -> bool {
+        let mut out = CompareWrite { expected, pos: 0 };
+        self.format(&mut out).is_ok() && out.pos == expected.len()
+    }
+
+    /// Formats and colorize the JSON input bytes in `out`, replacing its content. The capacity of
+    /// `out` is kept, so the same buffer can be reused to format several inputs.
+    pub fn format_into(&mut self, out: &mut String) -> FormatResult<()> {
+        out.clear();
+        self.format(out)
+    }
+
+    /// Formats and colorize the JSON input bytes, returns the formatted output or, on error, the
+    /// output formatted before the error with the error. The valid prefix of an invalid input can
+    /// be displayed with the error.
+    pub fn format_partial(&mut self) -> Result<String, PartialOutput> {
+        let mut output = String::new();
+        match self.format(&mut output) {
+            Ok(()) => Ok(output),
+            Err(error) => Err(PartialOutput { output, error }),
+        }
+    }
+
+    /// Resets the formatter to format `input`, with the same options. A formatter can be reused
+    /// for several inputs, instead of being created for each input.
+    pub fn reset(&mut self, input: &'input [u8]) {
+        self.input = input;
+        self.pos = BytePos(0);
+        // A previous format may have failed within a nested or single-line value.
+        self.level = 0;
+        self.single_line = false;
+        self.path.clear();
+        self.field_type = None;
+        self.elements = 0;
+        if let Some(warnings) = &mut self.warnings {
+            warnings.clear();
+        }
+        self.report = FormatReport::default();
+    }
+
     /// Skips BOM (Byte Order Mark) at the start of the read buffer.
-    fn skip_start_bom(&mut self) {
+    pub(crate) fn skip_start_bom(&mut self) {
         debug_assert!(self.pos.0 == 0);
         if self.input.len() < 3 {
             return;
@@ -160,8 +895,11 @@ impl<'input> Formatter<'input> {
         }
     }
 
-    fn skip_whitespace(&mut self) {
-        while matches!(self.peek_byte(), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+    pub(crate) fn skip_whitespace(&mut self) {
+        while self
+            .peek_byte()
+            .is_some_and(|b| classify(b) == Class::Whitespace)
+        {
             self.pos.0 += 1;
         }
     }
@@ -174,9 +912,65 @@ impl<'input> Formatter<'input> {
         // false = %x66.61.6c.73.65   ; false
         // null  = %x6e.75.6c.6c      ; null
         // true  = %x74.72.75.65      ; true
+        self.elements += 1;
+        if let Some(max) = self.max_elements
+            && self.elements > max
+        {
+            return Err(FormatError::LimitExceeded(Limit::Elements(max), self.pos));
+        }
         match self.peek_byte() {
+            Some(b) if classify(b) == Class::Digit => self.report.numbers += 1,
+            Some(b'"') => self.report.strings += 1,
+            Some(b'-') => self.report.numbers += 1,
+            Some(b'{') => self.report.objects += 1,
+            Some(b'[') => self.report.arrays += 1,
+            Some(b't' | b'f' | b'n') => self.report.literals += 1,
+            _ => {}
+        }
+        let Some(visitor) = self.visitor.as_deref_mut() else {
+            return self.parse_unvisited_value(out);
+        };
+        let start = self.pos;
+        match visitor.on_value_start(start.0, &self.path) {
+            Visit::Continue => self.parse_unvisited_value(out)?,
+            Visit::Replace(json) => {
+                self.parse_unvisited_value(&mut DiscardWrite)?;
+                let mut replacement = String::new();
+                let mut formatter = Formatter::new_styled(json.as_bytes(), self.style.clone())
+                    .with_indent(self.indent)
+                    .with_line_ending(self.line_ending);
+                formatter.level = self.level;
+                formatter.format(&mut replacement).map_err(|error| {
+                    FormatError::InvalidReplacement(Box::new(error), start.0..self.pos.0)
+                })?;
+                out.write_str(&replacement)?;
+            }
+        }
+        let input = self.input;
+        // The value has been validated, it's valid UTF-8.
+        let raw = unsafe { std::str::from_utf8_unchecked(&input[start.0..self.pos.0]) };
+        if let Some(visitor) = self.visitor.as_deref_mut() {
+            visitor.on_value_end(raw, start.0..self.pos.0, &self.path);
+        }
+        Ok(())
+    }
+
+    /// Value, without calling the visitor.
+    fn parse_unvisited_value(&mut self, out: &mut impl Write) -> FormatResult<()> {
+        if let Some(folds) = self.folds
+            && matches!(self.peek_byte(), Some(b'{' | b'['))
+            && folds.is_folded(&self.path)
+            && self.write_folded(out)?
+        {
+            return Ok(());
+        }
+        match self.peek_byte() {
+            // Numbers are the most frequent values in large inputs.
+            Some(b) if classify(b) == Class::Digit => self.parse_number(out),
             Some(b'"') => self.parse_string(out, StringMode::Value),
-            Some(b'-' | b'0'..=b'9') => self.parse_number(out),
+            Some(b'-') => self.parse_number(out),
+            Some(b'{') if self.comments => self.parse_object_jsonc(out),
+            Some(b'[') if self.comments => self.parse_array_jsonc(out),
             Some(b'{') => self.parse_object(out),
             Some(b'[') => self.parse_array(out),
             Some(b't') => self.parse_true(out),
@@ -195,7 +989,7 @@ impl<'input> Formatter<'input> {
         // member = string name-separator value
         self.expect_byte(b'{')?;
 
-        // For empty objects, we keep a short compact form:
+        // For empty objects, we keep a short compact form (unless expanded):
         self.skip_whitespace();
         if self.peek_byte() == Some(b'}') {
             self.next_byte();
@@ -203,42 +997,106 @@ impl<'input> Formatter<'input> {
             return Ok(());
         }
 
+        if self.avro_unions
+            && let Some((branch, value_start)) = self.avro_union()
+        {
+            self.pos = value_start;
+            self.parse_value(out)?;
+            self.skip_whitespace();
+            self.expect_byte(b'}')?;
+            out.write_str("  ")?;
+            self.write_styled(
+                Style::Comment,
+                &self.format_text(format_args!("/* {branch} */")),
+                out,
+            )?;
+            return Ok(());
+        }
+
         // Now, we have a non-empty object.
-        self.write_begin_obj(out)?;
+        let key_width = if self.align_values {
+            self.object_key_width()
+        } else {
+            0
+        };
+        let single_line = self.compact_single && (self.single_line || self.is_single_line(b'}'));
+        let enclosing_single_line = std::mem::replace(&mut self.single_line, single_line);
+        if single_line {
+            self.write_styled(Style::Punctuation, "{", out)?;
+        } else {
+            self.write_begin_obj(out)?;
+        }
         self.inc_level()?;
 
+        let mut keys = self.warnings.is_some().then(HashSet::new);
         let mut first = true;
         loop {
             self.skip_whitespace();
             if self.peek_byte() == Some(b'}') {
                 self.next_byte();
                 self.dec_level();
-                self.write_ln(out)?;
-                self.write_indent(out)?;
+                if !single_line {
+                    self.write_ln(out)?;
+                    self.write_indent(out)?;
+                }
                 self.write_end_obj(out)?;
+                self.single_line = enclosing_single_line;
                 return Ok(());
             }
 
             if first {
                 first = false;
+            } else if single_line {
+                // Only reached by the scanner of `is_single_line`: a second member.
+                return Err(FormatError::InvalidByte(b',', self.pos));
             } else {
                 self.expect_byte(b',')?;
+                self.report.commas += 1;
                 self.skip_whitespace();
                 self.write_value_sep(out)?;
             }
 
             // Parse key
-            self.write_indent(out)?;
+            if !single_line {
+                self.write_indent(out)?;
+            }
+            let key_start = self.pos;
             self.parse_string(out, StringMode::Key)?;
+            let key_end = self.pos;
+            if let Some(keys) = &mut keys {
+                self.check_duplicate_key(keys, key_start, key_end);
+            }
+            self.report.keys += 1;
+            let path_len = self.path.len();
+            if self.tracks_path() {
+                self.push_key_path(key_start);
+                self.field_type = self.types.and_then(|types| types.get(&self.path));
+            }
+            self.visit_key(key_start, key_end);
 
             // Parse colon
             self.skip_whitespace();
             self.expect_byte(b':')?;
             self.write_name_sep(out)?;
+            if key_width > 0 {
+                let width = self.slice_str_unchecked(key_start, key_end).chars().count();
+                self.write_spaces(key_width.saturating_sub(width), out)?;
+            }
 
             // Parse value
             self.skip_whitespace();
-            self.parse_value(out)?;
+            let value_start = self.pos;
+            let summarized = self.geojson
+                && self.slice_str_unchecked(key_start, key_end) == "\"coordinates\""
+                && self.write_coordinates_summary(out)?;
+            if !summarized {
+                self.parse_value(out)?;
+            }
+            if !self.decorators.is_empty() {
+                self.write_decorations(key_start, key_end, value_start, out)?;
+            }
+            self.path.truncate(path_len);
+            self.field_type = None;
         }
     }
 
@@ -248,7 +1106,7 @@ impl<'input> Formatter<'input> {
         // array = begin-array [ value *( value-separator value ) ] end-array
         self.expect_byte(b'[')?;
 
-        // For empty arrays, we keep a short compact form:
+        // For empty arrays, we keep a short compact form (unless expanded):
         self.skip_whitespace();
         if self.peek_byte() == Some(b']') {
             self.next_byte();
@@ -257,62 +1115,436 @@ impl<'input> Formatter<'input> {
         }
 
         // Now, we have a non-empty array.
-        self.write_begin_arr(out)?;
+        let single_line = self.compact_single && (self.single_line || self.is_single_line(b']'));
+        let enclosing_single_line = std::mem::replace(&mut self.single_line, single_line);
+        if single_line {
+            self.write_styled(Style::Punctuation, "[", out)?;
+        } else {
+            self.write_begin_arr(out)?;
+        }
         self.inc_level()?;
 
         let mut first = true;
+        let mut index = 0;
         loop {
             self.skip_whitespace();
             if self.peek_byte() == Some(b']') {
                 self.next_byte();
                 self.dec_level();
-                self.write_ln(out)?;
-                self.write_indent(out)?;
+                if !single_line {
+                    self.write_ln(out)?;
+                    self.write_indent(out)?;
+                }
                 self.write_end_arr(out)?;
+                self.single_line = enclosing_single_line;
                 return Ok(());
             }
 
             if first {
                 first = false;
+            } else if single_line {
+                // Only reached by the scanner of `is_single_line`: a second member.
+                return Err(FormatError::InvalidByte(b',', self.pos));
             } else {
                 self.expect_byte(b',')?;
+                self.report.commas += 1;
                 self.skip_whitespace();
                 self.write_value_sep(out)?;
             }
 
-            self.write_indent(out)?;
-            self.parse_value(out)?;
+            if !single_line {
+                self.write_indent(out)?;
+            }
+            let path_len = self.path.len();
+            if self.tracks_path() {
+                write!(self.path, "[{index}]")?;
+                self.field_type = self.types.and_then(|types| types.get(&self.path));
+            }
+            self.parse_value(out)?;
+            self.path.truncate(path_len);
+            self.field_type = None;
+            index += 1;
+        }
+    }
+
+    /// Returns `true` if the object or array starting at the current position (just after its
+    /// opening bracket) has a single member, formatted on one line. `end` is the closing bracket.
+    ///
+    /// Like [Formatter::object_key_width], the member is scanned without being written, and
+    /// errors are reported when it's actually formatted. The scan stops at the first nested
+    /// object or array with more than one member, and nested ones are not checked again once
+    /// formatted: checks are linear in the size of the member.
+    fn is_single_line(&self, end: u8) -> bool {
+        let mut scanner = Formatter::new(self.input, Color::NoColor)
+            .with_expand_embedded(self.expand_embedded)
+            .with_render_newlines(self.render_newlines)
+            .with_expand_empty(self.expand_empty)
+            .with_compact_single(true);
+        scanner.pos = self.pos;
+        scanner.level = self.level;
+        scanner.single_line = true;
+        let mut line = String::new();
+        if end == b'}' {
+            if scanner.parse_string(&mut line, StringMode::Key).is_err() {
+                return false;
+            }
+            scanner.skip_whitespace();
+            if scanner.expect_byte(b':').is_err() {
+                return false;
+            }
+            scanner.skip_whitespace();
+        }
+        if scanner.parse_value(&mut line).is_err() {
+            return false;
+        }
+        scanner.skip_whitespace();
+        scanner.peek_byte() == Some(end) && !line.contains('\n')
+    }
+
+    /// Returns the width, in chars, of the widest key of the object starting at the current
+    /// position (just after its opening brace).
+    ///
+    /// The object is scanned without being written, errors are ignored here and will be reported
+    /// when the object is actually formatted.
+    fn object_key_width(&self) -> usize {
+        let mut scanner = Formatter::new(self.input, Color::NoColor);
+        scanner.pos = self.pos;
+        scanner.level = self.level;
+        let mut sink = String::new();
+        let mut width = 0;
+        loop {
+            scanner.skip_whitespace();
+            let start = scanner.pos;
+            if scanner.scan_string().is_err() {
+                return width;
+            }
+            width = width.max(
+                scanner
+                    .slice_str_unchecked(start, scanner.pos)
+                    .chars()
+                    .count(),
+            );
+            scanner.skip_whitespace();
+            if scanner.expect_byte(b':').is_err() {
+                return width;
+            }
+            scanner.skip_whitespace();
+            sink.clear();
+            if scanner.parse_value(&mut sink).is_err() {
+                return width;
+            }
+            scanner.skip_whitespace();
+            if scanner.expect_byte(b',').is_err() {
+                return width;
+            }
+        }
+    }
+
+    /// Writes the object or array at the current position as a one-line summary:
+    /// `{…}  /* 12 members, 3.4 kB */`. Returns `false`, and consumes nothing, if the value is
+    /// empty or invalid (the error is then reported by the normal formatting).
+    fn write_folded(&mut self, out: &mut impl Write) -> FormatResult<bool> {
+        let mut scanner = Formatter::new(self.input, Color::NoColor);
+        scanner.pos = self.pos;
+        scanner.level = self.level;
+        let start = self.pos;
+        // Empty values are already on one line.
+        let Ok(count @ 1..) = scanner.count_children() else {
+            return Ok(false);
+        };
+        self.pos = scanner.pos;
+
+        let (folded, child) = if self.input[start.0] == b'{' {
+            ("{…}", "member")
+        } else {
+            ("[…]", "item")
+        };
+        let size = crate::decorate::humanize_bytes((self.pos.0 - start.0) as f64);
+        let summary = self.format_text(format_args!(
+            "/* {} {child}{}, {size} */",
+            group_thousands(count),
+            if count == 1 { "" } else { "s" }
+        ));
+        self.write_styled(Style::Punctuation, folded, out)?;
+        out.write_str("  ")?;
+        self.write_styled(Style::Comment, &summary, out)?;
+        Ok(true)
+    }
+
+    /// Skips an object or an array and returns its number of members or items.
+    fn count_children(&mut self) -> FormatResult<usize> {
+        let is_object = self.peek_byte() == Some(b'{');
+        let close = if is_object { b'}' } else { b']' };
+        self.next_byte();
+        self.inc_level()?;
+        let mut count = 0;
+        loop {
+            self.skip_whitespace();
+            if self.peek_byte() == Some(close) {
+                self.next_byte();
+                self.dec_level();
+                return Ok(count);
+            }
+            if count > 0 {
+                self.expect_byte(b',')?;
+                self.skip_whitespace();
+            }
+            if is_object {
+                self.scan_string()?;
+                self.skip_whitespace();
+                self.expect_byte(b':')?;
+                self.skip_whitespace();
+            }
+            self.skip_value()?;
+            count += 1;
+        }
+    }
+
+    /// Writes a summary of the GeoJSON coordinates array at the current position, returns
+    /// `false` (and writes nothing) for a single position or if the array is not made of
+    /// positions.
+    fn write_coordinates_summary(&mut self, out: &mut impl Write) -> FormatResult<bool> {
+        let mut scanner = Formatter::new(self.input, Color::NoColor);
+        scanner.pos = self.pos;
+        scanner.level = self.level;
+        let mut coordinates = Coordinates {
+            points: 0,
+            depth: 0,
+            bbox: [
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ],
+        };
+        if scanner.scan_coordinates(1, &mut coordinates).is_err() || coordinates.depth < 2 {
+            return Ok(false);
+        }
+        self.pos = scanner.pos;
+
+        let nesting = coordinates.depth - 2;
+        let points = coordinates.points;
+        let summary = self.format_text(format_args!(
+            "[{}…{} {} point{}]",
+            "[".repeat(nesting),
+            "]".repeat(nesting),
+            group_thousands(points),
+            if points == 1 { "" } else { "s" }
+        ));
+        self.write_styled(Style::Comment, &summary, out)?;
+        let [min_x, min_y, max_x, max_y] = coordinates.bbox;
+        let bbox = self.format_text(format_args!(
+            "/* bbox [{min_x}, {min_y}, {max_x}, {max_y}] */"
+        ));
+        out.write_str("  ")?;
+        self.write_styled(Style::Comment, &bbox, out)?;
+        Ok(true)
+    }
+    /// Consumes a GeoJSON coordinates array (positions, or nested arrays of positions) at
+    /// `depth`, counting its positions and updating the bounding box.
+    fn scan_coordinates(
+        &mut self,
+        depth: usize,
+        coordinates: &mut Coordinates,
+    ) -> FormatResult<()> {
+        self.expect_byte(b'[')?;
+        self.inc_level()?;
+        self.skip_whitespace();
+        let is_position = matches!(self.peek_byte(), Some(b'-' | b'0'..=b'9'));
+        if is_position {
+            coordinates.depth = coordinates.depth.max(depth);
+            coordinates.points += 1;
+        }
+        let mut index = 0;
+        while self.peek_byte() != Some(b']') {
+            if index > 0 {
+                self.expect_byte(b',')?;
+                self.skip_whitespace();
+            }
+            if is_position {
+                let start = self.pos;
+                self.scan_number()?;
+                let n = self.slice_str_unchecked(start, self.pos).parse::<f64>();
+                if let (Ok(n), 0 | 1) = (n, index) {
+                    coordinates.bbox[index] = coordinates.bbox[index].min(n);
+                    coordinates.bbox[index + 2] = coordinates.bbox[index + 2].max(n);
+                }
+            } else {
+                self.scan_coordinates(depth + 1, coordinates)?;
+            }
+            index += 1;
+            self.skip_whitespace();
+        }
+        self.next_byte();
+        self.dec_level();
+        Ok(())
+    }
+
+    /// Returns the branch type name of the Avro union starting at the current position (after
+    /// the opening brace), and the position of its value, or `None` if the object is not a union.
+    fn avro_union(&self) -> Option<(&'input str, BytePos)> {
+        let mut scanner = Formatter::new(self.input, Color::NoColor);
+        scanner.pos = self.pos;
+        scanner.level = self.level;
+        let key_start = scanner.pos;
+        scanner.scan_string().ok()?;
+        let input = self.input;
+        let key = std::str::from_utf8(&input[key_start.0 + 1..scanner.pos.0 - 1]).ok()?;
+        if !is_avro_type(key) {
+            return None;
+        }
+        scanner.skip_whitespace();
+        scanner.expect_byte(b':').ok()?;
+        scanner.skip_whitespace();
+        let value_start = scanner.pos;
+        scanner.skip_value().ok()?;
+        scanner.skip_whitespace();
+        (scanner.peek_byte() == Some(b'}')).then_some((key, value_start))
+    }
+
+    /// Appends the key starting at `start` (and ending at the current position) to the current
+    /// path, as displayed by [`crate::path::Path`].
+    fn push_key_path(&mut self, start: BytePos) {
+        let input = self.input;
+        let raw = &input[start.0..self.pos.0];
+        // The key has been validated, it's valid UTF-8.
+        let raw = unsafe { std::str::from_utf8_unchecked(raw) };
+        let name = &raw[1..raw.len() - 1];
+        if crate::path::is_identifier(name) {
+            self.path.push('.');
+            self.path.push_str(name);
+        } else {
+            self.path.push('[');
+            self.path.push_str(raw);
+            self.path.push(']');
+        }
+    }
+
+    /// Calls the visitor, if any, for the key from `start` to `end`.
+    fn visit_key(&mut self, start: BytePos, end: BytePos) {
+        let input = self.input;
+        if let Some(visitor) = self.visitor.as_deref_mut() {
+            // The key has been validated, it's valid UTF-8.
+            let key = unsafe { std::str::from_utf8_unchecked(&input[start.0..end.0]) };
+            visitor.on_key(key, start.0..end.0, &self.path);
+        }
+    }
+
+    pub(crate) fn slice_str_unchecked(&self, start: BytePos, end: BytePos) -> &str {
+        debug_assert!(start.0 <= end.0 && end.0 <= self.input.len());
+        let bytes = &self.input[start.0..end.0];
+        unsafe { std::str::from_utf8_unchecked(bytes) }
+    }
+
+    /// String (preserves escapes)
+    fn parse_string(&mut self, out: &mut impl Write, mode: StringMode) -> FormatResult<()> {
+        let start = self.pos;
+        self.scan_string()?;
+        let max = match mode {
+            StringMode::Key => self.max_key_length.map(Limit::KeyLength),
+            StringMode::Value => self.max_string_length.map(Limit::StringLength),
+        };
+        if let Some(limit @ (Limit::KeyLength(max) | Limit::StringLength(max))) = max
+            && self.pos.0 - start.0 - 2 > max
+        {
+            return Err(FormatError::LimitExceeded(limit, start));
+        }
+        if self.warnings.is_some() {
+            self.check_escapes(start, self.pos);
+            self.check_invisible(start, self.pos);
+        }
+        let string = self.slice_str_unchecked(start, self.pos);
+        let url = match mode {
+            StringMode::Value if self.hyperlinks => url(string),
+            _ => None,
+        };
+        if let Some(url) = &url {
+            self.style.begin_link(url, out)?;
+        }
+        match mode {
+            StringMode::Key => self.write_key(string, out)?,
+            StringMode::Value if self.expand_embedded && self.write_embedded(string, out)? => {}
+            StringMode::Value if self.render_newlines => self.write_multiline_value(string, out)?,
+            StringMode::Value => self.write_value(string, out)?,
+        };
+        if url.is_some() {
+            self.style.end_link(out)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the JSON object or array embedded in the raw string `s`, returns `false` (and
+    /// writes nothing) if `s` doesn't contain JSON.
+    fn write_embedded(&self, s: &str, out: &mut impl Write) -> FormatResult<bool> {
+        if !s.starts_with("\"{") && !s.starts_with("\"[") {
+            return Ok(false);
+        }
+        let json = crate::value::unescape(s);
+        let mut formatter = Formatter::new_styled(json.as_bytes(), self.style.clone())
+            .with_expand_embedded(true)
+            .with_split_strings(self.split_strings)
+            .with_reveal_invisible(self.reveal_invisible)
+            .with_expand_empty(self.expand_empty)
+            .with_hyperlinks(self.hyperlinks)
+            .with_compact_single(self.compact_single)
+            .with_line_ending(self.line_ending);
+        formatter.level = self.level;
+        let mut embedded = String::new();
+        if formatter.format(&mut embedded).is_err() {
+            return Ok(false);
         }
+        self.write_styled(Style::Comment, "/* json */", out)?;
+        out.write_char(' ')?;
+        out.write_str(&embedded)?;
+        Ok(true)
     }
 
-    fn slice_str_unchecked(&self, start: BytePos, end: BytePos) -> &str {
-        debug_assert!(start.0 <= end.0 && end.0 <= self.input.len());
-        let bytes = &self.input[start.0..end.0];
-        unsafe { std::str::from_utf8_unchecked(bytes) }
+    /// Writes the annotations of the member value from `value_start` to the current position, as
+    /// dimmed comments. Objects and arrays are not annotated.
+    fn write_decorations(
+        &self,
+        key_start: BytePos,
+        key_end: BytePos,
+        value_start: BytePos,
+        out: &mut impl Write,
+    ) -> FormatResult<()> {
+        let raw = self.slice_str_unchecked(value_start, self.pos);
+        if raw.starts_with(['{', '[']) {
+            return Ok(());
+        }
+        let key = crate::value::unescape(self.slice_str_unchecked(key_start, key_end));
+        for decorator in &self.decorators {
+            if let Some(annotation) = decorator.decorate(&key, raw) {
+                out.write_str("  ")?;
+                self.write_styled(
+                    Style::Comment,
+                    &self.format_text(format_args!("/* {annotation} */")),
+                    out,
+                )?;
+            }
+        }
+        Ok(())
     }
 
-    /// String (preserves escapes)
-    fn parse_string(&mut self, out: &mut impl Write, mode: StringMode) -> FormatResult<()> {
+    /// Consumes a string, with its quotes, validating escapes and UTF-8 sequences.
+    pub(crate) fn scan_string(&mut self) -> FormatResult<()> {
         // From <https://datatracker.ietf.org/doc/html/rfc7159#section-8>
 
-        let start = self.pos;
         self.expect_byte(b'"')?;
 
-        while let Some(b) = self.peek_byte() {
-            match b {
-                b'"' => {
-                    self.next_byte();
-
-                    // Flush plain segment before exit.
-                    let string = self.slice_str_unchecked(start, self.pos);
-                    match mode {
-                        StringMode::Key => self.write_key(string, out)?,
-                        StringMode::Value => self.write_value(string, out)?,
-                    };
+        loop {
+            // Most strings are plain ASCII, without escapes: skip them in one go.
+            self.pos.0 += plain_ascii_len(&self.input[self.pos.0..]);
+            let Some(b) = self.peek_byte() else {
+                break;
+            };
+            match classify(b) {
+                Class::Other | Class::Digit => self.pos.0 += 1,
+                Class::Quote => {
+                    self.pos.0 += 1;
                     return Ok(());
                 }
-                // Escaping
-                b'\\' => {
+                Class::Escape => {
                     self.next_byte();
                     match self.next_byte() {
                         Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => {}
@@ -331,43 +1563,100 @@ impl<'input> Formatter<'input> {
                         None => return Err(FormatError::Eof),
                     }
                 }
-                0x00..=0x1F => return Err(FormatError::InvalidByte(b, self.pos)),
-                _ => {
-                    // Decode valid UTF-8 char
-                    self.next_utf8_char()?;
+                Class::Whitespace if b == b' ' => self.pos.0 += 1,
+                Class::Control | Class::Whitespace => {
+                    return Err(FormatError::InvalidByte(b, self.pos));
                 }
+                // Decode valid UTF-8 chars
+                Class::NonAscii => self.next_utf8_span()?,
             }
         }
         Err(FormatError::Eof)
     }
 
-    /// Literals
-    fn parse_true(&mut self, out: &mut impl Write) -> FormatResult<()> {
-        for &b in b"true" {
+    /// Consumes the literal `literal`.
+    pub(crate) fn scan_literal(&mut self, literal: &[u8]) -> FormatResult<()> {
+        for &b in literal {
             self.expect_byte(b)?;
         }
+        Ok(())
+    }
+
+    /// Consumes a value, of any type, without writing it.
+    pub(crate) fn skip_value(&mut self) -> FormatResult<()> {
+        let (open, close) = match self.peek_byte() {
+            Some(b'"') => return self.scan_string(),
+            Some(b'-' | b'0'..=b'9') => return self.scan_number(),
+            Some(b't') => return self.scan_literal(b"true"),
+            Some(b'f') => return self.scan_literal(b"false"),
+            Some(b'n') => return self.scan_literal(b"null"),
+            Some(b'{') => (b'{', b'}'),
+            Some(b'[') => (b'[', b']'),
+            Some(b) => return Err(FormatError::InvalidByte(b, self.pos)),
+            None => return Err(FormatError::Eof),
+        };
+        self.expect_byte(open)?;
+        self.inc_level()?;
+        let mut first = true;
+        loop {
+            self.skip_whitespace();
+            if self.peek_byte() == Some(close) {
+                self.next_byte();
+                self.dec_level();
+                return Ok(());
+            }
+            if first {
+                first = false;
+            } else {
+                self.expect_byte(b',')?;
+                self.skip_whitespace();
+            }
+            if open == b'{' {
+                self.scan_string()?;
+                self.skip_whitespace();
+                self.expect_byte(b':')?;
+                self.skip_whitespace();
+            }
+            self.skip_value()?;
+        }
+    }
+
+    /// Literals
+    fn parse_true(&mut self, out: &mut impl Write) -> FormatResult<()> {
+        self.scan_literal(b"true")?;
         self.write_true(out)?;
         Ok(())
     }
 
     fn parse_false(&mut self, out: &mut impl Write) -> FormatResult<()> {
-        for &b in b"false" {
-            self.expect_byte(b)?;
-        }
+        self.scan_literal(b"false")?;
         self.write_false(out)?;
         Ok(())
     }
 
     fn parse_null(&mut self, out: &mut impl Write) -> FormatResult<()> {
-        for &b in b"null" {
-            self.expect_byte(b)?;
-        }
+        self.scan_literal(b"null")?;
         self.write_null(out)?;
         Ok(())
     }
 
     /// Parse a JSON number.
     fn parse_number(&mut self, out: &mut impl Write) -> FormatResult<()> {
+        let start = self.pos;
+        self.scan_number()?;
+        if self.warnings.is_some() {
+            self.check_number(start, self.pos);
+        }
+
+        // Finally, write numbers
+        let digits = self.slice_str_unchecked(start, self.pos);
+        self.write_number(digits, out)?;
+
+        Ok(())
+    }
+
+    /// Consumes a number.
+    pub(crate) fn scan_number(&mut self) -> FormatResult<()> {
         // From the spec <https://datatracker.ietf.org/doc/html/rfc7159#section-6>:
         //
         // number = [ minus ] int [ frac ] [ exp ]
@@ -381,21 +1670,13 @@ impl<'input> Formatter<'input> {
         // plus = %x2B                ; +
         // zero = %x30                ; 0
 
-        let start = self.pos;
-
         if self.peek_byte() == Some(b'-') {
             self.next_byte();
         }
 
         self.parse_integer()?;
         self.parse_fraction()?;
-        self.parse_exponent()?;
-
-        // Finally, write numbers
-        let digits = self.slice_str_unchecked(start, self.pos);
-        self.write_number(digits, out)?;
-
-        Ok(())
+        self.parse_exponent()
     }
 
     fn parse_integer(&mut self) -> FormatResult<()> {
@@ -458,64 +1739,29 @@ impl<'input> Formatter<'input> {
             _ => Ok(()),
         }
     }
+}
 
-    // -------- UTF-8 decoder --------
-    fn next_utf8_char(&mut self) -> FormatResult<()> {
-        #[inline(always)]
-        fn cont(b: u8) -> bool {
-            (b & 0xC0) == 0x80
-        }
-
-        let start_pos = self.pos;
-
-        let b1 = self.next_byte().ok_or(FormatError::Eof)?;
-        if b1 < 0x80 {
-            return Ok(());
-        }
+const SPACES: &str = "                                                                 ";
 
-        let b2 = self.next_byte().ok_or(FormatError::Eof)?;
-        if b1 < 0xE0 {
-            return if (0xC2..=0xDF).contains(&b1) && cont(b2) {
-                Ok(())
-            } else {
-                Err(FormatError::InvalidUtf8([b1, b2, 0, 0], 2, start_pos))
-            };
-        }
+/// Methods to print on a [Write], with color, or not.
+impl<S: OutputStyle> Formatter<'_, S> {
+    fn write_indent(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
+        self.write_level_indent(self.level, out)
+    }
 
-        let b3 = self.next_byte().ok_or(FormatError::Eof)?;
-        if b1 < 0xF0 {
-            return if match b1 {
-                0xE0 => (0xA0..=0xBF).contains(&b2) && cont(b3),
-                0xED => (0x80..=0x9F).contains(&b2) && cont(b3), // no surrogates
-                0xE1..=0xEC | 0xEE..=0xEF => cont(b2) && cont(b3),
-                _ => false,
-            } {
+    fn write_level_indent(&self, level: usize, out: &mut impl Write) -> Result<(), fmt::Error> {
+        match self.indent {
+            Indent::Spaces(n) => self.write_spaces(level * n, out),
+            Indent::Tab => {
+                for _ in 0..level {
+                    out.write_char('\t')?;
+                }
                 Ok(())
-            } else {
-                Err(FormatError::InvalidUtf8([b1, b2, b3, 0], 3, self.pos))
-            };
-        }
-
-        let b4 = self.next_byte().ok_or(FormatError::Eof)?;
-        if match b1 {
-            0xF0 => (0x90..=0xBF).contains(&b2) && cont(b3) && cont(b4),
-            0xF4 => (0x80..=0x8F).contains(&b2) && cont(b3) && cont(b4),
-            0xF1..=0xF3 => cont(b2) && cont(b3) && cont(b4),
-            _ => false,
-        } {
-            Ok(())
-        } else {
-            Err(FormatError::InvalidUtf8([b1, b2, b3, b4], 4, self.pos))
+            }
         }
     }
-}
 
-const SPACES: &str = "                                                                 ";
-
-/// Methods to print on a [Write], with color, or not.
-impl<'input> Formatter<'input> {
-    fn write_indent(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        let n = self.level * 2;
+    fn write_spaces(&self, n: usize, out: &mut impl Write) -> Result<(), fmt::Error> {
         let full_chunks = n / SPACES.len();
         let remainder = n % SPACES.len();
         for _ in 0..full_chunks {
@@ -526,146 +1772,274 @@ impl<'input> Formatter<'input> {
     }
 
     #[inline]
-    fn write_ln(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        out.write_char('\n')
+    pub(crate) fn write_ln(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
+        out.write_str(self.line_ending.as_str())
+    }
+
+    /// Writes `s` with the output style.
+    #[inline]
+    fn write_styled(&self, style: Style, s: &str, out: &mut impl Write) -> Result<(), fmt::Error> {
+        self.style.write_styled(style, s, out)
+    }
+
+    /// Formats a text written by the formatter, in the arena if there is one.
+    fn format_text(&self, args: fmt::Arguments) -> Cow<'_, str> {
+        match self.arena {
+            Some(arena) => Cow::Borrowed(arena.alloc_fmt(args)),
+            None => Cow::Owned(fmt::format(args)),
+        }
+    }
+
+    /// Returns the style of the current scalar value, `default` if this value has no semantic type.
+    #[inline]
+    fn value_style(&self, default: Style) -> Style {
+        match self.field_type {
+            Some(field_type) => Style::Type(field_type),
+            None => default,
+        }
     }
 
     #[inline]
     fn write_empty_obj(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[1;39m{}\x1b[0m")
-        } else {
-            out.write_str("{}")
+        if self.expand_empty {
+            self.write_begin_obj(out)?;
+            self.write_indent(out)?;
+            return self.write_end_obj(out);
         }
+        self.write_styled(Style::Punctuation, "{}", out)
     }
 
     #[inline]
     fn write_begin_obj(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[1;39m{\x1b[0m\n")
-        } else {
-            out.write_str("{\n")
-        }
+        self.write_styled(Style::Punctuation, "{", out)?;
+        self.write_ln(out)
     }
 
     #[inline]
     fn write_end_obj(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[1;39m}\x1b[0m")
-        } else {
-            out.write_char('}')
-        }
+        self.write_styled(Style::Punctuation, "}", out)
     }
 
     #[inline]
     fn write_value_sep(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[1;39m,\x1b[0m\n")
-        } else {
-            out.write_str(",\n")
-        }
+        self.write_styled(Style::Punctuation, ",", out)?;
+        self.write_ln(out)
     }
 
     #[inline]
     fn write_name_sep(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[1;39m:\x1b[0m ")
-        } else {
-            out.write_str(": ")
-        }
+        self.write_styled(Style::Punctuation, ":", out)?;
+        out.write_char(' ')
     }
 
     #[inline]
     fn write_empty_arr(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[1;39m[]\x1b[0m")
-        } else {
-            out.write_str("[]")
+        if self.expand_empty {
+            self.write_begin_arr(out)?;
+            self.write_indent(out)?;
+            return self.write_end_arr(out);
         }
+        self.write_styled(Style::Punctuation, "[]", out)
     }
 
     #[inline]
     fn write_begin_arr(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[1;39m[\x1b[0m\n")
-        } else {
-            out.write_str("[\n")
-        }
+        self.write_styled(Style::Punctuation, "[", out)?;
+        self.write_ln(out)
     }
 
     #[inline]
     fn write_end_arr(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[1;39m]\x1b[0m")
-        } else {
-            out.write_str("]")
-        }
+        self.write_styled(Style::Punctuation, "]", out)
     }
 
     #[inline]
     fn write_key(&self, s: &str, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[1;34m")?;
-            out.write_str(s)?;
-            out.write_str("\x1b[0m")
-        } else {
-            out.write_str(s)
-        }
+        self.write_string(Style::Key, s, out)
     }
 
     #[inline]
     fn write_value(&self, s: &str, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[0;32m")?;
-            out.write_str(s)?;
-            out.write_str("\x1b[0m")
-        } else {
-            out.write_str(s)
+        self.write_string(self.value_style(Style::String), s, out)
+    }
+
+    /// Writes a raw string, or a part of a raw string, with `style`. Invisible chars are revealed
+    /// as escapes, if enabled.
+    fn write_string(&self, style: Style, s: &str, out: &mut impl Write) -> Result<(), fmt::Error> {
+        if !self.reveal_invisible || s.is_ascii() {
+            return self.write_string_parts(style, s, out);
         }
+        let mut start = 0;
+        for (i, c) in s.char_indices().filter(|(_, c)| warning::is_invisible(*c)) {
+            if start < i {
+                self.write_string_parts(style, &s[start..i], out)?;
+            }
+            let mut units = [0; 2];
+            let escape = c
+                .encode_utf16(&mut units)
+                .iter()
+                .map(|unit| format!("\\u{unit:04x}"))
+                .collect::<String>();
+            self.write_styled(Style::Escape, &escape, out)?;
+            start = i + c.len_utf8();
+        }
+        if start < s.len() {
+            self.write_string_parts(style, &s[start..], out)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a raw string, or a part of a raw string, with `style`. With split strings, quotes
+    /// and escape sequences are written with their own styles.
+    fn write_string_parts(
+        &self,
+        style: Style,
+        s: &str,
+        out: &mut impl Write,
+    ) -> Result<(), fmt::Error> {
+        if !self.split_strings {
+            return self.write_styled(style, s, out);
+        }
+        let bytes = s.as_bytes();
+        let mut start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            // Quotes inside strings are escaped, so unescaped quotes are delimiters.
+            let (len, part_style) = match bytes[i] {
+                b'"' => (1, Style::Quote),
+                b'\\' if bytes.get(i + 1) == Some(&b'u') => (6, Style::Escape),
+                b'\\' => (2, Style::Escape),
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            };
+            if start < i {
+                self.write_styled(style, &s[start..i], out)?;
+            }
+            let end = (i + len).min(bytes.len());
+            self.write_styled(part_style, &s[i..end], out)?;
+            i = end;
+            start = end;
+        }
+        if start < bytes.len() {
+            self.write_styled(style, &s[start..], out)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a raw string value, breaking lines at `\n` escapes (`\r\n` escapes are considered
+    /// as one line break).
+    fn write_multiline_value(&self, s: &str, out: &mut impl Write) -> Result<(), fmt::Error> {
+        let style = self.value_style(Style::String);
+        let bytes = s.as_bytes();
+        let mut start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] != b'\\' {
+                i += 1;
+                continue;
+            }
+            let end = i;
+            if s[i..].starts_with("\\r\\n") {
+                i += 4;
+            } else if s[i..].starts_with("\\n") {
+                i += 2;
+            } else {
+                // Skip the escaped char, so `\\n` is not a line break.
+                i += 2;
+                continue;
+            }
+            self.write_string(style, &s[start..end], out)?;
+            self.write_styled(Style::Comment, "⏎", out)?;
+            self.write_ln(out)?;
+            self.write_level_indent(self.level + 1, out)?;
+            start = i;
+        }
+        self.write_string(style, &s[start..], out)
     }
 
     #[inline]
     fn write_true(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[0;33mtrue\x1b[0m")
-        } else {
-            out.write_str("true")
-        }
+        self.write_styled(self.value_style(Style::Boolean), "true", out)
     }
 
     #[inline]
     fn write_false(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[0;33mfalse\x1b[0m")
-        } else {
-            out.write_str("false")
-        }
+        self.write_styled(self.value_style(Style::Boolean), "false", out)
     }
 
     #[inline]
     fn write_null(&self, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[0;35mnull\x1b[0m")
-        } else {
-            out.write_str("null")
-        }
+        self.write_styled(self.value_style(Style::Null), "null", out)
     }
 
     #[inline]
     fn write_number(&self, s: &str, out: &mut impl Write) -> Result<(), fmt::Error> {
-        if self.color == Color::AnsiCode {
-            out.write_str("\x1b[0;36m")?;
-            out.write_str(s)?;
-            out.write_str("\x1b[0m")
-        } else {
-            out.write_str(s)
+        self.write_styled(self.value_style(Style::Number), s, out)
+    }
+}
+
+/// Positions of a GeoJSON coordinates array.
+struct Coordinates {
+    points: usize,
+    /// Depth of the positions (1 for a single position, 3 for a polygon).
+    depth: usize,
+    /// Min x, min y, max x and max y.
+    bbox: [f64; 4],
+}
+
+/// Formats a number with `,` thousands separators.
+fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
         }
+        grouped.push(c);
     }
+    grouped
+}
+
+/// Returns the URL of a JSON string (with its quotes), if it's an `http` or `https` URL without
+/// whitespace or control chars.
+fn url(string: &str) -> Option<String> {
+    let url = crate::value::unescape(string);
+    let rest = url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"));
+    let is_url = rest.is_some_and(|rest| {
+        !rest.is_empty() && !rest.chars().any(|c| c.is_whitespace() || c.is_control())
+    });
+    is_url.then_some(url)
+}
+
+/// Returns `true` if `name` is an Avro primitive or complex type, or a full name (with a
+/// namespace) of a named type.
+fn is_avro_type(name: &str) -> bool {
+    const TYPES: [&str; 9] = [
+        "boolean", "int", "long", "float", "double", "bytes", "string", "array", "map",
+    ];
+    if TYPES.contains(&name) {
+        return true;
+    }
+    let mut parts = name.split('.');
+    name.contains('.')
+        && parts.all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::format::{BytePos, Color, Formatter};
+    use crate::arena::Arena;
+    use crate::decorate::Humanizer;
+    use crate::format::{
+        BytePos, Color, ErrorKind, Folds, FormatError, Formatter, FormatterOptions, HtmlStyle,
+        Indent, Limit, LineEnding,
+    };
 
     #[test]
     fn parse_number_ok() {
@@ -709,7 +2083,7 @@ mod tests {
 
     fn assert_against_std(bytes: &[u8], len: usize) {
         // We pass the full buffer to the parser, with some trailing bytes
-        let mut formatter = Formatter::new(&bytes, Color::NoColor);
+        let mut formatter = Formatter::new(bytes, Color::NoColor);
         let ret = formatter.next_utf8_char();
 
         // We test against a buffer without trailing
@@ -777,6 +2151,490 @@ mod tests {
         }
     }
 
+    #[test]
+    fn format_with_report() {
+        let input = br#"{"a": [1, "x", null], "b": {}, "c": true}"#;
+        let mut formatter = Formatter::new(input, Color::NoColor);
+        let mut output = String::new();
+        let report = formatter.format_with_report(&mut output).unwrap();
+        assert_eq!(report.input_bytes, input.len());
+        assert_eq!(report.output_bytes, output.len());
+        assert_eq!(
+            (report.objects, report.arrays, report.keys, report.commas),
+            (2, 1, 3, 4)
+        );
+        assert_eq!((report.strings, report.numbers, report.literals), (1, 1, 2));
+        assert_eq!(report.max_depth, 2);
+        assert_eq!(report.tokens(), 20);
+    }
+
+    #[test]
+    fn format_with_decorators() {
+        let input = br#"{"size_bytes": 1536000, "ratio": "0.5", "elapsed_ms": [1]}"#;
+        let humanizers = Humanizer::defaults();
+        let mut formatter = Formatter::new(input, Color::NoColor);
+        for humanizer in &humanizers {
+            formatter = formatter.with_decorator(humanizer);
+        }
+        let mut out = String::new();
+        formatter.format(&mut out).unwrap();
+        assert_eq!(
+            out,
+            r#"{
+  "size_bytes": 1536000  /* 1.5 MB */,
+  "ratio": "0.5",
+  "elapsed_ms": [
+    1
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn format_avro_unions() {
+        let input = br#"{"id": {"long": 5}, "tags": {"array": [{"string": "a"}]}, "n": null, "o": {"x": 1}, "u": {"com.acme.User": {"name": {"string": "bob"}}}}"#;
+        let mut formatter = Formatter::new(input, Color::NoColor).with_avro_unions(true);
+        let mut out = String::new();
+        formatter.format(&mut out).unwrap();
+        assert_eq!(
+            out,
+            r#"{
+  "id": 5  /* long */,
+  "tags": [
+    "a"  /* string */
+  ]  /* array */,
+  "n": null,
+  "o": {
+    "x": 1
+  },
+  "u": {
+    "name": "bob"  /* string */
+  }  /* com.acme.User */
+}"#
+        );
+    }
+
+    #[test]
+    fn format_geojson() {
+        let input = br#"{"type": "Polygon", "coordinates": [[[1, 2], [3.5, -4], [1, 2]]], "point": {"coordinates": [1, 2]}, "bad": {"coordinates": [["a"]]}}"#;
+        let mut formatter = Formatter::new(input, Color::NoColor).with_geojson(true);
+        let mut out = String::new();
+        formatter.format(&mut out).unwrap();
+        assert_eq!(
+            out,
+            r#"{
+  "type": "Polygon",
+  "coordinates": [[…] 3 points]  /* bbox [1, -4, 3.5, 2] */,
+  "point": {
+    "coordinates": [
+      1,
+      2
+    ]
+  },
+  "bad": {
+    "coordinates": [
+      [
+        "a"
+      ]
+    ]
+  }
+}"#
+        );
+        assert_eq!(super::group_thousands(12408), "12,408");
+        assert_eq!(super::group_thousands(1234567), "1,234,567");
+        assert_eq!(super::group_thousands(999), "999");
+    }
+
+    #[test]
+    fn format_folds() {
+        let input = br#"{"paths": {"/pets": {"get": {"responses": {"200": {}, "404": {}}}}}, "components": {"schemas": {"Pet": {"type": "object"}, "Tags": [], "Bad": [1,]}}}"#;
+        let mut folds = Folds::default();
+        folds.fold(".components.schemas.*");
+        folds.fold(".paths[*].*.responses");
+        let mut formatter = Formatter::new(input, Color::NoColor).with_folds(&folds);
+        let mut out = String::new();
+        let err = formatter.format(&mut out).unwrap_err();
+        assert!(matches!(err, FormatError::InvalidByte(b']', _)));
+
+        let input = br#"{"paths": {"/pets": {"get": {"responses": {"200": {}, "404": {}}}}}, "components": {"schemas": {"Pet": {"type": "object"}, "Tags": []}}}"#;
+        let mut formatter = Formatter::new(input, Color::NoColor).with_folds(&folds);
+        let mut out = String::new();
+        formatter.format(&mut out).unwrap();
+        assert_eq!(
+            out,
+            r#"{
+  "paths": {
+    "/pets": {
+      "get": {
+        "responses": {…}  /* 2 members, 22 B */
+      }
+    }
+  },
+  "components": {
+    "schemas": {
+      "Pet": {…}  /* 1 member, 18 B */,
+      "Tags": []
+    }
+  }
+}"#
+        );
+    }
+
+    #[test]
+    fn format_with_arena() {
+        let input = br#"{"schemas": {"Pet": {"type": "object"}}}"#;
+        let mut folds = Folds::default();
+        folds.fold(".schemas.*");
+        let mut arena = Arena::default();
+        for _ in 0..2 {
+            arena.reset();
+            let mut formatter = Formatter::new(input, Color::NoColor)
+                .with_folds(&folds)
+                .with_arena(&arena);
+            let mut out = String::new();
+            formatter.format(&mut out).unwrap();
+            assert_eq!(
+                out,
+                "{\n  \"schemas\": {\n    \"Pet\": {…}  /* 1 member, 18 B */\n  }\n}"
+            );
+        }
+        assert!(arena.capacity() > 0);
+    }
+
+    #[test]
+    fn reuse_formatter_and_buffer() {
+        let mut formatter = Formatter::new(b"[1, 2]", Color::NoColor);
+        let mut out = String::new();
+        formatter.format_into(&mut out).unwrap();
+        assert_eq!(out, "[\n  1,\n  2\n]");
+
+        formatter.reset(b"{\"a\": 3");
+        assert!(formatter.format_into(&mut out).is_err());
+        formatter.reset(b"{\"a\": 3}");
+        formatter.format_into(&mut out).unwrap();
+        assert_eq!(out, "{\n  \"a\": 3\n}");
+
+        // A formatter that failed within a single-line value is reusable.
+        let mut formatter = Formatter::new(br#"{"a":[1]}"#, Color::NoColor)
+            .with_compact_single(true)
+            .with_max_output_size(8);
+        assert!(matches!(
+            formatter.format_into(&mut out),
+            Err(FormatError::OutputTooLarge(8))
+        ));
+        formatter.reset(br#"{"a":[1,2],"b":{"c":3}}"#);
+        let mut formatter = formatter.with_max_output_size(100);
+        formatter.format_into(&mut out).unwrap();
+        assert_eq!(
+            out,
+            "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {\"c\": 3}\n}"
+        );
+    }
+
+    #[test]
+    fn detect_formatted_input() {
+        let formatted = b"{\n  \"a\": [\n    1\n  ]\n}";
+        assert!(Formatter::new(formatted, Color::NoColor).formats_to(formatted));
+        assert!(Formatter::new(b"{\"a\":[1]}", Color::NoColor).formats_to(formatted));
+        assert!(!Formatter::new(b"{\"a\":[1, 2]}", Color::NoColor).formats_to(formatted));
+        assert!(!Formatter::new(b"{\"a\":[1]", Color::NoColor).formats_to(formatted));
+        assert!(!Formatter::new(formatted, Color::NoColor).formats_to(b"{\n  \"a\": ["));
+    }
+
+    #[test]
+    fn format_with_size_limits() {
+        let input = br#"{"a": [1, 2]}"#;
+        let mut out = String::new();
+        let err = Formatter::new(input, Color::NoColor)
+            .with_max_input_size(12)
+            .format(&mut out)
+            .unwrap_err();
+        assert!(matches!(err, FormatError::InputTooLarge(13, 12)));
+
+        let err = Formatter::new(input, Color::NoColor)
+            .with_max_output_size(20)
+            .format(&mut out)
+            .unwrap_err();
+        assert!(matches!(err, FormatError::OutputTooLarge(20)));
+        assert!(out.len() <= 20);
+
+        out.clear();
+        Formatter::new(input, Color::NoColor)
+            .with_max_input_size(13)
+            .with_max_output_size(29)
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(out.len(), 29);
+    }
+
+    #[test]
+    fn format_with_options() {
+        let input = br#"{"a": [1, 2]}"#;
+        let options = FormatterOptions {
+            compact_single: true,
+            indent: Indent::Tab,
+            max_input_size: Some(12),
+            ..FormatterOptions::default()
+        };
+        let mut out = String::new();
+        let err = Formatter::new(input, Color::NoColor)
+            .with_options(&options)
+            .format(&mut out)
+            .unwrap_err();
+        assert!(matches!(err, FormatError::InputTooLarge(13, 12)));
+
+        let options = FormatterOptions {
+            max_input_size: None,
+            max_output_size: Some(20),
+            ..options
+        };
+        let err = Formatter::new(input, Color::NoColor)
+            .with_options(&options)
+            .format(&mut out)
+            .unwrap_err();
+        assert!(matches!(err, FormatError::OutputTooLarge(20)));
+
+        out.clear();
+        Formatter::new(input, Color::NoColor)
+            .with_options(&FormatterOptions {
+                max_output_size: None,
+                ..options
+            })
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(out, "{\n\t\"a\": [\n\t\t1,\n\t\t2\n\t]\n}");
+    }
+
+    #[test]
+    fn format_with_limits() {
+        let input = br#"{"key": ["abc", 1, null]}"#;
+        let format = |mut formatter: Formatter| formatter.format(&mut String::new());
+        let formatter = || Formatter::new(input, Color::NoColor);
+        assert!(format(formatter().with_max_elements(5)).is_ok());
+        assert!(matches!(
+            format(formatter().with_max_elements(4)),
+            Err(FormatError::LimitExceeded(Limit::Elements(4), BytePos(19)))
+        ));
+        assert!(format(formatter().with_max_key_length(3)).is_ok());
+        assert!(matches!(
+            format(formatter().with_max_key_length(2)),
+            Err(FormatError::LimitExceeded(Limit::KeyLength(2), BytePos(1)))
+        ));
+        assert!(format(formatter().with_max_string_length(3)).is_ok());
+        assert!(matches!(
+            format(formatter().with_max_string_length(2)),
+            Err(FormatError::LimitExceeded(
+                Limit::StringLength(2),
+                BytePos(9)
+            ))
+        ));
+    }
+
+    #[test]
+    fn error_accessors() {
+        let input = "{\n  \"é\": tru\n}".as_bytes();
+        let err = Formatter::new(input, Color::NoColor)
+            .format(&mut String::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Syntax);
+        assert_eq!(err.offset(), Some(13));
+        assert_eq!(err.line_col(input), Some((2, 11)));
+
+        let input = b"[1,\n2";
+        let err = Formatter::new(input, Color::NoColor)
+            .format(&mut String::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(err.offset(), None);
+        assert_eq!(err.line_col(input), Some((2, 2)));
+    }
+
+    #[test]
+    fn format_partial_output() {
+        let mut formatter = Formatter::new(br#"{"a": [1, 2], "b": tru}"#, Color::NoColor);
+        let partial = formatter.format_partial().unwrap_err();
+        assert_eq!(
+            partial.output,
+            "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": "
+        );
+        assert_eq!(partial.error.offset(), Some(22));
+
+        let mut formatter = Formatter::new(b"[]", Color::NoColor);
+        assert_eq!(formatter.format_partial().unwrap(), "[]");
+    }
+
+    #[test]
+    fn format_with_crlf() {
+        let input = b"{\"a\": [1], /* two\r\n lines */ \"b\": {}}";
+        let mut formatter = Formatter::new(input, Color::NoColor)
+            .with_comments(true)
+            .with_line_ending(LineEnding::Crlf);
+        let mut out = String::new();
+        formatter.format(&mut out).unwrap();
+        assert_eq!(
+            out,
+            "{\r\n  \"a\": [\r\n    1\r\n  ], /* two\r\n lines */\r\n  \"b\": {}\r\n}"
+        );
+        assert!("cr".parse::<LineEnding>().is_err());
+    }
+
+    #[test]
+    fn format_with_indent() {
+        let input = br#"{"a": [1], "b": "x\ny"}"#;
+        let format = |indent: &str| {
+            let mut out = String::new();
+            Formatter::new(input, Color::NoColor)
+                .with_indent(indent.parse::<Indent>().unwrap())
+                .with_render_newlines(true)
+                .format(&mut out)
+                .unwrap();
+            out
+        };
+        assert_eq!(
+            format("4"),
+            "{\n    \"a\": [\n        1\n    ],\n    \"b\": \"x⏎\n        y\"\n}"
+        );
+        assert_eq!(
+            format("tab"),
+            "{\n\t\"a\": [\n\t\t1\n\t],\n\t\"b\": \"x⏎\n\t\ty\"\n}"
+        );
+        assert_eq!(format("0"), "{\n\"a\": [\n1\n],\n\"b\": \"x⏎\ny\"\n}");
+        assert!("17".parse::<Indent>().is_err());
+    }
+
+    #[test]
+    fn format_deep_single_members() {
+        // Each level is checked once: this used to take exponential time.
+        let input = format!("{}1{}", "[".repeat(90), "]".repeat(90));
+        let mut out = String::new();
+        Formatter::new(input.as_bytes(), Color::NoColor)
+            .with_compact_single(true)
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(out, input);
+
+        let input = format!("{}[1, 2]{}", r#"{"a": "#.repeat(90), "}".repeat(90));
+        let mut out = String::new();
+        Formatter::new(input.as_bytes(), Color::NoColor)
+            .with_compact_single(true)
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(out.lines().count(), 2 * 90 + 4);
+    }
+
+    #[test]
+    fn format_with_empty_and_single_members() {
+        let input = br#"{"a": {}, "b": [[]], "c": {"id": [1]}, "d": [1, {"e": null}]}"#;
+        let format = |expand_empty: bool, compact_single: bool| {
+            let mut out = String::new();
+            Formatter::new(input, Color::NoColor)
+                .with_expand_empty(expand_empty)
+                .with_compact_single(compact_single)
+                .format(&mut out)
+                .unwrap();
+            out
+        };
+        assert_eq!(
+            format(true, false),
+            r#"{
+  "a": {
+  },
+  "b": [
+    [
+    ]
+  ],
+  "c": {
+    "id": [
+      1
+    ]
+  },
+  "d": [
+    1,
+    {
+      "e": null
+    }
+  ]
+}"#
+        );
+        assert_eq!(
+            format(false, true),
+            r#"{
+  "a": {},
+  "b": [[]],
+  "c": {"id": [1]},
+  "d": [
+    1,
+    {"e": null}
+  ]
+}"#
+        );
+        assert_eq!(
+            format(true, true),
+            r#"{
+  "a": {
+  },
+  "b": [
+    [
+    ]
+  ],
+  "c": {"id": [1]},
+  "d": [
+    1,
+    {"e": null}
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn format_with_split_strings() {
+        let format = |input: &[u8], render_newlines: bool| {
+            let mut out = String::new();
+            Formatter::new_styled(input, HtmlStyle)
+                .with_split_strings(true)
+                .with_render_newlines(render_newlines)
+                .format(&mut out)
+                .unwrap();
+            out.replace("<span class=\"json-", "<")
+                .replace("</span>", ">")
+        };
+        assert_eq!(
+            format(br#"{"a\tb": "x\"\u00e9\n", "c": ""}"#, false),
+            r#"<punctuation">{>
+  <quote">&quot;><key">a><escape">\t><key">b><quote">&quot;><punctuation">:> <quote">&quot;><string">x><escape">\&quot;><escape">\u00e9><escape">\n><quote">&quot;><punctuation">,>
+  <quote">&quot;><key">c><quote">&quot;><punctuation">:> <quote">&quot;><quote">&quot;>
+<punctuation">}>"#
+        );
+        assert_eq!(
+            format(br#"["x\ny"]"#, true),
+            r#"<punctuation">[>
+  <quote">&quot;><string">x><comment">⏎>
+    <string">y><quote">&quot;>
+<punctuation">]>"#
+        );
+    }
+
+    #[test]
+    fn format_with_hyperlinks() {
+        let input = br#"{"https://a.com": "https://a.com/x?q=\u0031", "b": ["http://b", "http:// c", "ftp://d"]}"#;
+        let mut out = String::new();
+        Formatter::new(input, Color::NoColor)
+            .with_hyperlinks(true)
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(
+            out,
+            "{
+  \"https://a.com\": \x1b]8;;https://a.com/x?q=1\x1b\\\"https://a.com/x?q=\\u0031\"\x1b]8;;\x1b\\,
+  \"b\": [
+    \x1b]8;;http://b\x1b\\\"http://b\"\x1b]8;;\x1b\\,
+    \"http:// c\",
+    \"ftp://d\"
+  ]
+}"
+        );
+    }
+
     #[test]
     fn format_demo_string() {
         let input = r#"{"strings":{"english":"Hello, world!","chinese":"你好，世界","japanese":"こんにちは世界","korean":"안녕하세요 세계","arabic":"مرحبا بالعالم","hindi":"नमस्ते दुनिया","russian":"Привет, мир","greek":"Γειά σου Κόσμε","hebrew":"שלום עולם","accented":"Curaçao, naïve, façade, jalapeño"},"numbers":{"zero":0,"positive_int":42,"negative_int":-42,"large_int":1234567890123456789,"small_float":0.000123,"negative_float":-3.14159,"large_float":1.7976931348623157e308,"smallest_float":5e-324,"sci_notation_positive":6.022e23,"sci_notation_negative":-2.99792458e8},"booleans":{"isActive":true,"isDeleted":false},"emojis":{"happy":"😀","sad":"😢","fire":"🔥","rocket":"🚀","earth":"🌍","heart":"❤️","multi":"👩‍💻🧑🏽‍🚀👨‍👩‍👧‍👦"},"nothing":null}"#;
@@ -0,0 +1,111 @@
+//! Folding of objects and arrays to one-line summaries.
+use crate::path::wildcard_match;
+
+/// Paths of the objects and arrays to display as one-line summaries.
+///
+/// Paths are written like in `--types` (`.paths["/users"].get`), where `*` matches any sequence
+/// of chars. An expanded path is never folded, nor are its descendants or its ancestors.
+#[derive(Clone, Debug, Default)]
+pub struct Folds {
+    folds: Vec<String>,
+    expands: Vec<String>,
+}
+
+impl Folds {
+    /// Folds the values matching `pattern`.
+    pub fn fold(&mut self, pattern: &str) {
+        self.folds.push(pattern.to_string());
+    }
+
+    /// Expands the values matching `pattern`, with their ancestors and descendants.
+    pub fn expand(&mut self, pattern: &str) {
+        self.expands.push(pattern.to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.folds.is_empty()
+    }
+
+    /// Returns `true` if the value at `path` must be folded.
+    pub fn is_folded(&self, path: &str) -> bool {
+        if !self.folds.iter().any(|fold| wildcard_match(fold, path)) {
+            return false;
+        }
+        !self.expands.iter().any(|expand| {
+            // The path or one of its ancestors is expanded...
+            prefixes(path).any(|prefix| wildcard_match(expand, prefix))
+            // ... or one of its descendants.
+                || prefixes(expand).any(|prefix| wildcard_match(prefix, path))
+        })
+    }
+}
+
+/// Returns the prefixes of a path that are paths themselves, ending with the path itself:
+/// `.a`, `.a["b.c"]`, `.a["b.c"][0]`.
+fn prefixes(path: &str) -> impl Iterator<Item = &str> {
+    let mut in_string = false;
+    let mut escaped = false;
+    let ends = path.char_indices().filter_map(move |(i, c)| {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            return None;
+        }
+        match c {
+            '"' => in_string = true,
+            '.' | '[' if i > 0 => return Some(i),
+            _ => {}
+        }
+        None
+    });
+    ends.chain(std::iter::once(path.len()))
+        .map(move |end| &path[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Folds, prefixes};
+
+    #[test]
+    fn split_path_prefixes() {
+        let path = r#".a["b.c\"["][0].d"#;
+        assert_eq!(
+            prefixes(path).collect::<Vec<_>>(),
+            vec![".a", r#".a["b.c\"["]"#, r#".a["b.c\"["][0]"#, path]
+        );
+    }
+
+    #[test]
+    fn fold_and_expand_paths() {
+        let mut folds = Folds::default();
+        folds.fold(".components.schemas.*");
+        folds.fold(".paths[*].*.responses");
+        folds.expand(".components.schemas.User");
+        folds.expand(r#".paths["/pets"].get.responses["200"]"#);
+
+        assert!(folds.is_folded(".components.schemas.Pet"));
+        assert!(folds.is_folded(r#".paths["/users"].get.responses"#));
+        assert!(!folds.is_folded(".components"));
+        assert!(!folds.is_folded(".components.schemas.User"));
+        assert!(!folds.is_folded(".components.schemas.User.properties"));
+        assert!(!folds.is_folded(r#".paths["/pets"].get.responses"#));
+        assert!(folds.is_folded(r#".paths["/pets"].post.responses"#));
+    }
+
+    #[test]
+    fn collapse_all_but_expanded_paths() {
+        let mut folds = Folds::default();
+        folds.fold(".*");
+        folds.expand(".data.items");
+
+        assert!(!folds.is_folded(".data"));
+        assert!(!folds.is_folded(".data.items"));
+        assert!(!folds.is_folded(".data.items[0].tags"));
+        assert!(folds.is_folded(".data.cursor"));
+        assert!(folds.is_folded(".metadata"));
+    }
+}
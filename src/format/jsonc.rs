@@ -0,0 +1,264 @@
+//! JSON with comments (JSONC) formatting.
+//!
+//! Comments (`// line` and `/* block */`) are re-emitted attached to the nearest member: a comment
+//! on the same line as a value stays at the end of this line, other comments are written on their
+//! own lines, indented like the member that follows them. Trailing commas are accepted and removed.
+use super::{BytePos, FormatError, FormatResult, Formatter, OutputStyle, StringMode, Style};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// A comment in the input, with its delimiters.
+struct Comment {
+    start: BytePos,
+    end: BytePos,
+    /// `true` if there is no newline between the previous token and this comment.
+    same_line: bool,
+}
+
+impl<S: OutputStyle> Formatter<'_, S> {
+    /// Formats and colorize the JSONC input bytes.
+    pub(super) fn format_jsonc(&mut self, out: &mut impl Write) -> FormatResult<()> {
+        self.skip_start_bom();
+
+        let comments = self.scan_comments()?;
+        for comment in &comments {
+            self.write_comment(comment, out)?;
+            self.write_ln(out)?;
+        }
+        self.parse_value(out)?;
+        let comments = self.scan_comments()?;
+        for comment in &comments {
+            if comment.same_line {
+                out.write_char(' ')?;
+            } else {
+                self.write_ln(out)?;
+            }
+            self.write_comment(comment, out)?;
+        }
+
+        // Have we completely consumed our payload?
+        if let Some(b) = self.peek_byte() {
+            Err(FormatError::InvalidByte(b, self.pos))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Object, with comments.
+    pub(super) fn parse_object_jsonc(&mut self, out: &mut impl Write) -> FormatResult<()> {
+        self.expect_byte(b'{')?;
+
+        let mut comments = self.scan_comments()?;
+        if self.peek_byte() == Some(b'}') && comments.is_empty() {
+            self.next_byte();
+            self.write_empty_obj(out)?;
+            return Ok(());
+        }
+
+        self.write_styled(Style::Punctuation, "{", out)?;
+        self.inc_level()?;
+
+        let mut keys = self.warnings.is_some().then(HashSet::new);
+        loop {
+            self.write_comments_break(&comments, out)?;
+            if self.peek_byte() == Some(b'}') {
+                self.next_byte();
+                self.dec_level();
+                self.write_indent(out)?;
+                self.write_end_obj(out)?;
+                return Ok(());
+            }
+
+            // Parse key
+            self.write_indent(out)?;
+            let key_start = self.pos;
+            self.parse_string(out, StringMode::Key)?;
+            let key_end = self.pos;
+            if let Some(keys) = &mut keys {
+                self.check_duplicate_key(keys, key_start, key_end);
+            }
+            self.report.keys += 1;
+            let path_len = self.path.len();
+            if self.tracks_path() {
+                self.push_key_path(key_start);
+                self.field_type = self.types.and_then(|types| types.get(&self.path));
+            }
+            self.visit_key(key_start, key_end);
+
+            // Parse colon
+            let inline = self.scan_comments()?;
+            if !inline.is_empty() {
+                out.write_char(' ')?;
+            }
+            self.write_inline_comments(&inline, out)?;
+            self.expect_byte(b':')?;
+            self.write_name_sep(out)?;
+
+            // Parse value
+            let inline = self.scan_comments()?;
+            self.write_inline_comments(&inline, out)?;
+            let value_start = self.pos;
+            self.parse_value(out)?;
+            if !self.decorators.is_empty() {
+                self.write_decorations(key_start, key_end, value_start, out)?;
+            }
+            self.path.truncate(path_len);
+            self.field_type = None;
+
+            comments = self.parse_separator(b'}', out)?;
+        }
+    }
+
+    /// Array, with comments.
+    pub(super) fn parse_array_jsonc(&mut self, out: &mut impl Write) -> FormatResult<()> {
+        self.expect_byte(b'[')?;
+
+        let mut comments = self.scan_comments()?;
+        if self.peek_byte() == Some(b']') && comments.is_empty() {
+            self.next_byte();
+            self.write_empty_arr(out)?;
+            return Ok(());
+        }
+
+        self.write_styled(Style::Punctuation, "[", out)?;
+        self.inc_level()?;
+
+        let mut index = 0;
+        loop {
+            self.write_comments_break(&comments, out)?;
+            if self.peek_byte() == Some(b']') {
+                self.next_byte();
+                self.dec_level();
+                self.write_indent(out)?;
+                self.write_end_arr(out)?;
+                return Ok(());
+            }
+
+            self.write_indent(out)?;
+            let path_len = self.path.len();
+            if self.tracks_path() {
+                write!(self.path, "[{index}]")?;
+                self.field_type = self.types.and_then(|types| types.get(&self.path));
+            }
+            self.parse_value(out)?;
+            self.path.truncate(path_len);
+            self.field_type = None;
+            index += 1;
+
+            comments = self.parse_separator(b']', out)?;
+        }
+    }
+
+    /// Parses what follows a member or an element: comments and a value separator, or the end of
+    /// the container (`end`). Returns the comments, the value separator is written.
+    fn parse_separator(&mut self, end: u8, out: &mut impl Write) -> FormatResult<Vec<Comment>> {
+        let mut comments = self.scan_comments()?;
+        match self.peek_byte() {
+            Some(b',') => {
+                self.next_byte();
+                self.report.commas += 1;
+                let mut after = self.scan_comments()?;
+                // A trailing comma is dropped.
+                if self.peek_byte() != Some(end) {
+                    self.write_styled(Style::Punctuation, ",", out)?;
+                }
+                comments.append(&mut after);
+                Ok(comments)
+            }
+            Some(b) if b == end => Ok(comments),
+            Some(b) => Err(FormatError::InvalidByte(b, self.pos)),
+            None => Err(FormatError::Eof),
+        }
+    }
+
+    /// Skips whitespaces and returns the comments found.
+    fn scan_comments(&mut self) -> FormatResult<Vec<Comment>> {
+        let mut comments = Vec::new();
+        let mut same_line = true;
+        loop {
+            while let Some(b @ (b' ' | b'\n' | b'\r' | b'\t')) = self.peek_byte() {
+                if b == b'\n' {
+                    same_line = false;
+                }
+                self.pos.0 += 1;
+            }
+            if self.peek_byte() != Some(b'/') {
+                return Ok(comments);
+            }
+            let start = self.pos;
+            self.next_byte();
+            match self.next_byte() {
+                Some(b'/') => {
+                    while !matches!(self.peek_byte(), Some(b'\n' | b'\r') | None) {
+                        self.next_utf8_char()?;
+                    }
+                }
+                Some(b'*') => loop {
+                    match self.peek_byte() {
+                        Some(b'*') if self.input.get(self.pos.0 + 1) == Some(&b'/') => {
+                            self.pos.0 += 2;
+                            break;
+                        }
+                        Some(_) => self.next_utf8_char()?,
+                        None => return Err(FormatError::Eof),
+                    }
+                },
+                Some(b) => return Err(FormatError::InvalidByte(b, BytePos(self.pos.0 - 1))),
+                None => return Err(FormatError::Eof),
+            }
+            comments.push(Comment {
+                start,
+                end: self.pos,
+                same_line,
+            });
+        }
+    }
+
+    /// Ends the current line: comments on the same line as the previous token are written at the
+    /// end of the line, others on their own lines.
+    fn write_comments_break(&self, comments: &[Comment], out: &mut impl Write) -> FormatResult<()> {
+        for comment in comments.iter().filter(|c| c.same_line) {
+            out.write_char(' ')?;
+            self.write_comment(comment, out)?;
+        }
+        self.write_ln(out)?;
+        for comment in comments.iter().filter(|c| !c.same_line) {
+            self.write_indent(out)?;
+            self.write_comment(comment, out)?;
+            self.write_ln(out)?;
+        }
+        Ok(())
+    }
+
+    /// Writes comments found inside a member (around its colon).
+    fn write_inline_comments(
+        &self,
+        comments: &[Comment],
+        out: &mut impl Write,
+    ) -> FormatResult<()> {
+        for comment in comments {
+            self.write_comment(comment, out)?;
+            if self
+                .slice_str_unchecked(comment.start, comment.end)
+                .starts_with("//")
+            {
+                self.write_ln(out)?;
+                self.write_indent(out)?;
+            } else {
+                out.write_char(' ')?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_comment(&self, comment: &Comment, out: &mut impl Write) -> FormatResult<()> {
+        let text = self.slice_str_unchecked(comment.start, comment.end);
+        for (i, line) in text.trim_end().split('\n').enumerate() {
+            if i > 0 {
+                self.write_ln(out)?;
+            }
+            self.write_styled(Style::Comment, line.trim_end_matches('\r'), out)?;
+        }
+        Ok(())
+    }
+}
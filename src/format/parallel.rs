@@ -0,0 +1,260 @@
+//! Parallel formatting of a large top-level array.
+//!
+//! A first pass validates the input and finds the boundaries of the top-level array items, then
+//! the items are split in contiguous chunks of similar sizes, formatted concurrently in their own
+//! buffers and stitched together in order.
+use super::{BytePos, Folds, FormatError, FormatResult, Formatter, FormatterOptions, OutputStyle};
+use crate::arena::Arena;
+use crate::decorate::ValueDecorator;
+use crate::theme::TypeMap;
+use std::fmt::Write;
+use std::ops::Range;
+use std::thread;
+
+/// Minimum size of a chunk, in bytes: smaller inputs are not worth spawning threads.
+const MIN_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Top-level array items, split in chunks.
+struct Chunks {
+    /// Start positions of the items.
+    items: Vec<BytePos>,
+    /// Ranges of items of each chunk.
+    ranges: Vec<Range<usize>>,
+}
+
+impl<'input, S: OutputStyle + Send + Sync> Formatter<'input, S> {
+    /// Formats the JSON input bytes like [Formatter::format], the items of a top-level array
+    /// being formatted by up to `threads` threads.
+    ///
+    /// Other inputs (objects, small arrays) and JSONC inputs are formatted on the current thread.
+    pub fn format_parallel(&mut self, threads: usize, out: &mut String) -> FormatResult<()> {
+        if let Some(max) = self.max_input_size
+            && self.input.len() > max
+        {
+            return Err(FormatError::InputTooLarge(self.input.len(), max));
+        }
+        // Output size, values count, warnings and visitors are only handled by the sequential
+        // formatting.
+        let chunks = if self.comments
+            || threads <= 1
+            || self.max_output_size.is_some()
+            || self.max_elements.is_some()
+            || self.warnings.is_some()
+            || self.visitor.is_some()
+        {
+            None
+        } else {
+            self.split_items(threads)?
+        };
+        let Some(Chunks { items, ranges }) = chunks else {
+            return self.format(out);
+        };
+
+        let results = thread::scope(|scope| {
+            let workers = ranges
+                .iter()
+                .map(|range| {
+                    let items = &items[range.clone()];
+                    let first_index = range.start;
+                    let fork = self.fork();
+                    scope.spawn(move || fork.formatter().format_items(items, first_index))
+                })
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("formatting thread panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        self.write_begin_arr(out)?;
+        for (i, result) in results.into_iter().enumerate() {
+            if i > 0 {
+                self.write_value_sep(out)?;
+            }
+            out.push_str(&result?);
+        }
+        self.write_ln(out)?;
+        self.write_end_arr(out)?;
+        Ok(())
+    }
+
+    /// Validates the input and, if it's a top-level array large enough, returns its items split
+    /// in chunks.
+    fn split_items(&mut self, threads: usize) -> FormatResult<Option<Chunks>> {
+        let mut scanner = self.fork().formatter();
+        scanner.skip_start_bom();
+        scanner.skip_whitespace();
+        let is_array = scanner.peek_byte() == Some(b'[');
+        let start = scanner.pos;
+        let mut items = Vec::new();
+        if is_array {
+            scanner.next_byte();
+            scanner.inc_level()?;
+            loop {
+                scanner.skip_whitespace();
+                if scanner.peek_byte() == Some(b']') {
+                    scanner.next_byte();
+                    scanner.dec_level();
+                    break;
+                }
+                if !items.is_empty() {
+                    scanner.expect_byte(b',')?;
+                    scanner.skip_whitespace();
+                }
+                items.push(scanner.pos);
+                scanner.skip_value()?;
+            }
+        } else {
+            scanner.skip_value()?;
+        }
+        scanner.skip_whitespace();
+        if let Some(b) = scanner.peek_byte() {
+            return Err(FormatError::InvalidByte(b, scanner.pos));
+        }
+
+        let size = scanner.pos.0 - start.0;
+        let threads = threads.min(size / MIN_CHUNK_SIZE).min(items.len());
+        if threads <= 1 {
+            return Ok(None);
+        }
+        // Chunks of contiguous items, of about the same size in bytes.
+        let mut ranges = Vec::with_capacity(threads);
+        let mut chunk_start = 0;
+        for (i, pos) in items.iter().enumerate().skip(1) {
+            let target = start.0 + size * (ranges.len() + 1) / threads;
+            if pos.0 >= target && ranges.len() + 1 < threads {
+                ranges.push(chunk_start..i);
+                chunk_start = i;
+            }
+        }
+        ranges.push(chunk_start..items.len());
+        Ok(Some(Chunks { items, ranges }))
+    }
+
+    /// Formats the top-level array items starting at `items`, separated by value separators.
+    fn format_items(mut self, items: &[BytePos], first_index: usize) -> FormatResult<String> {
+        let mut out = String::new();
+        self.level = 1;
+        for (i, pos) in items.iter().enumerate() {
+            if i > 0 {
+                self.write_value_sep(&mut out)?;
+            }
+            self.write_indent(&mut out)?;
+            self.pos = *pos;
+            if self.tracks_path() {
+                self.path.clear();
+                write!(self.path, "[{}]", first_index + i)?;
+                self.field_type = self.types.and_then(|types| types.get(&self.path));
+            }
+            self.parse_value(&mut out)?;
+            self.field_type = None;
+        }
+        Ok(out)
+    }
+
+    /// Returns what a worker thread needs to create a formatter of the same input with the same
+    /// options. The visitor and the warnings are left out.
+    fn fork(&self) -> Fork<'input, S> {
+        Fork {
+            input: self.input,
+            style: self.style.clone(),
+            types: self.types,
+            folds: self.folds,
+            decorators: self.decorators.clone(),
+            arena: self.arena,
+            options: FormatterOptions {
+                align_values: self.align_values,
+                comments: self.comments,
+                expand_embedded: self.expand_embedded,
+                render_newlines: self.render_newlines,
+                split_strings: self.split_strings,
+                reveal_invisible: self.reveal_invisible,
+                expand_empty: self.expand_empty,
+                hyperlinks: self.hyperlinks,
+                compact_single: self.compact_single,
+                geojson: self.geojson,
+                avro_unions: self.avro_unions,
+                line_ending: self.line_ending,
+                indent: self.indent,
+                max_input_size: self.max_input_size,
+                max_output_size: self.max_output_size,
+                max_elements: None,
+                max_key_length: self.max_key_length,
+                max_string_length: self.max_string_length,
+                warnings: false,
+            },
+        }
+    }
+}
+
+/// The parts of a formatter sent to a worker thread. A formatter itself is not sent, so that
+/// visitors don't have to be [Send].
+struct Fork<'input, S> {
+    input: &'input [u8],
+    style: S,
+    types: Option<&'input TypeMap>,
+    folds: Option<&'input Folds>,
+    decorators: Vec<&'input dyn ValueDecorator>,
+    arena: Option<&'input Arena>,
+    options: FormatterOptions,
+}
+
+impl<'input, S: OutputStyle> Fork<'input, S> {
+    /// Returns a formatter of the forked input with the forked options, at the start of the input.
+    fn formatter(self) -> Formatter<'input, S> {
+        Formatter {
+            types: self.types,
+            folds: self.folds,
+            decorators: self.decorators,
+            arena: self.arena,
+            ..Formatter::new_styled(self.input, self.style)
+        }
+        .with_options(&self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{Color, FormatError, Formatter};
+    use crate::theme::{FieldType, TypeMap};
+
+    #[test]
+    fn format_in_parallel() {
+        let mut input = String::from("\u{feff} [");
+        for i in 0..5000 {
+            if i > 0 {
+                input.push(',');
+            }
+            input.push_str(&format!(
+                r#"{{"id":{i},"tags":["a","b"],"n":null,"x":{{}}}}"#
+            ));
+        }
+        input.push_str("] \n");
+        let mut types = TypeMap::default();
+        types.insert("[4999].id", FieldType::Id);
+
+        let mut expected = String::new();
+        Formatter::new(input.as_bytes(), Color::AnsiCode)
+            .with_types(&types)
+            .format(&mut expected)
+            .unwrap();
+        let mut out = String::new();
+        Formatter::new(input.as_bytes(), Color::AnsiCode)
+            .with_types(&types)
+            .format_parallel(4, &mut out)
+            .unwrap();
+        assert_eq!(out, expected);
+
+        let mut out = String::new();
+        let err = Formatter::new(br#"[1, 2] 3"#, Color::NoColor)
+            .format_parallel(4, &mut out)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "invalid byte <33> at offset 7");
+
+        let err = Formatter::new(input.as_bytes(), Color::NoColor)
+            .with_max_input_size(100)
+            .format_parallel(4, &mut out)
+            .unwrap_err();
+        assert!(matches!(err, FormatError::InputTooLarge(_, 100)));
+    }
+}
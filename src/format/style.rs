@@ -0,0 +1,236 @@
+//! Output styles: how the formatted text is decorated (ANSI colors, HTML elements...).
+//!
+//! The formatter handles the layout (indentation, line breaks) and writes each piece of text with
+//! its [Style] through an [OutputStyle].
+use super::Color;
+use crate::theme::{DEFAULT_THEME, FieldType, SgrStyle, Theme};
+use std::fmt;
+use std::fmt::Write;
+
+/// Role of a piece of formatted text.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Style {
+    /// Braces, brackets, commas and colons.
+    Punctuation,
+    /// An object key, with its quotes.
+    Key,
+    /// A string value, with its quotes.
+    String,
+    Number,
+    /// `true` and `false`.
+    Boolean,
+    Null,
+    /// Comments and annotations.
+    Comment,
+    /// The quotes of a key or a string value, when styled separately.
+    Quote,
+    /// An escape sequence in a key or a string value (`\n`, `\u00e9`), when styled separately.
+    Escape,
+    /// A scalar value with a semantic type.
+    Type(FieldType),
+}
+
+/// Writes formatted text with a given style.
+///
+/// Styles are cloned for nested formatters (embedded JSON), so they should be cheap to clone.
+pub trait OutputStyle: Clone {
+    /// Writes `s`, a piece of text with the role `style`.
+    fn write_styled<W: Write>(&self, style: Style, s: &str, out: &mut W) -> fmt::Result;
+
+    /// Starts a hyperlink to `url`, the text written until [OutputStyle::end_link] being the
+    /// link. Links are not written by default.
+    fn begin_link<W: Write>(&self, url: &str, out: &mut W) -> fmt::Result {
+        let _ = (url, out);
+        Ok(())
+    }
+
+    /// Ends a hyperlink started by [OutputStyle::begin_link].
+    fn end_link<W: Write>(&self, out: &mut W) -> fmt::Result {
+        let _ = out;
+        Ok(())
+    }
+}
+
+/// Text written as is.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PlainStyle;
+
+impl OutputStyle for PlainStyle {
+    fn write_styled<W: Write>(&self, _: Style, s: &str, out: &mut W) -> fmt::Result {
+        out.write_str(s)
+    }
+}
+
+/// Text colorized with ANSI escape codes, using the colors of a theme. With [Color::NoColor],
+/// text is written as is.
+#[derive(Copy, Clone, Debug)]
+pub struct AnsiStyle<'a> {
+    pub color: Color,
+    pub theme: &'a Theme,
+}
+
+impl<'a> AnsiStyle<'a> {
+    pub fn new(color: Color) -> Self {
+        Self::with_theme(color, &DEFAULT_THEME)
+    }
+
+    pub fn with_theme(color: Color, theme: &'a Theme) -> Self {
+        AnsiStyle { color, theme }
+    }
+}
+
+impl OutputStyle for AnsiStyle<'_> {
+    fn write_styled<W: Write>(&self, style: Style, s: &str, out: &mut W) -> fmt::Result {
+        if self.color == Color::NoColor {
+            return out.write_str(s);
+        }
+        let code = self.theme.code(style);
+        out.write_str("\x1b[")?;
+        out.write_str(code)?;
+        out.write_char('m')?;
+        out.write_str(s)?;
+        out.write_str("\x1b[0m")
+    }
+
+    /// Writes an OSC 8 hyperlink, clickable in most terminal emulators.
+    fn begin_link<W: Write>(&self, url: &str, out: &mut W) -> fmt::Result {
+        write!(out, "\x1b]8;;{url}\x1b\\")
+    }
+
+    fn end_link<W: Write>(&self, out: &mut W) -> fmt::Result {
+        out.write_str("\x1b]8;;\x1b\\")
+    }
+}
+
+/// Text escaped for HTML, in `<span>` elements with a class per style (`json-key`,
+/// `json-string`, `json-type-url`...). The output is meant to be embedded in a `<pre>` element.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HtmlStyle;
+
+impl OutputStyle for HtmlStyle {
+    fn write_styled<W: Write>(&self, style: Style, s: &str, out: &mut W) -> fmt::Result {
+        let class = match style {
+            Style::Punctuation => "punctuation",
+            Style::Key => "key",
+            Style::String => "string",
+            Style::Number => "number",
+            Style::Boolean => "boolean",
+            Style::Null => "null",
+            Style::Comment => "comment",
+            Style::Quote => "quote",
+            Style::Escape => "escape",
+            Style::Type(field_type) => return write_span(&format!("type-{field_type}"), s, out),
+        };
+        write_span(class, s, out)
+    }
+}
+
+fn write_span<W: Write>(class: &str, s: &str, out: &mut W) -> fmt::Result {
+    write!(out, "<span class=\"json-{class}\">")?;
+    write_escaped(s, out)?;
+    out.write_str("</span>")
+}
+
+/// Writes `s` escaped for HTML and XML.
+fn write_escaped<W: Write>(s: &str, out: &mut W) -> fmt::Result {
+    for c in s.chars() {
+        match c {
+            '&' => out.write_str("&amp;")?,
+            '<' => out.write_str("&lt;")?,
+            '>' => out.write_str("&gt;")?,
+            '"' => out.write_str("&quot;")?,
+            c => out.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Text escaped for SVG, in `<tspan>` elements with the colors and attributes of a theme. Each
+/// line of the output is meant to be embedded in a `<text>` element.
+#[derive(Copy, Clone, Debug)]
+pub struct SvgStyle<'a> {
+    pub theme: &'a Theme,
+}
+
+impl SvgStyle<'_> {
+    pub fn new() -> Self {
+        SvgStyle {
+            theme: &DEFAULT_THEME,
+        }
+    }
+}
+
+impl Default for SvgStyle<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputStyle for SvgStyle<'_> {
+    fn write_styled<W: Write>(&self, style: Style, s: &str, out: &mut W) -> fmt::Result {
+        let sgr = SgrStyle::parse(self.theme.code(style));
+        out.write_str("<tspan")?;
+        if let Some(color) = sgr.color {
+            write!(out, " fill=\"{color}\"")?;
+        }
+        if sgr.bold {
+            out.write_str(" font-weight=\"bold\"")?;
+        }
+        if sgr.dim {
+            out.write_str(" opacity=\"0.6\"")?;
+        }
+        if sgr.italic {
+            out.write_str(" font-style=\"italic\"")?;
+        }
+        if sgr.underline {
+            out.write_str(" text-decoration=\"underline\"")?;
+        }
+        out.write_char('>')?;
+        write_escaped(s, out)?;
+        out.write_str("</tspan>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{Formatter, HtmlStyle, PlainStyle, SvgStyle};
+    use crate::theme::{FieldType, TypeMap};
+
+    #[test]
+    fn format_with_styles() {
+        let input = br#"{"id": "<a&b>", "n": [1, true, null]}"#;
+        let mut out = String::new();
+        Formatter::new_styled(input, PlainStyle)
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(
+            out,
+            "{\n  \"id\": \"<a&b>\",\n  \"n\": [\n    1,\n    true,\n    null\n  ]\n}"
+        );
+
+        let mut types = TypeMap::default();
+        types.insert("id", FieldType::Id);
+        let mut out = String::new();
+        Formatter::new_styled(br#"{"id": "<a&b>"}"#, HtmlStyle)
+            .with_types(&types)
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(
+            out,
+            r#"<span class="json-punctuation">{</span>
+  <span class="json-key">&quot;id&quot;</span><span class="json-punctuation">:</span> <span class="json-type-id">&quot;&lt;a&amp;b&gt;&quot;</span>
+<span class="json-punctuation">}</span>"#
+        );
+
+        let mut out = String::new();
+        Formatter::new_styled(br#"{"a": null}"#, SvgStyle::new())
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(
+            out,
+            r##"<tspan font-weight="bold">{</tspan>
+  <tspan fill="#2472c8" font-weight="bold">&quot;a&quot;</tspan><tspan font-weight="bold">:</tspan> <tspan fill="#bc3fbc">null</tspan>
+<tspan font-weight="bold">}</tspan>"##
+        );
+    }
+}
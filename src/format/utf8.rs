@@ -0,0 +1,187 @@
+//! UTF-8 validation with a table-driven DFA (deterministic finite automaton), like
+//! <https://bjoern.hoehrmann.de/utf-8/decoder/dfa/>.
+//!
+//! Each byte is mapped to one of 12 classes, and the state of the automaton is updated with a
+//! transition table: there are no branches depending on the bytes values.
+use super::{BytePos, FormatError, FormatResult, Formatter, OutputStyle};
+
+/// A complete char has been read.
+const ACCEPT: u8 = 0;
+/// The bytes are not valid UTF-8.
+const REJECT: u8 = 1;
+/// Expecting 1, 2 or 3 continuation bytes (`80..=BF`).
+const CONT_1: u8 = 2;
+const CONT_2: u8 = 3;
+const CONT_3: u8 = 4;
+/// After `E0`, expecting `A0..=BF` (no overlong encoding).
+const AFTER_E0: u8 = 5;
+/// After `ED`, expecting `80..=9F` (no surrogates).
+const AFTER_ED: u8 = 6;
+/// After `F0`, expecting `90..=BF` (no overlong encoding).
+const AFTER_F0: u8 = 7;
+/// After `F4`, expecting `80..=8F` (no code point above U+10FFFF).
+const AFTER_F4: u8 = 8;
+const STATES: usize = 9;
+
+/// Byte classes.
+const ASCII: u8 = 0;
+/// `80..=8F`
+const CONT_LOW: u8 = 1;
+/// `90..=9F`
+const CONT_MID: u8 = 2;
+/// `A0..=BF`
+const CONT_HIGH: u8 = 3;
+/// `C2..=DF`
+const LEAD_2: u8 = 4;
+const LEAD_E0: u8 = 5;
+/// `E1..=EC` and `EE..=EF`
+const LEAD_3: u8 = 6;
+const LEAD_ED: u8 = 7;
+const LEAD_F0: u8 = 8;
+/// `F1..=F3`
+const LEAD_4: u8 = 9;
+const LEAD_F4: u8 = 10;
+/// `C0`, `C1` and `F5..=FF`, never valid.
+const INVALID: u8 = 11;
+const CLASSES: usize = 12;
+
+static BYTE_CLASSES: [u8; 256] = byte_classes();
+static TRANSITIONS: [u8; STATES * CLASSES] = transitions();
+
+const fn byte_classes() -> [u8; 256] {
+    let mut classes = [INVALID; 256];
+    let mut b = 0;
+    while b < 256 {
+        classes[b] = match b as u8 {
+            0x00..=0x7F => ASCII,
+            0x80..=0x8F => CONT_LOW,
+            0x90..=0x9F => CONT_MID,
+            0xA0..=0xBF => CONT_HIGH,
+            0xC2..=0xDF => LEAD_2,
+            0xE0 => LEAD_E0,
+            0xE1..=0xEC | 0xEE..=0xEF => LEAD_3,
+            0xED => LEAD_ED,
+            0xF0 => LEAD_F0,
+            0xF1..=0xF3 => LEAD_4,
+            0xF4 => LEAD_F4,
+            _ => INVALID,
+        };
+        b += 1;
+    }
+    classes
+}
+
+const fn transitions() -> [u8; STATES * CLASSES] {
+    let mut transitions = [REJECT; STATES * CLASSES];
+    let mut state = 0;
+    while state < STATES {
+        let mut class = 0;
+        while class < CLASSES {
+            let cont = matches!(class as u8, CONT_LOW | CONT_MID | CONT_HIGH);
+            transitions[state * CLASSES + class] = match (state as u8, class as u8) {
+                (ACCEPT, ASCII) => ACCEPT,
+                (ACCEPT, LEAD_2) => CONT_1,
+                (ACCEPT, LEAD_E0) => AFTER_E0,
+                (ACCEPT, LEAD_3) => CONT_2,
+                (ACCEPT, LEAD_ED) => AFTER_ED,
+                (ACCEPT, LEAD_F0) => AFTER_F0,
+                (ACCEPT, LEAD_4) => CONT_3,
+                (ACCEPT, LEAD_F4) => AFTER_F4,
+                (CONT_1, _) if cont => ACCEPT,
+                (CONT_2, _) if cont => CONT_1,
+                (CONT_3, _) if cont => CONT_2,
+                (AFTER_E0, CONT_HIGH) => CONT_1,
+                (AFTER_ED, CONT_LOW | CONT_MID) => CONT_1,
+                (AFTER_F0, CONT_MID | CONT_HIGH) => CONT_2,
+                (AFTER_F4, CONT_LOW) => CONT_2,
+                _ => REJECT,
+            };
+            class += 1;
+        }
+        state += 1;
+    }
+    transitions
+}
+
+/// Returns the state after reading `b` in `state`.
+#[inline(always)]
+fn next_state(state: u8, b: u8) -> u8 {
+    TRANSITIONS[state as usize * CLASSES + BYTE_CLASSES[b as usize] as usize]
+}
+
+impl<S: OutputStyle> Formatter<'_, S> {
+    /// Consumes one valid UTF-8 char.
+    pub(crate) fn next_utf8_char(&mut self) -> FormatResult<()> {
+        let start = self.pos.0;
+        let mut state = ACCEPT;
+        loop {
+            let b = self.next_byte().ok_or(FormatError::Eof)?;
+            state = next_state(state, b);
+            match state {
+                ACCEPT => return Ok(()),
+                REJECT => return Err(self.invalid_utf8(start)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Consumes a span of valid non-ASCII UTF-8 chars, up to the next ASCII byte.
+    pub(crate) fn next_utf8_span(&mut self) -> FormatResult<()> {
+        let mut start = self.pos.0;
+        let mut state = ACCEPT;
+        while let Some(&b) = self.input.get(self.pos.0) {
+            if state == ACCEPT {
+                if b < 0x80 {
+                    return Ok(());
+                }
+                start = self.pos.0;
+            }
+            state = next_state(state, b);
+            self.pos.0 += 1;
+            if state == REJECT {
+                return Err(self.invalid_utf8(start));
+            }
+        }
+        if state == ACCEPT {
+            Ok(())
+        } else {
+            Err(FormatError::Eof)
+        }
+    }
+
+    /// Returns the error for the invalid bytes of a char from `start` to the current position.
+    fn invalid_utf8(&self, start: usize) -> FormatError {
+        let invalid = &self.input[start..self.pos.0];
+        let mut bytes = [0; 4];
+        bytes[..invalid.len()].copy_from_slice(invalid);
+        FormatError::InvalidUtf8(bytes, invalid.len(), BytePos(start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{BytePos, Color, FormatError, Formatter};
+
+    #[test]
+    fn read_utf8_spans() {
+        let input = "été 日本語🎉 x".as_bytes();
+        let mut formatter = Formatter::new(input, Color::NoColor);
+        formatter.next_utf8_span().unwrap();
+        assert_eq!(formatter.pos, BytePos(2));
+        formatter.pos = BytePos(6);
+        formatter.next_utf8_span().unwrap();
+        assert_eq!(formatter.pos, BytePos(19));
+
+        let input = b"\xC3\xA9\xE6\x97\xFF";
+        let mut formatter = Formatter::new(input, Color::NoColor);
+        let err = formatter.next_utf8_span().unwrap_err();
+        assert!(matches!(
+            err,
+            FormatError::InvalidUtf8([0xE6, 0x97, 0xFF, 0], 3, BytePos(2))
+        ));
+
+        let mut formatter = Formatter::new(b"\xE6\x97", Color::NoColor);
+        let err = formatter.next_utf8_span().unwrap_err();
+        assert!(matches!(err, FormatError::Eof));
+    }
+}
@@ -0,0 +1,257 @@
+//! Warnings: valid JSON that may not be read as expected (duplicate keys, lossy numbers...).
+//!
+//! Unlike errors, warnings don't stop the formatting, they are collected when enabled with
+//! [Formatter::with_warnings].
+use super::{BytePos, Formatter, OutputStyle};
+use crate::value::unescape;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Valid JSON that may not be read as expected by JSON parsers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A key already present in its object (unescaped), only one of the values is usually kept.
+    DuplicateKey(String, BytePos),
+    /// A number that is changed when read as a 64 bits float (IEEE 754 double), like in most
+    /// JSON parsers.
+    LossyNumber(BytePos),
+    /// A `\u` escape of a UTF-16 surrogate without its pair, that is not a valid char.
+    LoneSurrogate(BytePos),
+    /// An unnecessary escape: `\/`, or a `\u` escape of a printable ASCII char or of a char with
+    /// a short escape (`\n`...).
+    DenormalEscape(BytePos),
+    /// An invisible or bidirectional formatting char (zero-width space, right-to-left
+    /// override...), that can hide the actual content of a string.
+    InvisibleChar(char, BytePos),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::DuplicateKey(key, pos) => {
+                write!(f, "duplicate key \"{key}\" at offset {}", pos.0)
+            }
+            Warning::LossyNumber(pos) => {
+                write!(
+                    f,
+                    "number not exactly representable as a double at offset {}",
+                    pos.0
+                )
+            }
+            Warning::LoneSurrogate(pos) => write!(f, "lone UTF-16 surrogate at offset {}", pos.0),
+            Warning::DenormalEscape(pos) => write!(f, "unnecessary escape at offset {}", pos.0),
+            Warning::InvisibleChar(c, pos) => {
+                write!(f, "invisible char U+{:04X} at offset {}", *c as u32, pos.0)
+            }
+        }
+    }
+}
+
+impl<S: OutputStyle> Formatter<'_, S> {
+    /// Adds a warning, if warnings are collected.
+    fn warn(&mut self, warning: Warning) {
+        if let Some(warnings) = &mut self.warnings {
+            warnings.push(warning);
+        }
+    }
+
+    /// Warns if the key from `start` to `end` is already in `keys`, the keys of its object.
+    pub(super) fn check_duplicate_key(
+        &mut self,
+        keys: &mut HashSet<String>,
+        start: BytePos,
+        end: BytePos,
+    ) {
+        let raw = self.slice_str_unchecked(start, end);
+        let key = unescape(raw);
+        if keys.contains(&key) {
+            self.warn(Warning::DuplicateKey(key, start));
+        } else {
+            keys.insert(key);
+        }
+    }
+
+    /// Warns if the number from `start` to `end` is lossy.
+    pub(super) fn check_number(&mut self, start: BytePos, end: BytePos) {
+        if is_lossy(self.slice_str_unchecked(start, end)) {
+            self.warn(Warning::LossyNumber(start));
+        }
+    }
+
+    /// Warns about lone surrogates and denormal escapes of the raw string (with its quotes) from
+    /// `start` to `end`.
+    pub(super) fn check_escapes(&mut self, start: BytePos, end: BytePos) {
+        let raw = self.slice_str_unchecked(start, end);
+        let bytes = raw.as_bytes();
+        let mut warnings = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] != b'\\' {
+                i += 1;
+                continue;
+            }
+            let pos = BytePos(start.0 + i);
+            match bytes[i + 1] {
+                b'/' => warnings.push(Warning::DenormalEscape(pos)),
+                b'u' => match code_unit(&raw[i + 2..i + 6]) {
+                    0xD800..=0xDBFF => {
+                        let low = raw[i + 6..].strip_prefix("\\u").map(|s| code_unit(&s[..4]));
+                        if matches!(low, Some(0xDC00..=0xDFFF)) {
+                            i += 6;
+                        } else {
+                            warnings.push(Warning::LoneSurrogate(pos));
+                        }
+                    }
+                    0xDC00..=0xDFFF => warnings.push(Warning::LoneSurrogate(pos)),
+                    0x08 | 0x09 | 0x0A | 0x0C | 0x0D | 0x20..=0x7E => {
+                        warnings.push(Warning::DenormalEscape(pos));
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+            i += if bytes[i + 1] == b'u' { 6 } else { 2 };
+        }
+        warnings.into_iter().for_each(|warning| self.warn(warning));
+    }
+
+    /// Warns about the invisible chars of the raw string from `start` to `end`.
+    pub(super) fn check_invisible(&mut self, start: BytePos, end: BytePos) {
+        let raw = self.slice_str_unchecked(start, end);
+        let warnings = raw
+            .char_indices()
+            .filter(|(_, c)| is_invisible(*c))
+            .map(|(i, c)| Warning::InvisibleChar(c, BytePos(start.0 + i)))
+            .collect::<Vec<_>>();
+        warnings.into_iter().for_each(|warning| self.warn(warning));
+    }
+}
+
+/// Returns `true` for chars that are not displayed, or that change the display of the following
+/// chars: zero-width chars, bidirectional formatting chars, tags... The zero-width joiner and
+/// variation selectors are not included, as they are used by emojis.
+pub(super) fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{061C}'
+            | '\u{115F}'
+            | '\u{1160}'
+            | '\u{180E}'
+            | '\u{200B}'
+            | '\u{200C}'
+            | '\u{200E}'
+            | '\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{206F}'
+            | '\u{3164}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+            | '\u{FFF9}'..='\u{FFFB}'
+            | '\u{E0000}'..='\u{E007F}'
+    )
+}
+
+/// Returns the UTF-16 code unit of 4 hex digits (already validated).
+fn code_unit(hex: &str) -> u16 {
+    u16::from_str_radix(hex, 16).unwrap_or(0)
+}
+
+/// Returns `true` if the number `raw` is changed when read as a 64 bits float: integers larger than
+/// 2^53, numbers with more than 17 significant digits, or out of the range of doubles.
+fn is_lossy(raw: &str) -> bool {
+    let value = raw.parse::<f64>().unwrap_or(f64::INFINITY);
+    if value.is_infinite() {
+        return true;
+    }
+    let mantissa = raw
+        .trim_start_matches('-')
+        .split(['e', 'E'])
+        .next()
+        .unwrap_or("");
+    if !mantissa.contains('.') && !raw.contains(['e', 'E']) {
+        return mantissa
+            .parse::<u64>()
+            .map_or(true, |n| n > 1 << f64::MANTISSA_DIGITS);
+    }
+    let digits = mantissa.replace('.', "");
+    let significant = digits.trim_start_matches('0').trim_end_matches('0');
+    // Non-zero numbers rounded to zero.
+    significant.len() > 17 || (value == 0.0 && !significant.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Warning, is_lossy};
+    use crate::format::{BytePos, Color, Formatter};
+
+    #[test]
+    fn lossy_numbers() {
+        for raw in [
+            "0",
+            "-12",
+            "9007199254740992",
+            "1.5",
+            "1e300",
+            "0.1",
+            "1.00000000000000000",
+        ] {
+            assert!(!is_lossy(raw), "{raw}");
+        }
+        for raw in [
+            "9007199254740993",
+            "-12345678901234567890",
+            "1e400",
+            "1e-400",
+        ] {
+            assert!(is_lossy(raw), "{raw}");
+        }
+        assert!(is_lossy("3.14159265358979323846"));
+    }
+
+    #[test]
+    fn collect_warnings() {
+        let input = r#"{"a": 1, "b": {"a": 2}, "a": 9007199254740993, "c": "\/ \ud83c\udf89 \ud83c \u0041 é"}"#;
+        let mut formatter = Formatter::new(input.as_bytes(), Color::NoColor).with_warnings(true);
+        formatter.format(&mut String::new()).unwrap();
+        assert_eq!(
+            formatter.warnings(),
+            [
+                Warning::DuplicateKey("a".to_string(), BytePos(24)),
+                Warning::LossyNumber(BytePos(29)),
+                Warning::DenormalEscape(BytePos(53)),
+                Warning::LoneSurrogate(BytePos(69)),
+                Warning::DenormalEscape(BytePos(76)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reveal_invisible_chars() {
+        let input =
+            "{\"admin\u{200B}\": \"x\u{202E}yz \u{E0041}\", \"b\": \"\u{1F469}\u{200D}\u{1F4BB}\"}";
+        let mut formatter = Formatter::new(input.as_bytes(), Color::NoColor)
+            .with_warnings(true)
+            .with_reveal_invisible(true);
+        let mut out = String::new();
+        formatter.format(&mut out).unwrap();
+        assert_eq!(
+            formatter.warnings(),
+            [
+                Warning::InvisibleChar('\u{200B}', BytePos(7)),
+                Warning::InvisibleChar('\u{202E}', BytePos(15)),
+                Warning::InvisibleChar('\u{E0041}', BytePos(21)),
+            ]
+        );
+        assert_eq!(
+            out,
+            "{\n  \"admin\\u200b\": \"x\\u202eyz \\udb40\\udc41\",\n  \"b\": \"\u{1F469}\u{200D}\u{1F4BB}\"\n}"
+        );
+        assert_eq!(
+            formatter.warnings()[1].to_string(),
+            "invisible char U+202E at offset 15"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Conversion of colored terminal output to HTML.
+use crate::theme::SgrStyle;
+use std::fmt::Write;
+
+/// Converts text colored with ANSI escape sequences, like the output of a
+/// [Formatter](crate::format::Formatter) with [Color::AnsiCode](crate::format::Color::AnsiCode),
+/// to HTML with inline styles.
+///
+/// Colors are converted with the same palette as [SvgStyle](crate::format::SvgStyle), so a
+/// theme renders the same in a terminal, an image and a web page. Text is escaped and runs of
+/// text with the same style are wrapped in a `<span>`; other escape sequences (cursor moves,
+/// hyperlinks) are removed. Each SGR sequence updates the current style, as in a terminal
+/// (`ESC[1m` then `ESC[34m` is bold and blue). The result is meant to be embedded in a `<pre>`
+/// element, with the background and foreground colors of the theme.
+pub fn ansi_to_html(ansi: &str) -> String {
+    let mut out = String::with_capacity(ansi.len());
+    let mut style = SgrStyle::default();
+    let mut run = String::new();
+    let mut chars = ansi.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c != '\x1b' {
+            match c {
+                '&' => run.push_str("&amp;"),
+                '<' => run.push_str("&lt;"),
+                '>' => run.push_str("&gt;"),
+                '"' => run.push_str("&quot;"),
+                c => run.push(c),
+            }
+            continue;
+        }
+        match chars.next() {
+            Some((_, '[')) => {
+                // Parameters up to a final byte in 0x40..=0x7e, only SGR (`m`) sequences are kept.
+                let start = i + 2;
+                let mut end = ansi.len();
+                let mut last = None;
+                for (j, c) in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        end = j;
+                        last = Some(c);
+                        break;
+                    }
+                }
+                if last == Some('m') {
+                    let mut next = style;
+                    next.apply(&ansi[start..end]);
+                    if next != style {
+                        write_run(&style, &run, &mut out);
+                        run.clear();
+                        style = next;
+                    }
+                }
+            }
+            Some((_, ']')) => {
+                // Operating System Commands end with BEL or `ESC \`.
+                while let Some((_, c)) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.next_if(|(_, c)| *c == '\\').is_some() {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    write_run(&style, &run, &mut out);
+    out
+}
+
+/// Writes an escaped text, in a `<span>` if it has a style.
+fn write_run(style: &SgrStyle, text: &str, out: &mut String) {
+    if text.is_empty() {
+        return;
+    }
+    let css = css(style);
+    if css.is_empty() {
+        out.push_str(text);
+    } else {
+        _ = write!(out, "<span style=\"{css}\">{text}</span>");
+    }
+}
+
+/// Returns the inline CSS of a style.
+fn css(style: &SgrStyle) -> String {
+    let mut css = Vec::new();
+    if let Some(color) = style.color {
+        css.push(format!("color:{color}"));
+    }
+    if style.bold {
+        css.push("font-weight:bold".to_string());
+    }
+    if style.dim {
+        css.push("opacity:0.6".to_string());
+    }
+    if style.italic {
+        css.push("font-style:italic".to_string());
+    }
+    if style.underline {
+        css.push("text-decoration:underline".to_string());
+    }
+    css.join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ansi_to_html;
+    use crate::format::{Color, Formatter};
+
+    #[test]
+    fn convert_ansi_to_html() {
+        let mut out = String::new();
+        Formatter::new(br#"{"a<b": [null, 1]}"#, Color::AnsiCode)
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(
+            ansi_to_html(&out),
+            r#"<span style="font-weight:bold">{</span>
+  <span style="color:#2472c8;font-weight:bold">&quot;a&lt;b&quot;</span><span style="font-weight:bold">:</span> <span style="font-weight:bold">[</span>
+    <span style="color:#bc3fbc">null</span><span style="font-weight:bold">,</span>
+    <span style="color:#11a8cd">1</span>
+  <span style="font-weight:bold">]</span>
+<span style="font-weight:bold">}</span>"#
+        );
+        assert_eq!(
+            ansi_to_html("\x1b]8;;https://a.b\x1b\\\x1b[4;34mlink\x1b[0m\x1b]8;;\x07 \x1b[2K!"),
+            r#"<span style="color:#2472c8;text-decoration:underline">link</span> !"#
+        );
+        assert_eq!(
+            ansi_to_html("\x1b[1ma\x1b[34mb\x1b[22mc\x1b[mWd"),
+            r#"<span style="font-weight:bold">a</span><span style="color:#2472c8;font-weight:bold">b</span><span style="color:#2472c8">c</span>Wd"#
+        );
+    }
+}
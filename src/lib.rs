@@ -1 +1,14 @@
+pub mod arena;
+pub mod classify;
+pub mod decorate;
 pub mod format;
+pub mod html;
+pub mod map;
+pub mod num;
+pub mod path;
+pub mod query;
+pub mod theme;
+pub mod token;
+pub mod transform;
+pub mod value;
+pub mod visit;
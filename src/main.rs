@@ -1,166 +1,1369 @@
-mod format;
+mod cli;
 
-use crate::format::{Color, Formatter};
+use crate::cli::log::Log;
+use crate::cli::ndjson::{BulkAction, Record};
+use crate::cli::split::SplitWriter;
+use crate::cli::{
+    Bom, Command, Config, EXIT_INVALID_JSON, EXIT_IO_ERROR, EXIT_OK, EXIT_UNFORMATTED, EXIT_USAGE,
+    ErrorFormat, Explode, Input, InputFormat, OutputFormat, parse_args,
+};
+use pretty::arena::Arena;
+use pretty::decorate::JsUnsafe;
+use pretty::format::{
+    AnsiStyle, Color, FormatReport, Formatter, FormatterOptions, Indent, OutputStyle, Style,
+    SvgStyle, Warning,
+};
+use pretty::query::Query;
+use pretty::value;
 use serde_json::Value;
+use std::borrow::Cow;
 use std::env;
-use std::env::Args;
-use std::io::Read;
-use std::path::PathBuf;
+use std::io::{BufRead, IsTerminal, Read, Write};
 
 fn main() {
-    let config = match parse_args(env::args()) {
+    let mut config = match parse_args(env::args()) {
         Ok(config) => config,
         Err(err) => {
             eprintln!("Error parsing arguments: {}", err);
-            std::process::exit(1);
+            std::process::exit(EXIT_USAGE);
         }
     };
+    if config.detect_background
+        && std::io::stdout().is_terminal()
+        && let Some(background) = cli::terminal::background()
+    {
+        config.theme = background.theme().clone();
+    }
+    if config.detect_hyperlinks {
+        config.hyperlinks = std::io::stdout().is_terminal() && cli::terminal::hyperlinks();
+    }
 
-    let buffer = match &config.file_path {
-        None => {
-            // Read from stdin
-            let mut buffer = Vec::new();
-            match std::io::stdin().read_to_end(&mut buffer) {
-                Ok(_) => buffer,
-                Err(err) => {
-                    eprintln!("Error reading from stdin: {}", err);
-                    std::process::exit(1);
-                }
+    let color = if config.with_color {
+        Color::AnsiCode
+    } else {
+        Color::NoColor
+    };
+
+    if config.command == Command::Repl {
+        if let Err(err) = cli::repl::run(color) {
+            eprintln!("Error reading from stdin: {}", err);
+            std::process::exit(EXIT_IO_ERROR);
+        }
+        return;
+    }
+
+    if config.command == Command::Serve {
+        let max_body = config
+            .max_input_size
+            .unwrap_or(cli::serve::DEFAULT_MAX_BODY);
+        if let Err(err) = cli::serve::run(&config.bind, config.port, max_body) {
+            eprintln!("Error running server: {}", err);
+            std::process::exit(EXIT_IO_ERROR);
+        }
+        return;
+    }
+
+    if let (Command::SideBySide, [left, right]) = (config.command, config.inputs.as_slice()) {
+        let mut outputs = Vec::new();
+        for input in [left, right] {
+            let buffer = read_input(input, &config).unwrap_or_else(|err| {
+                eprintln!("{err}");
+                std::process::exit(EXIT_IO_ERROR);
+            });
+            let output = pretty(&buffer, color, &config).unwrap_or_else(|err| {
+                eprintln!("Error in {input}: {err}");
+                std::process::exit(EXIT_INVALID_JSON);
+            });
+            outputs.push(output);
+        }
+        let width = config
+            .width
+            .unwrap_or_else(cli::side_by_side::terminal_width);
+        print!(
+            "{}",
+            cli::side_by_side::render(&outputs[0], &outputs[1], width)
+        );
+        return;
+    }
+
+    if let (Command::Example, Some(schema)) = (config.command, &config.schema) {
+        match example(schema, color, &config) {
+            Ok(output) => println!("{output}"),
+            Err((code, err)) => {
+                eprintln!("{err}");
+                std::process::exit(code);
             }
         }
-        Some(path) => {
-            // Read from file
-            match std::fs::read(path) {
-                Ok(data) => data,
-                Err(err) => {
-                    eprintln!("Error reading file '{}': {}", path.display(), err);
-                    std::process::exit(1);
+        return;
+    }
+
+    if config.command == Command::GitHook {
+        if let Err((code, err)) = git_hook(&config, color) {
+            eprint!("{err}");
+            std::process::exit(code);
+        }
+        return;
+    }
+
+    if let (true, [Input::File(path)]) = (config.watch, config.inputs.as_slice()) {
+        if let Err(err) = cli::watch::run(path, color, &config) {
+            eprintln!("Error watching file '{}': {}", path.display(), err);
+            std::process::exit(EXIT_IO_ERROR);
+        }
+        return;
+    }
+
+    // Expands directories and glob patterns.
+    let inputs = config
+        .inputs
+        .iter()
+        .flat_map(|input| match input {
+            Input::File(path) => cli::files::expand(path, config.gitignore)
+                .into_iter()
+                .map(|file| file.map(Input::File))
+                .collect(),
+            _ => vec![Ok(input.clone())],
+        })
+        .collect::<Vec<_>>();
+
+    let mut failures = 0;
+    let mut exit_code = EXIT_OK;
+    for input in &inputs {
+        let result = match input {
+            Ok(input) if config.write => write_input(input, &config),
+            Ok(input) if config.check => check_input(input, &config, color),
+            Ok(input) => format_input(input, &config, color),
+            Err(err) => Err((EXIT_IO_ERROR, err.clone())),
+        };
+        if let Err((code, err)) = result {
+            if config.error_format == ErrorFormat::Json {
+                let name = match input {
+                    Ok(input) => input.to_string().trim_matches('\'').to_string(),
+                    Err(_) => String::new(),
+                };
+                if !config.quiet {
+                    eprintln!("{}", error_json(&name, code, &err));
                 }
+            } else if code == EXIT_IO_ERROR {
+                eprintln!("{err}");
+            } else if config.quiet {
+                // In quiet mode, only the exit code signals invalid or unformatted JSON.
+            } else if code == EXIT_UNFORMATTED {
+                print!("{err}");
+            } else if let (true, Ok(input)) = (
+                inputs.len() > 1 || matches!(input, Ok(Input::Stdin(Some(_)))),
+                input,
+            ) {
+                eprintln!("Error in {input}: {err}");
+            } else {
+                eprintln!("Error: {err}");
+            }
+            // In check mode, we want to report all unformatted inputs.
+            if !config.skip_errors && code != EXIT_UNFORMATTED {
+                std::process::exit(code);
             }
+            failures += 1;
+            // I/O errors take precedence over invalid JSON, that takes precedence over
+            // unformatted inputs.
+            exit_code = match (exit_code, code) {
+                (EXIT_OK | EXIT_UNFORMATTED, code) => code,
+                (EXIT_INVALID_JSON, EXIT_IO_ERROR) => EXIT_IO_ERROR,
+                (exit_code, _) => exit_code,
+            };
         }
-    };
+    }
+
+    if failures > 0 {
+        if !config.quiet && config.error_format == ErrorFormat::Text {
+            eprintln!("{failures} of {} inputs failed", inputs.len());
+        }
+        std::process::exit(exit_code);
+    }
+}
+
+/// Formats one input and prints it on standard output, returns an exit code and an error message
+/// on failure.
+fn format_input(input: &Input, config: &Config, color: Color) -> Result<(), (i32, String)> {
+    if config.debug_dump {
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        let mut output = String::new();
+        let result = cli::debug_dump::dump(&buffer, &mut output);
+        if !config.quiet {
+            print!("{output}");
+        }
+        return result.map_err(|err| (EXIT_INVALID_JSON, err.to_string()));
+    }
+    if config.verify {
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        let message = match cli::verify::verify(&buffer) {
+            cli::verify::Verdict::Accepted => "accepted by the formatter and serde".to_string(),
+            cli::verify::Verdict::Rejected(err) => {
+                format!("rejected by the formatter and serde ({err})")
+            }
+            cli::verify::Verdict::Diverged(divergences) => {
+                let err = format!("divergence with serde\n  {}", divergences.join("\n  "));
+                return Err((EXIT_INVALID_JSON, err));
+            }
+        };
+        if !config.quiet {
+            println!("{input}: {message}");
+        }
+        return Ok(());
+    }
+    if config.strip_ansi {
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        if !config.quiet {
+            std::io::stdout()
+                .write_all(&cli::ansi::strip(&buffer))
+                .map_err(|err| (EXIT_IO_ERROR, format!("Error writing to stdout: {err}")))?;
+        }
+        return Ok(());
+    }
+    if config.collect {
+        return format_collected(input, config, color);
+    }
+    if config.ndjson
+        && let Some(columns) = &config.table
+    {
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        let mut rows = Vec::new();
+        for record in cli::ndjson::records(&buffer) {
+            let row = value::parse(record.bytes)
+                .map_err(|err| (EXIT_INVALID_JSON, format!("line {}: {err}", record.line)))?;
+            rows.push(row);
+        }
+        if !config.quiet {
+            let rows = rows.iter().collect::<Vec<_>>();
+            print!(
+                "{}",
+                cli::table::render(&rows, columns, style(color, config))
+            );
+        }
+        return Ok(());
+    }
+    if config.ndjson
+        && let Some(path) = &config.group_by
+    {
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        let records = cli::ndjson::records(&buffer).filter(|record| {
+            let window = config.time_window.as_ref();
+            window.is_none_or(|window| window.contains(record.bytes))
+        });
+        let groups = cli::ndjson::group_count(records, path);
+        if !config.quiet {
+            print!("{}", cli::ndjson::group_table(&groups, path));
+        }
+        return Ok(());
+    }
+    if config.ndjson {
+        // --tail and --dedup need to look at the next records before printing a record.
+        if let Input::Stdin(name) = input
+            && config.tail.is_none()
+            && !config.dedup
+        {
+            return format_stdin_records(name, config, color);
+        }
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        return format_records(&buffer, config, color);
+    }
+    if config.har {
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        return format_har(&buffer, config, color);
+    }
+    if config.aws {
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        return format_aws(&buffer, config, color);
+    }
+    if config.tf {
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        return format_tf(&buffer, config, color);
+    }
+    if config.es_bulk {
+        let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+        return format_bulk(&buffer, config, color);
+    }
+    let mut log = Log::new(config.log, input);
+    let buffer = read_transformed_input(input, config, &log)?;
+    log.phase("read", buffer.len());
+
+    if config.proto_strict {
+        let root = value::parse(&buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+        let errors = cli::proto::check(&root);
+        if !errors.is_empty() {
+            let err = format!("invalid proto3 JSON\n  {}", errors.join("\n  "));
+            return Err((EXIT_INVALID_JSON, err));
+        }
+    }
+
+    if let Some(algorithm) = config.hash {
+        let digest = cli::hash::hash(&buffer, algorithm).map_err(|err| (EXIT_INVALID_JSON, err))?;
+        let line = format!("{digest}  {}", input.to_string().trim_matches('\''));
+        if !config.hash_with_output {
+            if !config.quiet {
+                println!("{line}");
+            }
+            return Ok(());
+        }
+        eprintln!("{line}");
+    }
+
+    if let Some(query) = &config.query {
+        return format_matches(&buffer, query, config, color);
+    }
+
+    if let Some(explode) = config.explode {
+        return format_exploded(&buffer, explode, config, color);
+    }
+
+    if let Some(columns) = &config.table {
+        let root = value::parse(&buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+        let rows = match &root.value {
+            value::Value::Array(nodes) => nodes.iter().collect(),
+            _ => vec![&root],
+        };
+        if !config.quiet {
+            print!(
+                "{}",
+                cli::table::render(&rows, columns, style(color, config))
+            );
+        }
+        return Ok(());
+    }
+
+    if !config.aggregations.is_empty() {
+        let results = cli::agg::evaluate(&buffer, &config.aggregations)
+            .map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+        if !config.quiet {
+            let output = pretty(results.as_bytes(), color, config)
+                .map_err(|err| (EXIT_INVALID_JSON, err))?;
+            println!("{output}");
+        }
+        return Ok(());
+    }
+
+    if config.profile {
+        let root = value::parse(&buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+        if !config.quiet {
+            print!("{}", cli::profile::report(&root, config.top));
+        }
+        return Ok(());
+    }
+
+    if config.keys_only {
+        let root = value::parse(&buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+        if !config.quiet {
+            print!(
+                "{}",
+                cli::outline::outline(&root, config.depth, style(color, config))
+            );
+        }
+        return Ok(());
+    }
+
+    if config.to == OutputFormat::Xml {
+        let output = cli::xml::to_xml(&buffer, &config.xml)
+            .map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+        if !config.quiet {
+            print!("{output}");
+        }
+        return Ok(());
+    }
+
+    if config.to == OutputFormat::Markdown {
+        let root = value::parse(&buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+        if !config.quiet {
+            print!(
+                "{}",
+                cli::markdown::to_markdown(&root, config.markdown_layout)
+            );
+        }
+        return Ok(());
+    }
+
+    if config.to == OutputFormat::Svg {
+        let mut formatted = String::new();
+        let style = SvgStyle {
+            theme: &config.theme,
+        };
+        configure(Formatter::new_styled(&buffer, style), config)
+            .format(&mut formatted)
+            .map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+        if !config.quiet {
+            print!("{}", cli::svg::to_svg(&formatted, &config.theme));
+        }
+        return Ok(());
+    }
+
+    if config.to == OutputFormat::AnsiHtml {
+        let output =
+            pretty(&buffer, Color::AnsiCode, config).map_err(|err| (EXIT_INVALID_JSON, err))?;
+        if !config.quiet {
+            let (background, foreground) = (config.theme.background, config.theme.foreground);
+            println!(
+                "<pre style=\"background-color:{background};color:{foreground}\">{}</pre>",
+                pretty::html::ansi_to_html(&output)
+            );
+        }
+        return Ok(());
+    }
+
+    if config.compact {
+        let root = value::parse(&buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+        if !config.quiet {
+            let output = root.to_compact_string();
+            print!("{output}{}", final_newline(config, None));
+        }
+        return Ok(());
+    }
+
+    if config.report {
+        let (output, report) =
+            pretty_with_report(&buffer, color, config).map_err(|err| (EXIT_INVALID_JSON, err))?;
+        if !config.quiet {
+            let bom = bom(config, &buffer);
+            print!("{bom}{output}{}", final_newline(config, None));
+        }
+        eprint_report(&report);
+        return Ok(());
+    }
 
     let run = if config.with_serde {
         pretty_serde
     } else {
-        pretty
+        pretty_into
     };
 
+    let mut buffers = Buffers::default();
     for _ in 1..=config.iter_count {
-        match run(&buffer, config.with_color) {
-            Ok(s) => println!("{s}"),
-            Err(err) => {
-                eprintln!("Error: {err}");
-                std::process::exit(2);
+        // Memory of the previous iteration is reused.
+        buffers.arena.reset();
+        if let Err(err) = run(&buffer, color, config, &mut buffers) {
+            if config.partial && !config.quiet {
+                // The output buffer holds the output formatted before the error.
+                println!("{}", buffers.output);
             }
+            return Err((EXIT_INVALID_JSON, err));
+        }
+        log.phase("format", buffers.output.len());
+        if !config.quiet {
+            let bom = bom(config, &buffer);
+            let output = &buffers.output;
+            print!("{bom}{output}{}", final_newline(config, None));
+            log.phase("write", buffers.output.len());
         }
     }
+    if config.legend && !config.quiet {
+        println!("{}", cli::legend::legend(&style(color, config)));
+    }
+    std::io::stdout()
+        .flush()
+        .map_err(|err| (EXIT_IO_ERROR, format!("Error writing to stdout: {err}")))?;
+    log.phase("flush", 0);
+    Ok(())
 }
 
-fn pretty_serde(bytes: &[u8], _color: bool) -> Result<String, String> {
-    let json = serde_json::from_slice::<Value>(bytes).unwrap();
-    serde_json::to_string_pretty(&json).map_err(|err| err.to_string())
+/// Returns an error as a line of JSON, for `--error-format json`.
+fn error_json(input: &str, code: i32, message: &str) -> String {
+    format!(
+        r#"{{"input":{},"code":{code},"message":{}}}"#,
+        value::escape(input),
+        value::escape(message.trim_end())
+    )
 }
 
-fn pretty(bytes: &[u8], color: bool) -> Result<String, String> {
-    let color = if color {
-        Color::AnsiCode
-    } else {
-        Color::NoColor
+/// Prints the values of the input selected by `query`, as an array or one value per line.
+fn format_matches(
+    buffer: &[u8],
+    query: &Query,
+    config: &Config,
+    color: Color,
+) -> Result<(), (i32, String)> {
+    let root = value::parse(buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+    let matches = query.select(&root);
+    if config.quiet {
+        return Ok(());
+    }
+    if config.query_ndjson {
+        for node in matches {
+            println!("{}", node.to_compact_string());
+        }
+        return Ok(());
+    }
+    let mut array = String::from("[");
+    for (i, node) in matches.iter().enumerate() {
+        if i > 0 {
+            array.push(',');
+        }
+        node.write_compact(&mut array);
+    }
+    array.push(']');
+    let output = pretty(array.as_bytes(), color, config).map_err(|err| (EXIT_INVALID_JSON, err))?;
+    println!("{output}");
+    Ok(())
+}
+
+/// Prints each item of a top-level array as a record: on one line, or pretty printed.
+fn format_exploded(
+    buffer: &[u8],
+    explode: Explode,
+    config: &Config,
+    color: Color,
+) -> Result<(), (i32, String)> {
+    let root = value::parse(buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+    let value::Value::Array(items) = &root.value else {
+        let err = "--explode expects a top-level array".to_string();
+        return Err((EXIT_INVALID_JSON, err));
     };
-    let mut parser = Formatter::new(bytes, color);
-    let mut output = String::new();
-    parser.format(&mut output).map_err(|err| err.to_string())?;
-    Ok(output)
+    if config.quiet {
+        return Ok(());
+    }
+    let mut buffers = Buffers::for_records(config);
+    for item in items {
+        let record = item.to_compact_string();
+        let mut record = match explode {
+            Explode::Compact => record,
+            Explode::Pretty => {
+                pretty(record.as_bytes(), color, config).map_err(|err| (EXIT_INVALID_JSON, err))?
+            }
+        };
+        record.push('\n');
+        buffers.write_record(&record)?;
+    }
+    buffers.flush()
 }
 
-#[derive(Debug)]
-struct Config {
-    with_serde: bool,
-    with_color: bool,
-    iter_count: usize,
-    file_path: Option<PathBuf>,
+/// Formats each record of an NDJSON input and prints it on standard output.
+fn format_records(buffer: &[u8], config: &Config, color: Color) -> Result<(), (i32, String)> {
+    // Records are selected before being formatted, so only the printed records are parsed.
+    let records = cli::ndjson::records(buffer)
+        .enumerate()
+        .filter(|(_, record)| {
+            let window = config.time_window.as_ref();
+            window.is_none_or(|window| window.contains(record.bytes))
+        })
+        .step_by(config.sample.unwrap_or(1))
+        .take(config.head.unwrap_or(usize::MAX));
+    let records: Box<dyn Iterator<Item = _>> = match config.tail {
+        Some(n) => Box::new(cli::ndjson::last(records, n).into_iter()),
+        None => Box::new(records),
+    };
+    let mut buffers = Buffers::for_records(config);
+
+    if !config.dedup {
+        for (i, record) in records {
+            format_record(i, &record, 1, config, color, &mut buffers)?;
+        }
+        return buffers.flush();
+    }
+
+    // Consecutive identical records (once compacted) are printed once, with their count.
+    let mut previous: Option<(usize, Record, Option<String>, usize)> = None;
+    for (i, record) in records {
+        let canonical = value::parse(record.bytes)
+            .ok()
+            .map(|root| root.to_compact_string());
+        if let Some((_, _, previous, count)) = &mut previous
+            && canonical.is_some()
+            && *previous == canonical
+        {
+            *count += 1;
+            continue;
+        }
+        if let Some((i, record, _, count)) = previous.replace((i, record, canonical, 1)) {
+            format_record(i, &record, count, config, color, &mut buffers)?;
+        }
+    }
+    if let Some((i, record, _, count)) = previous {
+        format_record(i, &record, count, config, color, &mut buffers)?;
+    }
+    buffers.flush()
 }
 
-fn print_usage() {
-    println!("Usage: pretty [OPTIONS] <JSON_FILE>");
-    println!();
-    println!("A fast JSON pretty-printer");
-    println!();
-    println!("Arguments:");
-    println!("  <JSON_FILE>  Path to the JSON file to format (use '-' for stdin)");
-    println!();
-    println!("Options:");
-    println!("  --serde       Use serde for JSON parsing");
-    println!("  --no-color    Disable colored output");
-    println!("  --iter <N>    Number of iterations to run [default: 1]");
-    println!("  -h, --help    Print this help message");
+/// Lists the entries of a HAR file, or prints the request and response of one entry.
+fn format_har(buffer: &[u8], config: &Config, color: Color) -> Result<(), (i32, String)> {
+    let root = value::parse(buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+    let entries = cli::har::entries(&root).map_err(|err| (EXIT_INVALID_JSON, err))?;
+    let Some(n) = config.har_entry else {
+        if !config.quiet {
+            print!("{}", cli::har::list(&entries));
+        }
+        return Ok(());
+    };
+    let Some(entry) = entries.get(n - 1) else {
+        let err = format!("No entry {n}, the HAR file has {} entries", entries.len());
+        return Err((EXIT_USAGE, err));
+    };
+    if config.quiet {
+        return Ok(());
+    }
+    let time = cli::har::format_time(entry.time);
+    println!("{} {} → {} ({time})", entry.method, entry.url, entry.status);
+    let bodies = [
+        ("Request", &entry.request_body),
+        ("Response", &entry.response_body),
+    ];
+    for (name, body) in bodies {
+        let Some(body) = body else {
+            continue;
+        };
+        println!();
+        println!("{name} body ({}):", body.mime_type);
+        // JSON bodies are formatted, other bodies are printed as is.
+        match pretty(&body.content, color, config) {
+            Ok(output) => println!("{output}"),
+            Err(_) => println!("{}", String::from_utf8_lossy(&body.content)),
+        }
+    }
+    Ok(())
 }
 
-fn parse_args(args: Args) -> Result<Config, String> {
-    let args: Vec<String> = args.skip(1).collect();
+/// Formats each event of an AWS envelope, separated by a blank line.
+fn format_aws(buffer: &[u8], config: &Config, color: Color) -> Result<(), (i32, String)> {
+    let events = cli::aws::events(buffer).map_err(|err| (EXIT_INVALID_JSON, err))?;
+    if config.quiet {
+        return Ok(());
+    }
+    for (i, event) in events.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let output =
+            pretty(event.as_bytes(), color, config).map_err(|err| (EXIT_INVALID_JSON, err))?;
+        println!("{output}");
+    }
+    Ok(())
+}
 
-    // Handle help flags first
-    if args.is_empty() {
-        print_usage();
-        std::process::exit(0);
+/// Lists the resources of a Terraform plan or state, or formats the resource at
+/// `config.tf_address`.
+fn format_tf(buffer: &[u8], config: &Config, color: Color) -> Result<(), (i32, String)> {
+    let root = value::parse(buffer).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+    let resources = cli::tf::resources(&root).map_err(|err| (EXIT_INVALID_JSON, err))?;
+    let Some(address) = &config.tf_address else {
+        if !config.quiet {
+            print!("{}", cli::tf::list(&resources));
+        }
+        return Ok(());
+    };
+    let Some(resource) = resources.iter().find(|r| r.address == *address) else {
+        return Err((
+            EXIT_USAGE,
+            format!("No resource {address} in the Terraform JSON"),
+        ));
+    };
+    if config.quiet {
+        return Ok(());
+    }
+    if !resource.actions.is_empty() {
+        println!("# {address} ({})", resource.actions.join(", "));
     }
+    let output = pretty(resource.node.to_compact_string().as_bytes(), color, config)
+        .map_err(|err| (EXIT_INVALID_JSON, err))?;
+    println!("{output}");
+    Ok(())
+}
 
-    for arg in &args {
-        if arg == "--help" || arg == "-h" {
-            print_usage();
-            std::process::exit(0);
+/// Formats an Elasticsearch `_bulk` request body: each action is printed compact, followed by
+/// its formatted document.
+fn format_bulk(buffer: &[u8], config: &Config, color: Color) -> Result<(), (i32, String)> {
+    let actions = cli::ndjson::bulk_actions(buffer).map_err(|err| (EXIT_INVALID_JSON, err))?;
+    let mut buffers = Buffers::default();
+    for (i, BulkAction { action, document }) in actions.iter().enumerate() {
+        if !config.quiet {
+            if i > 0 {
+                println!();
+            }
+            let action =
+                value::parse(action.bytes).map_err(|err| (EXIT_INVALID_JSON, err.to_string()))?;
+            let mut text = String::new();
+            _ = style(color, config).write_styled(
+                Style::Punctuation,
+                &action.to_compact_string(),
+                &mut text,
+            );
+            println!("{text}");
+        }
+        if let Some(document) = document {
+            format_record(i, document, 1, config, color, &mut buffers)?;
         }
     }
+    Ok(())
+}
 
-    let mut with_serde = false;
-    let mut with_color = true;
-    let mut iter_count = 1;
-    let mut file_path: Option<Option<PathBuf>> = None;
-    let mut args_iter = args.into_iter();
+/// Formats each record of an NDJSON standard input as soon as its line is read, so records of a
+/// slow producer are printed without waiting for the end of the input.
+fn format_stdin_records(
+    name: &Option<String>,
+    config: &Config,
+    color: Color,
+) -> Result<(), (i32, String)> {
+    let mut stdin = std::io::stdin().lock();
+    let mut buffers = Buffers::for_records(config);
+    let mut line = Vec::new();
+    let mut line_number = 0;
+    let mut total = 0;
+    // Same selection as `format_records`: index of the non-blank records, records in the time
+    // window, and records printed.
+    let (mut index, mut matched, mut printed) = (0, 0, 0);
+    while printed < config.head.unwrap_or(usize::MAX) {
+        line.clear();
+        let read = stdin
+            .read_until(b'\n', &mut line)
+            .map_err(|err| (EXIT_IO_ERROR, format!("Error reading from stdin: {err}")))?;
+        if read == 0 {
+            break;
+        }
+        line_number += 1;
+        total += read;
+        if let Some(max) = config.max_input_size
+            && total > max
+        {
+            let err = format!(
+                "Error reading {}: input is larger than {max} bytes",
+                Input::Stdin(name.clone())
+            );
+            return Err((EXIT_IO_ERROR, err));
+        }
+        let bytes = line.strip_suffix(b"\n").unwrap_or(&line);
+        let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+        if bytes.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let i = index;
+        index += 1;
+        let window = config.time_window.as_ref();
+        if !window.is_none_or(|window| window.contains(bytes)) {
+            continue;
+        }
+        matched += 1;
+        if (matched - 1) % config.sample.unwrap_or(1) != 0 {
+            continue;
+        }
+        printed += 1;
+        let record = Record {
+            line: line_number,
+            bytes,
+        };
+        format_record(i, &record, 1, config, color, &mut buffers)?;
+    }
+    buffers.flush()
+}
 
-    while let Some(arg) = args_iter.next() {
-        match arg.as_str() {
-            "--serde" => {
-                with_serde = true;
-            }
-            "--no-color" => {
-                with_color = false;
-            }
-            "--iter" => {
-                if let Some(value) = args_iter.next() {
-                    match value.parse::<usize>() {
-                        Ok(v) => iter_count = v,
-                        Err(_) => {
-                            let err = format!("Invalid value for --iter: {value}");
-                            return Err(err);
-                        }
-                    }
-                } else {
-                    return Err("Missing value for --iter".to_string());
-                }
+/// Prints the records of an NDJSON input as the items of a pretty printed array. Records are
+/// read and printed one by one, so the memory used doesn't depend on the size of the input.
+fn format_collected(input: &Input, config: &Config, color: Color) -> Result<(), (i32, String)> {
+    let read_error = |err: std::io::Error| (EXIT_IO_ERROR, format!("Error reading {input}: {err}"));
+    let write_error =
+        |err: std::io::Error| (EXIT_IO_ERROR, format!("Error writing to stdout: {err}"));
+    let mut reader: Box<dyn BufRead> = match input {
+        Input::Stdin(_) => Box::new(std::io::stdin().lock()),
+        Input::File(path) => Box::new(std::io::BufReader::new(
+            std::fs::File::open(path).map_err(read_error)?,
+        )),
+        Input::Url(_) => {
+            let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+            Box::new(std::io::Cursor::new(buffer))
+        }
+    };
+    let style = style(color, config);
+    let indent = match config.indent.unwrap_or_default() {
+        Indent::Spaces(n) => " ".repeat(n),
+        Indent::Tab => "\t".to_string(),
+    };
+    let line_ending = config.line_ending.unwrap_or_default().as_str();
+    let mut out = std::io::stdout().lock();
+    let mut buffers = Buffers::default();
+    let mut line = Vec::new();
+    let (mut line_number, mut total, mut count) = (0, 0, 0);
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line).map_err(read_error)?;
+        if read == 0 {
+            break;
+        }
+        line_number += 1;
+        total += read;
+        if let Some(max) = config.max_input_size
+            && total > max
+        {
+            let err = format!("Error reading {input}: input is larger than {max} bytes");
+            return Err((EXIT_IO_ERROR, err));
+        }
+        let bytes = line.strip_suffix(b"\n").unwrap_or(&line);
+        let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+        if bytes.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let at_line = |(code, err)| (code, format!("line {line_number}: {err}"));
+        buffers.arena.reset();
+        let transformed = transform_input(bytes, config, &buffers.arena).map_err(at_line)?;
+        pretty_into(&transformed, color, config, &mut buffers)
+            .map_err(|err| at_line((EXIT_INVALID_JSON, err)))?;
+        if config.quiet {
+            continue;
+        }
+        // Items are separated by a comma, at the end of the previous item.
+        let mut text = String::new();
+        let separator = if count == 0 { "[" } else { "," };
+        _ = style.write_styled(Style::Punctuation, separator, &mut text);
+        text.push_str(line_ending);
+        for (i, line) in buffers.output.split('\n').enumerate() {
+            if i > 0 {
+                text.push('\n');
             }
-            other => {
-                if file_path.is_none() {
-                    if other == "-" {
-                        file_path = Some(None);
-                    } else {
-                        file_path = Some(Some(PathBuf::from(other)));
+            text.push_str(&indent);
+            text.push_str(line);
+        }
+        out.write_all(text.as_bytes()).map_err(write_error)?;
+        count += 1;
+    }
+    if config.quiet {
+        return Ok(());
+    }
+    let mut text = String::new();
+    if count == 0 {
+        _ = style.write_styled(Style::Punctuation, "[]", &mut text);
+    } else {
+        text.push_str(line_ending);
+        _ = style.write_styled(Style::Punctuation, "]", &mut text);
+    }
+    text.push_str(final_newline(config, None));
+    out.write_all(text.as_bytes()).map_err(write_error)?;
+    out.flush().map_err(write_error)
+}
+
+/// Formats the `i`th record of an NDJSON input, repeated `count` times, and prints it on standard
+/// output.
+fn format_record(
+    i: usize,
+    record: &Record,
+    count: usize,
+    config: &Config,
+    color: Color,
+    buffers: &mut Buffers,
+) -> Result<(), (i32, String)> {
+    let at_line = |(code, err)| (code, format!("line {}: {err}", record.line));
+    // Memory of the previous record is reused.
+    buffers.arena.reset();
+    let transformed = transform_input(record.bytes, config, &buffers.arena).map_err(at_line)?;
+    pretty_into(&transformed, color, config, buffers)
+        .map_err(|err| at_line((EXIT_INVALID_JSON, err)))?;
+    if config.quiet {
+        return Ok(());
+    }
+    let line_ending = config.line_ending.unwrap_or_default().as_str();
+    let mut text = std::mem::take(&mut buffers.record);
+    text.clear();
+    if let Some(template) = &config.record_template {
+        template.render(i + 1, record, &mut text);
+        text.push_str(line_ending);
+    }
+    text.push_str(&buffers.output);
+    text.push_str(line_ending);
+    if count > 1 {
+        _ = style(color, config).write_styled(Style::Comment, &format!("× {count}"), &mut text);
+        text.push_str(line_ending);
+    }
+    let written = buffers.write_record(&text);
+    buffers.record = text;
+    written
+}
+
+/// Returns the line break written at the end of the output (empty if there is none). `original`
+/// is the content of the file being formatted in place or checked, whose convention is kept by
+/// default.
+fn final_newline(config: &Config, original: Option<&[u8]>) -> &'static str {
+    let final_newline = config
+        .final_newline
+        .unwrap_or_else(|| original.is_none_or(|original| original.ends_with(b"\n")));
+    if final_newline {
+        config.line_ending.unwrap_or_default().as_str()
+    } else {
+        ""
+    }
+}
+
+/// Returns the byte order mark written at the start of the output (empty if there is none).
+fn bom(config: &Config, original: &[u8]) -> &'static str {
+    match config.bom {
+        Bom::Keep if original.starts_with(b"\xEF\xBB\xBF") => "\u{feff}",
+        Bom::Add => "\u{feff}",
+        Bom::Keep | Bom::Strip => "",
+    }
+}
+
+/// Returns `true` if `buffer` is already formatted. This is checked without building the formatted
+/// output, stopping at the first difference, which is fast for files that are mostly formatted.
+fn is_formatted(buffer: &[u8], config: &Config) -> bool {
+    // Warnings are reported by the complete formatting.
+    if cli::transform::has_transforms(config) || config.warnings {
+        return false;
+    }
+    let bom = bom(config, buffer);
+    let final_newline = final_newline(config, Some(buffer));
+    let Some(content) = buffer
+        .strip_prefix(bom.as_bytes())
+        .and_then(|content| content.strip_suffix(final_newline.as_bytes()))
+    else {
+        return false;
+    };
+    formatter(buffer, Color::NoColor, config).formats_to(content)
+}
+
+/// Formats one file in place, the file is only written if its content has changed.
+fn write_input(input: &Input, config: &Config) -> Result<(), (i32, String)> {
+    let Input::File(path) = input else {
+        unreachable!("--write is only allowed with files")
+    };
+    let config = &file_config(path, config);
+    let mut log = Log::new(config.log, input);
+    let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+    log.phase("read", buffer.len());
+    if is_formatted(&buffer, config) {
+        log.phase("check", buffer.len());
+        return Ok(());
+    }
+    let transformed = transform_input(&buffer, config, &Arena::default())?;
+    let mut output = bom(config, &buffer).to_string();
+    let formatted =
+        pretty(&transformed, Color::NoColor, config).map_err(|err| (EXIT_INVALID_JSON, err))?;
+    output.push_str(&formatted);
+    output.push_str(final_newline(config, Some(&buffer)));
+    log.phase("format", output.len());
+    if output.as_bytes() != buffer {
+        std::fs::write(path, &output).map_err(|err| {
+            let err = format!("Error writing file '{}': {}", path.display(), err);
+            (EXIT_IO_ERROR, err)
+        })?;
+        log.phase("write", output.len());
+    }
+    Ok(())
+}
+
+/// Checks that one input is formatted, returns an error with the input name, or a diff of the
+/// changes, if it's not.
+fn check_input(input: &Input, config: &Config, color: Color) -> Result<(), (i32, String)> {
+    let config = &match input {
+        Input::File(path) => file_config(path, config),
+        _ => Cow::Borrowed(config),
+    };
+    let mut log = Log::new(config.log, input);
+    let buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+    log.phase("read", buffer.len());
+    check_buffer(input, &buffer, config, color, &mut log)
+}
+
+/// Checks that the content of an input is formatted.
+fn check_buffer(
+    input: &Input,
+    buffer: &[u8],
+    config: &Config,
+    color: Color,
+    log: &mut Log,
+) -> Result<(), (i32, String)> {
+    if is_formatted(buffer, config) {
+        log.phase("check", buffer.len());
+        return Ok(());
+    }
+    let transformed = transform_input(buffer, config, &Arena::default())?;
+    let mut output = bom(config, buffer).to_string();
+    let formatted =
+        pretty(&transformed, Color::NoColor, config).map_err(|err| (EXIT_INVALID_JSON, err))?;
+    output.push_str(&formatted);
+    output.push_str(final_newline(config, Some(buffer)));
+    log.phase("format", output.len());
+    if output.as_bytes() == buffer {
+        return Ok(());
+    }
+    let message = if config.diff {
+        // The input is valid JSON, so valid UTF-8 (after a potential BOM).
+        let original = String::from_utf8_lossy(buffer);
+        let name = input.to_string();
+        let name = name.trim_matches('\'');
+        let color = color == Color::AnsiCode;
+        cli::diff::unified_diff(&original, &output, name, name, color)
+    } else {
+        format!("{input} is not formatted\n")
+    };
+    Err((EXIT_UNFORMATTED, message))
+}
+
+/// Checks the JSON files staged in the git index. With `--write`, unformatted files without
+/// unstaged changes are formatted and staged again. Returns the exit code and the report of the
+/// failed files, if any.
+fn git_hook(config: &Config, color: Color) -> Result<(), (i32, String)> {
+    let files = cli::git::staged_json_files().map_err(|err| (EXIT_IO_ERROR, format!("{err}\n")))?;
+    let mut report = String::new();
+    let mut exit_code = EXIT_OK;
+    let mut failures = 0;
+    for file in &files {
+        let input = Input::File(file.path.clone());
+        let config = &file_config(&file.path, config);
+        let mut log = Log::new(config.log, &input);
+        let result = cli::git::staged_content(file)
+            .map_err(|err| (EXIT_IO_ERROR, err))
+            .and_then(|staged| {
+                log.phase("read", staged.len());
+                match check_buffer(&input, &staged, config, color, &mut log) {
+                    Err((EXIT_UNFORMATTED, _)) if config.write => {
+                        fix_staged(&input, &staged, config)
                     }
-                } else {
-                    let err = format!("Unknown argument: {other}");
-                    return Err(err);
+                    result => result,
                 }
-            }
+            });
+        let Err((code, err)) = result else {
+            continue;
+        };
+        failures += 1;
+        // I/O errors take precedence over invalid JSON, that takes precedence over unformatted
+        // files.
+        exit_code = match (exit_code, code) {
+            (EXIT_OK | EXIT_UNFORMATTED, code) => code,
+            (EXIT_INVALID_JSON, EXIT_IO_ERROR) => EXIT_IO_ERROR,
+            (exit_code, _) => exit_code,
+        };
+        if code == EXIT_UNFORMATTED {
+            report.push_str(&err);
+        } else {
+            report.push_str(&format!("Error in {input}: {err}\n"));
+        }
+    }
+    if failures == 0 {
+        return Ok(());
+    }
+    if config.quiet {
+        report.clear();
+    } else {
+        report.push_str(&format!(
+            "{failures} of {} staged JSON files failed\n",
+            files.len()
+        ));
+    }
+    Err((exit_code, report))
+}
+
+/// Formats a staged file in place and stages it again, if it has no unstaged changes.
+fn fix_staged(input: &Input, staged: &[u8], config: &Config) -> Result<(), (i32, String)> {
+    let Input::File(path) = input else {
+        unreachable!("staged inputs are files")
+    };
+    let content = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+    if content != staged {
+        let err = format!("{input} is not formatted and has unstaged changes\n");
+        return Err((EXIT_UNFORMATTED, err));
+    }
+    write_input(input, config)?;
+    cli::git::stage(path).map_err(|err| (EXIT_IO_ERROR, err))
+}
+
+/// Returns the configuration of a file formatted in place or checked: the settings of its
+/// `.editorconfig` files are used for the options that are not set.
+fn file_config<'a>(path: &std::path::Path, config: &'a Config) -> Cow<'a, Config> {
+    if !config.editorconfig {
+        return Cow::Borrowed(config);
+    }
+    let settings = cli::editorconfig::settings(path);
+    if settings == cli::editorconfig::Settings::default() {
+        return Cow::Borrowed(config);
+    }
+    let mut config = config.clone();
+    config.indent = config.indent.or(settings.indent);
+    config.line_ending = config.line_ending.or(settings.line_ending);
+    config.final_newline = config.final_newline.or(settings.final_newline);
+    Cow::Owned(config)
+}
+
+/// Reads the whole input bytes, converted to JSON from the configured (or detected) format, with
+/// the configured transformations applied.
+fn read_transformed_input(
+    input: &Input,
+    config: &Config,
+    log: &Log,
+) -> Result<Vec<u8>, (i32, String)> {
+    let mut buffer = read_input(input, config).map_err(|err| (EXIT_IO_ERROR, err))?;
+    let from = match config.from {
+        InputFormat::Auto => {
+            let from = cli::detect::detect(&buffer).map_err(|err| (EXIT_INVALID_JSON, err))?;
+            log.detected(from);
+            from
+        }
+        from => from,
+    };
+    let converted = match from {
+        InputFormat::Auto => Ok(None),
+        InputFormat::Json => Ok(cli::base64::decode_json(&buffer)),
+        InputFormat::Base64 => cli::base64::decode(buffer.trim_ascii())
+            .map(Some)
+            .ok_or_else(|| "Invalid base64 input".to_string()),
+        InputFormat::Xml => cli::xml::from_xml(&buffer).map(Some),
+        InputFormat::Form => cli::form::from_form(&buffer).map(Some),
+        InputFormat::Jwt => cli::jwt::from_jwt(&buffer).map(Some),
+    };
+    if let Some(converted) = converted.map_err(|err| (EXIT_INVALID_JSON, err))? {
+        buffer = converted;
+    }
+    match transform_input(&buffer, config, &Arena::default())? {
+        Cow::Borrowed(_) => Ok(buffer),
+        Cow::Owned(transformed) => Ok(transformed),
+    }
+}
+
+/// Applies the configured transformations to the input bytes.
+fn transform_input<'a>(
+    buffer: &'a [u8],
+    config: &Config,
+    arena: &Arena,
+) -> Result<Cow<'a, [u8]>, (i32, String)> {
+    if cli::transform::has_transforms(config) {
+        let transformed = cli::transform::apply(buffer, config, arena)?;
+        Ok(Cow::Owned(transformed))
+    } else {
+        Ok(Cow::Borrowed(buffer))
+    }
+}
+
+/// Reads the whole input bytes, failing if the input is larger than the configured maximum size.
+fn read_input(input: &Input, config: &Config) -> Result<Vec<u8>, String> {
+    // One byte more than the maximum size is read to detect larger inputs.
+    let limit = config.max_input_size.map_or(u64::MAX, |max| max as u64 + 1);
+    let buffer = match input {
+        Input::Stdin(_) => {
+            let mut buffer = Vec::new();
+            std::io::stdin()
+                .take(limit)
+                .read_to_end(&mut buffer)
+                .map_err(|err| format!("Error reading from stdin: {err}"))?;
+            buffer
+        }
+        Input::File(path) => {
+            let mut buffer = Vec::new();
+            std::fs::File::open(path)
+                .and_then(|file| file.take(limit).read_to_end(&mut buffer))
+                .map_err(|err| format!("Error reading file '{}': {}", path.display(), err))?;
+            buffer
+        }
+        Input::Url(url) => cli::http::fetch(url, &config.headers, limit)
+            .map_err(|err| format!("Error fetching '{url}': {err}"))?,
+    };
+    match config.max_input_size {
+        Some(max) if buffer.len() > max => Err(format!(
+            "Error reading {input}: input is larger than {max} bytes"
+        )),
+        _ => Ok(buffer),
+    }
+}
+
+/// Memory reused between NDJSON records and `--iter` iterations.
+#[derive(Default)]
+struct Buffers {
+    /// Annotations and sort keys.
+    arena: Arena,
+    /// Formatted output.
+    output: String,
+    /// Printed record: the formatted output with its header and count.
+    record: String,
+    /// Files where records are written instead of standard output (`--split-size`,
+    /// `--split-records`).
+    split: Option<SplitWriter>,
+}
+
+impl Buffers {
+    /// Returns the buffers of records, written to files if the output is split.
+    fn for_records(config: &Config) -> Self {
+        Buffers {
+            split: config.split.clone().map(SplitWriter::new),
+            ..Buffers::default()
+        }
+    }
+
+    /// Prints a record, or writes it to the current output file.
+    fn write_record(&mut self, record: &str) -> Result<(), (i32, String)> {
+        match &mut self.split {
+            Some(split) => split.write_record(record),
+            None => std::io::stdout()
+                .write_all(record.as_bytes())
+                .map_err(|err| format!("Error writing to stdout: {err}")),
+        }
+        .map_err(|err| (EXIT_IO_ERROR, err))
+    }
+
+    /// Writes the buffered records.
+    fn flush(&mut self) -> Result<(), (i32, String)> {
+        match &mut self.split {
+            Some(split) => split.flush(),
+            None => std::io::stdout()
+                .flush()
+                .map_err(|err| format!("Error writing to stdout: {err}")),
         }
+        .map_err(|err| (EXIT_IO_ERROR, err))
     }
+}
+
+fn pretty_serde(
+    bytes: &[u8],
+    _color: Color,
+    _config: &Config,
+    buffers: &mut Buffers,
+) -> Result<(), String> {
+    let json = serde_json::from_slice::<Value>(bytes).map_err(|err| err.to_string())?;
+    buffers.output = serde_json::to_string_pretty(&json).map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Returns an example document of the JSON Schema `path`, formatted.
+fn example(path: &std::path::Path, color: Color, config: &Config) -> Result<String, (i32, String)> {
+    let schema = std::fs::read(path).map_err(|err| {
+        let err = format!("Error reading file '{}': {err}", path.display());
+        (EXIT_IO_ERROR, err)
+    })?;
+    let invalid = |err: String| {
+        (
+            EXIT_INVALID_JSON,
+            format!("Error in {}: {err}", path.display()),
+        )
+    };
+    let schema = value::parse(&schema).map_err(|err| invalid(err.to_string()))?;
+    let example = cli::example::generate(&schema).map_err(invalid)?;
+    pretty(example.as_bytes(), color, config).map_err(invalid)
+}
+
+fn pretty(bytes: &[u8], color: Color, config: &Config) -> Result<String, String> {
+    let mut buffers = Buffers::default();
+    pretty_into(bytes, color, config, &mut buffers)?;
+    Ok(buffers.output)
+}
+
+/// Formats `bytes` in the output buffer of `buffers`, replacing its content.
+fn pretty_into(
+    bytes: &[u8],
+    color: Color,
+    config: &Config,
+    buffers: &mut Buffers,
+) -> Result<(), String> {
+    buffers.output.clear();
+    let mut formatter = formatter(bytes, color, config).with_arena(&buffers.arena);
+    let result = if config.partial {
+        // Items of an array are only formatted in parallel once the whole input is validated.
+        formatter.format(&mut buffers.output)
+    } else {
+        formatter.format_parallel(config.threads, &mut buffers.output)
+    };
+    result.map_err(|err| err.to_string())?;
+    check_warnings(formatter.warnings(), config)
+}
+
+/// Prints the warnings of an input (unless quiet), fails if warnings are denied.
+fn check_warnings(warnings: &[Warning], config: &Config) -> Result<(), String> {
+    if !config.quiet {
+        for warning in warnings {
+            eprintln!("Warning: {warning}");
+        }
+    }
+    if config.deny_warnings && !warnings.is_empty() {
+        return Err(format!("{} warnings denied", warnings.len()));
+    }
+    Ok(())
+}
 
-    let file_path = file_path.ok_or("Missing required argument: JSON file path")?;
+fn pretty_with_report(
+    bytes: &[u8],
+    color: Color,
+    config: &Config,
+) -> Result<(String, FormatReport), String> {
+    let mut output = String::new();
+    let report = formatter(bytes, color, config)
+        .format_with_report(&mut output)
+        .map_err(|err| err.to_string())?;
+    Ok((output, report))
+}
+
+/// Returns a formatter of `bytes` with the configured options.
+fn formatter<'a>(bytes: &'a [u8], color: Color, config: &'a Config) -> Formatter<'a> {
+    configure(Formatter::new_styled(bytes, style(color, config)), config)
+}
+
+/// Returns the ANSI style of the output, with the theme of `config`.
+fn style(color: Color, config: &Config) -> AnsiStyle<'_> {
+    AnsiStyle::with_theme(color, &config.theme)
+}
+
+/// Sets the options of `config` to a formatter.
+fn configure<'a, S: OutputStyle>(
+    formatter: Formatter<'a, S>,
+    config: &'a Config,
+) -> Formatter<'a, S> {
+    let options = FormatterOptions {
+        align_values: config.align_values,
+        comments: config.jsonc,
+        expand_embedded: config.expand_embedded,
+        render_newlines: config.render_newlines,
+        split_strings: config.split_strings,
+        reveal_invisible: config.reveal_invisible,
+        expand_empty: config.expand_empty,
+        hyperlinks: config.hyperlinks,
+        compact_single: config.compact_single,
+        geojson: config.geojson,
+        avro_unions: config.avro,
+        line_ending: config.line_ending.unwrap_or_default(),
+        indent: config.indent.unwrap_or_default(),
+        // The input size is checked when reading the input, before any transformation.
+        max_input_size: None,
+        max_output_size: config.max_output_size,
+        max_elements: config.max_elements,
+        max_key_length: config.max_key_length,
+        max_string_length: config.max_string_length,
+        warnings: config.warnings,
+    };
+    let formatter = formatter
+        .with_options(&options)
+        .with_types(&config.types)
+        .with_folds(&config.folds);
+    let formatter = if config.proto {
+        formatter.with_decorator(&cli::proto::Proto3)
+    } else {
+        formatter
+    };
+    let formatter = if config.hex_fields.patterns.is_empty() {
+        formatter
+    } else {
+        formatter.with_decorator(&config.hex_fields)
+    };
+    let formatter = if config.file_sizes.patterns.is_empty() {
+        formatter
+    } else {
+        formatter.with_decorator(&config.file_sizes)
+    };
+    let formatter = if config.js_unsafe {
+        formatter.with_decorator(&JsUnsafe)
+    } else {
+        formatter
+    };
+    config
+        .humanizers
+        .iter()
+        .fold(formatter, |formatter, humanizer| {
+            formatter.with_decorator(humanizer)
+        })
+}
 
-    Ok(Config {
-        with_serde,
-        with_color,
-        iter_count,
-        file_path,
-    })
+/// Prints formatting statistics on standard error.
+fn eprint_report(report: &FormatReport) {
+    eprintln!("input size:  {} bytes", report.input_bytes);
+    eprintln!("output size: {} bytes", report.output_bytes);
+    eprintln!(
+        "tokens:      {} ({} objects, {} arrays, {} keys, {} strings, {} numbers, {} literals, {} commas)",
+        report.tokens(),
+        report.objects,
+        report.arrays,
+        report.keys,
+        report.strings,
+        report.numbers,
+        report.literals,
+        report.commas
+    );
+    eprintln!("max depth:   {}", report.max_depth);
+    eprintln!(
+        "elapsed:     {:.3} ms",
+        report.elapsed.as_secs_f64() * 1000.0
+    );
 }
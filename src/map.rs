@@ -0,0 +1,401 @@
+//! A tiny jq-like language to transform documents (`--map`).
+//!
+//! A program applies functions, separated by `|`, to the values selected by a [Query]:
+//! `.items[] |= del(.debug) | rename(.msg, .message)`. Without a query (`del(.a.b)`), functions
+//! are applied to the root value. As in jq, `[]` selects all the items of an array (like `[*]`).
+//!
+//! Functions take queries relative to the values they're applied to:
+//! - `del(QUERY, ...)` deletes the selected values,
+//! - `rename(.OLD, .NEW)` renames the member `OLD` of objects to `NEW`, replacing the member
+//!   `NEW` if there's one,
+//! - `pick(.KEY, ...)` only keeps the members `KEY` of objects.
+//!
+//! Programs are applied to the parsed document, not while formatting: the whole document is held
+//! in memory (as a [Node] tree), like the other transforms of `--set` or `--sort-keys`.
+use crate::query::{Query, Step};
+use crate::value::{Node, Value, escape, unescape};
+use std::str::FromStr;
+
+/// A transform program: functions applied to the values selected by a query.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Map {
+    pub target: Query,
+    pub functions: Vec<Function>,
+}
+
+/// A function of a [Map] program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Function {
+    /// Deletes the values selected by the queries.
+    Del(Vec<Query>),
+    /// Renames an object member, replacing the member with the new key if any.
+    Rename(String, String),
+    /// Only keeps these object members, in their original order.
+    Pick(Vec<String>),
+}
+
+impl FromStr for Map {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = jq_wildcards(s);
+        let (target, functions) = match split_top_level(&s, "|=").as_slice() {
+            [functions] => (Query::default(), *functions),
+            [target, functions] => (query(target)?, *functions),
+            _ => return Err(format!("invalid program {s}: more than one '|='")),
+        };
+        let functions = split_top_level(functions, "|")
+            .into_iter()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Map { target, functions })
+    }
+}
+
+impl FromStr for Function {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let Some((name, args)) = s.strip_suffix(')').and_then(|s| s.split_once('(')) else {
+            return Err(format!("invalid function {s} (expected NAME(ARGS))"));
+        };
+        let args = split_top_level(args, ",")
+            .into_iter()
+            .map(query)
+            .collect::<Result<Vec<_>, _>>()?;
+        let key = |query: &Query| match query.steps.as_slice() {
+            [Step::Key(key)] => Ok(key.clone()),
+            _ => Err(format!("{name} only accepts keys (ex: {name}(.id))")),
+        };
+        match (name.trim(), args.as_slice()) {
+            ("del", [_, ..]) => Ok(Function::Del(args)),
+            ("rename", [old, new]) => Ok(Function::Rename(key(old)?, key(new)?)),
+            ("rename", _) => Err("rename expects 2 keys (ex: rename(.msg, .message))".to_string()),
+            ("pick", [_, ..]) => Ok(Function::Pick(
+                args.iter().map(key).collect::<Result<_, _>>()?,
+            )),
+            ("del" | "pick", []) => Err(format!("{name} expects at least one query")),
+            (name, _) => Err(format!(
+                "unknown function {name} (expected del, rename or pick)"
+            )),
+        }
+    }
+}
+
+/// Parses a query, trimmed.
+fn query(s: &str) -> Result<Query, String> {
+    s.trim().parse()
+}
+
+/// Replaces the `[]` of queries, outside of quoted keys, by `[*]`.
+fn jq_wildcards(s: &str) -> String {
+    let mut replaced = String::with_capacity(s.len());
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        replaced.push(c);
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '[' if !quoted && chars.peek() == Some(&']') => replaced.push('*'),
+            _ => {}
+        }
+    }
+    replaced
+}
+
+/// Splits `s` on `separator`, outside of quoted keys, brackets and parentheses.
+fn split_top_level<'a>(s: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            // `|` of `|=` is not a pipe.
+            _ if depth == 0
+                && i >= start
+                && s[i..].starts_with(separator)
+                && !(separator == "|" && s[i..].starts_with("|=")) =>
+            {
+                parts.push(&s[start..i]);
+                start = i + separator.len();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+impl Map {
+    /// Applies this program to `root`.
+    pub fn apply(&self, root: &mut Node) {
+        for_each_selected(root, &self.target.steps, &mut |node| {
+            for function in &self.functions {
+                function.apply(node);
+            }
+        });
+    }
+}
+
+impl Function {
+    fn apply(&self, node: &mut Node) {
+        match self {
+            Function::Del(queries) => {
+                // As in jq, all the values are selected before any is deleted: indexes of later
+                // queries don't shift.
+                let mut paths = Vec::new();
+                for query in queries {
+                    select_paths(node, &query.steps, &mut Vec::new(), &mut paths);
+                }
+                paths.sort();
+                paths.dedup();
+                // Last items first, so the indexes of the remaining paths stay valid.
+                for path in paths.iter().rev() {
+                    delete(node, path);
+                }
+            }
+            Function::Rename(old, new) => {
+                if let Value::Object(members) = &mut node.value
+                    && old != new
+                    && members.iter().any(|m| unescape(&m.key) == *old)
+                {
+                    members.retain(|m| unescape(&m.key) != *new);
+                    for member in members.iter_mut().filter(|m| unescape(&m.key) == *old) {
+                        member.key = escape(new).into();
+                    }
+                }
+            }
+            Function::Pick(keys) => {
+                if let Value::Object(members) = &mut node.value {
+                    members.retain(|m| keys.contains(&unescape(&m.key)));
+                }
+            }
+        }
+    }
+}
+
+/// Calls `f` on the values of `node` selected by `steps`.
+fn for_each_selected(node: &mut Node, steps: &[Step], f: &mut impl FnMut(&mut Node)) {
+    let Some((step, rest)) = steps.split_first() else {
+        f(node);
+        return;
+    };
+    if *step == Step::Descendants {
+        for_each_selected(node, rest, f);
+    }
+    match (step, &mut node.value) {
+        (Step::Key(key), Value::Object(members)) => {
+            if let Some(member) = members.iter_mut().find(|m| unescape(&m.key) == *key) {
+                for_each_selected(&mut member.node, rest, f);
+            }
+        }
+        (Step::Index(index), Value::Array(nodes)) => {
+            if let Some(node) = nodes.get_mut(*index) {
+                for_each_selected(node, rest, f);
+            }
+        }
+        (Step::Wildcard, Value::Object(members)) => {
+            for member in members {
+                for_each_selected(&mut member.node, rest, f);
+            }
+        }
+        (Step::Wildcard, Value::Array(nodes)) => {
+            for node in nodes {
+                for_each_selected(node, rest, f);
+            }
+        }
+        (Step::Slice(start, end), Value::Array(nodes)) => {
+            let end = end.unwrap_or(nodes.len()).min(nodes.len());
+            let start = start.unwrap_or(0).min(end);
+            for node in &mut nodes[start..end] {
+                for_each_selected(node, rest, f);
+            }
+        }
+        (Step::Descendants, Value::Object(members)) => {
+            for member in members {
+                for_each_selected(&mut member.node, steps, f);
+            }
+        }
+        (Step::Descendants, Value::Array(nodes)) => {
+            for node in nodes {
+                for_each_selected(node, steps, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A member key or an array index of a selected value.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum Location {
+    Key(String),
+    Index(usize),
+}
+
+/// Adds to `paths` the locations of the values of `node` selected by `steps`, `path` being the
+/// location of `node`.
+fn select_paths(
+    node: &Node,
+    steps: &[Step],
+    path: &mut Vec<Location>,
+    paths: &mut Vec<Vec<Location>>,
+) {
+    let Some((step, rest)) = steps.split_first() else {
+        paths.push(path.clone());
+        return;
+    };
+    if *step == Step::Descendants {
+        select_paths(node, rest, path, paths);
+    }
+    let mut select = |location, node, steps| {
+        path.push(location);
+        select_paths(node, steps, path, paths);
+        path.pop();
+    };
+    match (step, &node.value) {
+        (Step::Key(key), Value::Object(members)) => {
+            if let Some(member) = members.iter().find(|m| unescape(&m.key) == *key) {
+                select(Location::Key(key.clone()), &member.node, rest);
+            }
+        }
+        (Step::Index(index), Value::Array(nodes)) => {
+            if let Some(node) = nodes.get(*index) {
+                select(Location::Index(*index), node, rest);
+            }
+        }
+        (Step::Wildcard | Step::Descendants, Value::Object(members)) => {
+            let steps = if *step == Step::Wildcard { rest } else { steps };
+            for member in members {
+                select(Location::Key(unescape(&member.key)), &member.node, steps);
+            }
+        }
+        (Step::Wildcard | Step::Descendants, Value::Array(nodes)) => {
+            let steps = if *step == Step::Wildcard { rest } else { steps };
+            for (i, node) in nodes.iter().enumerate() {
+                select(Location::Index(i), node, steps);
+            }
+        }
+        (Step::Slice(start, end), Value::Array(nodes)) => {
+            let end = end.unwrap_or(nodes.len()).min(nodes.len());
+            let start = start.unwrap_or(0).min(end);
+            for (i, node) in nodes.iter().enumerate().take(end).skip(start) {
+                select(Location::Index(i), node, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Deletes the value of `node` at `path`. The value itself can't be deleted.
+fn delete(node: &mut Node, path: &[Location]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut node = node;
+    for location in parents {
+        let child = match (location, &mut node.value) {
+            (Location::Key(key), Value::Object(members)) => members
+                .iter_mut()
+                .find(|m| unescape(&m.key) == *key)
+                .map(|m| &mut m.node),
+            (Location::Index(index), Value::Array(nodes)) => nodes.get_mut(*index),
+            _ => None,
+        };
+        let Some(child) = child else {
+            return;
+        };
+        node = child;
+    }
+    match (last, &mut node.value) {
+        (Location::Key(key), Value::Object(members)) => {
+            members.retain(|m| unescape(&m.key) != *key)
+        }
+        (Location::Index(index), Value::Array(nodes)) if *index < nodes.len() => {
+            nodes.remove(*index);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Function, Map};
+    use crate::value::parse;
+
+    fn map(program: &str, input: &str) -> String {
+        let mut root = parse(input.as_bytes()).unwrap();
+        program.parse::<Map>().unwrap().apply(&mut root);
+        root.to_compact_string()
+    }
+
+    #[test]
+    fn parse_programs() {
+        let program = r#".items[] |= del(.debug, .["a|b"]) | rename(.msg, .message)"#;
+        let program = program.parse::<Map>().unwrap();
+        assert_eq!(program.target, ".items[*]".parse().unwrap());
+        assert_eq!(
+            program.functions,
+            [
+                Function::Del(vec![
+                    ".debug".parse().unwrap(),
+                    r#".["a|b"]"#.parse().unwrap()
+                ]),
+                Function::Rename("msg".to_string(), "message".to_string()),
+            ]
+        );
+        let program = "pick(.id, .name)".parse::<Map>().unwrap();
+        assert_eq!(program.target, ".".parse().unwrap());
+        assert!("del()".parse::<Map>().is_err());
+        assert!("keys(.a)".parse::<Map>().is_err());
+        assert!("rename(.a)".parse::<Map>().is_err());
+        assert!("pick(.a.b)".parse::<Map>().is_err());
+        assert!(".a |= del(.b) |= del(.c)".parse::<Map>().is_err());
+    }
+
+    #[test]
+    fn apply_programs() {
+        let input =
+            r#"{"items": [{"id": 1, "debug": {}, "msg": "a"}, {"id": 2, "msg": "b"}], "n": 2}"#;
+        assert_eq!(
+            map(".items[] |= del(.debug) | rename(.msg, .message)", input),
+            r#"{"items":[{"id":1,"message":"a"},{"id":2,"message":"b"}],"n":2}"#
+        );
+        assert_eq!(
+            map(".items[] |= pick(.msg)", input),
+            r#"{"items":[{"msg":"a"},{"msg":"b"}],"n":2}"#
+        );
+        assert_eq!(map("del(.items, .x)", input), r#"{"n":2}"#);
+        assert_eq!(
+            map("del(..id, .items[0])", input),
+            r#"{"items":[{"msg":"b"}],"n":2}"#
+        );
+        assert_eq!(
+            map(".items |= del(.[1:])", input),
+            r#"{"items":[{"id":1,"debug":{},"msg":"a"}],"n":2}"#
+        );
+        assert_eq!(map(". |= del(.)", "[1]"), "[1]");
+        // Renaming to an existing key replaces its member.
+        assert_eq!(map("rename(.a, .b)", r#"{"a":1,"b":2}"#), r#"{"b":1}"#);
+        assert_eq!(map("rename(.b, .a)", r#"{"a":1,"b":2}"#), r#"{"a":2}"#);
+        assert_eq!(map("rename(.x, .a)", r#"{"a":1}"#), r#"{"a":1}"#);
+        assert_eq!(map("rename(.a, .a)", r#"{"a":1}"#), r#"{"a":1}"#);
+        // Values are selected before deletions, as in jq.
+        assert_eq!(
+            map("del(.items[0], .items[1])", r#"{"items":[{"a":1},5,"x"]}"#),
+            r#"{"items":["x"]}"#
+        );
+        assert_eq!(map("del(.[0], .[1:], .[0].a)", r#"[{"a":1},2,3]"#), "[]");
+    }
+}
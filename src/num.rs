@@ -0,0 +1,79 @@
+//! Rendering of floating point numbers, identical on every platform.
+//!
+//! Floats are written with the shortest decimal that parses back to the same double (the Ryū
+//! algorithm), in the canonical form of [normalize_numbers](crate::transform::normalize_numbers):
+//! no trailing zeros in fractions and lowercase exponents without `+`.
+/// Returns the shortest JSON number parsing back to `x`, `None` for NaN and infinities that
+/// can't be written in JSON.
+pub fn format_f64(x: f64) -> Option<String> {
+    if !x.is_finite() {
+        return None;
+    }
+    let mut buffer = ryu::Buffer::new();
+    let formatted = buffer.format_finite(x);
+    let formatted = formatted.strip_suffix(".0").unwrap_or(formatted);
+    Some(formatted.to_string())
+}
+
+/// Returns the shortest form of a JSON float, `None` if it's already in this form. Integers are
+/// kept, as they may not be representable as doubles, and so are floats overflowing a double.
+pub fn shortest(raw: &str) -> Option<String> {
+    if !raw.contains(['.', 'e', 'E']) {
+        return None;
+    }
+    let shortest = format_f64(raw.parse().ok()?)?;
+    (shortest != raw).then_some(shortest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_floats() {
+        let datas = [
+            (0.0, "0"),
+            (-0.0, "-0"),
+            (1.0, "1"),
+            (0.1, "0.1"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (135.5, "135.5"),
+            (1.5e7, "15000000"),
+            (1e16, "1e16"),
+            (-1.25e-7, "-1.25e-7"),
+            // Extreme exponents
+            (f64::MAX, "1.7976931348623157e308"),
+            (f64::MIN, "-1.7976931348623157e308"),
+            (1e-300, "1e-300"),
+            // Subnormals
+            (f64::MIN_POSITIVE, "2.2250738585072014e-308"),
+            (2.225073858507201e-308, "2.225073858507201e-308"),
+            (5e-324, "5e-324"),
+            (-5e-324, "-5e-324"),
+        ];
+        for (x, formatted) in datas {
+            assert_eq!(format_f64(x).as_deref(), Some(formatted));
+            assert_eq!(formatted.parse::<f64>().unwrap().to_bits(), x.to_bits());
+        }
+        assert_eq!(format_f64(f64::NAN), None);
+        assert_eq!(format_f64(f64::INFINITY), None);
+        assert_eq!(format_f64(f64::NEG_INFINITY), None);
+    }
+
+    #[test]
+    fn shortest_floats() {
+        let datas = [
+            ("12345678901234567890", None),
+            ("1.0", Some("1")),
+            ("0.1000000000000000055511151231257827", Some("0.1")),
+            ("1.50E+07", Some("15000000")),
+            ("4.9406564584124654e-324", Some("5e-324")),
+            ("2e-324", Some("0")),
+            ("1e400", None),
+            ("1e16", None),
+        ];
+        for (raw, formatted) in datas {
+            assert_eq!(shortest(raw).as_deref(), formatted);
+        }
+    }
+}
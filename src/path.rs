@@ -0,0 +1,194 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A path to a value in a JSON document: `.store.book[0].title`, `.["key with spaces"]`.
+///
+/// The root path is `.`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Path {
+    pub segments: Vec<Segment>,
+}
+
+/// A path segment.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Segment {
+    /// An object key (decoded, without quotes and escapes).
+    Key(String),
+    /// An array index.
+    Index(usize),
+}
+
+impl FromStr for Path {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = PathParser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        parser
+            .parse()
+            .map_err(|err| format!("invalid path {s}: {err}"))
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segments.is_empty() {
+            return write!(f, ".");
+        }
+        for segment in &self.segments {
+            match segment {
+                Segment::Key(key) if is_identifier(key) => write!(f, ".{key}")?,
+                Segment::Key(key) => write!(f, "[{}]", crate::value::escape(key))?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if this key can be written without quotes in a path (`.key`).
+pub fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Matches `text` against a pattern where `*` matches any sequence of chars.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[u8], text: &[u8]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some((b'*', rest)) => (0..=text.len()).any(|i| matches(rest, &text[i..])),
+            Some((b, rest)) => text.first() == Some(b) && matches(rest, &text[1..]),
+        }
+    }
+    matches(pattern.as_bytes(), text.as_bytes())
+}
+
+struct PathParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl PathParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse(&mut self) -> Result<Path, String> {
+        let mut segments = Vec::new();
+        if self.peek() != Some('.') && self.peek() != Some('[') {
+            return Err("a path must start with '.' or '['".to_string());
+        }
+        // The root path
+        if self.chars == ['.'] {
+            return Ok(Path { segments });
+        }
+        while let Some(c) = self.peek() {
+            match c {
+                '.' => {
+                    self.pos += 1;
+                    if self.peek() == Some('[') {
+                        continue;
+                    }
+                    segments.push(Segment::Key(self.parse_identifier()?));
+                }
+                '[' => {
+                    self.pos += 1;
+                    segments.push(self.parse_bracket()?);
+                }
+                c => return Err(format!("unexpected char '{c}' at position {}", self.pos)),
+            }
+        }
+        Ok(Path { segments })
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c == '.' || c == '[' {
+                break;
+            }
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(format!("missing key at position {start}"));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// Parses `[0]` or `["key"]`, the opening bracket being already consumed.
+    fn parse_bracket(&mut self) -> Result<Segment, String> {
+        let segment = if self.peek() == Some('"') {
+            let start = self.pos;
+            self.pos += 1;
+            loop {
+                match self.peek() {
+                    Some('"') => break,
+                    Some('\\') => self.pos += 2,
+                    Some(_) => self.pos += 1,
+                    None => return Err("unterminated key".to_string()),
+                }
+            }
+            self.pos += 1;
+            let raw = self.chars[start..self.pos].iter().collect::<String>();
+            Segment::Key(crate::value::unescape(&raw))
+        } else {
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.pos += 1;
+            }
+            let digits = self.chars[start..self.pos].iter().collect::<String>();
+            let index = digits
+                .parse::<usize>()
+                .map_err(|_| format!("invalid index at position {start}"))?;
+            Segment::Index(index)
+        };
+        if self.peek() != Some(']') {
+            return Err(format!("missing ']' at position {}", self.pos));
+        }
+        self.pos += 1;
+        Ok(segment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(k: &str) -> Segment {
+        Segment::Key(k.to_string())
+    }
+
+    #[test]
+    fn parse_paths() {
+        assert_eq!(".".parse::<Path>().unwrap().segments, vec![]);
+        assert_eq!(
+            ".store.book[0].title".parse::<Path>().unwrap().segments,
+            vec![key("store"), key("book"), Segment::Index(0), key("title")]
+        );
+        assert_eq!(
+            r#".["a b"].c[12]["d\"e"]"#.parse::<Path>().unwrap().segments,
+            vec![key("a b"), key("c"), Segment::Index(12), key("d\"e")]
+        );
+        assert_eq!(
+            "[1][2]".parse::<Path>().unwrap().segments,
+            vec![Segment::Index(1), Segment::Index(2)]
+        );
+        assert!("a.b".parse::<Path>().is_err());
+        assert!(".a[".parse::<Path>().is_err());
+        assert!(".a[x]".parse::<Path>().is_err());
+        assert!(".a..b".parse::<Path>().is_err());
+    }
+
+    #[test]
+    fn display_paths() {
+        for path in [".", ".a.b[0]", r#".a["b c"][1]"#, ".x-y"] {
+            assert_eq!(path.parse::<Path>().unwrap().to_string(), path);
+        }
+    }
+}
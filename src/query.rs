@@ -0,0 +1,339 @@
+use crate::path::{Path, Segment};
+use crate::value::{Node, Value, unescape};
+use std::str::FromStr;
+
+/// A query selecting values in a JSON document, a [Path] extended with wildcards, slices and
+/// recursive descent: `.items[*].name`, `.data[2:10]`, `..id`.
+///
+/// The root query is `.`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Query {
+    pub steps: Vec<Step>,
+}
+
+/// A query step, each step selects values from the values selected by the previous step.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Step {
+    /// The value of an object key (decoded, without quotes and escapes).
+    Key(String),
+    /// An array item.
+    Index(usize),
+    /// All the values of an object, or all the items of an array: `.*`, `[*]`.
+    Wildcard,
+    /// Array items from `start` (included) to `end` (excluded): `[2:10]`, `[:3]`, `[5:]`.
+    Slice(Option<usize>, Option<usize>),
+    /// The value and all its descendants: `..`, always followed by another step.
+    Descendants,
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = QueryParser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        parser
+            .parse()
+            .map_err(|err| format!("invalid query {s}: {err}"))
+    }
+}
+
+impl From<&Path> for Query {
+    fn from(path: &Path) -> Self {
+        let steps = path
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => Step::Key(key.clone()),
+                Segment::Index(index) => Step::Index(*index),
+            })
+            .collect();
+        Query { steps }
+    }
+}
+
+impl Query {
+    /// Returns the values selected by this query, in the order of the document.
+    pub fn select<'a, 'input>(&self, root: &'a Node<'input>) -> Vec<&'a Node<'input>> {
+        let mut nodes = vec![root];
+        for step in &self.steps {
+            let mut selected = Vec::new();
+            for node in nodes {
+                step.select(node, &mut selected);
+            }
+            nodes = selected;
+        }
+        nodes
+    }
+
+    /// Returns `true` if the value at `path` is selected by this query. Unlike [Query::select],
+    /// values of duplicate keys are all matched.
+    pub fn matches(&self, path: &Path) -> bool {
+        fn matches(steps: &[Step], segments: &[Segment]) -> bool {
+            let Some((step, rest)) = steps.split_first() else {
+                return segments.is_empty();
+            };
+            if *step == Step::Descendants {
+                return (0..=segments.len()).any(|i| matches(rest, &segments[i..]));
+            }
+            let Some((segment, segments)) = segments.split_first() else {
+                return false;
+            };
+            let matched = match (step, segment) {
+                (Step::Key(key), Segment::Key(name)) => key == name,
+                (Step::Index(index), Segment::Index(i)) => index == i,
+                (Step::Wildcard, _) => true,
+                (Step::Slice(start, end), Segment::Index(i)) => {
+                    start.is_none_or(|start| *i >= start) && end.is_none_or(|end| *i < end)
+                }
+                _ => false,
+            };
+            matched && matches(rest, segments)
+        }
+        matches(&self.steps, &path.segments)
+    }
+}
+
+impl Step {
+    /// Pushes the values selected by this step from `node` to `selected`.
+    fn select<'a, 'input>(&self, node: &'a Node<'input>, selected: &mut Vec<&'a Node<'input>>) {
+        match (self, &node.value) {
+            (Step::Key(key), Value::Object(members)) => {
+                if let Some(member) = members.iter().find(|m| unescape(&m.key) == *key) {
+                    selected.push(&member.node);
+                }
+            }
+            (Step::Index(index), Value::Array(nodes)) => selected.extend(nodes.get(*index)),
+            (Step::Wildcard, Value::Object(members)) => {
+                selected.extend(members.iter().map(|m| &m.node));
+            }
+            (Step::Wildcard, Value::Array(nodes)) => selected.extend(nodes),
+            (Step::Slice(start, end), Value::Array(nodes)) => {
+                let end = end.unwrap_or(nodes.len()).min(nodes.len());
+                let start = start.unwrap_or(0).min(end);
+                selected.extend(&nodes[start..end]);
+            }
+            (Step::Descendants, value) => {
+                selected.push(node);
+                match value {
+                    Value::Object(members) => {
+                        for member in members {
+                            Step::Descendants.select(&member.node, selected);
+                        }
+                    }
+                    Value::Array(nodes) => {
+                        for node in nodes {
+                            Step::Descendants.select(node, selected);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct QueryParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse(&mut self) -> Result<Query, String> {
+        let mut steps = Vec::new();
+        if self.peek() != Some('.') && self.peek() != Some('[') {
+            return Err("a query must start with '.' or '['".to_string());
+        }
+        // The root query
+        if self.chars == ['.'] {
+            return Ok(Query { steps });
+        }
+        while let Some(c) = self.peek() {
+            match c {
+                '.' => {
+                    self.pos += 1;
+                    if self.peek() == Some('.') {
+                        self.pos += 1;
+                        steps.push(Step::Descendants);
+                    }
+                    match self.peek() {
+                        Some('[') => continue,
+                        Some('*') => {
+                            self.pos += 1;
+                            steps.push(Step::Wildcard);
+                        }
+                        _ => steps.push(Step::Key(self.parse_identifier()?)),
+                    }
+                }
+                '[' => {
+                    self.pos += 1;
+                    steps.push(self.parse_bracket()?);
+                }
+                c => return Err(format!("unexpected char '{c}' at position {}", self.pos)),
+            }
+        }
+        Ok(Query { steps })
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c == '.' || c == '[' {
+                break;
+            }
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(format!("missing key at position {start}"));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// Parses `[0]`, `["key"]`, `[*]` or `[2:10]`, the opening bracket being already consumed.
+    fn parse_bracket(&mut self) -> Result<Step, String> {
+        let step = match self.peek() {
+            Some('"') => {
+                let start = self.pos;
+                self.pos += 1;
+                loop {
+                    match self.peek() {
+                        Some('"') => break,
+                        Some('\\') => self.pos += 2,
+                        Some(_) => self.pos += 1,
+                        None => return Err("unterminated key".to_string()),
+                    }
+                }
+                self.pos += 1;
+                let raw = self.chars[start..self.pos].iter().collect::<String>();
+                Step::Key(unescape(&raw))
+            }
+            Some('*') => {
+                self.pos += 1;
+                Step::Wildcard
+            }
+            _ => {
+                let start = self.parse_index()?;
+                if self.peek() == Some(':') {
+                    self.pos += 1;
+                    Step::Slice(start, self.parse_index()?)
+                } else {
+                    let index =
+                        start.ok_or_else(|| format!("missing index at position {}", self.pos))?;
+                    Step::Index(index)
+                }
+            }
+        };
+        if self.peek() != Some(']') {
+            return Err(format!("missing ']' at position {}", self.pos));
+        }
+        self.pos += 1;
+        Ok(step)
+    }
+
+    /// Parses an optional index.
+    fn parse_index(&mut self) -> Result<Option<usize>, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        let digits = self.chars[start..self.pos].iter().collect::<String>();
+        digits
+            .parse::<usize>()
+            .map(Some)
+            .map_err(|_| format!("invalid index at position {start}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::parse;
+
+    fn key(k: &str) -> Step {
+        Step::Key(k.to_string())
+    }
+
+    #[test]
+    fn parse_queries() {
+        assert_eq!(".".parse::<Query>().unwrap().steps, vec![]);
+        assert_eq!(
+            ".items[*].name".parse::<Query>().unwrap().steps,
+            vec![key("items"), Step::Wildcard, key("name")]
+        );
+        assert_eq!(
+            r#".data[2:10][:3][5:]["a b"].*"#.parse::<Query>().unwrap().steps,
+            vec![
+                key("data"),
+                Step::Slice(Some(2), Some(10)),
+                Step::Slice(None, Some(3)),
+                Step::Slice(Some(5), None),
+                key("a b"),
+                Step::Wildcard,
+            ]
+        );
+        assert_eq!(
+            "..id..[0]".parse::<Query>().unwrap().steps,
+            vec![
+                Step::Descendants,
+                key("id"),
+                Step::Descendants,
+                Step::Index(0)
+            ]
+        );
+        assert!("items".parse::<Query>().is_err());
+        assert!(".a[]".parse::<Query>().is_err());
+        assert!(".a[1:x]".parse::<Query>().is_err());
+        assert!(".a...b".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn select_values() {
+        let input =
+            br#"{"items": [{"id": 1, "name": "a"}, {"id": 2}, {"id": 3, "name": "c"}], "id": 0}"#;
+        let root = parse(input).unwrap();
+        let select = |query: &str| {
+            let query = query.parse::<Query>().unwrap();
+            query
+                .select(&root)
+                .iter()
+                .map(|node| node.to_compact_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(select(".items[*].name"), [r#""a""#, r#""c""#]);
+        assert_eq!(select(".items[1:].id"), ["2", "3"]);
+        assert_eq!(select(".items[:10][0].id"), Vec::<String>::new());
+        assert_eq!(select(".items[2:1]"), Vec::<String>::new());
+        assert_eq!(select("..id"), ["0", "1", "2", "3"]);
+        assert_eq!(select(".items[0].*"), ["1", r#""a""#]);
+        assert_eq!(select(".id"), ["0"]);
+        assert_eq!(select(".missing[*]"), Vec::<String>::new());
+        assert_eq!(select(".").len(), 1);
+    }
+
+    #[test]
+    fn match_paths() {
+        let matches = |query: &str, path: &str| {
+            let path = path.parse::<Path>().unwrap();
+            query.parse::<Query>().unwrap().matches(&path)
+        };
+        assert!(matches(".", "."));
+        assert!(matches(".items[*].name", ".items[3].name"));
+        assert!(!matches(".items[*].name", ".items[3]"));
+        assert!(matches(".data[2:10]", ".data[2]"));
+        assert!(!matches(".data[2:10]", ".data[10]"));
+        assert!(matches("..id", ".id"));
+        assert!(matches("..id", ".a[0].b.id"));
+        assert!(!matches("..id", ".a[0].b"));
+        assert!(matches(".*", r#".["a b"]"#));
+    }
+}
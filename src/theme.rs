@@ -0,0 +1,533 @@
+use crate::format::Style;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// Colors used to render JSON tokens, as ANSI SGR parameters (ex: `"1;34"` for bold blue).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Theme {
+    /// Braces, brackets, commas and colons.
+    pub punctuation: Cow<'static, str>,
+    /// Object keys.
+    pub key: Cow<'static, str>,
+    /// String values.
+    pub string: Cow<'static, str>,
+    /// Number values.
+    pub number: Cow<'static, str>,
+    /// `true` and `false`.
+    pub boolean: Cow<'static, str>,
+    /// `null`.
+    pub null: Cow<'static, str>,
+    /// Comments (JSONC).
+    pub comment: Cow<'static, str>,
+    /// Quotes of keys and strings, when styled separately.
+    pub quote: Cow<'static, str>,
+    /// Escape sequences in keys and strings, when styled separately.
+    pub escape: Cow<'static, str>,
+    /// Values typed as [`FieldType::Id`].
+    pub id: Cow<'static, str>,
+    /// Values typed as [`FieldType::Timestamp`].
+    pub timestamp: Cow<'static, str>,
+    /// Values typed as [`FieldType::Url`].
+    pub url: Cow<'static, str>,
+    /// Values typed as [`FieldType::Email`].
+    pub email: Cow<'static, str>,
+    /// Background color of exported outputs (SVG, HTML), that the colors are readable on.
+    pub background: Rgb,
+    /// Color of text without color (`39`) in exported outputs.
+    pub foreground: Rgb,
+}
+
+/// The default theme.
+pub static DEFAULT_THEME: Theme = Theme {
+    punctuation: Cow::Borrowed("1;39"),
+    key: Cow::Borrowed("1;34"),
+    string: Cow::Borrowed("0;32"),
+    number: Cow::Borrowed("0;36"),
+    boolean: Cow::Borrowed("0;33"),
+    null: Cow::Borrowed("0;35"),
+    comment: Cow::Borrowed("0;90"),
+    quote: Cow::Borrowed("2;39"),
+    escape: Cow::Borrowed("1;91"),
+    id: Cow::Borrowed("1;33"),
+    timestamp: Cow::Borrowed("0;95"),
+    url: Cow::Borrowed("4;34"),
+    email: Cow::Borrowed("4;32"),
+    background: Rgb(0x1e, 0x1e, 0x1e),
+    foreground: Rgb(0xd4, 0xd4, 0xd4),
+};
+
+/// A theme for terminals with a light background: yellow and cyan are replaced by darker colors.
+pub static LIGHT_THEME: Theme = Theme {
+    punctuation: Cow::Borrowed("1;39"),
+    key: Cow::Borrowed("1;38;5;25"),
+    string: Cow::Borrowed("38;5;28"),
+    number: Cow::Borrowed("38;5;30"),
+    boolean: Cow::Borrowed("38;5;130"),
+    null: Cow::Borrowed("38;5;90"),
+    comment: Cow::Borrowed("38;5;244"),
+    quote: Cow::Borrowed("2;39"),
+    escape: Cow::Borrowed("1;38;5;160"),
+    id: Cow::Borrowed("1;38;5;130"),
+    timestamp: Cow::Borrowed("38;5;127"),
+    url: Cow::Borrowed("4;38;5;25"),
+    email: Cow::Borrowed("4;38;5;28"),
+    background: Rgb(0xff, 0xff, 0xff),
+    foreground: Rgb(0x1e, 0x1e, 0x1e),
+};
+
+/// Background of a terminal, to choose between the default theme and [LIGHT_THEME].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Background {
+    Dark,
+    Light,
+}
+
+impl Background {
+    /// Returns the background of a color, from its relative luminance.
+    pub fn of(color: Rgb) -> Self {
+        let Rgb(r, g, b) = color;
+        let luminance = 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+        if luminance > 127.5 {
+            Background::Light
+        } else {
+            Background::Dark
+        }
+    }
+
+    /// Returns the default theme for this background.
+    pub fn theme(self) -> &'static Theme {
+        match self {
+            Background::Dark => &DEFAULT_THEME,
+            Background::Light => &LIGHT_THEME,
+        }
+    }
+}
+
+impl FromStr for Background {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dark" => Ok(Background::Dark),
+            "light" => Ok(Background::Light),
+            _ => Err(format!("unknown background {s} (expected dark or light)")),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        DEFAULT_THEME.clone()
+    }
+}
+
+impl Theme {
+    /// Returns the color of a piece of text with the role `style`.
+    pub fn code(&self, style: Style) -> &str {
+        match style {
+            Style::Punctuation => &self.punctuation,
+            Style::Key => &self.key,
+            Style::String => &self.string,
+            Style::Number => &self.number,
+            Style::Boolean => &self.boolean,
+            Style::Null => &self.null,
+            Style::Comment => &self.comment,
+            Style::Quote => &self.quote,
+            Style::Escape => &self.escape,
+            Style::Type(field_type) => self.type_color(field_type),
+        }
+    }
+
+    /// Returns the color of values of a given type.
+    pub fn type_color(&self, field_type: FieldType) -> &str {
+        match field_type {
+            FieldType::Id => &self.id,
+            FieldType::Timestamp => &self.timestamp,
+            FieldType::Url => &self.url,
+            FieldType::Email => &self.email,
+        }
+    }
+
+    /// Parses a theme file, in TOML: a color per token class (`key = "bold blue"`), in an optional
+    /// `[colors]` table. Classes that are not defined keep the colors of the default theme.
+    ///
+    /// Colors are a list of attributes (`bold`, `dim`, `italic`, `underline`), with at most one
+    /// color: a name (`red`, `bright-red`, `gray`, `default`), a 256 colors index or a `#rrggbb`
+    /// true color. `background` and `foreground`, the colors of exported outputs, are `#rrggbb`
+    /// colors.
+    pub fn parse(text: &str) -> Result<Theme, String> {
+        let mut theme = Theme::default();
+        for (i, line) in text.lines().enumerate() {
+            let err = |message: String| format!("line {}: {message}", i + 1);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(table) = line.strip_prefix('[') {
+                match table.split('#').next().unwrap_or("").trim() {
+                    "colors]" => continue,
+                    _ => return Err(err(format!("unknown table {line} (expected [colors])"))),
+                }
+            }
+            let Some((name, value)) = line.split_once('=') else {
+                return Err(err(format!(
+                    "invalid line {line} (expected CLASS = \"COLOR\")"
+                )));
+            };
+            let value = value.trim();
+            let Some((quote, value)) = value.split_at_checked(1) else {
+                return Err(err("missing color".to_string()));
+            };
+            let color = match value.split_once(quote) {
+                Some((color, rest)) if matches!(quote, "\"" | "'") => {
+                    let rest = rest.trim();
+                    if !rest.is_empty() && !rest.starts_with('#') {
+                        return Err(err(format!("unexpected {rest} after the color")));
+                    }
+                    color
+                }
+                _ => return Err(err("colors must be quoted strings".to_string())),
+            };
+            match name.trim() {
+                "background" => {
+                    theme.background = parse_rgb(color).map_err(err)?;
+                    continue;
+                }
+                "foreground" => {
+                    theme.foreground = parse_rgb(color).map_err(err)?;
+                    continue;
+                }
+                _ => {}
+            }
+            let code = Cow::Owned(parse_color(color).map_err(err)?);
+            match name.trim() {
+                "punctuation" => theme.punctuation = code,
+                "key" => theme.key = code,
+                "string" => theme.string = code,
+                "number" => theme.number = code,
+                "boolean" => theme.boolean = code,
+                "null" => theme.null = code,
+                "comment" => theme.comment = code,
+                "quote" => theme.quote = code,
+                "escape" => theme.escape = code,
+                "id" => theme.id = code,
+                "timestamp" => theme.timestamp = code,
+                "url" => theme.url = code,
+                "email" => theme.email = code,
+                name => return Err(err(format!("unknown token class {name}"))),
+            }
+        }
+        Ok(theme)
+    }
+}
+
+/// Returns the SGR parameters of a color: `"bold #ff8700"` is `"1;38;2;255;135;0"`.
+fn parse_color(color: &str) -> Result<String, String> {
+    const NAMES: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    ];
+    let mut params = Vec::new();
+    let mut colored = false;
+    for word in color.split_whitespace() {
+        let param = match word {
+            "bold" => "1".to_string(),
+            "dim" => "2".to_string(),
+            "italic" => "3".to_string(),
+            "underline" => "4".to_string(),
+            _ if colored => return Err(format!("more than one color in {color}")),
+            "default" => "39".to_string(),
+            "gray" | "grey" => "90".to_string(),
+            _ => {
+                let (bright, name) = match word.strip_prefix("bright-") {
+                    Some(name) => (true, name),
+                    None => (false, word),
+                };
+                if let Some(n) = NAMES.iter().position(|c| *c == name) {
+                    let base = if bright { 90 } else { 30 };
+                    (base + n).to_string()
+                } else if let Ok(n) = word.parse::<u8>() {
+                    format!("38;5;{n}")
+                } else if let Some(hex) = word.strip_prefix('#')
+                    && hex.len() == 6
+                    && let Ok(rgb) = u32::from_str_radix(hex, 16)
+                {
+                    format!("38;2;{};{};{}", rgb >> 16, rgb >> 8 & 0xff, rgb & 0xff)
+                } else {
+                    return Err(format!("unknown color {word}"));
+                }
+            }
+        };
+        colored |= !matches!(word, "bold" | "dim" | "italic" | "underline");
+        params.push(param);
+    }
+    if params.is_empty() {
+        return Err("empty color".to_string());
+    }
+    Ok(params.join(";"))
+}
+
+/// Parses a `#rrggbb` color.
+fn parse_rgb(color: &str) -> Result<Rgb, String> {
+    match color.trim().strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => {
+            let rgb = u32::from_str_radix(hex, 16).map_err(|_| format!("invalid color {color}"))?;
+            Ok(Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+        }
+        _ => Err(format!("invalid color {color} (expected #rrggbb)")),
+    }
+}
+
+/// A RGB color, displayed as `#rrggbb`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// The 16 standard terminal colors (VS Code terminal palette), used to render ANSI colors outside
+/// of a terminal.
+pub static ANSI_PALETTE: [Rgb; 16] = [
+    Rgb(0x00, 0x00, 0x00),
+    Rgb(0xcd, 0x31, 0x31),
+    Rgb(0x0d, 0xbc, 0x79),
+    Rgb(0xe5, 0xe5, 0x10),
+    Rgb(0x24, 0x72, 0xc8),
+    Rgb(0xbc, 0x3f, 0xbc),
+    Rgb(0x11, 0xa8, 0xcd),
+    Rgb(0xe5, 0xe5, 0xe5),
+    Rgb(0x66, 0x66, 0x66),
+    Rgb(0xf1, 0x4c, 0x4c),
+    Rgb(0x23, 0xd1, 0x8b),
+    Rgb(0xf5, 0xf5, 0x43),
+    Rgb(0x3b, 0x8e, 0xea),
+    Rgb(0xd6, 0x70, 0xd6),
+    Rgb(0x29, 0xb8, 0xdb),
+    Rgb(0xe5, 0xe5, 0xe5),
+];
+
+/// Text attributes of ANSI SGR parameters (ex: `"1;34"`), to render themes outside of a
+/// terminal.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SgrStyle {
+    /// Foreground color, `None` for the default color.
+    pub color: Option<Rgb>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl SgrStyle {
+    /// Parses SGR parameters: attributes, 16 colors, 256 colors (`38;5;N`) and RGB colors
+    /// (`38;2;R;G;B`). Unknown parameters are ignored.
+    pub fn parse(params: &str) -> Self {
+        let mut style = SgrStyle::default();
+        style.apply(params);
+        style
+    }
+
+    /// Updates this style with SGR parameters, as a terminal does: only the attributes set (or
+    /// reset) by the parameters are changed.
+    pub fn apply(&mut self, params: &str) {
+        let mut params = params.split(';').map(|p| p.parse::<u8>().unwrap_or(0));
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = SgrStyle::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => (self.bold, self.dim) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.color = Some(ANSI_PALETTE[param as usize - 30]),
+                39 => self.color = None,
+                90..=97 => self.color = Some(ANSI_PALETTE[param as usize - 90 + 8]),
+                38 => match params.next() {
+                    Some(5) => self.color = params.next().map(color_256),
+                    Some(2) => {
+                        let (r, g, b) = (params.next(), params.next(), params.next());
+                        self.color = Some(Rgb(r.unwrap_or(0), g.unwrap_or(0), b.unwrap_or(0)));
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Returns a color of the 256 colors palette.
+fn color_256(n: u8) -> Rgb {
+    match n {
+        0..=15 => ANSI_PALETTE[n as usize],
+        16..=231 => {
+            let level = |i: u8| if i == 0 { 0 } else { 55 + i * 40 };
+            let n = n - 16;
+            Rgb(level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (n - 232) * 10;
+            Rgb(gray, gray, gray)
+        }
+    }
+}
+
+/// Semantic type of a field, rendered with a dedicated color.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FieldType {
+    Id,
+    Timestamp,
+    Url,
+    Email,
+}
+
+impl FromStr for FieldType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(FieldType::Id),
+            "timestamp" => Ok(FieldType::Timestamp),
+            "url" => Ok(FieldType::Url),
+            "email" => Ok(FieldType::Email),
+            _ => Err(format!(
+                "unknown type {s} (expected id, timestamp, url or email)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FieldType::Id => "id",
+            FieldType::Timestamp => "timestamp",
+            FieldType::Url => "url",
+            FieldType::Email => "email",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Associates fields, by path patterns, to semantic types.
+///
+/// A pattern is either a key name, matching this key at any depth (`id`), or a path where `*`
+/// matches any sequence of characters (`.users[*].created`, `*.id`).
+#[derive(Clone, Debug, Default)]
+pub struct TypeMap {
+    patterns: Vec<(String, FieldType)>,
+}
+
+impl TypeMap {
+    /// Adds a pattern, later patterns take precedence over earlier ones.
+    pub fn insert(&mut self, pattern: &str, field_type: FieldType) {
+        let pattern = if pattern.starts_with(['.', '[', '*']) {
+            pattern.to_string()
+        } else if crate::path::is_identifier(pattern) {
+            format!("*.{pattern}")
+        } else {
+            format!("*[{}]", crate::value::escape(pattern))
+        };
+        self.patterns.push((pattern, field_type));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns the type of the field at `path` (as displayed by [`crate::path::Path`]).
+    pub fn get(&self, path: &str) -> Option<FieldType> {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|(pattern, _)| crate::path::wildcard_match(pattern, path))
+            .map(|(_, field_type)| *field_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Background, FieldType, Rgb, SgrStyle, Theme, TypeMap};
+
+    #[test]
+    fn get_field_types() {
+        let mut types = TypeMap::default();
+        types.insert("id", FieldType::Id);
+        types.insert(".users[*].created", FieldType::Timestamp);
+        types.insert("home page", FieldType::Url);
+
+        assert_eq!(types.get(".id"), Some(FieldType::Id));
+        assert_eq!(types.get(".users[3].id"), Some(FieldType::Id));
+        assert_eq!(types.get(".users[3].uid"), None);
+        assert_eq!(types.get(".users[3].created"), Some(FieldType::Timestamp));
+        assert_eq!(types.get(".admins[3].created"), None);
+        assert_eq!(types.get(r#".user["home page"]"#), Some(FieldType::Url));
+    }
+
+    #[test]
+    fn parse_sgr_styles() {
+        assert_eq!(SgrStyle::parse(""), SgrStyle::default());
+        let style = SgrStyle::parse("1;34");
+        assert!(style.bold && !style.underline);
+        assert_eq!(style.color, Some(Rgb(0x24, 0x72, 0xc8)));
+        assert_eq!(SgrStyle::parse("0;95").color, Some(Rgb(0xd6, 0x70, 0xd6)));
+        assert_eq!(SgrStyle::parse("1;39").color, None);
+        assert_eq!(SgrStyle::parse("38;5;208").color, Some(Rgb(255, 135, 0)));
+        assert_eq!(SgrStyle::parse("38;5;244").color, Some(Rgb(128, 128, 128)));
+        assert_eq!(SgrStyle::parse("4;38;2;1;2;3").color, Some(Rgb(1, 2, 3)));
+        assert!(SgrStyle::parse("4;34").underline);
+        assert_eq!(Rgb(255, 135, 0).to_string(), "#ff8700");
+    }
+
+    #[test]
+    fn parse_theme_files() {
+        let text = r##"
+# Monokai
+[colors]
+key = "bold #66d9ef"  # keys
+string = 'bright-green'
+number = "208"
+comment = "dim italic"
+null = "underline default"
+background = "#FDF6E3"
+"##;
+        let theme = Theme::parse(text).unwrap();
+        assert_eq!(theme.key, "1;38;2;102;217;239");
+        assert_eq!(theme.string, "92");
+        assert_eq!(theme.number, "38;5;208");
+        assert_eq!(theme.comment, "2;3");
+        assert_eq!(theme.null, "4;39");
+        assert_eq!(theme.boolean, Theme::default().boolean);
+        assert_eq!(theme.background, Rgb(0xfd, 0xf6, 0xe3));
+        assert_eq!(theme.foreground, Theme::default().foreground);
+        assert!(Theme::parse("background = \"white\"").is_err());
+
+        assert!(Theme::parse("key = bold").is_err());
+        assert!(Theme::parse("key = \"blue red\"").is_err());
+        assert!(Theme::parse("key = \"#12345\"").is_err());
+        assert!(Theme::parse("keys = \"blue\"").is_err());
+        assert!(Theme::parse("[theme]").is_err());
+        assert_eq!(
+            Theme::parse("\nkey = \"purple\"").unwrap_err(),
+            "line 2: unknown color purple"
+        );
+    }
+
+    #[test]
+    fn backgrounds() {
+        assert_eq!(Background::of(Rgb(0x1e, 0x1e, 0x1e)), Background::Dark);
+        assert_eq!(Background::of(Rgb(0xff, 0xff, 0xff)), Background::Light);
+        assert_eq!(Background::of(Rgb(0xfd, 0xf6, 0xe3)), Background::Light);
+        assert_eq!(Background::of(Rgb(0x00, 0x2b, 0x36)), Background::Dark);
+        assert_eq!(
+            "light".parse::<Background>().unwrap().theme().number,
+            "38;5;30"
+        );
+        assert!("auto".parse::<Background>().is_err());
+    }
+}
@@ -0,0 +1,529 @@
+use crate::format::{Color, FormatError, Formatter, OutputStyle, Style};
+use crate::theme::FieldType;
+use std::fmt;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Kind of a JSON token.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TokenKind {
+    /// `{`
+    BeginObject,
+    /// `}`
+    EndObject,
+    /// `[`
+    BeginArray,
+    /// `]`
+    EndArray,
+    /// `:`
+    NameSeparator,
+    /// `,`
+    ValueSeparator,
+    /// An object key (a string, with its quotes).
+    Key,
+    /// A string value, with its quotes.
+    String,
+    Number,
+    True,
+    False,
+    Null,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TokenKind::BeginObject => "begin-object",
+            TokenKind::EndObject => "end-object",
+            TokenKind::BeginArray => "begin-array",
+            TokenKind::EndArray => "end-array",
+            TokenKind::NameSeparator => "name-separator",
+            TokenKind::ValueSeparator => "value-separator",
+            TokenKind::Key => "key",
+            TokenKind::String => "string",
+            TokenKind::Number => "number",
+            TokenKind::True => "true",
+            TokenKind::False => "false",
+            TokenKind::Null => "null",
+        };
+        f.pad(name)
+    }
+}
+
+/// A token of a JSON input.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Byte range of the token in the input.
+    pub span: Range<usize>,
+    /// Nesting level of the token (0 for the root value, brackets and braces have the level of
+    /// their container).
+    pub level: usize,
+}
+
+/// Tokenizes JSON input bytes, calling `on_token` for each token.
+///
+/// The input is validated as by the [Formatter] (grammar, UTF-8, nesting limit): tokens are
+/// reported until the first error, which is returned.
+pub fn tokenize(input: &[u8], on_token: impl FnMut(Token)) -> Result<(), FormatError> {
+    let mut tokenizer = Tokenizer {
+        scanner: Formatter::new(input, Color::NoColor),
+        level: 0,
+        on_token,
+    };
+    tokenizer.scanner.skip_start_bom();
+    tokenizer.scanner.skip_whitespace();
+    tokenizer.value()?;
+    tokenizer.scanner.skip_whitespace();
+    match tokenizer.scanner.peek_byte() {
+        Some(b) => Err(FormatError::InvalidByte(b, tokenizer.scanner.pos)),
+        None => Ok(()),
+    }
+}
+
+/// Returns the kinds of the tokens of JSON input bytes, with their byte ranges in the input, to
+/// highlight a document in an editor.
+///
+/// Unlike [tokenize], errors are not reported: the spans of the tokens before the first error are
+/// returned, so a document being edited is highlighted up to the first invalid token.
+pub fn highlight_spans(input: &[u8]) -> Vec<(Range<usize>, TokenKind)> {
+    let mut spans = Vec::new();
+    _ = tokenize(input, |token| spans.push((token.span, token.kind)));
+    spans
+}
+
+/// The recorded output of a [Formatter]: the input is parsed (and validated) and laid out once,
+/// with the options of the formatter (indent, line ending, compact values...), and can be rendered
+/// several times, with different output styles, without being parsed again.
+///
+/// ```
+/// use pretty::format::{Formatter, Indent, PlainStyle};
+/// use pretty::token::{Recorder, TokenStream};
+///
+/// let formatter = Formatter::new_styled(b"[1, 2]", Recorder).with_indent(Indent::Tab);
+/// let stream = TokenStream::record(formatter).unwrap();
+/// let mut out = String::new();
+/// stream.replay(&PlainStyle, &mut out).unwrap();
+/// assert_eq!(out, "[\n\t1,\n\t2\n]");
+/// ```
+pub struct TokenStream {
+    /// The formatted text, styled pieces and links being delimited by control chars (which can't
+    /// be written raw by the formatter).
+    layout: String,
+    /// Number of styled pieces of text.
+    len: usize,
+}
+
+/// Starts a styled piece of text in a recorded layout, followed by the style code and the text.
+const BEGIN_STYLED: char = '\u{1}';
+/// Ends a styled piece of text, or the URL of a link, in a recorded layout.
+const END: char = '\u{2}';
+/// Starts a link in a recorded layout, followed by the URL.
+const BEGIN_LINK: char = '\u{3}';
+/// Ends a link in a recorded layout.
+const END_LINK: char = '\u{4}';
+
+/// The output style used to record a [TokenStream]: styles and links are kept in the text, to be
+/// replayed later.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Recorder;
+
+impl OutputStyle for Recorder {
+    fn write_styled<W: Write>(&self, style: Style, s: &str, out: &mut W) -> fmt::Result {
+        out.write_char(BEGIN_STYLED)?;
+        out.write_char(style_code(style))?;
+        out.write_str(s)?;
+        out.write_char(END)
+    }
+
+    fn begin_link<W: Write>(&self, url: &str, out: &mut W) -> fmt::Result {
+        out.write_char(BEGIN_LINK)?;
+        out.write_str(url)?;
+        out.write_char(END)
+    }
+
+    fn end_link<W: Write>(&self, out: &mut W) -> fmt::Result {
+        out.write_char(END_LINK)
+    }
+}
+
+impl TokenStream {
+    /// Records the output of `formatter`.
+    pub fn record(mut formatter: Formatter<'_, Recorder>) -> Result<Self, FormatError> {
+        let mut layout = String::new();
+        formatter.format(&mut layout)?;
+        let len = layout.matches(BEGIN_STYLED).count();
+        Ok(TokenStream { layout, len })
+    }
+
+    /// Returns the number of recorded styled pieces of text.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Writes the recorded output decorated with `style`, as the [Formatter] would have written
+    /// it with this style.
+    pub fn replay<S: OutputStyle>(&self, style: &S, out: &mut impl Write) -> fmt::Result {
+        let mut rest = self.layout.as_str();
+        while let Some(i) = rest.find([BEGIN_STYLED, BEGIN_LINK, END_LINK]) {
+            out.write_str(&rest[..i])?;
+            let mut chars = rest[i..].chars();
+            match chars.next() {
+                Some(BEGIN_STYLED) => {
+                    let code = chars.next().unwrap_or_default();
+                    let (s, next) = chars.as_str().split_once(END).unwrap_or_default();
+                    style.write_styled(code_style(code), s, out)?;
+                    rest = next;
+                }
+                Some(BEGIN_LINK) => {
+                    let (url, next) = chars.as_str().split_once(END).unwrap_or_default();
+                    style.begin_link(url, out)?;
+                    rest = next;
+                }
+                _ => {
+                    style.end_link(out)?;
+                    rest = chars.as_str();
+                }
+            }
+        }
+        out.write_str(rest)
+    }
+}
+
+/// Returns the char coding `style` in a recorded layout.
+fn style_code(style: Style) -> char {
+    match style {
+        Style::Punctuation => 'p',
+        Style::Key => 'k',
+        Style::String => 's',
+        Style::Number => 'n',
+        Style::Boolean => 'b',
+        Style::Null => 'z',
+        Style::Comment => 'c',
+        Style::Quote => 'q',
+        Style::Escape => 'e',
+        Style::Type(FieldType::Id) => 'I',
+        Style::Type(FieldType::Timestamp) => 'T',
+        Style::Type(FieldType::Url) => 'U',
+        Style::Type(FieldType::Email) => 'E',
+    }
+}
+
+/// Returns the style coded by `code` in a recorded layout.
+fn code_style(code: char) -> Style {
+    match code {
+        'k' => Style::Key,
+        's' => Style::String,
+        'n' => Style::Number,
+        'b' => Style::Boolean,
+        'z' => Style::Null,
+        'c' => Style::Comment,
+        'q' => Style::Quote,
+        'e' => Style::Escape,
+        'I' => Style::Type(FieldType::Id),
+        'T' => Style::Type(FieldType::Timestamp),
+        'U' => Style::Type(FieldType::Url),
+        'E' => Style::Type(FieldType::Email),
+        _ => Style::Punctuation,
+    }
+}
+
+/// An iterator over the raw tokens of JSON input bytes, without grammar: brackets don't have to
+/// match and values don't have to be separated, only each token is validated.
+///
+/// Strings are all [TokenKind::String] (keys are not distinguished from values). The iterator
+/// stops after the first invalid token, returned as an error.
+pub struct Lexer<'input> {
+    scanner: Formatter<'input>,
+    failed: bool,
+}
+
+impl<'input> Lexer<'input> {
+    pub fn new(input: &'input [u8]) -> Self {
+        let mut scanner = Formatter::new(input, Color::NoColor);
+        scanner.skip_start_bom();
+        Lexer {
+            scanner,
+            failed: false,
+        }
+    }
+
+    fn next_token(&mut self, b: u8) -> Result<TokenKind, FormatError> {
+        let punct = match b {
+            b'{' => Some(TokenKind::BeginObject),
+            b'}' => Some(TokenKind::EndObject),
+            b'[' => Some(TokenKind::BeginArray),
+            b']' => Some(TokenKind::EndArray),
+            b':' => Some(TokenKind::NameSeparator),
+            b',' => Some(TokenKind::ValueSeparator),
+            _ => None,
+        };
+        if let Some(kind) = punct {
+            self.scanner.expect_byte(b)?;
+            return Ok(kind);
+        }
+        match b {
+            b'"' => self.scanner.scan_string().map(|_| TokenKind::String),
+            b'-' | b'0'..=b'9' => self.scanner.scan_number().map(|_| TokenKind::Number),
+            b't' => self.scanner.scan_literal(b"true").map(|_| TokenKind::True),
+            b'f' => self
+                .scanner
+                .scan_literal(b"false")
+                .map(|_| TokenKind::False),
+            b'n' => self.scanner.scan_literal(b"null").map(|_| TokenKind::Null),
+            b => Err(FormatError::InvalidByte(b, self.scanner.pos)),
+        }
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<(Range<usize>, TokenKind), FormatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        self.scanner.skip_whitespace();
+        let start = self.scanner.pos.0;
+        let b = self.scanner.peek_byte()?;
+        match self.next_token(b) {
+            Ok(kind) => Some(Ok((start..self.scanner.pos.0, kind))),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+struct Tokenizer<'input, F> {
+    scanner: Formatter<'input>,
+    level: usize,
+    on_token: F,
+}
+
+impl<F: FnMut(Token)> Tokenizer<'_, F> {
+    /// Emits a token from `start` to the current position.
+    fn emit(&mut self, kind: TokenKind, start: usize) {
+        (self.on_token)(Token {
+            kind,
+            span: start..self.scanner.pos.0,
+            level: self.level,
+        });
+    }
+
+    /// Consumes the byte `b` and emits it as a `kind` token.
+    fn punct(&mut self, b: u8, kind: TokenKind) -> Result<(), FormatError> {
+        let start = self.scanner.pos.0;
+        self.scanner.expect_byte(b)?;
+        self.emit(kind, start);
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<(), FormatError> {
+        let start = self.scanner.pos.0;
+        let kind = match self.scanner.peek_byte() {
+            Some(b'"') => {
+                self.scanner.scan_string()?;
+                TokenKind::String
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                self.scanner.scan_number()?;
+                TokenKind::Number
+            }
+            Some(b'{') => return self.container(b'{', b'}'),
+            Some(b'[') => return self.container(b'[', b']'),
+            Some(b't') => {
+                self.scanner.scan_literal(b"true")?;
+                TokenKind::True
+            }
+            Some(b'f') => {
+                self.scanner.scan_literal(b"false")?;
+                TokenKind::False
+            }
+            Some(b'n') => {
+                self.scanner.scan_literal(b"null")?;
+                TokenKind::Null
+            }
+            Some(b) => return Err(FormatError::InvalidByte(b, self.scanner.pos)),
+            None => return Err(FormatError::Eof),
+        };
+        self.emit(kind, start);
+        Ok(())
+    }
+
+    fn container(&mut self, open: u8, close: u8) -> Result<(), FormatError> {
+        let (begin, end) = if open == b'{' {
+            (TokenKind::BeginObject, TokenKind::EndObject)
+        } else {
+            (TokenKind::BeginArray, TokenKind::EndArray)
+        };
+        self.punct(open, begin)?;
+        self.scanner.inc_level()?;
+        let mut first = true;
+        loop {
+            self.scanner.skip_whitespace();
+            if self.scanner.peek_byte() == Some(close) {
+                self.scanner.dec_level();
+                return self.punct(close, end);
+            }
+            self.level += 1;
+            if first {
+                first = false;
+            } else {
+                self.punct(b',', TokenKind::ValueSeparator)?;
+                self.scanner.skip_whitespace();
+            }
+            if open == b'{' {
+                let start = self.scanner.pos.0;
+                self.scanner.scan_string()?;
+                self.emit(TokenKind::Key, start);
+                self.scanner.skip_whitespace();
+                self.punct(b':', TokenKind::NameSeparator)?;
+                self.scanner.skip_whitespace();
+            }
+            self.value()?;
+            self.level -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lexer, Recorder, Token, TokenKind, TokenStream, highlight_spans, tokenize};
+    use crate::format::{AnsiStyle, Color, Formatter, Indent, LineEnding, OutputStyle, PlainStyle};
+
+    #[test]
+    fn tokenize_input() {
+        let mut tokens = Vec::new();
+        tokenize(br#" {"a": [1, null]} "#, |token| tokens.push(token)).unwrap();
+        let tokens = tokens
+            .iter()
+            .map(|Token { kind, span, level }| (*kind, span.clone(), *level))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenKind::BeginObject, 1..2, 0),
+                (TokenKind::Key, 2..5, 1),
+                (TokenKind::NameSeparator, 5..6, 1),
+                (TokenKind::BeginArray, 7..8, 1),
+                (TokenKind::Number, 8..9, 2),
+                (TokenKind::ValueSeparator, 9..10, 2),
+                (TokenKind::Null, 11..15, 2),
+                (TokenKind::EndArray, 15..16, 1),
+                (TokenKind::EndObject, 16..17, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_tokens() {
+        let input = br#" {"a": [1, null, {}, []], "b": {"c": "d"}} "#;
+        let stream = TokenStream::record(Formatter::new_styled(input, Recorder)).unwrap();
+        assert_eq!(stream.len(), 21);
+        let mut plain = String::new();
+        stream.replay(&PlainStyle, &mut plain).unwrap();
+        let mut expected = String::new();
+        Formatter::new(input, Color::NoColor)
+            .format(&mut expected)
+            .unwrap();
+        assert_eq!(plain, expected);
+
+        let mut colored = String::new();
+        stream
+            .replay(&AnsiStyle::new(Color::AnsiCode), &mut colored)
+            .unwrap();
+        let mut expected = String::new();
+        Formatter::new(input, Color::AnsiCode)
+            .format(&mut expected)
+            .unwrap();
+        assert_eq!(colored, expected);
+
+        assert!(TokenStream::record(Formatter::new_styled(b"[1,]", Recorder)).is_err());
+    }
+
+    #[test]
+    fn replay_tokens_with_options() {
+        fn configure<S: OutputStyle>(formatter: Formatter<'_, S>) -> Formatter<'_, S> {
+            formatter
+                .with_indent(Indent::Tab)
+                .with_line_ending(LineEnding::Crlf)
+                .with_compact_single(true)
+                .with_align_values(true)
+                .with_hyperlinks(true)
+        }
+        let input = br#"{"a": [1], "long": {"url": "https://example.com"}}"#;
+        let stream =
+            TokenStream::record(configure(Formatter::new_styled(input, Recorder))).unwrap();
+        let style = AnsiStyle::new(Color::AnsiCode);
+        let mut colored = String::new();
+        stream.replay(&style, &mut colored).unwrap();
+        let mut expected = String::new();
+        configure(Formatter::new_styled(input, style))
+            .format(&mut expected)
+            .unwrap();
+        assert_eq!(colored, expected);
+        assert!(colored.contains("\r\n\t"));
+    }
+
+    #[test]
+    fn tokenize_until_error() {
+        let mut count = 0;
+        let result = tokenize(b"[1,]", |_| count += 1);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid byte <5d> at offset 3"
+        );
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn highlight_input() {
+        assert_eq!(
+            highlight_spans(br#"{"a": tru"#),
+            vec![
+                (0..1, TokenKind::BeginObject),
+                (1..4, TokenKind::Key),
+                (4..5, TokenKind::NameSeparator),
+            ]
+        );
+        assert_eq!(
+            highlight_spans(b"\xef\xbb\xbf false"),
+            vec![(4..9, TokenKind::False)]
+        );
+        assert!(highlight_spans(b"").is_empty());
+    }
+
+    #[test]
+    fn lex_raw_tokens() {
+        let tokens = Lexer::new(br#" }}"a":1.5e3 true [ , null"#)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                (1..2, TokenKind::EndObject),
+                (2..3, TokenKind::EndObject),
+                (3..6, TokenKind::String),
+                (6..7, TokenKind::NameSeparator),
+                (7..12, TokenKind::Number),
+                (13..17, TokenKind::True),
+                (18..19, TokenKind::BeginArray),
+                (20..21, TokenKind::ValueSeparator),
+                (22..26, TokenKind::Null),
+            ]
+        );
+
+        let mut lexer = Lexer::new(b"[1, nul]");
+        assert_eq!(lexer.by_ref().take(3).filter(Result::is_ok).count(), 3);
+        assert_eq!(
+            lexer.next().unwrap().unwrap_err().to_string(),
+            "invalid byte <5d> at offset 7"
+        );
+        assert!(lexer.next().is_none());
+    }
+}
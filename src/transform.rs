@@ -0,0 +1,540 @@
+use crate::arena::Arena;
+use crate::num;
+use crate::path::{Path, Segment};
+use crate::value::{Member, Node, Unescaped, Value, escape, unescape};
+use std::borrow::Cow;
+use std::str::FromStr;
+
+/// A case convention for object keys.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Case {
+    /// `snake_case`
+    Snake,
+    /// `camelCase`
+    Camel,
+    /// `kebab-case`
+    Kebab,
+}
+
+impl FromStr for Case {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "snake_case" => Ok(Case::Snake),
+            "camelCase" => Ok(Case::Camel),
+            "kebab-case" => Ok(Case::Kebab),
+            _ => Err(format!(
+                "invalid case {s}, expected snake_case, camelCase or kebab-case"
+            )),
+        }
+    }
+}
+
+/// Renames recursively all object keys of `node` to the `case` convention.
+pub fn rename_keys(node: &mut Node, case: Case) {
+    match &mut node.value {
+        Value::Array(nodes) => nodes.iter_mut().for_each(|n| rename_keys(n, case)),
+        Value::Object(members) => {
+            for member in members {
+                let key = unescape(&member.key);
+                let renamed = convert_case(&key, case);
+                if renamed != key {
+                    member.key = Cow::Owned(escape(&renamed));
+                }
+                rename_keys(&mut member.node, case);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Converts a name to the `case` convention.
+///
+/// Words are delimited by `_`, `-`, spaces and case changes (`HTTPServer` is `http` and
+/// `server`). Other chars are kept as is.
+pub fn convert_case(name: &str, case: Case) -> String {
+    let words = split_words(name);
+    let mut out = String::with_capacity(name.len());
+    for (i, word) in words.iter().enumerate() {
+        match case {
+            Case::Snake | Case::Kebab => {
+                if i > 0 {
+                    out.push(if case == Case::Snake { '_' } else { '-' });
+                }
+                out.push_str(&word.to_lowercase());
+            }
+            Case::Camel => {
+                let lower = word.to_lowercase();
+                if i == 0 {
+                    out.push_str(&lower);
+                } else {
+                    let mut chars = lower.chars();
+                    if let Some(first) = chars.next() {
+                        out.extend(first.to_uppercase());
+                        out.push_str(chars.as_str());
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+fn split_words(name: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let chars = name.char_indices().collect::<Vec<_>>();
+    let mut start = None;
+    for (i, &(pos, c)) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c.is_whitespace() {
+            if let Some(s) = start.take() {
+                words.push(&name[s..pos]);
+            }
+            continue;
+        }
+        let Some(s) = start else {
+            start = Some(pos);
+            continue;
+        };
+        let prev = chars[i - 1].1;
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        // `aB` or `ABc` (the last upper char of an acronym starts a new word)
+        let boundary = c.is_uppercase()
+            && (prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next.is_some_and(char::is_lowercase)));
+        if boundary {
+            words.push(&name[s..pos]);
+            start = Some(pos);
+        }
+    }
+    if let Some(s) = start {
+        words.push(&name[s..]);
+    }
+    words
+}
+
+/// Kinds of object members to remove with [prune].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Prune {
+    pub nulls: bool,
+    pub empty_objects: bool,
+    pub empty_arrays: bool,
+}
+
+impl FromStr for Prune {
+    type Err = String;
+
+    /// Parses a comma separated list of `nulls`, `empty-objects` and `empty-arrays`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut prune = Prune::default();
+        for kind in s.split(',') {
+            match kind.trim() {
+                "nulls" => prune.nulls = true,
+                "empty-objects" => prune.empty_objects = true,
+                "empty-arrays" => prune.empty_arrays = true,
+                kind => {
+                    return Err(format!(
+                        "invalid kind {kind}, expected nulls, empty-objects or empty-arrays"
+                    ));
+                }
+            }
+        }
+        Ok(prune)
+    }
+}
+
+impl Prune {
+    fn matches(&self, value: &Value) -> bool {
+        match value {
+            Value::Null => self.nulls,
+            Value::Object(members) => self.empty_objects && members.is_empty(),
+            Value::Array(nodes) => self.empty_arrays && nodes.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// Removes recursively the object members whose values are null, empty objects or empty arrays.
+///
+/// If `fixed_point` is `true`, members whose values become empty after pruning are also removed
+/// (`{"a":{"b":null}}` is pruned to `{}` instead of `{"a":{}}`).
+pub fn prune(node: &mut Node, kinds: Prune, fixed_point: bool) {
+    match &mut node.value {
+        Value::Array(nodes) => nodes.iter_mut().for_each(|n| prune(n, kinds, fixed_point)),
+        Value::Object(members) => {
+            members.retain_mut(|member| {
+                // Without fixed point, only values that are initially empty are removed.
+                if !fixed_point && kinds.matches(&member.node.value) {
+                    return false;
+                }
+                prune(&mut member.node, kinds, fixed_point);
+                !(fixed_point && kinds.matches(&member.node.value))
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Replaces the value at `path` by `value`.
+///
+/// Missing object members are created (with intermediate objects if needed), an array index can
+/// be the array length to append a value. Returns an error if the path goes through a scalar value
+/// or an out of bounds index, `root` being left unchanged.
+pub fn set<'input>(
+    root: &mut Node<'input>,
+    path: &Path,
+    value: Node<'input>,
+) -> Result<(), String> {
+    // The whole path is checked first, so nothing is created on errors.
+    check_set(root, path)?;
+    let mut node = root;
+    for segment in &path.segments {
+        node = match (segment, &mut node.value) {
+            (Segment::Key(key), Value::Object(members)) => {
+                let pos = members.iter().position(|m| unescape(&m.key) == *key);
+                let pos = match pos {
+                    Some(pos) => pos,
+                    None => {
+                        members.push(Member {
+                            key: Cow::Owned(escape(key)),
+                            key_span: 0..0,
+                            node: Node::new(Value::Object(Vec::new())),
+                        });
+                        members.len() - 1
+                    }
+                };
+                &mut members[pos].node
+            }
+            (Segment::Index(index), Value::Array(nodes)) => {
+                if *index == nodes.len() {
+                    nodes.push(Node::new(Value::Null));
+                }
+                &mut nodes[*index]
+            }
+            _ => unreachable!("path checked by check_set"),
+        };
+    }
+    *node = value;
+    Ok(())
+}
+
+/// Returns an error if a value can't be set at `path` with [set], without changing `root`.
+fn check_set(root: &Node, path: &Path) -> Result<(), String> {
+    // Values created by `set` for missing members and appended items.
+    let created_member = Node::new(Value::Object(Vec::new()));
+    let created_item = Node::new(Value::Null);
+    let mut node = root;
+    for (i, segment) in path.segments.iter().enumerate() {
+        let at = || Path {
+            segments: path.segments[..i].to_vec(),
+        };
+        node = match (segment, &node.value) {
+            (Segment::Key(key), Value::Object(members)) => members
+                .iter()
+                .find(|m| unescape(&m.key) == *key)
+                .map_or(&created_member, |m| &m.node),
+            (Segment::Index(index), Value::Array(nodes)) if *index == nodes.len() => &created_item,
+            (Segment::Index(index), Value::Array(nodes)) => nodes.get(*index).ok_or_else(|| {
+                let len = nodes.len();
+                format!("index {index} out of bounds at {} (length {len})", at())
+            })?,
+            (Segment::Key(_), _) => return Err(format!("{} is not an object", at())),
+            (Segment::Index(_), _) => return Err(format!("{} is not an array", at())),
+        };
+    }
+    Ok(())
+}
+
+/// Removes the value at `path` (an object member or an array item), returns `false` if there is
+/// no value at `path`. The root value can't be removed.
+pub fn remove(root: &mut Node, path: &Path) -> bool {
+    let Some((last, parents)) = path.segments.split_last() else {
+        return false;
+    };
+    let mut node = root;
+    for segment in parents {
+        let child = match (segment, &mut node.value) {
+            (Segment::Key(key), Value::Object(members)) => members
+                .iter_mut()
+                .find(|m| unescape(&m.key) == *key)
+                .map(|m| &mut m.node),
+            (Segment::Index(index), Value::Array(nodes)) => nodes.get_mut(*index),
+            _ => None,
+        };
+        let Some(child) = child else {
+            return false;
+        };
+        node = child;
+    }
+    match (last, &mut node.value) {
+        (Segment::Key(key), Value::Object(members)) => {
+            let len = members.len();
+            members.retain(|m| unescape(&m.key) != *key);
+            members.len() != len
+        }
+        (Segment::Index(index), Value::Array(nodes)) if *index < nodes.len() => {
+            nodes.remove(*index);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Moves the members of an object whose keys are in `keys` first, in the order of `keys`. Other
+/// members keep their relative order.
+pub fn order_keys(node: &mut Node, keys: &[&str]) {
+    if let Value::Object(members) = &mut node.value {
+        members.sort_by_cached_key(|m| {
+            let key = unescape(&m.key);
+            keys.iter().position(|k| *k == key).unwrap_or(keys.len())
+        });
+    }
+}
+
+/// Sorts recursively the members of all objects by key (decoded keys, in byte order). Members
+/// with the same key keep their relative order.
+pub fn sort_keys(node: &mut Node) {
+    sort_keys_in(node, &Arena::default());
+}
+
+/// Sorts recursively the members of all objects by key, like [sort_keys], with the decoded keys
+/// written straight into `arena` (keys are not allocated on the heap).
+pub fn sort_keys_in(node: &mut Node, arena: &Arena) {
+    match &mut node.value {
+        Value::Array(nodes) => nodes.iter_mut().for_each(|n| sort_keys_in(n, arena)),
+        Value::Object(members) => {
+            members.sort_by_cached_key(|m| arena.alloc_fmt(format_args!("{}", Unescaped(&m.key))));
+            members
+                .iter_mut()
+                .for_each(|m| sort_keys_in(&mut m.node, arena));
+        }
+        _ => {}
+    }
+}
+
+/// Rewrites recursively all numbers in a canonical form, without changing their values: no
+/// trailing zeros in fractions, lowercase exponents without `+` and leading zeros (`1.50E+07`
+/// is `1.5e7`, `2.0` is `2`).
+pub fn normalize_numbers(node: &mut Node) {
+    match &mut node.value {
+        Value::Number(raw) => {
+            if let Some(normalized) = normalize_number(raw) {
+                *raw = Cow::Owned(normalized);
+            }
+        }
+        Value::Array(nodes) => nodes.iter_mut().for_each(normalize_numbers),
+        Value::Object(members) => members
+            .iter_mut()
+            .for_each(|m| normalize_numbers(&mut m.node)),
+        _ => {}
+    }
+}
+
+/// Rewrites recursively all floats with the shortest decimal parsing back to the same double
+/// (`0.10000000000000001` is `0.1`), see [num::shortest].
+pub fn shortest_floats(node: &mut Node) {
+    match &mut node.value {
+        Value::Number(raw) => {
+            if let Some(shortest) = num::shortest(raw) {
+                *raw = Cow::Owned(shortest);
+            }
+        }
+        Value::Array(nodes) => nodes.iter_mut().for_each(shortest_floats),
+        Value::Object(members) => members
+            .iter_mut()
+            .for_each(|m| shortest_floats(&mut m.node)),
+        _ => {}
+    }
+}
+
+/// Returns the canonical form of a valid JSON number, `None` if it's already canonical.
+fn normalize_number(raw: &str) -> Option<String> {
+    let (mantissa, exponent) = raw.split_once(['e', 'E']).unwrap_or((raw, ""));
+    let mantissa = match mantissa.split_once('.') {
+        Some((int, frac)) => match frac.trim_end_matches('0') {
+            "" => int.to_string(),
+            frac => format!("{int}.{frac}"),
+        },
+        None => mantissa.to_string(),
+    };
+    let (sign, digits) = match exponent.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", exponent.trim_start_matches('+')),
+    };
+    let normalized = match digits.trim_start_matches('0') {
+        "" => mantissa,
+        digits => format!("{mantissa}e{sign}{digits}"),
+    };
+    (normalized != raw).then_some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::parse;
+
+    #[test]
+    fn convert_names() {
+        let datas = [
+            ("userId", "user_id", "userId", "user-id"),
+            ("user_id", "user_id", "userId", "user-id"),
+            ("user-id", "user_id", "userId", "user-id"),
+            ("UserID", "user_id", "userId", "user-id"),
+            ("HTTPServer", "http_server", "httpServer", "http-server"),
+            (
+                "version2Beta",
+                "version2_beta",
+                "version2Beta",
+                "version2-beta",
+            ),
+            ("__private", "private", "private", "private"),
+            ("é_à", "é_à", "éÀ", "é-à"),
+            ("", "", "", ""),
+        ];
+        for (name, snake, camel, kebab) in datas {
+            assert_eq!(convert_case(name, Case::Snake), snake);
+            assert_eq!(convert_case(name, Case::Camel), camel);
+            assert_eq!(convert_case(name, Case::Kebab), kebab);
+        }
+    }
+
+    #[test]
+    fn prune_members() {
+        let input = br#"{"a":null,"b":{},"c":[],"d":{"e":null},"f":[null,{"g":[]}],"h":0}"#;
+        let all = "nulls,empty-objects,empty-arrays".parse::<Prune>().unwrap();
+
+        let mut node = parse(input).unwrap();
+        prune(&mut node, all, false);
+        assert_eq!(node.to_compact_string(), r#"{"d":{},"f":[null,{}],"h":0}"#);
+
+        let mut node = parse(input).unwrap();
+        prune(&mut node, all, true);
+        assert_eq!(node.to_compact_string(), r#"{"f":[null,{}],"h":0}"#);
+
+        let mut node = parse(input).unwrap();
+        prune(&mut node, "nulls".parse().unwrap(), true);
+        assert_eq!(
+            node.to_compact_string(),
+            r#"{"b":{},"c":[],"d":{},"f":[null,{"g":[]}],"h":0}"#
+        );
+
+        assert!("nulls,foo".parse::<Prune>().is_err());
+    }
+
+    #[test]
+    fn set_values() {
+        let input = br#"{"config":{"debug":false},"items":[1,2]}"#;
+        let value = |s: &'static str| parse(s.as_bytes()).unwrap();
+        let path = |s: &str| s.parse::<Path>().unwrap();
+
+        let mut node = parse(input).unwrap();
+        set(&mut node, &path(".config.debug"), value("true")).unwrap();
+        set(&mut node, &path(".version"), value(r#""2.0""#)).unwrap();
+        set(&mut node, &path(".a.b"), value("{}")).unwrap();
+        set(&mut node, &path(".items[0]"), value("[0]")).unwrap();
+        set(&mut node, &path(".items[2]"), value("3")).unwrap();
+        assert_eq!(
+            node.to_compact_string(),
+            r#"{"config":{"debug":true},"items":[[0],2,3],"version":"2.0","a":{"b":{}}}"#
+        );
+
+        assert_eq!(
+            set(&mut node, &path(".items[4]"), value("1")).unwrap_err(),
+            "index 4 out of bounds at .items (length 3)"
+        );
+        assert_eq!(
+            set(&mut node, &path(".version.x"), value("1")).unwrap_err(),
+            ".version is not an object"
+        );
+        // Nothing is created when the path is invalid.
+        let before = node.to_compact_string();
+        assert_eq!(
+            set(&mut node, &path(".x.y[0]"), value("1")).unwrap_err(),
+            ".x.y is not an array"
+        );
+        assert_eq!(
+            set(&mut node, &path(".items[3].a"), value("1")).unwrap_err(),
+            ".items[3] is not an object"
+        );
+        assert_eq!(node.to_compact_string(), before);
+        set(&mut node, &path("."), value("null")).unwrap();
+        assert_eq!(node.to_compact_string(), "null");
+    }
+
+    #[test]
+    fn remove_values() {
+        let path = |s: &str| s.parse::<Path>().unwrap();
+        let mut node = parse(br#"{"a":{"b":1,"c":2},"d":[1,2,3]}"#).unwrap();
+        assert!(remove(&mut node, &path(".a.b")));
+        assert!(remove(&mut node, &path(".d[1]")));
+        assert!(!remove(&mut node, &path(".d[5]")));
+        assert!(!remove(&mut node, &path(".x.y")));
+        assert!(!remove(&mut node, &path(".")));
+        assert_eq!(node.to_compact_string(), r#"{"a":{"c":2},"d":[1,3]}"#);
+    }
+
+    #[test]
+    fn order_members() {
+        let mut node = parse(br#"{"z":1,"spec":2,"a":3,"kind":4}"#).unwrap();
+        order_keys(&mut node, &["kind", "metadata", "spec"]);
+        assert_eq!(
+            node.to_compact_string(),
+            r#"{"kind":4,"spec":2,"z":1,"a":3}"#
+        );
+    }
+
+    #[test]
+    fn sort_all_keys() {
+        let mut node = parse(br#"{"b":1,"a":[{"z":1,"\u0041":2}],"":3}"#).unwrap();
+        sort_keys(&mut node);
+        assert_eq!(
+            node.to_compact_string(),
+            r#"{"":3,"a":[{"\u0041":2,"z":1}],"b":1}"#
+        );
+    }
+
+    #[test]
+    fn normalize_all_numbers() {
+        let datas = [
+            ("1", None),
+            ("-0", None),
+            ("1.50", Some("1.5")),
+            ("2.000", Some("2")),
+            ("1.50E+07", Some("1.5e7")),
+            ("1e-007", Some("1e-7")),
+            ("3.0e00", Some("3")),
+            ("100", None),
+            ("1.5e7", None),
+        ];
+        for (raw, normalized) in datas {
+            assert_eq!(normalize_number(raw).as_deref(), normalized);
+        }
+        let mut node = parse(b"[1.0,{\"a\":2E2}]").unwrap();
+        normalize_numbers(&mut node);
+        assert_eq!(node.to_compact_string(), r#"[1,{"a":2e2}]"#);
+    }
+
+    #[test]
+    fn rewrite_shortest_floats() {
+        let mut node =
+            parse(b"[1.0,{\"a\":0.30000000000000000001,\"b\":2E2}, 123456789012345678901]")
+                .unwrap();
+        shortest_floats(&mut node);
+        assert_eq!(
+            node.to_compact_string(),
+            r#"[1,{"a":0.3,"b":200},123456789012345678901]"#
+        );
+    }
+
+    #[test]
+    fn rename_all_keys() {
+        let mut node =
+            parse(br#"{"firstName":"a","addressList":[{"zipCode":1,"a\"B":2}]}"#).unwrap();
+        rename_keys(&mut node, Case::Snake);
+        assert_eq!(
+            node.to_compact_string(),
+            r#"{"first_name":"a","address_list":[{"zip_code":1,"a\"b":2}]}"#
+        );
+    }
+}
@@ -0,0 +1,476 @@
+use crate::format::{Color, FormatError, Formatter};
+use crate::path::{Path, Segment};
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// A JSON document tree.
+///
+/// Strings and numbers are kept raw, as in the input (with quotes and escapes for strings), so
+/// a tree can be written back without altering the original payload. Each value keeps its byte
+/// range in the input, which makes the tree usable for reporting (sizes, positions).
+/// Values that are not coming from the input (transformed or added values) use `Cow::Owned`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node<'input> {
+    pub value: Value<'input>,
+    /// Byte range of this value in the input (empty for a value not coming from the input).
+    pub span: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'input> {
+    Null,
+    Bool(bool),
+    /// A raw number, as in the input.
+    Number(Cow<'input, str>),
+    /// A raw string, with its quotes and escapes, as in the input.
+    String(Cow<'input, str>),
+    Array(Vec<Node<'input>>),
+    Object(Vec<Member<'input>>),
+}
+
+/// An object member.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member<'input> {
+    /// The raw key, with its quotes and escapes, as in the input.
+    pub key: Cow<'input, str>,
+    /// Byte range of the key in the input.
+    pub key_span: Range<usize>,
+    pub node: Node<'input>,
+}
+
+impl<'input> Node<'input> {
+    /// Creates a node that is not coming from an input.
+    pub fn new(value: Value<'input>) -> Self {
+        Node { value, span: 0..0 }
+    }
+
+    /// Converts this node to a node owning all its strings, independent of the input.
+    pub fn into_owned(self) -> Node<'static> {
+        let value = match self.value {
+            Value::Null => Value::Null,
+            Value::Bool(b) => Value::Bool(b),
+            Value::Number(s) => Value::Number(Cow::Owned(s.into_owned())),
+            Value::String(s) => Value::String(Cow::Owned(s.into_owned())),
+            Value::Array(nodes) => Value::Array(nodes.into_iter().map(Node::into_owned).collect()),
+            Value::Object(members) => Value::Object(
+                members
+                    .into_iter()
+                    .map(|m| Member {
+                        key: Cow::Owned(m.key.into_owned()),
+                        key_span: m.key_span,
+                        node: m.node.into_owned(),
+                    })
+                    .collect(),
+            ),
+        };
+        Node {
+            value,
+            span: self.span,
+        }
+    }
+
+    /// Writes this node as compact JSON (without any whitespace).
+    pub fn write_compact(&self, out: &mut String) {
+        match &self.value {
+            Value::Null => out.push_str("null"),
+            Value::Bool(true) => out.push_str("true"),
+            Value::Bool(false) => out.push_str("false"),
+            Value::Number(s) | Value::String(s) => out.push_str(s),
+            Value::Array(nodes) => {
+                out.push('[');
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    node.write_compact(out);
+                }
+                out.push(']');
+            }
+            Value::Object(members) => {
+                out.push('{');
+                for (i, member) in members.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(&member.key);
+                    out.push(':');
+                    member.node.write_compact(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+
+    /// Returns this node as compact JSON.
+    pub fn to_compact_string(&self) -> String {
+        let mut out = String::new();
+        self.write_compact(&mut out);
+        out
+    }
+
+    /// Returns the node at `path`, if any.
+    pub fn get(&self, path: &Path) -> Option<&Node<'input>> {
+        let mut node = self;
+        for segment in &path.segments {
+            node = match (segment, &node.value) {
+                (Segment::Key(key), Value::Object(members)) => {
+                    &members.iter().find(|m| unescape(&m.key) == *key)?.node
+                }
+                (Segment::Index(index), Value::Array(nodes)) => nodes.get(*index)?,
+                _ => return None,
+            };
+        }
+        Some(node)
+    }
+
+    /// Returns this node as text: the unescaped content for a string, compact JSON otherwise.
+    pub fn to_text(&self) -> String {
+        match &self.value {
+            Value::String(raw) => unescape(raw),
+            _ => self.to_compact_string(),
+        }
+    }
+}
+
+/// Parses JSON input bytes to a tree.
+///
+/// The parsing is done by the [Formatter] scanner, so the same JSON is accepted (nesting limit,
+/// BOM, UTF-8 validation) and the same errors are raised.
+pub fn parse(input: &[u8]) -> Result<Node<'_>, FormatError> {
+    let mut parser = Parser {
+        scanner: Formatter::new(input, Color::NoColor),
+        input,
+    };
+    parser.scanner.skip_start_bom();
+    parser.scanner.skip_whitespace();
+    let node = parser.parse_value()?;
+    parser.scanner.skip_whitespace();
+    match parser.scanner.peek_byte() {
+        Some(b) => Err(FormatError::InvalidByte(b, parser.scanner.pos)),
+        None => Ok(node),
+    }
+}
+
+/// Returns the raw value at `path` in JSON input bytes, if any, without building a tree.
+///
+/// Values not on the path are skipped, and the input after the value found is not read: unlike
+/// [parse], the input may not be entirely validated.
+pub fn get_raw<'input>(
+    input: &'input [u8],
+    path: &Path,
+) -> Result<Option<&'input str>, FormatError> {
+    let mut scanner = Formatter::new(input, Color::NoColor);
+    scanner.skip_start_bom();
+    scanner.skip_whitespace();
+    'segments: for segment in &path.segments {
+        let (open, close) = match segment {
+            Segment::Key(_) => (b'{', b'}'),
+            Segment::Index(_) => (b'[', b']'),
+        };
+        if scanner.peek_byte() != Some(open) {
+            return Ok(None);
+        }
+        scanner.next_byte();
+        let mut index = 0;
+        loop {
+            scanner.skip_whitespace();
+            if scanner.peek_byte() == Some(close) {
+                return Ok(None);
+            }
+            if index > 0 {
+                scanner.expect_byte(b',')?;
+                scanner.skip_whitespace();
+            }
+            let found = match segment {
+                Segment::Key(key) => {
+                    let start = scanner.pos;
+                    scanner.scan_string()?;
+                    let found = unescape(scanner.slice_str_unchecked(start, scanner.pos)) == *key;
+                    scanner.skip_whitespace();
+                    scanner.expect_byte(b':')?;
+                    scanner.skip_whitespace();
+                    found
+                }
+                Segment::Index(i) => index == *i,
+            };
+            if found {
+                continue 'segments;
+            }
+            scanner.skip_value()?;
+            index += 1;
+        }
+    }
+    let start = scanner.pos;
+    scanner.skip_value()?;
+    let bytes = &input[start.0..scanner.pos.0];
+    // The value has been validated by the scanner.
+    Ok(Some(unsafe { std::str::from_utf8_unchecked(bytes) }))
+}
+
+struct Parser<'input> {
+    scanner: Formatter<'input>,
+    input: &'input [u8],
+}
+
+impl<'input> Parser<'input> {
+    fn pos(&self) -> usize {
+        self.scanner.pos.0
+    }
+
+    /// Returns the input between `start` and the current position.
+    fn slice(&self, start: usize) -> &'input str {
+        let bytes = &self.input[start..self.pos()];
+        // Strings and numbers have been validated by the scanner.
+        unsafe { std::str::from_utf8_unchecked(bytes) }
+    }
+
+    fn parse_value(&mut self) -> Result<Node<'input>, FormatError> {
+        let start = self.pos();
+        let value = match self.scanner.peek_byte() {
+            Some(b'"') => {
+                self.scanner.scan_string()?;
+                Value::String(Cow::Borrowed(self.slice(start)))
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                self.scanner.scan_number()?;
+                Value::Number(Cow::Borrowed(self.slice(start)))
+            }
+            Some(b'{') => self.parse_object()?,
+            Some(b'[') => self.parse_array()?,
+            Some(b't') => {
+                self.scanner.scan_literal(b"true")?;
+                Value::Bool(true)
+            }
+            Some(b'f') => {
+                self.scanner.scan_literal(b"false")?;
+                Value::Bool(false)
+            }
+            Some(b'n') => {
+                self.scanner.scan_literal(b"null")?;
+                Value::Null
+            }
+            Some(b) => return Err(FormatError::InvalidByte(b, self.scanner.pos)),
+            None => return Err(FormatError::Eof),
+        };
+        Ok(Node {
+            value,
+            span: start..self.pos(),
+        })
+    }
+
+    fn parse_object(&mut self) -> Result<Value<'input>, FormatError> {
+        self.scanner.expect_byte(b'{')?;
+        self.scanner.inc_level()?;
+        let mut members = Vec::new();
+        loop {
+            self.scanner.skip_whitespace();
+            if self.scanner.peek_byte() == Some(b'}') {
+                self.scanner.next_byte();
+                self.scanner.dec_level();
+                return Ok(Value::Object(members));
+            }
+            if !members.is_empty() {
+                self.scanner.expect_byte(b',')?;
+                self.scanner.skip_whitespace();
+            }
+
+            let start = self.pos();
+            self.scanner.scan_string()?;
+            let key = self.slice(start);
+            let key_span = start..self.pos();
+
+            self.scanner.skip_whitespace();
+            self.scanner.expect_byte(b':')?;
+            self.scanner.skip_whitespace();
+            let node = self.parse_value()?;
+            members.push(Member {
+                key: Cow::Borrowed(key),
+                key_span,
+                node,
+            });
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value<'input>, FormatError> {
+        self.scanner.expect_byte(b'[')?;
+        self.scanner.inc_level()?;
+        let mut nodes = Vec::new();
+        loop {
+            self.scanner.skip_whitespace();
+            if self.scanner.peek_byte() == Some(b']') {
+                self.scanner.next_byte();
+                self.scanner.dec_level();
+                return Ok(Value::Array(nodes));
+            }
+            if !nodes.is_empty() {
+                self.scanner.expect_byte(b',')?;
+                self.scanner.skip_whitespace();
+            }
+            nodes.push(self.parse_value()?);
+        }
+    }
+}
+
+/// Decodes a raw JSON string (with quotes and escapes) to its actual value.
+///
+/// The raw string must be valid (as returned by [parse]). Lone surrogates in `\u` escapes are
+/// replaced by U+FFFD.
+pub fn unescape(raw: &str) -> String {
+    Unescaped(raw).to_string()
+}
+
+/// The decoded value of a raw JSON string, as returned by [unescape], written by [fmt::Display]
+/// without allocating (ex: straight into an [crate::arena::Arena] with `alloc_fmt`).
+pub struct Unescaped<'a>(pub &'a str);
+
+impl fmt::Display for Unescaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let raw = self
+            .0
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .unwrap_or(self.0);
+        if !raw.contains('\\') {
+            return f.write_str(raw);
+        }
+        let mut chars = raw.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                f.write_char(c)?;
+                continue;
+            }
+            match chars.next() {
+                Some('b') => f.write_char('\u{8}')?,
+                Some('f') => f.write_char('\u{c}')?,
+                Some('n') => f.write_char('\n')?,
+                Some('r') => f.write_char('\r')?,
+                Some('t') => f.write_char('\t')?,
+                Some('u') => {
+                    let high = hex4(&mut chars);
+                    let c = if (0xD800..0xDC00).contains(&high) {
+                        // A surrogate pair
+                        let mut lookahead = chars.clone();
+                        if lookahead.next() == Some('\\') && lookahead.next() == Some('u') {
+                            let low = hex4(&mut lookahead);
+                            if (0xDC00..0xE000).contains(&low) {
+                                chars = lookahead;
+                                char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                            } else {
+                                None
+                            }
+                        } else {
+                            None
+                        }
+                    } else {
+                        char::from_u32(high)
+                    };
+                    f.write_char(c.unwrap_or(char::REPLACEMENT_CHARACTER))?;
+                }
+                Some(c) => f.write_char(c)?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn hex4(chars: &mut impl Iterator<Item = char>) -> u32 {
+    chars
+        .take(4)
+        .fold(0, |acc, c| acc * 16 + c.to_digit(16).unwrap_or(0))
+}
+
+/// Encodes a string value to a raw JSON string, with quotes and minimal escapes.
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_write_compact() {
+        let input =
+            br#" { "a" : [1, 2.5e3, "x\"y"], "b": {}, "c": [], "d": [true, false, null] } "#;
+        let node = parse(input).unwrap();
+        assert_eq!(
+            node.to_compact_string(),
+            r#"{"a":[1,2.5e3,"x\"y"],"b":{},"c":[],"d":[true,false,null]}"#
+        );
+        assert_eq!(node.span, 1..input.len() - 1);
+        let Value::Object(members) = &node.value else {
+            panic!("expected object")
+        };
+        assert_eq!(members[0].key, "\"a\"");
+        assert_eq!(&input[members[0].key_span.clone()], b"\"a\"");
+        assert_eq!(
+            &input[members[0].node.span.clone()],
+            br#"[1, 2.5e3, "x\"y"]"#
+        );
+    }
+
+    #[test]
+    fn get_nodes() {
+        let node = parse(br#"{"a":[1,{"b c":"x\ny"}]}"#).unwrap();
+        let get = |path: &str| node.get(&path.parse().unwrap()).map(Node::to_text);
+        assert_eq!(get(".a[0]"), Some("1".to_string()));
+        assert_eq!(get(r#".a[1]["b c"]"#), Some("x\ny".to_string()));
+        assert_eq!(get(".a[1]"), Some(r#"{"b c":"x\ny"}"#.to_string()));
+        assert_eq!(get(".a[2]"), None);
+        assert_eq!(get(".a.b"), None);
+    }
+
+    #[test]
+    fn get_raw_values() {
+        let input = br#" {"a": [1, {"b": [true, null]}], "c": "x", "d": {"#;
+        let get = |path: &str| get_raw(input, &path.parse().unwrap()).unwrap();
+        assert_eq!(get(".a[1].b"), Some("[true, null]"));
+        assert_eq!(get(".c"), Some(r#""x""#));
+        assert_eq!(get(".a[2]"), None);
+        assert_eq!(get(".c.d"), None);
+        assert!(get_raw(input, &".e".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse(b"[1,]").is_err());
+        assert!(parse(b"{\"a\" 1}").is_err());
+        assert!(parse(b"[1] x").is_err());
+        assert!(parse(b"").is_err());
+    }
+
+    #[test]
+    fn unescape_strings() {
+        assert_eq!(unescape(r#""abc""#), "abc");
+        assert_eq!(unescape(r#""a\"b\\c\/d\n""#), "a\"b\\c/d\n");
+        assert_eq!(unescape(r#""é😀""#), "é😀");
+        assert_eq!(unescape(r#""\uD83D""#), "\u{FFFD}");
+        assert_eq!(Unescaped(r#""a\u0041\n""#).to_string(), "aA\n");
+    }
+
+    #[test]
+    fn escape_strings() {
+        assert_eq!(escape("abc"), r#""abc""#);
+        assert_eq!(escape("a\"b\\c\n\u{1}"), r#""a\"b\\c\n\u0001""#);
+        assert_eq!(unescape(&escape("é😀\t")), "é😀\t");
+    }
+}
@@ -0,0 +1,194 @@
+//! Hooks called while formatting, to process an input in the same pass.
+use std::ops::Range;
+
+/// Visits the keys and values of the input while it is formatted.
+///
+/// A visitor is called by the [Formatter](crate::format::Formatter) as the input is streamed, in
+/// the order of the input, so statistics can be computed, fields extracted or values redacted
+/// without parsing the input a second time. Spans are byte ranges in the input, and paths are
+/// displayed like [`crate::path::Path`] (`.items[0].id`), the path of the root value being empty.
+///
+/// Inputs are formatted on the current thread when a visitor is set, even with
+/// [parallel formatting](crate::format::Formatter::format_parallel).
+pub trait Visitor {
+    /// Called for each key of an object. `key` is the raw key, as in the input (with its quotes
+    /// and escapes) and `path` is the path of the member.
+    fn on_key(&mut self, key: &str, span: Range<usize>, path: &str) {
+        let _ = (key, span, path);
+    }
+
+    /// Called before each value, starting at `start`. The value is replaced in the output if
+    /// [Visit::Replace] is returned.
+    fn on_value_start(&mut self, start: usize, path: &str) -> Visit {
+        let _ = (start, path);
+        Visit::Continue
+    }
+
+    /// Called after each value, `raw` being the value as in the input. For objects and arrays,
+    /// this is called after the calls for their members.
+    fn on_value_end(&mut self, raw: &str, span: Range<usize>, path: &str) {
+        let _ = (raw, span, path);
+    }
+}
+
+/// What to do with a visited value.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum Visit {
+    /// Formats the value.
+    #[default]
+    Continue,
+    /// Validates the value but writes this JSON value instead (ex: `"***"` to redact a string).
+    Replace(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Visit, Visitor};
+    use crate::format::{Color, FormatError, Formatter, Indent, LineEnding};
+    use std::ops::Range;
+
+    #[derive(Default)]
+    struct Redact {
+        keys: Vec<(String, Range<usize>, String)>,
+        values: Vec<(String, String)>,
+    }
+
+    impl Visitor for Redact {
+        fn on_key(&mut self, key: &str, span: Range<usize>, path: &str) {
+            self.keys.push((key.to_string(), span, path.to_string()));
+        }
+
+        fn on_value_start(&mut self, _start: usize, path: &str) -> Visit {
+            if path.ends_with(".password") {
+                Visit::Replace(r#""***""#.to_string())
+            } else {
+                Visit::Continue
+            }
+        }
+
+        fn on_value_end(&mut self, raw: &str, _span: Range<usize>, path: &str) {
+            self.values.push((path.to_string(), raw.to_string()));
+        }
+    }
+
+    #[test]
+    fn visit_and_redact() {
+        let input = br#"{"user": {"name": "bob", "password": "secret"}, "ids": [1, 2]}"#;
+        let mut visitor = Redact::default();
+        let mut out = String::new();
+        Formatter::new(input, Color::NoColor)
+            .with_visitor(&mut visitor)
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(
+            out,
+            r#"{
+  "user": {
+    "name": "bob",
+    "password": "***"
+  },
+  "ids": [
+    1,
+    2
+  ]
+}"#
+        );
+
+        let keys = visitor
+            .keys
+            .iter()
+            .map(|(key, span, path)| (key.as_str(), span.clone(), path.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                (r#""user""#, 1..7, ".user"),
+                (r#""name""#, 10..16, ".user.name"),
+                (r#""password""#, 25..35, ".user.password"),
+                (r#""ids""#, 48..53, ".ids"),
+            ]
+        );
+        let values = visitor
+            .values
+            .iter()
+            .map(|(path, raw)| (path.as_str(), raw.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [
+                (".user.name", r#""bob""#),
+                (".user.password", r#""secret""#),
+                (".user", r#"{"name": "bob", "password": "secret"}"#),
+                (".ids[0]", "1"),
+                (".ids[1]", "2"),
+                (".ids", "[1, 2]"),
+                ("", std::str::from_utf8(input).unwrap()),
+            ]
+        );
+    }
+
+    struct ReplaceWith(&'static str);
+
+    impl Visitor for ReplaceWith {
+        fn on_value_start(&mut self, _start: usize, path: &str) -> Visit {
+            if path == ".a.b" {
+                Visit::Replace(self.0.to_string())
+            } else {
+                Visit::Continue
+            }
+        }
+    }
+
+    #[test]
+    fn replace_at_current_level() {
+        let input = br#"{"a": {"b": 1}}"#;
+        let mut visitor = ReplaceWith(r#"{"c": [true]}"#);
+        let mut out = String::new();
+        Formatter::new(input, Color::NoColor)
+            .with_indent(Indent::Tab)
+            .with_line_ending(LineEnding::Crlf)
+            .with_visitor(&mut visitor)
+            .format(&mut out)
+            .unwrap();
+        assert_eq!(
+            out,
+            "{\r\n\t\"a\": {\r\n\t\t\"b\": {\r\n\t\t\t\"c\": [\r\n\t\t\t\ttrue\r\n\t\t\t]\r\n\t\t}\r\n\t}\r\n}"
+        );
+
+        let mut visitor = ReplaceWith(r#"{"c" 1}"#);
+        let error = Formatter::new(input, Color::NoColor)
+            .with_visitor(&mut visitor)
+            .format(&mut String::new())
+            .unwrap_err();
+        assert!(
+            matches!(&error, FormatError::InvalidReplacement(_, span) if *span == (12..13)),
+            "{error:?}"
+        );
+        assert_eq!(error.offset(), Some(12));
+        assert_eq!(
+            error.to_string(),
+            "invalid replacement of the value at offset 12..13: invalid byte <31> at offset 5"
+        );
+    }
+
+    /// A visitor that can't be sent to another thread.
+    struct CountValues(std::rc::Rc<std::cell::Cell<usize>>);
+
+    impl Visitor for CountValues {
+        fn on_value_end(&mut self, _raw: &str, _span: Range<usize>, _path: &str) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn visit_without_send() {
+        let count = std::rc::Rc::default();
+        let mut visitor = CountValues(std::rc::Rc::clone(&count));
+        let mut out = String::new();
+        Formatter::new(b"[1, [2, 3]]", Color::NoColor)
+            .with_visitor(&mut visitor)
+            .format_parallel(4, &mut out)
+            .unwrap();
+        assert_eq!(count.get(), 5);
+    }
+}
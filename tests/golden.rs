@@ -0,0 +1,245 @@
+//! Golden tests: each fixture of `tests/fixtures` is formatted with each CLI mode and compared to
+//! the expected output in `tests/golden/<fixture>.<mode>.out`.
+//!
+//! Run with `UPDATE_GOLDEN=1 cargo test --test golden` to (re)generate expected outputs, then
+//! review the changes with `git diff tests/golden`. New formatting features must add their mode
+//! (and fixtures if needed) here.
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// CLI formatting modes: a name, used in the expected file name, and the CLI arguments.
+const MODES: &[(&str, &[&str])] = &[
+    ("color", &[]),
+    ("no-color", &["--no-color"]),
+    (
+        "rename-keys",
+        &["--no-color", "--rename-keys", "snake_case"],
+    ),
+    (
+        "prune",
+        &[
+            "--no-color",
+            "--prune",
+            "nulls,empty-objects,empty-arrays",
+            "--prune-fixed-point",
+        ],
+    ),
+    ("set", &["--no-color", "--set", r#".added={"a":[1,"x"]}"#]),
+    ("align-values", &["--no-color", "--align-values"]),
+    ("jsonc", &["--no-color", "--jsonc"]),
+    (
+        "sample-tail",
+        &["--no-color", "--ndjson", "--sample", "2", "--tail", "2"],
+    ),
+    ("dedup", &["--no-color", "--ndjson", "--dedup"]),
+    ("expand-embedded", &["--expand-embedded"]),
+    ("render-newlines", &["--no-color", "--render-newlines"]),
+    ("debug-dump", &["--debug-dump"]),
+    (
+        "ndjson",
+        &[
+            "--no-color",
+            "--ndjson",
+            "--record-template",
+            "--- {n} ({bytes} bytes) {.ts} ---",
+        ],
+    ),
+    (
+        "since-until",
+        &[
+            "--no-color",
+            "--ndjson",
+            "--since",
+            "2024-01-01T10:00:01Z",
+            "--until",
+            "2024-01-02",
+            "--time-field",
+            ".ts",
+        ],
+    ),
+    ("types", &["--types", "id=id", "--types", "*[*]=timestamp"]),
+    ("xml", &["--to", "xml", "--xml-root", "doc"]),
+    ("geojson", &["--no-color", "--geojson"]),
+    ("es-bulk", &["--es-bulk"]),
+    ("lockfile", &["--no-color", "--lockfile"]),
+    ("crlf", &["--no-color", "--line-ending", "crlf"]),
+    ("no-final-newline", &["--no-color", "--final-newline=false"]),
+    ("bom", &["--no-color", "--bom", "add"]),
+    (
+        "humanize",
+        &["--humanize", "--humanize-field", "*_s=duration-s"],
+    ),
+    ("avro", &["--no-color", "--avro"]),
+    ("compact", &["--compact"]),
+    ("indent-4", &["--no-color", "--indent", "4"]),
+    ("indent-tab", &["--no-color", "--indent", "tab"]),
+    ("shortest-floats", &["--no-color", "--shortest-floats"]),
+    (
+        "hex-fields",
+        &["--no-color", "--hex-fields", "latency_ms,statusCode,userId"],
+    ),
+    ("split-strings", &["--split-strings"]),
+    ("reveal-invisible", &["--no-color", "--reveal-invisible"]),
+    ("expand-empty", &["--no-color", "--expand-empty"]),
+    ("compact-single", &["--no-color", "--compact-single"]),
+    ("hyperlinks", &["--hyperlinks", "always"]),
+    ("file-sizes", &["--no-color", "--file-sizes"]),
+    (
+        "collapse",
+        &["--no-color", "--collapse", ".*", "--expand", ".request"],
+    ),
+];
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut fixtures = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "json" || ext == "jsonc" || ext == "ndjson")
+        })
+        .collect::<Vec<_>>();
+    fixtures.sort();
+    fixtures
+}
+
+/// Runs pretty with `args` on `fixture`, returns stdout, stderr and the exit code.
+fn run(fixture: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_pretty"))
+        .args(args)
+        .arg(fixture)
+        .output()
+        .unwrap();
+    let mut actual = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    if !stderr.is_empty() {
+        actual.push_str("--- stderr\n");
+        actual.push_str(&stderr);
+    }
+    if let Some(code) = output.status.code().filter(|&code| code != 0) {
+        actual.push_str(&format!("--- exit code {code}\n"));
+    }
+    actual
+}
+
+#[test]
+fn golden_outputs() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let golden_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut failures = Vec::new();
+
+    for fixture in fixtures() {
+        let name = fixture.file_stem().unwrap().to_string_lossy();
+        for (mode, args) in MODES {
+            let expected_path = golden_dir.join(format!("{name}.{mode}.out"));
+            let actual = run(&fixture, args);
+            if update {
+                std::fs::write(&expected_path, &actual).unwrap();
+                continue;
+            }
+            let expected = std::fs::read_to_string(&expected_path).unwrap_or_default();
+            if actual != expected {
+                failures.push(format!(
+                    "{}:\n--- expected\n{expected}\n--- actual\n{actual}",
+                    expected_path.display()
+                ));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} golden output(s) differ (run with UPDATE_GOLDEN=1 to update):\n\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+/// Runs pretty with `args` on `input` given on stdin, returns stdout.
+fn run_stdin(input: &[u8], args: &[&str]) -> Vec<u8> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pretty"))
+        .args(args)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap().stdout
+}
+
+/// Without color, the output is the colored output without any ANSI escape sequence.
+#[test]
+fn no_color_is_color_without_escapes() {
+    let mut failures = Vec::new();
+    for fixture in fixtures() {
+        for (mode, args) in MODES
+            .iter()
+            .filter(|(_, args)| args.contains(&"--no-color"))
+        {
+            let no_color = Command::new(env!("CARGO_BIN_EXE_pretty"))
+                .args(*args)
+                .arg(&fixture)
+                .output()
+                .unwrap()
+                .stdout;
+            let color_args = args
+                .iter()
+                .copied()
+                .filter(|&arg| arg != "--no-color")
+                .collect::<Vec<_>>();
+            let color = Command::new(env!("CARGO_BIN_EXE_pretty"))
+                .args(&color_args)
+                .arg(&fixture)
+                .output()
+                .unwrap()
+                .stdout;
+            let stripped = run_stdin(&color, &["--strip-ansi"]);
+            if stripped != no_color {
+                failures.push(format!("{} ({mode})", fixture.display()));
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "colored output differs from output without color:\n{}",
+        failures.join("\n")
+    );
+}
+
+/// `--lockfile` writes LF line endings, whatever the `.editorconfig` of the file.
+#[test]
+fn lockfile_ignores_editorconfig_line_endings() {
+    let dir = std::env::temp_dir().join(format!("pretty-lockfile-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(".editorconfig"), "[*]\nend_of_line = crlf\n").unwrap();
+    let write = |name: &str, args: &[&str]| {
+        let file = dir.join(name);
+        std::fs::write(&file, r#"{"b": 1, "a": 2}"#).unwrap();
+        run(&file, args);
+        std::fs::read_to_string(&file).unwrap()
+    };
+
+    let crlf = write("data.json", &["--write"]);
+    let lockfile = write("package-lock.json", &["--write", "--lockfile"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(crlf, "{\r\n  \"b\": 1,\r\n  \"a\": 2\r\n}");
+    assert_eq!(lockfile, "{\n  \"a\": 2,\n  \"b\": 1\n}");
+}
+
+/// `--quiet` hides warnings, `--deny-warnings` still fails.
+#[test]
+fn quiet_hides_warnings() {
+    let dir = std::env::temp_dir().join(format!("pretty-quiet-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("duplicate.json");
+    std::fs::write(&file, r#"{"a": 1, "a": 2}"#).unwrap();
+
+    let warnings = run(&file, &["--no-color", "--warnings", "--quiet"]);
+    let denied = run(&file, &["--no-color", "--deny-warnings", "--quiet"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(warnings, "");
+    assert_eq!(denied, "--- exit code 1\n");
+}